@@ -0,0 +1,12 @@
+//! Run with:
+//!
+//! ```not_rust
+//! cargo run --example try_json_to_bencode
+//! ```
+use torrust_bencode2json::try_json_to_bencode;
+
+fn main() {
+    let result = try_json_to_bencode(r#"{"spam":"eggs"}"#).unwrap();
+
+    assert_eq!(result, b"d4:spam4:eggse");
+}
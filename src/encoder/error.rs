@@ -0,0 +1,74 @@
+//! Bencode writer errors.
+use std::io;
+
+use thiserror::Error;
+
+use crate::parsers::{
+    stack::{State, TransitionError},
+    BencodeType,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// A value was written where the dictionary expects a field key. Keys must
+    /// be written with [`key`](super::BencodeWriter::key).
+    #[error("Expected dictionary field key, but got: {0}")]
+    ExpectedDictKeyGot(BencodeType),
+
+    /// A key was written outside a dictionary, or before the value for the
+    /// previous key.
+    #[error("Unexpected dictionary field key `{0}`")]
+    UnexpectedDictKey(String),
+
+    /// Dictionary keys must be sorted by their raw bytes.
+    #[error("Unsorted dictionary field key `{key}`, it must be after `{previous_key}`")]
+    UnsortedDictKey { key: String, previous_key: String },
+
+    #[error("Duplicate dictionary field key `{0}`")]
+    DuplicateDictKey(String),
+
+    /// The dictionary ended without the value for the last field key.
+    #[error("Unexpected end of dictionary. Premature end of dictionary")]
+    PrematureEndOfDict,
+
+    #[error("Unexpected end of list or dict. No matching start for the list or dict end")]
+    NoMatchingStartForListOrDictEnd,
+
+    /// Only one top-level value can be written.
+    #[error("Unexpected value after the top-level value")]
+    TrailingValue,
+
+    /// The writer finished before writing a value.
+    #[error("Nothing written, expecting a top-level value")]
+    EmptyOutput,
+
+    /// The writer finished before ending a list or dictionary.
+    #[error("Unexpected end of output. Unfinished {0}")]
+    Unfinished(BencodeType),
+}
+
+impl From<TransitionError> for Error {
+    fn from(err: TransitionError) -> Self {
+        match err {
+            TransitionError::ExpectedStringForDictKey(bencode_type) => {
+                Error::ExpectedDictKeyGot(bencode_type)
+            }
+            TransitionError::PrematureEndOfDict => Error::PrematureEndOfDict,
+            TransitionError::NoMatchingStartForListOrDictEnd => {
+                Error::NoMatchingStartForListOrDictEnd
+            }
+            TransitionError::UnexpectedEndOfInput(state) => match state {
+                State::Initial => unreachable!("the output can always end in the initial state"),
+                State::ExpectingFirstListItemOrEnd | State::ExpectingNextListItem => {
+                    Error::Unfinished(BencodeType::List)
+                }
+                State::ExpectingFirstDictFieldOrEnd
+                | State::ExpectingDictFieldValue
+                | State::ExpectingDictFieldKeyOrEnd => Error::Unfinished(BencodeType::Dict),
+            },
+        }
+    }
+}
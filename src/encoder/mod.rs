@@ -0,0 +1,641 @@
+//! Bencode writer.
+//!
+//! The [`BencodeWriter`] writes bencoded values to any [`Write`] output, one
+//! token at a time, like the JSON emitter used by the
+//! [`BencodeParser`](crate::parsers::BencodeParser). It validates the
+//! structure with the same [`Stack`] the parsers use, so it can only write
+//! valid Bencode:
+//!
+//! - Lists and dictionaries must be ended before finishing.
+//! - Dictionary fields are written with [`key`](BencodeWriter::key) followed
+//!   by the value. Keys must be unique and sorted by their raw bytes.
+//! - Only one top-level value can be written.
+//!
+//! ```rust
+//! use torrust_bencode2json::encoder::BencodeWriter;
+//!
+//! let mut writer = BencodeWriter::new(Vec::new());
+//!
+//! writer
+//!     .begin_dict()?
+//!     .key("interval")?
+//!     .write_int(1800)?
+//!     .key("peers")?
+//!     .write_bytes([1, 2, 3, 4, 0x1A, 0xE1])?
+//!     .end()?;
+//!
+//! assert_eq!(
+//!     writer.finish()?,
+//!     b"d8:intervali1800e5:peers6:\x01\x02\x03\x04\x1A\xE1e"
+//! );
+//! # Ok::<(), torrust_bencode2json::encoder::error::Error>(())
+//! ```
+//!
+//! Every method writes straight to the output. Wrap unbuffered outputs, like
+//! files or sockets, in a [`BufWriter`](std::io::BufWriter).
+pub mod error;
+
+use std::io::Write;
+
+use error::Error;
+
+use crate::parsers::{
+    event::Integer,
+    stack::{Stack, State},
+    BencodeType,
+};
+
+pub struct BencodeWriter<W: Write> {
+    writer: W,
+    stack: Stack,
+    /// The last field key of each open dictionary, if any.
+    dict_keys: Vec<Option<Vec<u8>>>,
+    has_value: bool,
+}
+
+impl<W: Write> BencodeWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            stack: Stack::default(),
+            dict_keys: Vec::new(),
+            has_value: false,
+        }
+    }
+
+    /// It writes an integer.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't write to the output.
+    /// - A dictionary field key is expected.
+    /// - The top-level value has already been written.
+    pub fn write_int(&mut self, value: i64) -> Result<&mut Self, Error> {
+        self.begin_value(BencodeType::Integer)?;
+
+        write!(self.writer, "i{value}e")?;
+
+        Ok(self)
+    }
+
+    /// It writes an integer of any size, like the ones in the parser
+    /// [events](crate::parsers::event::Event). Negative zero is written as
+    /// `0`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't write to the output.
+    /// - A dictionary field key is expected.
+    /// - The top-level value has already been written.
+    pub fn write_integer(&mut self, value: &Integer) -> Result<&mut Self, Error> {
+        self.begin_value(BencodeType::Integer)?;
+
+        let digits = match value.as_str() {
+            "-0" => "0",
+            digits => digits,
+        };
+
+        write!(self.writer, "i{digits}e")?;
+
+        Ok(self)
+    }
+
+    /// It writes a string. Strings are byte strings, they don't need to be
+    /// valid UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't write to the output.
+    /// - A dictionary field key is expected.
+    /// - The top-level value has already been written.
+    pub fn write_bytes(&mut self, value: impl AsRef<[u8]>) -> Result<&mut Self, Error> {
+        self.begin_value(BencodeType::String)?;
+
+        self.write_string(value.as_ref())?;
+
+        Ok(self)
+    }
+
+    /// It begins a list. Items are written until the list is
+    /// [ended](Self::end).
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't write to the output.
+    /// - A dictionary field key is expected.
+    /// - The top-level value has already been written.
+    pub fn begin_list(&mut self) -> Result<&mut Self, Error> {
+        self.begin_value(BencodeType::List)?;
+
+        self.writer.write_all(b"l")?;
+
+        self.stack.push(State::ExpectingFirstListItemOrEnd);
+
+        Ok(self)
+    }
+
+    /// It begins a dictionary. Fields are written with a [key](Self::key)
+    /// followed by the value until the dictionary is [ended](Self::end).
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't write to the output.
+    /// - A dictionary field key is expected.
+    /// - The top-level value has already been written.
+    pub fn begin_dict(&mut self) -> Result<&mut Self, Error> {
+        self.begin_value(BencodeType::Dict)?;
+
+        self.writer.write_all(b"d")?;
+
+        self.stack.push(State::ExpectingFirstDictFieldOrEnd);
+        self.dict_keys.push(None);
+
+        Ok(self)
+    }
+
+    /// It writes the key of the next dictionary field.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't write to the output.
+    /// - There is no dictionary expecting a field key.
+    /// - The key is not after the previous key of the dictionary.
+    pub fn key(&mut self, key: impl AsRef<[u8]>) -> Result<&mut Self, Error> {
+        let key = key.as_ref();
+
+        if !matches!(
+            self.stack.peek(),
+            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd
+        ) {
+            return Err(Error::UnexpectedDictKey(display_key(key)));
+        }
+
+        if let Some(previous_key) = self.dict_keys.last().and_then(Option::as_ref) {
+            if key == previous_key.as_slice() {
+                return Err(Error::DuplicateDictKey(display_key(key)));
+            }
+
+            if key < previous_key.as_slice() {
+                return Err(Error::UnsortedDictKey {
+                    key: display_key(key),
+                    previous_key: display_key(previous_key),
+                });
+            }
+        }
+
+        self.stack.begin_value(BencodeType::String)?;
+
+        self.write_string(key)?;
+
+        if let Some(last_key) = self.dict_keys.last_mut() {
+            *last_key = Some(key.to_vec());
+        }
+
+        Ok(self)
+    }
+
+    /// It ends the innermost list or dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't write to the output.
+    /// - There is no list or dictionary to end.
+    /// - The dictionary is expecting the value for the last field key.
+    pub fn end(&mut self) -> Result<&mut Self, Error> {
+        let previous_state = self.stack.end_list_or_dict()?;
+
+        if matches!(
+            previous_state,
+            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd
+        ) {
+            self.dict_keys.pop();
+        }
+
+        self.writer.write_all(b"e")?;
+
+        Ok(self)
+    }
+
+    /// It checks the top-level value is complete, flushes the output and
+    /// returns it.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't flush the output.
+    /// - Nothing has been written.
+    /// - There are unfinished lists or dictionaries.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.stack.end_of_input()?;
+
+        if !self.has_value {
+            return Err(Error::EmptyOutput);
+        }
+
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    /// It updates the stack for a new value that is not a dictionary field
+    /// key.
+    fn begin_value(&mut self, bencode_type: BencodeType) -> Result<(), Error> {
+        match self.stack.peek() {
+            State::Initial => {
+                if self.has_value {
+                    return Err(Error::TrailingValue);
+                }
+
+                self.has_value = true;
+            }
+            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd => {
+                return Err(Error::ExpectedDictKeyGot(bencode_type));
+            }
+            State::ExpectingFirstListItemOrEnd
+            | State::ExpectingNextListItem
+            | State::ExpectingDictFieldValue => {}
+        }
+
+        self.stack.begin_value(bencode_type)?;
+
+        Ok(())
+    }
+
+    fn write_string(&mut self, value: &[u8]) -> Result<(), Error> {
+        write!(self.writer, "{}:", value.len())?;
+
+        self.writer.write_all(value)?;
+
+        Ok(())
+    }
+}
+
+/// It returns the key for error messages, escaping non printable bytes.
+fn display_key(key: &[u8]) -> String {
+    key.escape_ascii().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoder::{error::Error, BencodeWriter};
+
+    mod it_should {
+        use crate::{
+            encoder::{error::Error, tests::write, BencodeWriter},
+            parsers::event::Integer,
+            try_bencode_to_json,
+        };
+
+        #[test]
+        fn write_integers() {
+            assert_eq!(write(|w| w.write_int(42)).unwrap(), b"i42e");
+            assert_eq!(write(|w| w.write_int(-42)).unwrap(), b"i-42e");
+            assert_eq!(
+                write(|w| w.write_int(i64::MIN)).unwrap(),
+                format!("i{}e", i64::MIN).as_bytes()
+            );
+        }
+
+        #[test]
+        fn write_integers_of_any_size() {
+            let big_integer = format!("{}1", i64::MAX);
+
+            assert_eq!(
+                write(|w| w.write_integer(&Integer::new(big_integer.clone()))).unwrap(),
+                format!("i{big_integer}e").as_bytes()
+            );
+        }
+
+        #[test]
+        fn write_negative_zero_as_zero() {
+            assert_eq!(
+                write(|w| w.write_integer(&Integer::new("-0".to_string()))).unwrap(),
+                b"i0e"
+            );
+        }
+
+        #[test]
+        fn write_byte_strings() {
+            assert_eq!(write(|w| w.write_bytes("")).unwrap(), b"0:");
+            assert_eq!(write(|w| w.write_bytes("spam")).unwrap(), b"4:spam");
+            assert_eq!(
+                write(|w| w.write_bytes([0xFF, 0xFE])).unwrap(),
+                b"2:\xFF\xFE"
+            );
+        }
+
+        #[test]
+        fn write_lists() {
+            assert_eq!(write(|w| w.begin_list()?.end()).unwrap(), b"le");
+            assert_eq!(
+                write(|w| w.begin_list()?.write_int(1)?.begin_list()?.end()?.end()).unwrap(),
+                b"li1elee"
+            );
+        }
+
+        #[test]
+        fn write_dictionaries() {
+            assert_eq!(write(|w| w.begin_dict()?.end()).unwrap(), b"de");
+            assert_eq!(
+                write(|w| w
+                    .begin_dict()?
+                    .key("bar")?
+                    .write_bytes("spam")?
+                    .key("foo")?
+                    .begin_dict()?
+                    .key("foo")?
+                    .write_int(42)?
+                    .end()?
+                    .end())
+                .unwrap(),
+                b"d3:bar4:spam3:food3:fooi42eee"
+            );
+        }
+
+        #[test]
+        fn write_values_the_parser_can_read() {
+            let output = write(|w| {
+                w.begin_dict()?
+                    .key("list")?
+                    .begin_list()?
+                    .write_bytes("a")?
+                    .write_int(1)?
+                    .end()?
+                    .key("name")?
+                    .write_bytes("ñandú")?
+                    .end()
+            })
+            .unwrap();
+
+            assert_eq!(
+                try_bencode_to_json(&output).unwrap(),
+                r#"{"list":["a",1],"name":"ñandú"}"#
+            );
+        }
+
+        #[test]
+        fn write_a_torrent_file() {
+            assert_eq!(
+                write(|w| w
+                    .begin_dict()?
+                    .key("announce")?
+                    .write_bytes("spam")?
+                    .key("info")?
+                    .begin_dict()?
+                    .key("length")?
+                    .write_int(1)?
+                    .key("name")?
+                    .write_bytes("a")?
+                    .key("piece length")?
+                    .write_int(16384)?
+                    .key("pieces")?
+                    .write_bytes([0xAA; 20])?
+                    .end()?
+                    .end())
+                .unwrap(),
+                [
+                    &b"d8:announce4:spam4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:"[..],
+                    &[0xAA; 20],
+                    b"ee",
+                ]
+                .concat()
+            );
+        }
+
+        #[test]
+        fn write_tracker_responses() {
+            assert_eq!(
+                write(|w| w
+                    .begin_dict()?
+                    .key("interval")?
+                    .write_int(1800)?
+                    .key("peers")?
+                    .write_bytes([1, 2, 3, 4, 0x1A, 0xE1])?
+                    .end())
+                .unwrap(),
+                [
+                    &b"d8:intervali1800e5:peers6:"[..],
+                    &[1, 2, 3, 4, 0x1A, 0xE1],
+                    b"e"
+                ]
+                .concat()
+            );
+
+            assert_eq!(
+                write(|w| w
+                    .begin_dict()?
+                    .key("files")?
+                    .begin_dict()?
+                    .key([0xAB; 20])?
+                    .begin_dict()?
+                    .key("complete")?
+                    .write_int(5)?
+                    .key("downloaded")?
+                    .write_int(50)?
+                    .key("incomplete")?
+                    .write_int(10)?
+                    .end()?
+                    .end()?
+                    .end())
+                .unwrap(),
+                [
+                    &b"d5:filesd20:"[..],
+                    &[0xAB; 20],
+                    b"d8:completei5e10:downloadedi50e10:incompletei10eeee",
+                ]
+                .concat()
+            );
+        }
+
+        #[test]
+        fn sort_keys_by_their_raw_bytes() {
+            assert_eq!(
+                write(|w| w
+                    .begin_dict()?
+                    .key("B")?
+                    .write_int(1)?
+                    .key("a")?
+                    .write_int(2)?
+                    .key([0xFF])?
+                    .write_int(3)?
+                    .end())
+                .unwrap(),
+                b"d1:Bi1e1:ai2e1:\xFFi3ee"
+            );
+        }
+
+        #[test]
+        fn only_compare_keys_of_the_same_dictionary() {
+            assert_eq!(
+                write(|w| w
+                    .begin_dict()?
+                    .key("b")?
+                    .begin_dict()?
+                    .key("a")?
+                    .write_int(1)?
+                    .end()?
+                    .key("c")?
+                    .write_int(2)?
+                    .end())
+                .unwrap(),
+                b"d1:bd1:ai1ee1:ci2ee"
+            );
+        }
+
+        #[test]
+        fn stream_the_output_as_values_are_written() {
+            let mut output = Vec::new();
+
+            let mut writer = BencodeWriter::new(&mut output);
+
+            writer.begin_list().unwrap().write_int(1).unwrap();
+
+            drop(writer);
+
+            assert_eq!(output, b"li1e");
+        }
+
+        #[test]
+        fn return_the_output_errors() {
+            struct BrokenPipe;
+
+            impl std::io::Write for BrokenPipe {
+                fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                    Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+                }
+
+                fn flush(&mut self) -> std::io::Result<()> {
+                    Ok(())
+                }
+            }
+
+            let result = BencodeWriter::new(BrokenPipe).write_int(1).map(|_| ());
+
+            assert!(matches!(result, Err(Error::Io(_))));
+        }
+    }
+
+    mod it_should_fail {
+        use crate::{
+            encoder::{error::Error, tests::write, BencodeWriter},
+            parsers::BencodeType,
+        };
+
+        #[test]
+        fn when_a_dictionary_value_is_written_without_a_key() {
+            let result = write(|w| w.begin_dict()?.write_int(1));
+
+            assert!(matches!(
+                result,
+                Err(Error::ExpectedDictKeyGot(BencodeType::Integer))
+            ));
+        }
+
+        #[test]
+        fn when_a_key_is_written_outside_a_dictionary() {
+            assert!(matches!(
+                write(|w| w.key("foo")),
+                Err(Error::UnexpectedDictKey(_))
+            ));
+            assert!(matches!(
+                write(|w| w.begin_list()?.key("foo")),
+                Err(Error::UnexpectedDictKey(_))
+            ));
+        }
+
+        #[test]
+        fn when_a_key_is_written_instead_of_a_value() {
+            assert!(matches!(
+                write(|w| w.begin_dict()?.key("foo")?.key("gaa")),
+                Err(Error::UnexpectedDictKey(_))
+            ));
+        }
+
+        #[test]
+        fn when_keys_are_not_sorted() {
+            let result = write(|w| w.begin_dict()?.key("b")?.write_int(1)?.key("a"));
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "Unsorted dictionary field key `a`, it must be after `b`"
+            );
+        }
+
+        #[test]
+        fn when_keys_are_duplicated() {
+            let result = write(|w| w.begin_dict()?.key([0xFF])?.write_int(1)?.key([0xFF]));
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                r"Duplicate dictionary field key `\xff`"
+            );
+        }
+
+        #[test]
+        fn when_a_dictionary_ends_without_the_value_for_the_last_key() {
+            assert!(matches!(
+                write(|w| w.begin_dict()?.key("foo")?.end()),
+                Err(Error::PrematureEndOfDict)
+            ));
+        }
+
+        #[test]
+        fn when_there_is_no_list_or_dictionary_to_end() {
+            assert!(matches!(
+                write(BencodeWriter::end),
+                Err(Error::NoMatchingStartForListOrDictEnd)
+            ));
+        }
+
+        #[test]
+        fn when_writing_more_than_one_top_level_value() {
+            assert!(matches!(
+                write(|w| w.write_int(1)?.write_int(2)),
+                Err(Error::TrailingValue)
+            ));
+        }
+
+        #[test]
+        fn when_finishing_without_writing_a_value() {
+            assert!(matches!(write(|w| Ok(w)), Err(Error::EmptyOutput)));
+        }
+
+        #[test]
+        fn when_finishing_with_unfinished_lists_or_dictionaries() {
+            assert!(matches!(
+                write(BencodeWriter::begin_list),
+                Err(Error::Unfinished(BencodeType::List))
+            ));
+            assert!(matches!(
+                write(|w| w.begin_list()?.begin_dict()?.key("foo")),
+                Err(Error::Unfinished(BencodeType::Dict))
+            ));
+        }
+    }
+
+    /// It builds the value with a new writer and returns the finished output.
+    fn write(
+        build: impl FnOnce(&mut BencodeWriter<Vec<u8>>) -> Result<&mut BencodeWriter<Vec<u8>>, Error>,
+    ) -> Result<Vec<u8>, Error> {
+        let mut writer = BencodeWriter::new(Vec::new());
+
+        build(&mut writer)?;
+
+        writer.finish()
+    }
+}
@@ -0,0 +1,15 @@
+//! JSON to Bencode converter errors.
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// Invalid JSON input or JSON values that can't be represented in Bencode,
+    /// like floats, booleans or null.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
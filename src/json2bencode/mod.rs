@@ -0,0 +1,721 @@
+//! JSON to Bencode converter.
+//!
+//! It reads a JSON value from the input and writes the corresponding bencoded
+//! value to the output. It's the reverse of the [`BencodeParser`](crate::parsers::BencodeParser).
+//!
+//! JSON values are mapped to bencoded values as follows:
+//!
+//! - Integers -> integers.
+//! - Strings -> strings.
+//! - Arrays -> lists.
+//! - Objects -> dictionaries. Keys are sorted by their raw bytes, as the
+//!   Bencode specification requires.
+//!
+//! Floats, booleans and `null` do not have a bencoded representation and are
+//! rejected. Integers must fit into an `i64` or `u64`.
+//!
+//! Strings are decoded with the same [`ByteStringEncoding`] used to write
+//! them:
+//!
+//! - `Hex` and `Base64`: strings using the `<hex>…</hex>` or
+//!   `<base64>…</base64>` formats produced for non UTF-8 bencoded strings are
+//!   decoded back into their original bytes. Since these formats are only used
+//!   when the bytes are not valid UTF-8, a string whose content decodes into
+//!   valid UTF-8 is kept as it is.
+//! - `Escaped`: `\\` and `\xNN` escapes are decoded in every string.
+//! - `Tagged`: objects like `{"$bytes":"//4="}` are decoded into the base64
+//!   bytes, and keys are decoded like in `Escaped` after removing the extra `$`.
+//! - `Lossy` and `Array`: strings are kept as they are. The original bytes
+//!   can't be recovered, and arrays of bytes are converted into lists.
+//!
+//! Dictionaries are kept in memory until they end, because their fields have
+//! to be sorted before writing them. Everything nested in a dictionary is kept
+//! with it, so a top-level dictionary, like a torrent file, is entirely kept in
+//! memory before it's written. Only the items of lists that are not inside a
+//! dictionary are written as they are read.
+pub mod error;
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufWriter, Read, Write},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+use crate::parsers::{options::ByteStringEncoding, string::TAGGED_BYTES_KEY};
+
+const HEX_PREFIX: &str = "<hex>";
+const HEX_SUFFIX: &str = "</hex>";
+
+const BASE64_PREFIX: &str = "<base64>";
+const BASE64_SUFFIX: &str = "</base64>";
+
+/// A converter from JSON into bencode.
+///
+/// It reads a single JSON value and writes it bencoded, decoding the strings
+/// with the chosen [`ByteStringEncoding`].
+pub struct JsonParser<R: Read> {
+    reader: R,
+    encoding: ByteStringEncoding,
+}
+
+impl<R: Read> JsonParser<R> {
+    /// It creates a parser decoding strings written with the default
+    /// [`ByteStringEncoding`].
+    pub fn new(reader: R) -> Self {
+        Self::with_encoding(reader, ByteStringEncoding::default())
+    }
+
+    /// It creates a parser decoding strings written with the given
+    /// [`ByteStringEncoding`].
+    pub fn with_encoding(reader: R, encoding: ByteStringEncoding) -> Self {
+        JsonParser { reader, encoding }
+    }
+
+    /// It parses a JSON value read from input and writes the corresponding
+    /// bencoded value as bytes to the output.
+    ///
+    /// The output is written while the input is read, so when the input is
+    /// invalid, the bytes written before the error are left in the output.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input or write to the output.
+    /// - The input is invalid JSON.
+    /// - The input contains JSON values that can't be bencoded.
+    pub fn write_bytes<W: Write>(&mut self, writer: W) -> Result<(), error::Error> {
+        let mut output = Output::new(BufWriter::new(writer));
+
+        let mut deserializer = serde_json::Deserializer::from_reader(&mut self.reader);
+
+        let result = BencodeSeed {
+            writer: &mut output,
+            encoding: self.encoding,
+        }
+        .deserialize(&mut deserializer)
+        .and_then(|()| deserializer.end());
+
+        if let Some(err) = output.error.take() {
+            return Err(err.into());
+        }
+
+        result?;
+
+        output.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// A writer that keeps the original I/O error.
+///
+/// Errors returned while deserializing are converted into JSON errors. We keep
+/// the original one so that output errors are not reported as JSON errors.
+struct Output<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Output<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf).map_err(|err| {
+            let kind = err.kind();
+            self.error = Some(err);
+            io::Error::from(kind)
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// It writes the bencoded version of the JSON value it visits.
+struct BencodeSeed<'w, W: Write> {
+    writer: &'w mut W,
+    encoding: ByteStringEncoding,
+}
+
+impl<'de, W: Write> DeserializeSeed<'de> for BencodeSeed<'_, W> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, W: Write> Visitor<'de> for BencodeSeed<'_, W> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an integer, string, array or object")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        write!(self.writer, "i{value}e").map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        write!(self.writer, "i{value}e").map_err(E::custom)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        write_bencoded_string(self.writer, &json_string_to_bytes(value, self.encoding))
+            .map_err(E::custom)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.writer.write_all(b"l").map_err(de::Error::custom)?;
+
+        while let Some(()) = seq.next_element_seed(BencodeSeed {
+            writer: &mut *self.writer,
+            encoding: self.encoding,
+        })? {}
+
+        self.writer.write_all(b"e").map_err(de::Error::custom)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut fields: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if self.encoding == ByteStringEncoding::Tagged && key == TAGGED_BYTES_KEY {
+                if !fields.is_empty() {
+                    return Err(de::Error::custom(format!(
+                        "`{TAGGED_BYTES_KEY}` must be the only field of a tagged byte string"
+                    )));
+                }
+
+                return write_tagged_bytes(self.writer, map);
+            }
+
+            let key_bytes = json_key_to_bytes(&key, self.encoding);
+
+            let mut value = Vec::new();
+
+            map.next_value_seed(BencodeSeed {
+                writer: &mut value,
+                encoding: self.encoding,
+            })?;
+
+            if fields.insert(key_bytes, value).is_some() {
+                return Err(de::Error::custom(format!(
+                    "duplicate dictionary key `{key}`"
+                )));
+            }
+        }
+
+        self.writer.write_all(b"d").map_err(de::Error::custom)?;
+
+        for (key, value) in fields {
+            write_bencoded_string(self.writer, &key).map_err(de::Error::custom)?;
+            self.writer.write_all(&value).map_err(de::Error::custom)?;
+        }
+
+        self.writer.write_all(b"e").map_err(de::Error::custom)
+    }
+}
+
+fn write_bencoded_string<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write!(writer, "{}:", bytes.len())?;
+    writer.write_all(bytes)
+}
+
+/// It writes the bytes of a tagged byte string like `{"$bytes":"//4="}`,
+/// once its `$bytes` key has been read.
+fn write_tagged_bytes<'de, W: Write, A: MapAccess<'de>>(
+    writer: &mut W,
+    mut map: A,
+) -> Result<(), A::Error> {
+    let encoded_bytes = map.next_value::<String>()?;
+
+    let bytes = BASE64
+        .decode(encoded_bytes)
+        .map_err(|err| de::Error::custom(format!("invalid base64 in tagged byte string: {err}")))?;
+
+    if map.next_key::<IgnoredAny>()?.is_some() {
+        return Err(de::Error::custom(format!(
+            "`{TAGGED_BYTES_KEY}` must be the only field of a tagged byte string"
+        )));
+    }
+
+    write_bencoded_string(writer, &bytes).map_err(de::Error::custom)
+}
+
+/// It returns the bytes a JSON string represents.
+///
+/// With the `Hex` and `Base64` encodings, JSON strings with the format
+/// `<hex>fffe</hex>` or `<base64>//4=</base64>` are converted back into the
+/// original non UTF-8 bytes. With the `Escaped` encoding, the escapes are
+/// decoded. Any other string is returned as UTF-8 bytes.
+fn json_string_to_bytes(value: &str, encoding: ByteStringEncoding) -> Vec<u8> {
+    let decoded_bytes = match encoding {
+        ByteStringEncoding::Hex => value
+            .strip_prefix(HEX_PREFIX)
+            .and_then(|value| value.strip_suffix(HEX_SUFFIX))
+            .and_then(|hex_bytes| hex::decode(hex_bytes).ok()),
+        ByteStringEncoding::Base64 => value
+            .strip_prefix(BASE64_PREFIX)
+            .and_then(|value| value.strip_suffix(BASE64_SUFFIX))
+            .and_then(|base64_bytes| BASE64.decode(base64_bytes).ok()),
+        ByteStringEncoding::Escaped => return unescape(value),
+        ByteStringEncoding::Lossy | ByteStringEncoding::Tagged | ByteStringEncoding::Array => None,
+    };
+
+    match decoded_bytes {
+        Some(bytes) if std::str::from_utf8(&bytes).is_err() => bytes,
+        _ => value.as_bytes().to_vec(),
+    }
+}
+
+/// It returns the bytes a JSON object key represents.
+///
+/// Keys are decoded like strings, except for the encodings writing non UTF-8
+/// values as objects or arrays, which use escaped keys.
+fn json_key_to_bytes(key: &str, encoding: ByteStringEncoding) -> Vec<u8> {
+    match encoding {
+        ByteStringEncoding::Tagged => unescape(
+            key.strip_prefix('$')
+                .filter(|key| key.starts_with('$'))
+                .unwrap_or(key),
+        ),
+        ByteStringEncoding::Array => unescape(key),
+        ByteStringEncoding::Hex
+        | ByteStringEncoding::Base64
+        | ByteStringEncoding::Lossy
+        | ByteStringEncoding::Escaped => json_string_to_bytes(key, encoding),
+    }
+}
+
+/// It decodes the `\\` and `\xNN` escapes written by the `Escaped` encoding.
+///
+/// Any other backslash is kept as it is.
+fn unescape(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+
+    let mut rest = value.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'\\' {
+            if let Some(tail) = tail.strip_prefix(b"\\") {
+                bytes.push(b'\\');
+                rest = tail;
+                continue;
+            }
+
+            if let Some(escaped_byte) = tail
+                .strip_prefix(b"x")
+                .and_then(|tail| tail.get(..2))
+                .and_then(|hex_byte| hex::decode(hex_byte).ok())
+            {
+                bytes.extend(escaped_byte);
+                rest = &tail[3..];
+                continue;
+            }
+        }
+
+        bytes.push(byte);
+        rest = tail;
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::error::Error;
+    use crate::json2bencode::JsonParser;
+
+    fn json_to_bencode_unchecked(input: &str) -> Vec<u8> {
+        try_json_to_bencode(input).expect("JSON to Bencode conversion failed")
+    }
+
+    fn try_json_to_bencode(input: &str) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+
+        JsonParser::new(input.as_bytes()).write_bytes(&mut output)?;
+
+        Ok(output)
+    }
+
+    mod it_should_allow_writing {
+        use crate::json2bencode::JsonParser;
+
+        #[test]
+        fn to_any_type_implementing_io_write_trait() {
+            let mut output = Vec::new();
+
+            let mut parser = JsonParser::new(&b"0"[..]);
+
+            parser
+                .write_bytes(&mut output)
+                .expect("JSON to Bencode conversion failed");
+
+            assert_eq!(output, b"i0e");
+        }
+    }
+
+    mod integers {
+        use crate::json2bencode::tests::json_to_bencode_unchecked;
+
+        #[test]
+        fn zero() {
+            assert_eq!(json_to_bencode_unchecked("0"), b"i0e");
+        }
+
+        #[test]
+        fn positive_integer() {
+            assert_eq!(json_to_bencode_unchecked("42"), b"i42e");
+        }
+
+        #[test]
+        fn negative_integer() {
+            assert_eq!(json_to_bencode_unchecked("-42"), b"i-42e");
+        }
+
+        #[test]
+        fn max_u64_integer() {
+            assert_eq!(
+                json_to_bencode_unchecked(&u64::MAX.to_string()),
+                format!("i{}e", u64::MAX).as_bytes()
+            );
+        }
+    }
+
+    mod strings {
+        use crate::json2bencode::tests::json_to_bencode_unchecked;
+
+        #[test]
+        fn empty_string() {
+            assert_eq!(json_to_bencode_unchecked(r#""""#), b"0:");
+        }
+
+        #[test]
+        fn utf8() {
+            assert_eq!(json_to_bencode_unchecked(r#""spam""#), b"4:spam");
+        }
+
+        #[test]
+        fn with_multi_byte_utf8_chars() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#""ñandú""#),
+                "7:ñandú".as_bytes()
+            );
+        }
+
+        #[test]
+        fn with_escaped_json_chars() {
+            assert_eq!(json_to_bencode_unchecked(r#""\n\"""#), b"2:\n\"");
+        }
+
+        #[test]
+        fn non_utf8_in_hex_format() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#""<hex>fffe</hex>""#),
+                b"2:\xFF\xFE"
+            );
+        }
+
+        #[test]
+        fn in_hex_format_whose_bytes_are_valid_utf8_as_it_is() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#""<hex>6162</hex>""#),
+                b"15:<hex>6162</hex>"
+            );
+        }
+
+        #[test]
+        fn with_invalid_hex_content_as_it_is() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#""<hex>zz</hex>""#),
+                b"13:<hex>zz</hex>"
+            );
+        }
+    }
+
+    mod strings_with_the_byte_string_encoding {
+        use crate::{json2bencode::JsonParser, parsers::options::ByteStringEncoding};
+
+        fn json_to_bencode(encoding: ByteStringEncoding, input: &str) -> Vec<u8> {
+            let mut output = Vec::new();
+
+            JsonParser::with_encoding(input.as_bytes(), encoding)
+                .write_bytes(&mut output)
+                .expect("JSON to Bencode conversion failed");
+
+            output
+        }
+
+        #[test]
+        fn base64() {
+            assert_eq!(
+                json_to_bencode(
+                    ByteStringEncoding::Base64,
+                    r#"{"<base64>//4=</base64>":"<base64>YWI=</base64>"}"#
+                ),
+                b"d2:\xFF\xFE21:<base64>YWI=</base64>e"
+            );
+        }
+
+        #[test]
+        fn escaped() {
+            assert_eq!(
+                json_to_bencode(ByteStringEncoding::Escaped, r#"{"\\xff":"a\\\\b\\n\\x4"}"#),
+                b"d1:\xFF8:a\\b\\n\\x4e"
+            );
+        }
+
+        #[test]
+        fn tagged() {
+            assert_eq!(
+                json_to_bencode(
+                    ByteStringEncoding::Tagged,
+                    r#"{"$$bytes":{"$bytes":"//4="},"$a":"\\xff"}"#
+                ),
+                b"d2:$a4:\\xff6:$bytes2:\xFF\xFEe"
+            );
+        }
+
+        #[test]
+        fn lossy_as_they_are() {
+            assert_eq!(
+                json_to_bencode(ByteStringEncoding::Lossy, r#""<hex>ff</hex>""#),
+                b"13:<hex>ff</hex>"
+            );
+        }
+
+        #[test]
+        fn array_with_arrays_of_bytes_as_lists() {
+            assert_eq!(
+                json_to_bencode(ByteStringEncoding::Array, r#"{"\\xff":[255]}"#),
+                b"d1:\xFFli255eee"
+            );
+        }
+    }
+
+    mod lists {
+        use crate::json2bencode::tests::json_to_bencode_unchecked;
+
+        #[test]
+        fn empty_list() {
+            assert_eq!(json_to_bencode_unchecked("[]"), b"le");
+        }
+
+        #[test]
+        fn nested_lists() {
+            assert_eq!(json_to_bencode_unchecked("[[]]"), b"llee");
+        }
+
+        #[test]
+        fn with_items_of_different_types() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#"[42,"spam",[],{}]"#),
+                b"li42e4:spamledee"
+            );
+        }
+    }
+
+    mod dictionaries {
+        use crate::json2bencode::tests::json_to_bencode_unchecked;
+
+        #[test]
+        fn empty_dictionary() {
+            assert_eq!(json_to_bencode_unchecked("{}"), b"de");
+        }
+
+        #[test]
+        fn with_one_field() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#"{"spam":"eggs"}"#),
+                b"d4:spam4:eggse"
+            );
+        }
+
+        #[test]
+        fn with_keys_sorted_by_raw_bytes() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#"{"foo":1,"bar":2,"<hex>ff</hex>":3,"Z":4}"#),
+                b"d1:Zi4e3:bari2e3:fooi1e1:\xFFi3ee"
+            );
+        }
+
+        #[test]
+        fn nested_dictionaries() {
+            assert_eq!(
+                json_to_bencode_unchecked(r#"{"foo":{"bar":[1,{}]}}"#),
+                b"d3:food3:barli1edeeee"
+            );
+        }
+    }
+
+    mod it_should_round_trip {
+        use crate::{json2bencode::tests::json_to_bencode_unchecked, try_bencode_to_json};
+
+        #[test]
+        fn non_utf8_strings_in_keys_and_values() {
+            let bencode = b"d2:\xFF\xFEl2:\xFD\xFCi-1eee";
+
+            let json = try_bencode_to_json(bencode).unwrap();
+
+            assert_eq!(json_to_bencode_unchecked(&json), bencode);
+        }
+
+        mod with_the_unambiguous_byte_string_encodings {
+            use crate::{
+                json2bencode::JsonParser,
+                parsers::options::{BencodeParserBuilder, ByteStringEncoding},
+            };
+
+            /// Strings that look like the output of the encodings, with the
+            /// keys sorted as the converter writes them.
+            const BENCODE: &[u8] = b"d7:$$bytesd6:$bytes4://4=e6:$bytes2:\xFF\xFE\
+                1:\\l4:\\xff13:<hex>ff</hex>2:\xFF\\ee";
+
+            fn round_trip(encoding: ByteStringEncoding) -> Vec<u8> {
+                let mut json = Vec::new();
+
+                BencodeParserBuilder::default()
+                    .byte_string_encoding(encoding)
+                    .build(BENCODE)
+                    .write_bytes(&mut json)
+                    .unwrap();
+
+                let mut bencode = Vec::new();
+
+                JsonParser::with_encoding(&json[..], encoding)
+                    .write_bytes(&mut bencode)
+                    .unwrap();
+
+                bencode
+            }
+
+            #[test]
+            fn escaped() {
+                assert_eq!(round_trip(ByteStringEncoding::Escaped), BENCODE);
+            }
+
+            #[test]
+            fn tagged() {
+                assert_eq!(round_trip(ByteStringEncoding::Tagged), BENCODE);
+            }
+        }
+    }
+
+    mod it_should_fail {
+        use crate::json2bencode::{error::Error, tests::try_json_to_bencode};
+
+        #[test]
+        fn when_the_input_is_not_valid_json() {
+            assert!(matches!(try_json_to_bencode("[1,"), Err(Error::Json(_))));
+        }
+
+        #[test]
+        fn when_there_is_trailing_data_after_the_json_value() {
+            assert!(matches!(try_json_to_bencode("1 2"), Err(Error::Json(_))));
+        }
+
+        #[test]
+        fn with_floats() {
+            assert!(matches!(try_json_to_bencode("1.5"), Err(Error::Json(_))));
+        }
+
+        #[test]
+        fn with_integers_that_do_not_fit_into_64_bits() {
+            assert!(matches!(
+                try_json_to_bencode("18446744073709551616"),
+                Err(Error::Json(_))
+            ));
+        }
+
+        #[test]
+        fn with_booleans() {
+            assert!(matches!(try_json_to_bencode("true"), Err(Error::Json(_))));
+        }
+
+        #[test]
+        fn with_null() {
+            assert!(matches!(try_json_to_bencode("null"), Err(Error::Json(_))));
+        }
+
+        #[test]
+        fn with_duplicate_dictionary_keys() {
+            assert!(matches!(
+                try_json_to_bencode(r#"{"foo":1,"foo":2}"#),
+                Err(Error::Json(_))
+            ));
+        }
+
+        #[test]
+        fn with_tagged_byte_strings_with_more_fields() {
+            use crate::{json2bencode::JsonParser, parsers::options::ByteStringEncoding};
+
+            for input in [r#"{"$bytes":"//4=","a":1}"#, r#"{"a":1,"$bytes":"//4="}"#] {
+                let result =
+                    JsonParser::with_encoding(input.as_bytes(), ByteStringEncoding::Tagged)
+                        .write_bytes(Vec::new());
+
+                assert!(matches!(result, Err(Error::Json(_))));
+            }
+        }
+
+        #[test]
+        fn with_tagged_byte_strings_with_invalid_base64() {
+            use crate::{json2bencode::JsonParser, parsers::options::ByteStringEncoding};
+
+            let result =
+                JsonParser::with_encoding(&br#"{"$bytes":"!"}"#[..], ByteStringEncoding::Tagged)
+                    .write_bytes(Vec::new());
+
+            assert!(matches!(result, Err(Error::Json(_))));
+        }
+
+        #[test]
+        fn when_there_is_a_problem_writing_to_the_output() {
+            use std::io::{self, Write};
+
+            use crate::json2bencode::JsonParser;
+
+            struct FaultyWriter;
+
+            impl Write for FaultyWriter {
+                fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                    Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "Permission denied",
+                    ))
+                }
+
+                fn flush(&mut self) -> io::Result<()> {
+                    Ok(())
+                }
+            }
+
+            let result = JsonParser::new(&b"[1]"[..]).write_bytes(FaultyWriter);
+
+            assert!(matches!(result, Err(Error::Io(_))));
+        }
+    }
+}
@@ -1,13 +1,19 @@
-//! This lib contains functions to convert bencoded bytes into a JSON string.
+//! This lib contains functions to convert bencoded bytes into a JSON string,
+//! and JSON strings back into bencoded bytes.
 //!
 //! There are high-level functions for common purposes that call the lower level
 //! parser. You can use the low-lever parser if the high-level wrappers are not
 //! suitable for your needs.
+use json2bencode::JsonParser;
 use parsers::{error::Error, BencodeParser};
+use serde::de::DeserializeOwned;
 
+pub mod encoder;
+pub mod json2bencode;
 pub mod parsers;
 pub mod rw;
 mod test;
+pub mod value;
 
 /// It converts bencoded bytes into a JSON string.
 ///
@@ -25,7 +31,52 @@ pub fn try_bencode_to_json(input_buffer: &[u8]) -> Result<String, Error> {
     }
 }
 
+/// It deserializes bencoded bytes into any type implementing `Deserialize`.
+///
+/// The input must contain exactly one bencoded value, nested at most
+/// [`DEFAULT_MAX_DEPTH`](value::DEFAULT_MAX_DEPTH) levels deep.
+///
+/// # Errors
+///
+/// Will return an error if:
+///
+/// - The input is invalid Bencode. The error contains the usual parser context.
+/// - The input is nested too deeply.
+/// - The input is empty or there is trailing data after the bencoded value.
+/// - The bencoded value does not match the target type.
+pub fn from_bytes<T: DeserializeOwned>(input_buffer: &[u8]) -> Result<T, Error> {
+    let mut parser = BencodeParser::new(input_buffer);
+
+    let Some(value) = parser.read_value()? else {
+        return Err(Error::Deserialize(
+            "empty input, expecting a bencoded value".to_string(),
+        ));
+    };
+
+    parser.expect_end_of_input()?;
+
+    T::deserialize(value)
+}
+
+/// It converts a JSON string into bencoded bytes.
+///
+/// # Errors
+///
+/// Will return an error if the conversion fails.
+pub fn try_json_to_bencode(input: &str) -> Result<Vec<u8>, json2bencode::error::Error> {
+    let mut output = Vec::new();
+
+    let mut parser = JsonParser::new(input.as_bytes());
+
+    match parser.write_bytes(&mut output) {
+        Ok(()) => Ok(output),
+        Err(err) => Err(err),
+    }
+}
+
 /// Helper to convert a string into a bencoded string.
+///
+/// Use the [`BencodeWriter`](encoder::BencodeWriter) to write other values.
 #[must_use]
 pub fn to_bencode(value: &str) -> Vec<u8> {
     let bencoded_str = format!("{}:{}", value.len(), value);
@@ -53,6 +104,24 @@ mod tests {
         }
     }
 
+    mod converting_json_to_bencode {
+        use crate::try_json_to_bencode;
+
+        #[test]
+        fn when_it_succeeds() {
+            let result = try_json_to_bencode(r#"{"spam":"eggs"}"#).unwrap();
+
+            assert_eq!(result, b"d4:spam4:eggse");
+        }
+
+        #[test]
+        fn when_it_fails() {
+            let result = try_json_to_bencode("invalid JSON value");
+
+            assert!(result.is_err());
+        }
+    }
+
     mod converting_string_to_bencode {
         use crate::to_bencode;
 
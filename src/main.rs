@@ -1,4 +1,7 @@
-//! Converts Bencode to JSON.
+//! Converts between Bencode and JSON, and inspects bencoded data.
+//!
+//! Without a subcommand, it converts Bencode to JSON like the `decode`
+//! subcommand.
 //!
 //! Usage:
 //!
@@ -13,65 +16,889 @@
 //! ```text
 //! cargo run -- -i ./tests/fixtures/sample.bencode -o output.json
 //! ```
-use clap::{Arg, Command};
+//!
+//! Converting a torrent file:
+//!
+//! ```text
+//! cargo run -- --torrent -i ./file.torrent
+//! ```
+//!
+//! Pretty-printing the JSON:
+//!
+//! ```text
+//! echo "d3:foold3:bari42eeee" | cargo run -- --pretty
+//! ```
+//!
+//! Only writing some fields:
+//!
+//! ```text
+//! cargo run -- --select info.name --select announce-list -i ./file.torrent
+//! ```
+//!
+//! Converting big files without keeping long strings in memory:
+//!
+//! ```text
+//! cargo run -- --string-buffer-size 65536 -i ./big.bencode
+//! ```
+//!
+//! Getting well-formed JSON from a damaged torrent file:
+//!
+//! ```text
+//! cargo run -- --on-error close -i ./damaged.torrent
+//! ```
+//!
+//! Pointing at the offending byte of invalid input:
+//!
+//! ```text
+//! echo -n "li1ei2xe" | cargo run -- --error-format pretty
+//! ```
+//!
+//! Only accepting canonical bencode:
+//!
+//! ```text
+//! echo -n "i-0e" | cargo run -- --strict
+//! ```
+//!
+//! Fixing a torrent file that is not in canonical form:
+//!
+//! ```text
+//! cargo run -- canonicalize -i ./file.torrent -o ./fixed.torrent
+//! ```
+//!
+//! Converting JSON back to Bencode:
+//!
+//! ```text
+//! echo '{"spam":["a","b"]}' | cargo run -- encode
+//! ```
+//!
+//! Checking that a file is valid Bencode, only with the exit status:
+//!
+//! ```text
+//! cargo run -- validate --strict -i ./file.torrent
+//! ```
+//!
+//! Summarizing a torrent file: name, size, files, piece count and info hash:
+//!
+//! ```text
+//! cargo run -- info -i ./file.torrent
+//! ```
+//!
+//! Extracting a value:
+//!
+//! ```text
+//! cargo run -- query info.name -i ./file.torrent
+//! ```
+use clap::{builder::RangedU64ValueParser, value_parser, Arg, ArgAction, ArgMatches, Command};
 use std::fs::File;
-use std::io::{self, Read, Write};
-use torrust_bencode2json::parsers::BencodeParser;
+use std::io::{self, IsTerminal, Read, Write};
+use torrust_bencode2json::{
+    json2bencode::{self, JsonParser},
+    parsers::{
+        canonical::DuplicateKeys,
+        error::{Error, ErrorKind},
+        options::{
+            BencodeParserBuilder, ByteStringEncoding, ErrorRecovery, MultipleValues,
+            StreamedInvalidUtf8,
+        },
+        query::Query,
+        render::ErrorRenderer,
+        torrent::TorrentInfo,
+        BencodeParser,
+    },
+};
+
+/// Number of spaces used to indent pretty-printed JSON by default.
+const DEFAULT_INDENT: usize = 2;
+
+/// Maximum number of nested lists and dictionaries by default for the
+/// commands keeping the whole value in memory.
+const DEFAULT_MAX_DEPTH: &str = "1000";
+
+/// Exit code when the input is invalid or exceeds a limit (`EX_DATAERR` in
+/// `sysexits.h`).
+const EXIT_INVALID_INPUT: i32 = 65;
+
+/// Exit code when it can't read from the input or write to the output
+/// (`EX_IOERR` in `sysexits.h`).
+const EXIT_IO_ERROR: i32 = 74;
 
 fn main() {
     run();
 }
 
 fn run() {
-    let matches = Command::new("torrust-bencode2json")
-        .version("0.1.0")
-        .author("Torrust Organization")
-        .about("Converts Bencode to JSON")
-        .arg(
-            Arg::new("input")
-                .short('i')
-                .long("input")
-                .default_value(None)
-                .help("Optional input file (defaults to stdin)"),
-        )
-        .arg(
-            Arg::new("output")
-                .short('o')
-                .long("output")
-                .default_value(None)
-                .help("Optional output file (defaults to stdout)"),
-        )
-        .get_matches();
+    let matches = cli().get_matches();
+
+    match matches.subcommand() {
+        Some(("decode", sub_matches)) => decode(sub_matches),
+        Some(("encode", sub_matches)) => encode(sub_matches),
+        Some(("validate", sub_matches)) => validate(sub_matches),
+        Some(("info", sub_matches)) => info(sub_matches),
+        Some(("query", sub_matches)) => query(sub_matches),
+        Some(("canonicalize", sub_matches)) => canonicalize(sub_matches),
+        _ => decode(&matches),
+    }
+}
+
+/// It converts the bencoded input into JSON.
+fn decode(matches: &ArgMatches) {
+    if is_pretty(matches) && writes_one_value_per_line(matches) {
+        cli()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--pretty and --indent can't be used when the values are written one per line: with --multiple-values ndjson, or with --on-error close or resync unless --multiple-values is array or reject",
+            )
+            .exit();
+    }
+
+    let parser_builder = with_selected_paths(
+        with_streaming(
+            with_indent(with_json_output(parser_builder(matches), matches), matches),
+            matches,
+        ),
+        matches,
+        "select",
+    )
+    .multiple_values(multiple_values(matches))
+    .error_recovery(error_recovery(matches));
+
+    let error_format = error_format(matches);
+
+    let input = open_input(matches, &error_format);
+
+    let output = create_output(matches, &error_format);
+
+    write_json(parser_builder.build(input), output, &error_format);
+}
+
+/// It converts the JSON input into Bencode.
+fn encode(matches: &ArgMatches) {
+    let input = open_input(matches, &ErrorFormat::Human);
+
+    let output = create_output(matches, &ErrorFormat::Human);
+
+    let mut parser = JsonParser::with_encoding(input, byte_string_encoding(matches));
+
+    if let Err(e) = parser.write_bytes(output) {
+        eprintln!("Error: {e}");
+
+        std::process::exit(match &e {
+            json2bencode::error::Error::Json(err) if !err.is_io() => EXIT_INVALID_INPUT,
+            _ => EXIT_IO_ERROR,
+        })
+    }
+}
+
+/// It checks the bencoded input without writing anything. Only the errors
+/// are printed.
+fn validate(matches: &ArgMatches) {
+    let parser_builder = parser_builder(matches)
+        .multiple_values(multiple_values(matches))
+        .error_recovery(error_recovery(matches));
+
+    let error_format = error_format(matches);
+
+    let input = open_input(matches, &error_format);
+
+    let mut parser = parser_builder.build(input);
+
+    // The JSON is discarded, so the errors are printed without its context
+    if let Err(e) = parser.write_bytes(io::sink()) {
+        print_input_error(&e, &error_format);
+        std::process::exit(exit_code(&e));
+    }
+
+    // Errors recovered with `--on-error`
+    if let Some((last, others)) = parser.diagnostics().split_last() {
+        for diagnostic in others {
+            print_input_error(&diagnostic.error, &error_format);
+        }
+        print_input_error(&last.error, &error_format);
+        std::process::exit(exit_code(&last.error));
+    }
+}
+
+/// It writes a summary of the torrent file.
+fn info(matches: &ArgMatches) {
+    let options = with_limits(
+        BencodeParserBuilder::default().capture_size(capture_size(matches)),
+        matches,
+    )
+    .options()
+    .clone();
+
+    let error_format = error_format(matches);
+
+    let input = open_input(matches, &error_format);
+
+    let mut output = create_output(matches, &error_format);
+
+    let torrent_info = match TorrentInfo::read(input, options) {
+        Ok(torrent_info) => torrent_info,
+        Err(e) => exit_with_error(&e, &error_format),
+    };
+
+    let summary = if matches.get_flag("json") {
+        torrent_info_json(&torrent_info)
+    } else {
+        torrent_info_text(&torrent_info)
+    };
+
+    if let Err(e) = output
+        .write_all(summary.as_bytes())
+        .and_then(|()| output.flush())
+    {
+        exit_with_error(&e.into(), &error_format);
+    }
+}
+
+/// It writes the values at the given paths as JSON, one per line.
+fn query(matches: &ArgMatches) {
+    let parser_builder = with_selected_paths(
+        with_json_output(parser_builder(matches), matches),
+        matches,
+        "path",
+    );
+
+    let error_format = error_format(matches);
+
+    let input = open_input(matches, &error_format);
+
+    let output = create_output(matches, &error_format);
+
+    write_json(parser_builder.build(input), output, &error_format);
+}
+
+/// It writes the JSON and exits with an error if the input is invalid, even
+/// if the errors were recovered.
+fn write_json<R: Read>(
+    mut parser: BencodeParser<R>,
+    mut output: impl Write,
+    error_format: &ErrorFormat,
+) {
+    if let Err(e) = parser.write_bytes(&mut output) {
+        exit_with_error(&e, error_format);
+    }
+
+    // Errors recovered with `--on-error`
+    if let Some((last, others)) = parser.diagnostics().split_last() {
+        for diagnostic in others {
+            print_error(&diagnostic.error, error_format);
+        }
+        exit_with_error(&last.error, error_format);
+    }
+}
+
+/// It returns the torrent summary as text, one field per line followed by
+/// the files.
+fn torrent_info_text(torrent_info: &TorrentInfo) -> String {
+    let mut text = format!(
+        "name: {}\nsize: {}\npiece length: {}\npieces: {}\ninfo hash v1: {}\ninfo hash v2: {}\nfiles: {}\n",
+        torrent_info.name,
+        torrent_info.total_size,
+        torrent_info.piece_length,
+        torrent_info.piece_count,
+        hex::encode(torrent_info.info_hash.v1),
+        hex::encode(torrent_info.info_hash.v2),
+        torrent_info.files.len()
+    );
+
+    for file in &torrent_info.files {
+        text.push_str(&format!("  {} ({} bytes)\n", file.path, file.length));
+    }
+
+    text
+}
+
+/// It returns the torrent summary as a line of JSON.
+fn torrent_info_json(torrent_info: &TorrentInfo) -> String {
+    let files: Vec<serde_json::Value> = torrent_info
+        .files
+        .iter()
+        .map(|file| serde_json::json!({ "path": file.path, "length": file.length }))
+        .collect();
+
+    let summary = serde_json::json!({
+        "name": torrent_info.name,
+        "size": torrent_info.total_size,
+        "piece_length": torrent_info.piece_length,
+        "piece_count": torrent_info.piece_count,
+        "info_hash": {
+            "v1": hex::encode(torrent_info.info_hash.v1),
+            "v2": hex::encode(torrent_info.info_hash.v2),
+        },
+        "files": files,
+    });
+
+    format!("{summary}\n")
+}
+
+/// It writes the bencoded input in canonical form and prints the changes to
+/// stderr.
+fn canonicalize(matches: &ArgMatches) {
+    let error_format = error_format(matches);
+
+    let input = open_input(matches, &error_format);
+
+    let mut output = create_output(matches, &error_format);
+
+    let mut canonicalizer = with_limits(BencodeParserBuilder::default(), matches)
+        .capture_size(capture_size(matches))
+        .build_canonicalizer(input)
+        .duplicate_keys(duplicate_keys(matches));
+
+    if let Err(e) = canonicalizer.write_bytes(&mut output) {
+        exit_with_error(&e, &error_format);
+    }
+
+    for change in canonicalizer.changes() {
+        eprintln!("Changed: {change}");
+    }
+}
 
-    // Handle input stream (file or stdin)
-    let input: Box<dyn Read> = if let Some(input_path) = matches.get_one::<String>("input") {
+/// It opens the input file, or stdin if there is none.
+fn open_input(matches: &ArgMatches, error_format: &ErrorFormat) -> Box<dyn Read> {
+    if let Some(input_path) = matches.get_one::<String>("input") {
         match File::open(input_path) {
             Ok(file) => Box::new(file),
-            Err(e) => {
-                eprintln!("Error: {e}");
-                std::process::exit(1);
-            }
+            Err(e) => exit_with_error(&e.into(), error_format),
         }
     } else {
         Box::new(io::stdin())
-    };
+    }
+}
 
-    // Handle output stream (file or stdout)
-    let mut output: Box<dyn Write> = if let Some(output_path) = matches.get_one::<String>("output")
-    {
+/// It creates the output file, or uses stdout if there is none.
+fn create_output(matches: &ArgMatches, error_format: &ErrorFormat) -> Box<dyn Write> {
+    if let Some(output_path) = matches.get_one::<String>("output") {
         match File::create(output_path) {
             Ok(file) => Box::new(file),
-            Err(e) => {
-                eprintln!("Error: {e}");
-                std::process::exit(1);
-            }
+            Err(e) => exit_with_error(&e.into(), error_format),
         }
     } else {
         Box::new(io::stdout())
-    };
+    }
+}
 
-    if let Err(e) = BencodeParser::new(input).write_bytes(&mut output) {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+/// How errors are printed to stderr.
+enum ErrorFormat {
+    /// The error message with the reader and writer contexts.
+    Human,
+
+    /// A dump of the latest input bytes pointing at the offending byte.
+    Pretty(ErrorRenderer),
+
+    /// A line of JSON.
+    Json,
+}
+
+/// It returns the error format selected with the `--error-format` and
+/// `--color` flags.
+fn error_format(matches: &ArgMatches) -> ErrorFormat {
+    match matches
+        .get_one::<String>("error-format")
+        .map(String::as_str)
+    {
+        Some("json") => ErrorFormat::Json,
+        Some("pretty") => {
+            let color = match matches.get_one::<String>("color").map(String::as_str) {
+                Some("always") => true,
+                Some("never") => false,
+                _ => io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            };
+
+            ErrorFormat::Pretty(ErrorRenderer::default().color(color))
+        }
+        _ => ErrorFormat::Human,
+    }
+}
+
+/// It prints the error to stderr in the selected format.
+fn print_error(error: &Error, error_format: &ErrorFormat) {
+    match error_format {
+        ErrorFormat::Human => eprintln!("Error: {error}"),
+        ErrorFormat::Pretty(renderer) => eprint!("{}", renderer.render(error)),
+        ErrorFormat::Json => eprintln!("{}", error.to_json()),
+    }
+}
+
+/// It prints the error without the writer context, for commands that don't
+/// show the output.
+fn print_input_error(error: &Error, error_format: &ErrorFormat) {
+    match error_format {
+        ErrorFormat::Human => eprintln!("Error: {}", error.to_string_without_output()),
+        ErrorFormat::Pretty(renderer) => eprint!("{}", renderer.render(error)),
+        ErrorFormat::Json => eprintln!("{}", error.to_json_without_output()),
+    }
+}
+
+/// It prints the error and exits with the exit code for its kind.
+fn exit_with_error(error: &Error, error_format: &ErrorFormat) -> ! {
+    print_error(error, error_format);
+
+    std::process::exit(exit_code(error))
+}
+
+/// It returns the exit code for the kind of the error.
+fn exit_code(error: &Error) -> i32 {
+    match error.kind() {
+        ErrorKind::Io => EXIT_IO_ERROR,
+        ErrorKind::Syntax | ErrorKind::Limit => EXIT_INVALID_INPUT,
+    }
+}
+
+/// It builds the parser with the parsing options and limits passed as flags.
+fn parser_builder(matches: &ArgMatches) -> BencodeParserBuilder {
+    let parser_builder = BencodeParserBuilder::default()
+        .capture_size(capture_size(matches))
+        .skip_newlines(!matches.get_flag("no-skip-newlines"))
+        .strict(matches.get_flag("strict"))
+        .i64_integers(matches.get_flag("i64-integers"));
+
+    with_limits(parser_builder, matches)
+}
+
+/// It sets how the JSON is written.
+fn with_json_output(
+    parser_builder: BencodeParserBuilder,
+    matches: &ArgMatches,
+) -> BencodeParserBuilder {
+    parser_builder
+        .byte_string_encoding(byte_string_encoding(matches))
+        .torrent(matches.get_flag("torrent"))
+        .tracker_response(matches.get_flag("tracker-response"))
+        .sort_keys(matches.get_flag("sort-keys"))
+}
+
+/// It sets how the JSON is pretty-printed.
+fn with_indent(parser_builder: BencodeParserBuilder, matches: &ArgMatches) -> BencodeParserBuilder {
+    if let Some(indent) = matches.get_one::<usize>("indent") {
+        parser_builder.indent(*indent)
+    } else if matches.get_flag("pretty") {
+        parser_builder.indent(DEFAULT_INDENT)
+    } else {
+        parser_builder
+    }
+}
+
+/// It returns `true` if the JSON is pretty-printed.
+fn is_pretty(matches: &ArgMatches) -> bool {
+    matches.get_flag("pretty") || matches.contains_id("indent")
+}
+
+/// It returns `true` if the top-level values are written one per line,
+/// which can't be pretty-printed.
+fn writes_one_value_per_line(matches: &ArgMatches) -> bool {
+    match multiple_values(matches) {
+        MultipleValues::Ndjson => true,
+        MultipleValues::Concatenate => error_recovery(matches) != ErrorRecovery::Fail,
+        MultipleValues::Array | MultipleValues::Reject => false,
+    }
+}
+
+/// It sets how long strings are written without keeping them in memory.
+fn with_streaming(
+    mut parser_builder: BencodeParserBuilder,
+    matches: &ArgMatches,
+) -> BencodeParserBuilder {
+    parser_builder = parser_builder.streamed_invalid_utf8(streamed_invalid_utf8(matches));
+
+    if let Some(string_buffer_size) = matches.get_one::<usize>("string-buffer-size") {
+        parser_builder = parser_builder.string_buffer_size(*string_buffer_size);
+    }
+
+    parser_builder
+}
+
+/// It selects the paths passed in the given argument.
+fn with_selected_paths(
+    mut parser_builder: BencodeParserBuilder,
+    matches: &ArgMatches,
+    id: &str,
+) -> BencodeParserBuilder {
+    for query in matches.get_many::<Query>(id).into_iter().flatten() {
+        parser_builder = parser_builder.select(query.clone());
+    }
+
+    parser_builder
+}
+
+/// It returns the number of latest bytes shown in error messages.
+fn capture_size(matches: &ArgMatches) -> usize {
+    *matches
+        .get_one::<usize>("capture-size")
+        .expect("capture size has a default value")
+}
+
+/// It sets the limits passed as flags.
+fn with_limits(
+    mut parser_builder: BencodeParserBuilder,
+    matches: &ArgMatches,
+) -> BencodeParserBuilder {
+    if let Some(max_depth) = matches.get_one::<usize>("max-depth") {
+        parser_builder = parser_builder.max_depth(*max_depth);
+    }
+
+    if let Some(max_string_length) = matches.get_one::<usize>("max-string-length") {
+        parser_builder = parser_builder.max_string_length(*max_string_length);
+    }
+
+    if let Some(max_container_items) = matches.get_one::<usize>("max-container-items") {
+        parser_builder = parser_builder.max_container_items(*max_container_items);
+    }
+
+    if let Some(max_input_bytes) = matches.get_one::<u64>("max-input-bytes") {
+        parser_builder = parser_builder.max_input_bytes(*max_input_bytes);
+    }
+
+    parser_builder
+}
+
+/// It returns how non UTF-8 strings are written to JSON.
+fn byte_string_encoding(matches: &ArgMatches) -> ByteStringEncoding {
+    match matches
+        .get_one::<String>("byte-strings")
+        .map(String::as_str)
+    {
+        Some("base64") => ByteStringEncoding::Base64,
+        Some("lossy") => ByteStringEncoding::Lossy,
+        Some("escaped") => ByteStringEncoding::Escaped,
+        Some("tagged") => ByteStringEncoding::Tagged,
+        Some("array") => ByteStringEncoding::Array,
+        _ => ByteStringEncoding::Hex,
     }
 }
+
+/// It returns how invalid UTF-8 is written in the middle of streamed strings.
+fn streamed_invalid_utf8(matches: &ArgMatches) -> StreamedInvalidUtf8 {
+    match matches
+        .get_one::<String>("streamed-invalid-utf8")
+        .map(String::as_str)
+    {
+        Some("switch") => StreamedInvalidUtf8::SwitchEncoding,
+        Some("lossy") => StreamedInvalidUtf8::Lossy,
+        _ => StreamedInvalidUtf8::Error,
+    }
+}
+
+/// It returns what to do with the JSON written so far when the input is
+/// invalid.
+fn error_recovery(matches: &ArgMatches) -> ErrorRecovery {
+    match matches.get_one::<String>("on-error").map(String::as_str) {
+        Some("close") => ErrorRecovery::Close,
+        Some("resync") => ErrorRecovery::Resynchronize,
+        _ => ErrorRecovery::Fail,
+    }
+}
+
+/// It returns what to do with duplicate dictionary keys when writing
+/// canonical bencode.
+fn duplicate_keys(matches: &ArgMatches) -> DuplicateKeys {
+    match matches
+        .get_one::<String>("duplicate-keys")
+        .map(String::as_str)
+    {
+        Some("first") => DuplicateKeys::First,
+        Some("reject") => DuplicateKeys::Reject,
+        _ => DuplicateKeys::Last,
+    }
+}
+
+/// It returns how several top-level values are written to JSON.
+fn multiple_values(matches: &ArgMatches) -> MultipleValues {
+    match matches
+        .get_one::<String>("multiple-values")
+        .map(String::as_str)
+    {
+        Some("ndjson") => MultipleValues::Ndjson,
+        Some("array") => MultipleValues::Array,
+        Some("reject") => MultipleValues::Reject,
+        _ => MultipleValues::Concatenate,
+    }
+}
+
+/// It defines the command line arguments.
+fn cli() -> Command {
+    Command::new("torrust-bencode2json")
+        .version("0.1.0")
+        .author("Torrust Organization")
+        .about("Converts between Bencode and JSON, and inspects bencoded data. Without a subcommand, it converts Bencode to JSON")
+        .args_conflicts_with_subcommands(true)
+        .subcommand(decode_command())
+        .subcommand(encode_command())
+        .subcommand(validate_command())
+        .subcommand(info_command())
+        .subcommand(query_command())
+        .subcommand(canonicalize_command())
+        .args(decode_args())
+}
+
+/// It defines the command converting Bencode to JSON.
+fn decode_command() -> Command {
+    Command::new("decode")
+        .about("Converts Bencode to JSON")
+        .args(decode_args())
+}
+
+/// It defines the command converting JSON to Bencode.
+fn encode_command() -> Command {
+    Command::new("encode")
+        .about("Converts JSON to Bencode. The output is written while the input is read, so invalid JSON leaves it unfinished")
+        .args(io_args())
+        .arg(byte_strings_arg().help(
+            "How non UTF-8 strings are written in the JSON input, as when converting Bencode to JSON",
+        ))
+}
+
+/// It defines the command checking Bencode.
+fn validate_command() -> Command {
+    Command::new("validate")
+        .about("Checks that the input is valid Bencode. Nothing is written, the result is the exit status and the errors printed to stderr")
+        .arg(input_arg())
+        .args(parsing_args())
+        .arg(multiple_values_arg())
+        .args(error_args())
+        .arg(on_error_arg().value_parser(["fail", "resync"]).help(
+            "What to do with invalid input: stop, or report every error resuming at the next value with `resync`",
+        ))
+        .args(limit_args())
+}
+
+/// It defines the command summarizing torrent files.
+fn info_command() -> Command {
+    Command::new("info")
+        .about("Writes a summary of a torrent file: name, size, piece length, piece count, info hashes and files")
+        .args(io_args())
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Write the summary as JSON"),
+        )
+        .args(error_args())
+        .args(limit_args())
+        .mut_arg("max-depth", |arg| arg.default_value(DEFAULT_MAX_DEPTH))
+}
+
+/// It defines the command extracting values.
+fn query_command() -> Command {
+    Command::new("query")
+        .about("Writes the values at the given paths as JSON, one per line")
+        .arg(
+            Arg::new("path")
+                .value_name("PATH")
+                .required(true)
+                .num_args(1..)
+                .value_parser(|query: &str| query.parse::<Query>())
+                .help(r#"Paths of the values. They have dotted keys, list indexes and wildcards, for example: info.files[*].length. Keys that are empty or contain "." or "[" are quoted in brackets, escaping quotes and backslashes with a backslash, for example: info["a.b"]"#),
+        )
+        .args(io_args())
+        .args(parsing_args())
+        .args(json_args())
+        .args(error_args())
+        .args(limit_args())
+}
+
+/// It defines the arguments of the command converting Bencode to JSON, also
+/// accepted without a subcommand.
+fn decode_args() -> Vec<Arg> {
+    io_args()
+        .into_iter()
+        .chain(parsing_args())
+        .chain([multiple_values_arg()])
+        .chain(json_args())
+        .chain(pretty_args())
+        .chain([Arg::new("select")
+            .long("select")
+            .value_name("PATH")
+            .action(ArgAction::Append)
+            .value_parser(|query: &str| query.parse::<Query>())
+            .conflicts_with("multiple-values")
+            .help(r#"Only write the selected values, one per line. Paths have dotted keys, list indexes and wildcards, for example: info.files[*].length. Keys that are empty or contain "." or "[" are quoted in brackets, for example: info["a.b"]"#)])
+        .chain(error_args())
+        .chain([on_error_arg()])
+        .chain(streaming_args())
+        .chain(limit_args())
+        .collect()
+}
+
+/// It defines the command writing bencode in canonical form.
+fn canonicalize_command() -> Command {
+    Command::new("canonicalize")
+        .about("Writes Bencode in canonical form: sorted and unique dictionary keys, no negative zero, no leading zeros in string lengths and no line breaks. The changes are printed to stderr")
+        .args(io_args())
+        .arg(
+            Arg::new("duplicate-keys")
+                .long("duplicate-keys")
+                .value_parser(["first", "last", "reject"])
+                .default_value("last")
+                .help("What to do with duplicate dictionary keys: keep the first or the last value, or fail"),
+        )
+        .args(error_args())
+        .args(limit_args())
+        .mut_arg("max-depth", |arg| arg.default_value(DEFAULT_MAX_DEPTH))
+}
+
+/// It defines the input and output files.
+fn io_args() -> [Arg; 2] {
+    [
+        input_arg(),
+        Arg::new("output")
+            .short('o')
+            .long("output")
+            .default_value(None)
+            .help("Optional output file (defaults to stdout)"),
+    ]
+}
+
+/// It defines the input file.
+fn input_arg() -> Arg {
+    Arg::new("input")
+        .short('i')
+        .long("input")
+        .default_value(None)
+        .help("Optional input file (defaults to stdin)")
+}
+
+/// It defines how the bencoded input is parsed.
+fn parsing_args() -> [Arg; 3] {
+    [
+        Arg::new("no-skip-newlines")
+            .long("no-skip-newlines")
+            .action(ArgAction::SetTrue)
+            .help("Reject line breaks between values instead of ignoring them"),
+        Arg::new("strict")
+            .long("strict")
+            .action(ArgAction::SetTrue)
+            .help("Only accept canonical bencode"),
+        Arg::new("i64-integers")
+            .long("i64-integers")
+            .action(ArgAction::SetTrue)
+            .help("Reject integers that do not fit into a 64-bit signed integer"),
+    ]
+}
+
+/// It defines how several top-level values are written.
+fn multiple_values_arg() -> Arg {
+    Arg::new("multiple-values")
+        .long("multiple-values")
+        .value_parser(["concatenate", "ndjson", "array", "reject"])
+        .default_value("concatenate")
+        .help("How to write several top-level values: concatenated, one JSON document per line, as a JSON array, or rejecting trailing data")
+}
+
+/// It defines how the JSON is written.
+fn json_args() -> [Arg; 4] {
+    [
+        byte_strings_arg(),
+        Arg::new("torrent")
+            .long("torrent")
+            .action(ArgAction::SetTrue)
+            .help("Render torrent files: write the pieces and other binary fields as hashes and add the info hash"),
+        Arg::new("tracker-response")
+            .long("tracker-response")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("torrent")
+            .help("Render tracker announce and scrape responses: decode compact peer lists and write info hashes as hex"),
+        Arg::new("sort-keys")
+            .long("sort-keys")
+            .action(ArgAction::SetTrue)
+            .help("Write dictionary fields sorted by their raw keys. Each dictionary, with the ones nested in it, is kept in memory until it ends, unless --strict is used"),
+    ]
+}
+
+/// It defines how the JSON is pretty-printed. Values written one per line
+/// can't be pretty-printed.
+fn pretty_args() -> [Arg; 2] {
+    [
+        Arg::new("pretty")
+            .long("pretty")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("select")
+            .help("Pretty-print the JSON, indented with 2 spaces. It can't be used when the values are written one per line"),
+        Arg::new("indent")
+            .long("indent")
+            .value_name("N")
+            .value_parser(value_parser!(usize))
+            .conflicts_with("select")
+            .help("Pretty-print the JSON, indented with N spaces. It can't be used when the values are written one per line"),
+    ]
+}
+
+/// It defines how non UTF-8 strings are written to JSON.
+fn byte_strings_arg() -> Arg {
+    Arg::new("byte-strings")
+        .long("byte-strings")
+        .value_parser(["hex", "base64", "lossy", "escaped", "tagged", "array"])
+        .default_value("hex")
+        .help("How to write non UTF-8 strings. Only `escaped` and `tagged` are unambiguous")
+}
+
+/// It defines what to do with invalid input.
+fn on_error_arg() -> Arg {
+    Arg::new("on-error")
+        .long("on-error")
+        .value_parser(["fail", "close", "resync"])
+        .default_value("fail")
+        .help("What to do with invalid input: stop, close the JSON with a \"<corrupt>\" marker, or also resume at the next value, unless --multiple-values is reject. Concatenated values are written one per line. Recovered errors are printed and the exit code is still an error")
+}
+
+/// It defines how errors are reported.
+fn error_args() -> [Arg; 3] {
+    [
+        Arg::new("capture-size")
+            .long("capture-size")
+            .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+            .default_value("1024")
+            .help("Number of latest input and output bytes shown in error messages"),
+        Arg::new("error-format")
+            .long("error-format")
+            .value_parser(["human", "pretty", "json"])
+            .default_value("human")
+            .help("How errors are printed to stderr: as text, as a hex dump of the latest input bytes pointing at the offending byte, or as one JSON object per line with the error code, kind, positions, path and latest bytes"),
+        Arg::new("color")
+            .long("color")
+            .value_parser(["auto", "always", "never"])
+            .default_value("auto")
+            .help("Highlight pretty errors with colors: when stderr is a terminal and NO_COLOR is not set, always, or never"),
+    ]
+}
+
+/// It defines how long strings are written without keeping them in memory.
+fn streaming_args() -> [Arg; 2] {
+    [
+        Arg::new("string-buffer-size")
+            .long("string-buffer-size")
+            .value_name("N")
+            .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+            .conflicts_with("sort-keys")
+            .help("Write strings longer than N bytes in chunks of N bytes, without keeping them in memory. It can't be combined with --sort-keys, which keeps the dictionaries in memory"),
+        Arg::new("streamed-invalid-utf8")
+            .long("streamed-invalid-utf8")
+            .value_parser(["error", "lossy", "switch"])
+            .default_value("error")
+            .help("How to write invalid UTF-8 found after the text already written for a streamed string: fail, replace it with U+FFFD, or switch to the hex or base64 encoding (the JSON can't be converted back)"),
+    ]
+}
+
+/// It defines the limits for untrusted input.
+fn limit_args() -> [Arg; 4] {
+    [
+        Arg::new("max-depth")
+            .long("max-depth")
+            .value_parser(value_parser!(usize))
+            .help("Maximum number of nested lists and dictionaries"),
+        Arg::new("max-string-length")
+            .long("max-string-length")
+            .value_parser(value_parser!(usize))
+            .help("Maximum length of a single string"),
+        Arg::new("max-container-items")
+            .long("max-container-items")
+            .value_parser(value_parser!(usize))
+            .help("Maximum number of items in a list, or fields in a dictionary"),
+        Arg::new("max-input-bytes")
+            .long("max-input-bytes")
+            .value_parser(value_parser!(u64))
+            .help("Maximum number of bytes read from the input"),
+    ]
+}
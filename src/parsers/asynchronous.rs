@@ -0,0 +1,260 @@
+//! Async parser for inputs implementing `tokio`'s `AsyncRead`.
+//!
+//! It's only available with the `tokio` cargo feature.
+//!
+//! It feeds the bytes read from the input to a [`PushParser`], which runs the
+//! same parser as the [`BencodeParser`](super::BencodeParser) for every
+//! complete token. The JSON written for each chunk of input is sent to the
+//! `AsyncWrite` output. The output and the errors are the same as the ones of
+//! the sync parser.
+//!
+//! Strings are kept in memory until they are complete, even when
+//! [`string_buffer_size`](super::options::ParserOptions::string_buffer_size)
+//! is set. Use the `max_string_length` limit for untrusted input.
+//!
+//! ```rust
+//! use torrust_bencode2json::parsers::asynchronous::AsyncBencodeParser;
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mut output = Vec::new();
+//!
+//! AsyncBencodeParser::new(&b"d3:foold3:bari42eeee"[..])
+//!     .write_bytes(&mut output)
+//!     .await
+//!     .unwrap();
+//!
+//! assert_eq!(output, br#"{"foo":[{"bar":42}]}"#);
+//! # });
+//! ```
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{
+    capture::CapturedValue,
+    error::Error,
+    options::ParserOptions,
+    push::{PushParser, Status},
+    recovery::Diagnostic,
+};
+use crate::rw;
+
+/// Number of bytes read from the input at once.
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+pub struct AsyncBencodeParser<R: AsyncRead + Unpin> {
+    reader: R,
+    parser: PushParser,
+    read_buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBencodeParser<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// It creates a parser with custom options. See also the
+    /// [`BencodeParserBuilder`](super::options::BencodeParserBuilder).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader,
+            parser: PushParser::with_options(options),
+            read_buffer: vec![0; READ_BUFFER_SIZE],
+        }
+    }
+
+    /// It parses a bencoded value read from input and writes the corresponding
+    /// JSON UTF-8 string value as bytes to the output.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input or write to the output.
+    /// - The input is invalid Bencode.
+    pub async fn write_bytes<W: AsyncWrite + Unpin>(&mut self, mut writer: W) -> Result<(), Error> {
+        loop {
+            let num_bytes = self.reader.read(&mut self.read_buffer).await?;
+
+            if num_bytes == 0 {
+                break;
+            }
+
+            let status = self.parser.feed(&self.read_buffer[..num_bytes]);
+
+            // The JSON written before an error is also sent
+            self.send(&mut writer).await?;
+
+            if status? == Status::Done {
+                break;
+            }
+        }
+
+        let result = self.parser.finish();
+
+        self.send(&mut writer).await?;
+
+        writer.flush().await.map_err(rw::error::Error::from)?;
+
+        result
+    }
+
+    /// It returns the values selected by the captured paths. See
+    /// [`BencodeParser::captured_values`](super::BencodeParser::captured_values).
+    pub fn captured_values(&self) -> &[CapturedValue] {
+        self.parser.captured_values()
+    }
+
+    /// It returns the errors recovered while writing the JSON. See
+    /// [`BencodeParser::diagnostics`](super::BencodeParser::diagnostics).
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.parser.diagnostics()
+    }
+
+    /// It sends the JSON written so far to the async output.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if it can't write to the output.
+    async fn send<W: AsyncWrite + Unpin>(&mut self, writer: &mut W) -> Result<(), Error> {
+        writer
+            .write_all(&self.parser.take_json())
+            .await
+            .map_err(rw::error::Error::from)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    use crate::{
+        parsers::{
+            asynchronous::AsyncBencodeParser,
+            options::{BencodeParserBuilder, ParserOptions},
+        },
+        test::{sync_json, PARSER_INPUTS},
+    };
+
+    /// An input returning one byte on each read, so tokens are split
+    /// between reads.
+    struct OneByteAtATime<'a>(&'a [u8]);
+
+    impl AsyncRead for OneByteAtATime<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if let Some((byte, rest)) = self.0.split_first() {
+                buf.put_slice(&[*byte]);
+                self.0 = rest;
+            }
+
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// It returns the JSON and the error message, if any.
+    fn async_json<R: AsyncRead + Unpin>(
+        input: R,
+        options: &ParserOptions,
+    ) -> (String, Option<String>) {
+        let mut output = Vec::new();
+
+        let result = block_on(
+            AsyncBencodeParser::with_options(input, options.clone()).write_bytes(&mut output),
+        );
+
+        (
+            String::from_utf8(output).unwrap(),
+            result.err().map(|err| err.to_string()),
+        )
+    }
+
+    /// It checks the async parser writes the same JSON and errors as the
+    /// sync one, reading the whole input at once or byte by byte.
+    fn assert_same_as_sync(input: &[u8], options: &ParserOptions) {
+        let expected = sync_json(input, options);
+
+        assert_eq!(async_json(input, options), expected, "input: {input:?}");
+        assert_eq!(
+            async_json(OneByteAtATime(input), options),
+            expected,
+            "input: {input:?}"
+        );
+    }
+
+    #[test]
+    fn it_should_write_the_same_json_and_errors_as_the_sync_parser() {
+        for input in PARSER_INPUTS {
+            assert_same_as_sync(input, &ParserOptions::default());
+        }
+    }
+
+    #[test]
+    fn it_should_write_the_sample_fixture_like_the_sync_parser() {
+        let input = include_bytes!("../../tests/fixtures/sample.bencode");
+
+        assert_same_as_sync(input, &ParserOptions::default());
+        assert_same_as_sync(input, BencodeParserBuilder::default().indent(2).options());
+    }
+
+    #[test]
+    fn it_should_apply_the_parser_options_like_the_sync_parser() {
+        use crate::parsers::options::{ErrorRecovery, MultipleValues};
+
+        let options = [
+            BencodeParserBuilder::default().strict(true),
+            BencodeParserBuilder::default().torrent(true),
+            BencodeParserBuilder::default().multiple_values(MultipleValues::Reject),
+            BencodeParserBuilder::default().multiple_values(MultipleValues::Array),
+            BencodeParserBuilder::default().string_buffer_size(2),
+            BencodeParserBuilder::default().max_string_length(3),
+            BencodeParserBuilder::default().max_input_bytes(5),
+            BencodeParserBuilder::default().max_depth(1),
+            BencodeParserBuilder::default().error_recovery(ErrorRecovery::Close),
+            BencodeParserBuilder::default().error_recovery(ErrorRecovery::Resynchronize),
+            BencodeParserBuilder::default().select("foo".parse().unwrap()),
+        ];
+
+        for builder in options {
+            for input in PARSER_INPUTS {
+                assert_same_as_sync(input, builder.options());
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_keep_the_recovered_errors() {
+        use crate::parsers::options::ErrorRecovery;
+
+        let mut output = Vec::new();
+
+        let mut parser = BencodeParserBuilder::default()
+            .error_recovery(ErrorRecovery::Resynchronize)
+            .build_async(OneByteAtATime(b"i1ex:i2e"));
+
+        block_on(parser.write_bytes(&mut output)).unwrap();
+
+        assert_eq!(output, b"1\n2\n");
+        assert_eq!(parser.diagnostics().len(), 1);
+    }
+}
@@ -0,0 +1,1039 @@
+//! Zero-copy parser for bencoded values already in memory.
+//!
+//! It builds a [`BorrowedValue`] whose strings and dictionary keys are slices of
+//! the input buffer, so strings are never copied.
+//!
+//! It applies the same validation rules and limits as the
+//! [`BencodeParser`](super::BencodeParser), using the same stack and integer
+//! and string length state machines, and it returns the same errors, with the
+//! same paths.
+use std::cmp::Ordering;
+
+use super::{
+    error::{Error, ReadContext, WriteContext},
+    integer,
+    options::ParserOptions,
+    path::{PathSegment, ValuePath},
+    stack::{Stack, State},
+    string, BencodeType, BENCODE_BEGIN_DICT, BENCODE_BEGIN_INTEGER, BENCODE_BEGIN_LIST,
+    BENCODE_END_LIST_OR_DICT,
+};
+use crate::value::{borrowed::BorrowedValue, builder::Builder, DEFAULT_MAX_DEPTH};
+
+pub struct BorrowedParser<'a> {
+    /// The input, cut at the maximum number of input bytes.
+    input: &'a [u8],
+
+    /// The input is longer than the maximum number of input bytes.
+    input_limit_exceeded: bool,
+
+    /// Number of bytes read from the input.
+    pos: usize,
+
+    stack: Stack,
+
+    /// The open lists and dictionaries, from the outermost to the innermost.
+    containers: Vec<Container<'a>>,
+
+    options: ParserOptions,
+}
+
+/// An open list or dictionary.
+#[derive(Debug, Default)]
+struct Container<'a> {
+    /// `true` for dictionaries.
+    is_dict: bool,
+
+    /// Number of list items or dictionary fields.
+    items: usize,
+
+    /// The latest dictionary key. It's `None` while the next key is parsed.
+    last_key: Option<&'a [u8]>,
+}
+
+impl Container<'_> {
+    /// It returns the path segment of the current item, if there is one.
+    /// When `next_item` is `true`, it's the segment of the next list item,
+    /// which has not begun yet.
+    fn current_segment(&self, next_item: bool) -> Option<PathSegment> {
+        if self.is_dict {
+            self.last_key.map(|key| PathSegment::Key(key.to_vec()))
+        } else if next_item {
+            Some(PathSegment::Index(self.items))
+        } else {
+            self.items.checked_sub(1).map(PathSegment::Index)
+        }
+    }
+}
+
+impl<'a> BorrowedParser<'a> {
+    /// It creates a lenient parser limiting the nesting depth to
+    /// [`DEFAULT_MAX_DEPTH`].
+    #[must_use]
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    /// It creates a lenient parser limiting the nesting depth to the given
+    /// number of lists and dictionaries.
+    #[must_use]
+    pub fn with_max_depth(input: &'a [u8], max_depth: usize) -> Self {
+        Self::with_options(
+            input,
+            ParserOptions {
+                max_depth: Some(max_depth),
+                ..ParserOptions::default()
+            },
+        )
+    }
+
+    /// It creates a parser with the strict mode, the line breaks handling,
+    /// the limits and the capture size of the options. The nesting depth is
+    /// limited to [`DEFAULT_MAX_DEPTH`] unless the options set another limit.
+    /// The other options only apply to the JSON output, so they are ignored.
+    #[must_use]
+    pub fn with_options(input: &'a [u8], options: ParserOptions) -> Self {
+        let max_input_len = options
+            .max_input_bytes
+            .map_or(usize::MAX, |max_input_bytes| {
+                usize::try_from(max_input_bytes).unwrap_or(usize::MAX)
+            });
+
+        BorrowedParser {
+            input: &input[..input.len().min(max_input_len)],
+            input_limit_exceeded: input.len() > max_input_len,
+            pos: 0,
+            stack: Stack::default(),
+            containers: vec![],
+            options,
+        }
+    }
+
+    /// It parses the next bencoded value in the input. It returns `None` if
+    /// the input has ended.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - The input is invalid Bencode.
+    /// - The input exceeds one of the limits.
+    /// - An integer does not fit into an `i64`.
+    pub fn read_value(&mut self) -> Result<Option<BorrowedValue<'a>>, Error> {
+        let mut builder = Builder::<BorrowedValue<'a>>::default();
+
+        loop {
+            let depth = self.containers.len();
+            let items = self.containers.last().map(|container| container.items);
+            let state = self.stack.peek();
+
+            let value = self.read_token(&mut builder).map_err(|err| {
+                // Errors before a new list item begins belong to that item
+                let next_item = self.containers.len() == depth
+                    && self.containers.last().map(|container| container.items) == items;
+
+                err.with_path(self.value_path(next_item)).with_state(state)
+            })?;
+
+            match value {
+                Token::Value(value) => {
+                    if let Some(top_level_value) = builder.add(value) {
+                        return Ok(Some(top_level_value));
+                    }
+                }
+                Token::Other => {}
+                Token::EndOfInput => return Ok(None),
+            }
+        }
+    }
+
+    /// It parses the next token of the input. It returns the value it
+    /// completes, if any.
+    fn read_token(&mut self, builder: &mut Builder<BorrowedValue<'a>>) -> Result<Token<'a>, Error> {
+        let Some(&byte) = self.input.get(self.pos) else {
+            if self.input_limit_exceeded {
+                return Err(self.max_input_bytes_exceeded());
+            }
+
+            self.stack
+                .end_of_input()
+                .map_err(|err| err.into_error(self.read_context(None), write_context()))?;
+
+            return Ok(Token::EndOfInput);
+        };
+
+        let token = match byte {
+            BENCODE_BEGIN_INTEGER => {
+                self.begin_value(BencodeType::Integer)?;
+                Token::Value(BorrowedValue::Integer(self.read_integer()?))
+            }
+            b'0'..=b'9' => {
+                let previous_state = self.begin_value(BencodeType::String)?;
+
+                match previous_state {
+                    State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd => {
+                        let key = self.read_dict_key()?;
+                        builder.set_dict_field_key(key);
+                        Token::Other
+                    }
+                    _ => Token::Value(BorrowedValue::Bytes(self.read_string()?)),
+                }
+            }
+            BENCODE_BEGIN_LIST => {
+                self.pos += 1;
+                self.begin_value(BencodeType::List)?;
+                self.begin_container(State::ExpectingFirstListItemOrEnd)?;
+                builder.begin_list();
+                Token::Other
+            }
+            BENCODE_BEGIN_DICT => {
+                self.pos += 1;
+                self.begin_value(BencodeType::Dict)?;
+                self.begin_container(State::ExpectingFirstDictFieldOrEnd)?;
+                builder.begin_dict();
+                Token::Other
+            }
+            BENCODE_END_LIST_OR_DICT => {
+                self.pos += 1;
+                self.stack
+                    .end_list_or_dict()
+                    .map_err(|err| err.into_error(self.read_context(None), write_context()))?;
+                self.containers.pop();
+                Token::Value(builder.end_list_or_dict())
+            }
+            b'\n' if self.options.strict => {
+                return Err(Error::LineBreakNotAllowed(
+                    self.read_context_at_next_byte(byte),
+                    write_context(),
+                ));
+            }
+            b'\n' if self.options.skip_newlines => {
+                // Ignore line breaks at the beginning, the end, or between values
+                self.pos += 1;
+                Token::Other
+            }
+            _ => {
+                return Err(Error::UnrecognizedFirstBencodeValueByte(
+                    self.read_context_at_next_byte(byte),
+                    write_context(),
+                ));
+            }
+        };
+
+        Ok(token)
+    }
+
+    /// It checks that there are no more bencoded values in the input, apart
+    /// from the ignored line breaks.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if there is more data in the input.
+    pub fn expect_end_of_input(&mut self) -> Result<(), Error> {
+        while let Some(&byte) = self.input.get(self.pos) {
+            let error = match byte {
+                b'\n' if self.options.strict => Error::LineBreakNotAllowed,
+                b'\n' if self.options.skip_newlines => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => Error::TrailingData,
+            };
+
+            return Err(error(self.read_context_at_next_byte(byte), write_context()));
+        }
+
+        if self.input_limit_exceeded {
+            return Err(self.max_input_bytes_exceeded());
+        }
+
+        Ok(())
+    }
+
+    /// It returns the path of the current value. When `next_item` is `true`,
+    /// the path points to the next item of the innermost list instead.
+    fn value_path(&self, next_item: bool) -> ValuePath {
+        let innermost = self.containers.len().saturating_sub(1);
+
+        ValuePath::new(
+            self.containers
+                .iter()
+                .enumerate()
+                .filter_map(|(depth, container)| {
+                    container.current_segment(next_item && depth == innermost)
+                })
+                .collect(),
+        )
+    }
+
+    /// It updates the stack state when the first byte of a new bencoded value
+    /// is found, and counts the value as a new item of the current list or
+    /// dictionary. It returns the previous state.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the new value is not allowed in the current
+    /// state, or the current list or dictionary has too many items.
+    fn begin_value(&mut self, bencode_type: BencodeType) -> Result<State, Error> {
+        let previous_state = self
+            .stack
+            .begin_value(bencode_type)
+            .map_err(|err| err.into_error(self.read_context(None), write_context()))?;
+
+        let is_new_item = match previous_state {
+            State::Initial | State::ExpectingDictFieldValue => false,
+            State::ExpectingFirstListItemOrEnd
+            | State::ExpectingNextListItem
+            | State::ExpectingFirstDictFieldOrEnd
+            | State::ExpectingDictFieldKeyOrEnd => true,
+        };
+
+        if is_new_item {
+            let container = self.current_container();
+
+            container.items += 1;
+
+            let items = container.items;
+
+            if self
+                .options
+                .max_container_items
+                .is_some_and(|max_container_items| items > max_container_items)
+            {
+                return Err(Error::MaxContainerItemsExceeded(
+                    self.read_context(None),
+                    write_context(),
+                ));
+            }
+        }
+
+        Ok(previous_state)
+    }
+
+    /// It opens a new list or dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the maximum nesting depth is exceeded.
+    fn begin_container(&mut self, state: State) -> Result<(), Error> {
+        let max_depth = self.options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+
+        if self.containers.len() >= max_depth {
+            return Err(Error::MaxDepthExceeded(
+                self.read_context(None),
+                write_context(),
+            ));
+        }
+
+        self.containers.push(Container {
+            is_dict: state == State::ExpectingFirstDictFieldOrEnd,
+            ..Container::default()
+        });
+        self.stack.push(state);
+
+        Ok(())
+    }
+
+    /// It returns the innermost open list or dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is no open list or dictionary.
+    fn current_container(&mut self) -> &mut Container<'a> {
+        self.containers
+            .last_mut()
+            .expect("items are only parsed inside lists or dictionaries")
+    }
+
+    /// It parses the new key of the current dictionary. In strict mode, it
+    /// checks that the key is greater than the previous one, so keys are
+    /// sorted and unique.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the key is invalid, or it is not greater than
+    /// the previous key in strict mode.
+    fn read_dict_key(&mut self) -> Result<&'a [u8], Error> {
+        let previous_key = self.current_container().last_key.take();
+
+        let key = self.read_string()?;
+
+        self.current_container().last_key = Some(key);
+
+        if !self.options.strict {
+            return Ok(key);
+        }
+
+        let error = match previous_key.map(|previous_key| key.cmp(previous_key)) {
+            None | Some(Ordering::Greater) => return Ok(key),
+            Some(Ordering::Less) => Error::UnsortedDictKeys,
+            Some(Ordering::Equal) => Error::DuplicateDictKey,
+        };
+
+        Err(error(self.read_context(None), write_context()))
+    }
+
+    /// It parses a bencoded integer and converts it into an `i64`.
+    fn read_integer(&mut self) -> Result<i64, Error> {
+        let mut state_machine = integer::StateMachine::default();
+
+        let digits_start = self.pos + 1; // Skip the 'i' byte
+
+        loop {
+            let Some(&byte) = self.input.get(self.pos) else {
+                return Err(self.end_of_input_error(Error::UnexpectedEndOfInputParsingInteger));
+            };
+
+            self.pos += 1;
+
+            match state_machine.feed(byte) {
+                Ok(integer::Step::Begin | integer::Step::Digit) => {}
+                Ok(integer::Step::End) => {
+                    if self.options.strict {
+                        self.check_canonical_integer(&state_machine, byte)?;
+                    }
+
+                    break;
+                }
+                Err(invalid_byte) => {
+                    return Err(
+                        invalid_byte.into_error(self.read_context(Some(byte)), write_context())
+                    );
+                }
+            }
+        }
+
+        let digits = &self.input[digits_start..self.pos - 1]; // Skip the 'e' byte
+
+        std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| digits.parse::<i64>().ok())
+            .ok_or_else(|| Error::IntegerOverflow(self.read_context(None), write_context()))
+    }
+
+    /// It checks that an integer is canonical: no negative zero and no
+    /// leading zeros.
+    fn check_canonical_integer(
+        &self,
+        state_machine: &integer::StateMachine,
+        byte: u8,
+    ) -> Result<(), Error> {
+        let error = if state_machine.is_negative_zero() {
+            Error::NegativeZeroNotAllowed
+        } else if state_machine.has_leading_zeros() {
+            Error::LeadingZerosInIntegersNotAllowed
+        } else {
+            return Ok(());
+        };
+
+        Err(error(self.read_context(Some(byte)), write_context()))
+    }
+
+    /// It parses a bencoded string and returns the slice of the input
+    /// containing its value.
+    fn read_string(&mut self) -> Result<&'a [u8], Error> {
+        let mut length = string::Length::default();
+
+        let length = loop {
+            let Some(&byte) = self.input.get(self.pos) else {
+                return Err(self.end_of_input_error(Error::UnexpectedEndOfInputParsingStringLength));
+            };
+
+            self.pos += 1;
+
+            let error = match length.feed(byte) {
+                Ok(Some(length)) => break length,
+                Ok(None) if self.options.strict && length.has_leading_zeros() => {
+                    Error::LeadingZerosInStringLengthNotAllowed
+                }
+                // Checked while parsing the length, like the streaming parser
+                Ok(None) if length.exceeds_max_string_length(&self.options) => {
+                    Error::MaxStringLengthExceeded
+                }
+                Ok(None) => continue,
+                Err(invalid_byte) => {
+                    return Err(
+                        invalid_byte.into_error(self.read_context(Some(byte)), write_context())
+                    );
+                }
+            };
+
+            return Err(error(self.read_context(Some(byte)), write_context()));
+        };
+
+        let value_start = self.pos;
+
+        match value_start.checked_add(length) {
+            Some(value_end) if value_end <= self.input.len() => {
+                self.pos = value_end;
+                Ok(&self.input[value_start..value_end])
+            }
+            _ => {
+                self.pos = self.input.len();
+                Err(self.end_of_input_error(Error::UnexpectedEndOfInputParsingStringValue))
+            }
+        }
+    }
+
+    /// It returns the error for the end of the input, or for reaching the
+    /// maximum number of input bytes if the input is longer.
+    fn end_of_input_error(&self, error: fn(ReadContext, WriteContext) -> Error) -> Error {
+        if self.input_limit_exceeded {
+            return self.max_input_bytes_exceeded();
+        }
+
+        error(self.read_context(None), write_context())
+    }
+
+    /// It returns the error for reading beyond the maximum number of input
+    /// bytes.
+    fn max_input_bytes_exceeded(&self) -> Error {
+        Error::MaxInputBytesExceeded(self.read_context(None), write_context())
+    }
+
+    /// The read context after reading up to the current position.
+    fn read_context(&self, byte: Option<u8>) -> ReadContext {
+        ReadContext {
+            byte,
+            pos: self.pos as u64,
+            latest_bytes: self.latest_bytes(self.pos),
+            path: ValuePath::default(),
+            state: None,
+        }
+    }
+
+    /// The read context including the next byte, which has not been consumed.
+    fn read_context_at_next_byte(&self, byte: u8) -> ReadContext {
+        let pos = self.pos + 1;
+
+        ReadContext {
+            byte: Some(byte),
+            pos: pos as u64,
+            latest_bytes: self.latest_bytes(pos),
+            path: ValuePath::default(),
+            state: None,
+        }
+    }
+
+    /// The latest input bytes before the position, up to the capture size.
+    fn latest_bytes(&self, pos: usize) -> Vec<u8> {
+        self.input[pos.saturating_sub(self.options.capture_size)..pos].to_vec()
+    }
+}
+
+/// A token of the input.
+enum Token<'a> {
+    /// A token completing a value: an integer, a string or the end of a list
+    /// or dictionary.
+    Value(BorrowedValue<'a>),
+
+    /// A token that does not complete a value.
+    Other,
+
+    /// The input has ended.
+    EndOfInput,
+}
+
+/// The write context. This parser does not write any output.
+fn write_context() -> WriteContext {
+    WriteContext {
+        byte: None,
+        pos: 0,
+        latest_bytes: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parsers::{
+            borrowed::BorrowedParser, error::Error, options::BencodeParserBuilder, BencodeParser,
+        },
+        value::{borrowed::BorrowedValue, DEFAULT_MAX_DEPTH},
+    };
+
+    fn read_value(input: &[u8]) -> Result<Option<BorrowedValue<'_>>, Error> {
+        BorrowedParser::new(input).read_value()
+    }
+
+    mod it_should_parse {
+        use std::collections::BTreeMap;
+
+        use crate::{parsers::borrowed::tests::read_value, value::borrowed::BorrowedValue};
+
+        #[test]
+        fn integers() {
+            assert_eq!(
+                read_value(b"i-42e").unwrap(),
+                Some(BorrowedValue::Integer(-42))
+            );
+        }
+
+        #[test]
+        fn strings_as_slices_of_the_input() {
+            let input = b"4:spam";
+
+            let value = read_value(input).unwrap().unwrap();
+
+            let bytes = value.as_bytes().unwrap();
+
+            assert_eq!(bytes, b"spam");
+            assert!(std::ptr::eq(bytes.as_ptr(), input[2..].as_ptr()));
+        }
+
+        #[test]
+        fn non_utf8_strings() {
+            assert_eq!(
+                read_value(b"2:\xFF\xFE").unwrap(),
+                Some(BorrowedValue::Bytes(b"\xFF\xFE"))
+            );
+        }
+
+        #[test]
+        fn lists() {
+            assert_eq!(
+                read_value(b"li1e4:spamlee").unwrap(),
+                Some(BorrowedValue::List(vec![
+                    BorrowedValue::Integer(1),
+                    BorrowedValue::Bytes(b"spam"),
+                    BorrowedValue::List(vec![])
+                ]))
+            );
+        }
+
+        #[test]
+        fn dictionaries_with_borrowed_keys() {
+            let mut fields = BTreeMap::new();
+            fields.insert(&b"\xFF"[..], BorrowedValue::Integer(1));
+            fields.insert(&b"foo"[..], BorrowedValue::Dict(BTreeMap::new()));
+
+            assert_eq!(
+                read_value(b"d3:foode1:\xFFi1ee").unwrap(),
+                Some(BorrowedValue::Dict(fields))
+            );
+        }
+
+        #[test]
+        fn line_breaks_between_values() {
+            assert_eq!(
+                read_value(b"\nli0e\ni1ee\n").unwrap(),
+                Some(BorrowedValue::List(vec![
+                    BorrowedValue::Integer(0),
+                    BorrowedValue::Integer(1)
+                ]))
+            );
+        }
+
+        #[test]
+        fn one_top_level_value_at_a_time() {
+            let mut parser = crate::parsers::borrowed::BorrowedParser::new(b"i1e4:spam");
+
+            assert_eq!(
+                parser.read_value().unwrap(),
+                Some(BorrowedValue::Integer(1))
+            );
+            assert_eq!(
+                parser.read_value().unwrap(),
+                Some(BorrowedValue::Bytes(b"spam"))
+            );
+            assert_eq!(parser.read_value().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn it_should_return_none_for_an_empty_input() {
+        assert_eq!(read_value(b"").unwrap(), None);
+    }
+
+    #[test]
+    fn it_should_build_the_same_value_as_the_owned_parser() {
+        let input = b"d3:bard1:ai-1ee3:fooli42e2:\xFF\xFEee";
+
+        let borrowed = read_value(input).unwrap().unwrap();
+
+        let owned = crate::parsers::BencodeParser::new(&input[..])
+            .read_value()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(borrowed.to_owned_value(), owned);
+    }
+
+    #[test]
+    fn it_should_allow_a_nesting_depth_limit_greater_than_the_default() {
+        let depth = DEFAULT_MAX_DEPTH + 1;
+        let deeply_nested_list = format!("{}{}", "l".repeat(depth), "e".repeat(depth));
+
+        let mut parser = BorrowedParser::with_max_depth(deeply_nested_list.as_bytes(), depth);
+
+        assert!(parser.read_value().unwrap().is_some());
+    }
+
+    mod strict_mode {
+        use crate::parsers::{
+            borrowed::BorrowedParser, error::Error, options::BencodeParserBuilder,
+        };
+
+        fn strict_read_value(input: &[u8]) -> Result<(), Error> {
+            let mut parser = BorrowedParser::with_options(
+                input,
+                BencodeParserBuilder::default()
+                    .strict(true)
+                    .options()
+                    .clone(),
+            );
+
+            parser.read_value()?;
+
+            parser.expect_end_of_input()
+        }
+
+        #[test]
+        fn it_should_accept_canonical_bencode() {
+            assert!(strict_read_value(b"d1:ai-1e1:bl0:i0eee").is_ok());
+        }
+
+        #[test]
+        fn it_should_reject_unsorted_dictionary_keys() {
+            assert!(matches!(
+                strict_read_value(b"d1:bi1e1:ai2ee"),
+                Err(Error::UnsortedDictKeys(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_include_the_key_in_the_path_of_unsorted_keys() {
+            let err = strict_read_value(b"ld1:bi1e1:ai2eee").unwrap_err();
+
+            assert!(matches!(err, Error::UnsortedDictKeys(..)));
+            assert_eq!(err.path().unwrap().to_string(), "[0].a");
+        }
+
+        #[test]
+        fn it_should_reject_duplicate_dictionary_keys() {
+            assert!(matches!(
+                strict_read_value(b"d1:ai1e1:ai2ee"),
+                Err(Error::DuplicateDictKey(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_reject_negative_zero() {
+            assert!(matches!(
+                strict_read_value(b"i-0e"),
+                Err(Error::NegativeZeroNotAllowed(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_reject_leading_zeros_in_string_lengths() {
+            assert!(matches!(
+                strict_read_value(b"03:abc"),
+                Err(Error::LeadingZerosInStringLengthNotAllowed(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_reject_line_breaks() {
+            assert!(matches!(
+                strict_read_value(b"\ni1e"),
+                Err(Error::LineBreakNotAllowed(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_reject_a_trailing_line_break_at_the_end_of_the_input() {
+            assert!(matches!(
+                strict_read_value(b"i1e\n"),
+                Err(Error::LineBreakNotAllowed(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn it_should_reject_line_breaks_when_they_are_not_skipped() {
+        let options = BencodeParserBuilder::default()
+            .skip_newlines(false)
+            .options()
+            .clone();
+
+        let mut parser = BorrowedParser::with_options(b"i1e\n", options.clone());
+
+        assert_eq!(
+            parser.read_value().unwrap(),
+            Some(BorrowedValue::Integer(1))
+        );
+        assert!(matches!(
+            parser.expect_end_of_input(),
+            Err(Error::TrailingData(..))
+        ));
+
+        assert!(matches!(
+            BorrowedParser::with_options(b"\ni1e", options).read_value(),
+            Err(Error::UnrecognizedFirstBencodeValueByte(..))
+        ));
+    }
+
+    mod it_should_fail {
+        use crate::{
+            parsers::{borrowed::tests::read_value, error::Error},
+            value::DEFAULT_MAX_DEPTH,
+        };
+
+        #[test]
+        fn when_the_nesting_depth_exceeds_the_default_limit() {
+            let depth = DEFAULT_MAX_DEPTH + 1;
+            let deeply_nested_list = format!("{}{}", "l".repeat(depth), "e".repeat(depth));
+
+            assert!(matches!(
+                read_value(deeply_nested_list.as_bytes()),
+                Err(Error::MaxDepthExceeded(..))
+            ));
+        }
+
+        #[test]
+        fn with_leading_zeros_in_integers() {
+            assert!(matches!(
+                read_value(b"i-00e"),
+                Err(Error::LeadingZerosInIntegersNotAllowed(..))
+            ));
+        }
+
+        #[test]
+        fn with_unexpected_bytes_in_integers() {
+            assert!(matches!(
+                read_value(b"i1ae"),
+                Err(Error::UnexpectedByteParsingInteger(..))
+            ));
+        }
+
+        #[test]
+        fn with_unfinished_integers() {
+            assert!(matches!(
+                read_value(b"i42"),
+                Err(Error::UnexpectedEndOfInputParsingInteger(..))
+            ));
+        }
+
+        #[test]
+        fn with_integers_that_do_not_fit_into_an_i64() {
+            let big_integer = format!("i{}1e", i64::MAX);
+
+            assert!(matches!(
+                read_value(big_integer.as_bytes()),
+                Err(Error::IntegerOverflow(..))
+            ));
+        }
+
+        #[test]
+        fn with_invalid_string_length_bytes() {
+            assert!(matches!(
+                read_value(b"4a:spam"),
+                Err(Error::InvalidStringLengthByte(..))
+            ));
+        }
+
+        #[test]
+        fn with_string_lengths_that_do_not_fit_into_a_usize() {
+            let huge_length = format!("{}0:", usize::MAX);
+
+            assert!(matches!(
+                read_value(huge_length.as_bytes()),
+                Err(Error::StringLengthOverflow(..))
+            ));
+        }
+
+        #[test]
+        fn with_unfinished_string_lengths() {
+            assert!(matches!(
+                read_value(b"4"),
+                Err(Error::UnexpectedEndOfInputParsingStringLength(..))
+            ));
+        }
+
+        #[test]
+        fn with_strings_longer_than_the_input() {
+            assert!(matches!(
+                read_value(b"5:spam"),
+                Err(Error::UnexpectedEndOfInputParsingStringValue(..))
+            ));
+        }
+
+        #[test]
+        fn with_dictionary_keys_that_are_not_strings() {
+            assert!(matches!(
+                read_value(b"di1ei2ee"),
+                Err(Error::ExpectedStringForDictKeyGot(..))
+            ));
+        }
+
+        #[test]
+        fn with_a_premature_end_of_dictionary() {
+            assert!(matches!(
+                read_value(b"d3:fooe"),
+                Err(Error::PrematureEndOfDict(..))
+            ));
+        }
+
+        #[test]
+        fn with_an_end_without_a_matching_list_or_dict() {
+            assert!(matches!(
+                read_value(b"e"),
+                Err(Error::NoMatchingStartForListOrDictEnd(..))
+            ));
+        }
+
+        #[test]
+        fn with_unfinished_dictionaries() {
+            assert!(matches!(
+                read_value(b"d3:foo"),
+                Err(Error::UnexpectedEndOfInputExpectingDictFieldValue(..))
+            ));
+        }
+
+        #[test]
+        fn with_unrecognized_bytes() {
+            let result = read_value(b"x");
+
+            let Err(Error::UnrecognizedFirstBencodeValueByte(read_context, _)) = result else {
+                panic!("expected an unrecognized byte error");
+            };
+
+            assert_eq!(read_context.byte, Some(b'x'));
+            assert_eq!(read_context.pos, 1);
+            assert_eq!(read_context.latest_bytes, b"x");
+        }
+    }
+
+    #[test]
+    fn errors_should_include_the_path_of_the_value_being_parsed() {
+        let err = read_value(b"d5:filesld4:pathl1:ax").unwrap_err();
+
+        assert_eq!(err.path().unwrap().to_string(), "files[0].path[1]");
+    }
+
+    mod limits {
+        use crate::parsers::{
+            borrowed::BorrowedParser,
+            error::Error,
+            options::{BencodeParserBuilder, ParserOptions},
+        };
+
+        fn read_value(input: &[u8], options: &BencodeParserBuilder) -> Result<(), Error> {
+            let options: ParserOptions = options.options().clone();
+
+            BorrowedParser::with_options(input, options)
+                .read_value()
+                .map(|_| ())
+        }
+
+        #[test]
+        fn it_should_limit_the_string_length() {
+            let options = BencodeParserBuilder::default().max_string_length(4);
+
+            assert!(read_value(b"l4:spame", &options).is_ok());
+            assert!(matches!(
+                read_value(b"l5:spamse", &options),
+                Err(Error::MaxStringLengthExceeded(..))
+            ));
+            assert!(matches!(
+                read_value(b"d5:spamsi1ee", &options),
+                Err(Error::MaxStringLengthExceeded(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_limit_the_items_of_lists_and_dictionaries() {
+            let options = BencodeParserBuilder::default().max_container_items(2);
+
+            assert!(read_value(b"li1ei2ee", &options).is_ok());
+            assert!(matches!(
+                read_value(b"li1ei2ei3ee", &options),
+                Err(Error::MaxContainerItemsExceeded(..))
+            ));
+            assert!(matches!(
+                read_value(b"d1:ai1e1:bi2e1:ci3ee", &options),
+                Err(Error::MaxContainerItemsExceeded(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_limit_the_input_size() {
+            let options = BencodeParserBuilder::default().max_input_bytes(5);
+
+            assert!(read_value(b"li1ee", &options).is_ok());
+            assert!(matches!(
+                read_value(b"li12ee", &options),
+                Err(Error::MaxInputBytesExceeded(..))
+            ));
+            assert!(matches!(
+                read_value(b"5:spams", &options),
+                Err(Error::MaxInputBytesExceeded(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_reject_trailing_data_beyond_the_input_size_limit() {
+            let options = BencodeParserBuilder::default().max_input_bytes(3);
+
+            let mut parser = BorrowedParser::with_options(b"i1ei2e", options.options().clone());
+
+            assert!(parser.read_value().is_ok());
+            assert!(matches!(
+                parser.expect_end_of_input(),
+                Err(Error::MaxInputBytesExceeded(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn it_should_return_the_same_errors_and_paths_as_the_owned_parser() {
+        let inputs: [&[u8]; 14] = [
+            b"ld1:bi1e1:ai2eee",
+            b"ld1:ai1e1:ai2eee",
+            b"d1:ad1:bi1e1:bi2eee",
+            b"li1ei2ei3ee",
+            b"d1:ai1e1:bi2e1:ci3ee",
+            b"d1:ali1ei2ei3eee",
+            b"l5:spamse",
+            b"d5:spamsi1ee",
+            b"d1:a5:spamse",
+            b"li1ei2ei3ei4ee",
+            b"d1:ali1ei-0eee",
+            b"ld1:ai1e1:b",
+            b"l1:ad1:ai1e",
+            b"d1:al1:x03:abcee",
+        ];
+
+        let builders = [
+            BencodeParserBuilder::default(),
+            BencodeParserBuilder::default().strict(true),
+            BencodeParserBuilder::default().max_container_items(2),
+            BencodeParserBuilder::default().max_string_length(4),
+            BencodeParserBuilder::default().max_input_bytes(9),
+            BencodeParserBuilder::default().max_depth(1),
+        ];
+
+        for input in inputs {
+            for builder in &builders {
+                let borrowed = BorrowedParser::with_options(input, builder.options().clone())
+                    .read_value()
+                    .map(|value| value.map(|value| value.to_owned_value()));
+
+                let owned =
+                    BencodeParser::with_options(input, builder.options().clone()).read_value();
+
+                match (borrowed, owned) {
+                    (Ok(borrowed), Ok(owned)) => assert_eq!(borrowed, owned),
+                    (Err(borrowed), Err(owned)) => {
+                        assert_eq!(borrowed.code(), owned.code(), "{input:?}");
+                        assert_eq!(borrowed.path(), owned.path(), "{input:?} {}", owned.code());
+                    }
+                    (borrowed, owned) => {
+                        panic!("{input:?}: {borrowed:?} is not {owned:?}")
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,783 @@
+//! Canonical bencode.
+//!
+//! The default parser is lenient. It accepts bencode that is not in the
+//! canonical form, which is needed to calculate meaningful info hashes:
+//!
+//! - Dictionary keys that are not sorted by their raw bytes, or duplicate.
+//! - Negative zero: `i-0e`.
+//! - Leading zeros in string lengths: `03:abc`.
+//! - Line breaks before, after or between values.
+//!
+//! The [`Canonicalizer`] reads the input with the same state machine and
+//! writes it in canonical form with a [`BencodeWriter`]. It keeps a
+//! [`Change`] for each fix:
+//!
+//! ```rust
+//! use torrust_bencode2json::parsers::canonical::{Canonicalizer, Change};
+//!
+//! let mut output = Vec::new();
+//!
+//! let mut canonicalizer = Canonicalizer::new(&b"d1:bi-0e1:a03:fooe\n"[..]);
+//!
+//! canonicalizer.write_bytes(&mut output).unwrap();
+//!
+//! assert_eq!(output, b"d1:a3:foo1:bi0ee");
+//!
+//! let changes: Vec<String> = canonicalizer
+//!     .changes()
+//!     .iter()
+//!     .map(Change::to_string)
+//!     .collect();
+//!
+//! assert_eq!(
+//!     changes,
+//!     [
+//!         "negative zero written as `0` at `b`",
+//!         "sorted the dictionary keys at the top-level value",
+//!         "removed leading zeros from the string length at `a`",
+//!         "removed 1 line break",
+//!     ]
+//! );
+//! ```
+//!
+//! The top-level value is kept in memory until it ends, because the fields of
+//! each dictionary have to be sorted before writing them.
+use std::{
+    collections::{btree_map, BTreeMap},
+    fmt,
+    io::{BufWriter, Read, Write},
+    slice,
+};
+
+use super::{
+    error::{Error, ReadContext, WriteContext},
+    event::{Event, Integer},
+    options::ParserOptions,
+    path::{PathSegment, ValuePath},
+    BencodeParser,
+};
+use crate::encoder::{self, BencodeWriter};
+
+/// What to do with dictionaries that have several fields with the same key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Keep the value of the first field.
+    First,
+
+    /// Keep the value of the last field, like
+    /// [`from_bytes`](crate::from_bytes) does.
+    #[default]
+    Last,
+
+    /// Fail with a [`DuplicateDictKey`](Error::DuplicateDictKey) error.
+    Reject,
+}
+
+/// A fix applied to write the input in canonical form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The keys of the dictionary at the path were not sorted by their raw
+    /// bytes.
+    SortedDictKeys(ValuePath),
+
+    /// The dictionary had several fields with the key at the end of the path.
+    /// Only one of them was written.
+    RemovedDuplicateDictKey(ValuePath),
+
+    /// The integer at the path was a negative zero.
+    NegativeZero(ValuePath),
+
+    /// The length of the string at the path had leading zeros. For
+    /// dictionary keys, the path is the one of the field.
+    LeadingZerosInStringLength(ValuePath),
+
+    /// Number of line breaks before, after or between values.
+    RemovedLineBreaks(u64),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::SortedDictKeys(path) => {
+                write!(f, "sorted the dictionary keys at {}", DisplayPath(path))
+            }
+            Change::RemovedDuplicateDictKey(path) => {
+                write!(
+                    f,
+                    "removed the duplicate dictionary key {}",
+                    DisplayPath(path)
+                )
+            }
+            Change::NegativeZero(path) => {
+                write!(f, "negative zero written as `0` at {}", DisplayPath(path))
+            }
+            Change::LeadingZerosInStringLength(path) => write!(
+                f,
+                "removed leading zeros from the string length at {}",
+                DisplayPath(path)
+            ),
+            Change::RemovedLineBreaks(1) => write!(f, "removed 1 line break"),
+            Change::RemovedLineBreaks(count) => write!(f, "removed {count} line breaks"),
+        }
+    }
+}
+
+/// It displays an empty path as the top-level value.
+struct DisplayPath<'a>(&'a ValuePath);
+
+impl fmt::Display for DisplayPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            write!(f, "the top-level value")
+        } else {
+            write!(f, "`{}`", self.0)
+        }
+    }
+}
+
+/// A parsed value, with the dictionary fields already sorted.
+#[derive(Debug)]
+enum Node {
+    Integer(Integer),
+    Bytes(Vec<u8>),
+    List(Vec<Node>),
+    Dict(BTreeMap<Vec<u8>, Node>),
+}
+
+impl Drop for Node {
+    /// It drops the nested nodes with a loop instead of recursively, to
+    /// avoid overflowing the stack with deeply nested input.
+    fn drop(&mut self) {
+        let mut nodes = Vec::new();
+
+        take_children(self, &mut nodes);
+
+        while let Some(mut node) = nodes.pop() {
+            take_children(&mut node, &mut nodes);
+        }
+    }
+}
+
+/// It moves the items of a list or dictionary node to the given nodes.
+fn take_children(node: &mut Node, nodes: &mut Vec<Node>) {
+    match node {
+        Node::Integer(_) | Node::Bytes(_) => {}
+        Node::List(items) => nodes.append(items),
+        Node::Dict(fields) => nodes.extend(std::mem::take(fields).into_values()),
+    }
+}
+
+/// The remaining items of a list or dictionary being written.
+enum Items<'a> {
+    List(slice::Iter<'a, Node>),
+    Dict(btree_map::Iter<'a, Vec<u8>, Node>),
+}
+
+/// An unfinished list or dictionary.
+#[derive(Debug)]
+enum Container {
+    List(Vec<Node>),
+    Dict {
+        fields: BTreeMap<Vec<u8>, Node>,
+
+        /// The key of the field whose value is being parsed.
+        key: Option<Vec<u8>>,
+
+        /// The last key, in input order.
+        last_key: Option<Vec<u8>>,
+
+        /// The value is dropped because the key is a duplicate.
+        drops_value: bool,
+
+        /// The unsorted keys have already been reported.
+        is_unsorted: bool,
+    },
+}
+
+pub struct Canonicalizer<R: Read> {
+    parser: BencodeParser<R>,
+    duplicate_keys: DuplicateKeys,
+    changes: Vec<Change>,
+}
+
+impl<R: Read> Canonicalizer<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// It creates a canonicalizer with custom parser options, for example to
+    /// set limits for untrusted input. Options for the JSON output are
+    /// ignored.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            // Skipped strings would be lost
+            parser: BencodeParser::with_options(
+                reader,
+                ParserOptions {
+                    selected_paths: Vec::new(),
+                    ..options
+                },
+            ),
+            duplicate_keys: DuplicateKeys::default(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// It sets what to do with duplicate dictionary keys.
+    #[must_use]
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    /// It parses a bencoded value read from input and writes it in canonical
+    /// form to the output. Nothing is written if the input is empty.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input or write to the output.
+    /// - The input is invalid Bencode.
+    /// - There is more than one top-level value.
+    /// - There are duplicate keys and they are rejected.
+    pub fn write_bytes<W: Write>(&mut self, writer: W) -> Result<(), Error> {
+        let value = self.read_value()?;
+
+        self.parser.expect_end_of_input()?;
+
+        if self.parser.num_skipped_line_breaks > 0 {
+            self.changes.push(Change::RemovedLineBreaks(
+                self.parser.num_skipped_line_breaks,
+            ));
+        }
+
+        let Some(value) = value else {
+            return Ok(());
+        };
+
+        let mut writer = BencodeWriter::new(BufWriter::new(writer));
+
+        write_node(&mut writer, &value).map_err(write_error)?;
+
+        writer.finish().map_err(write_error)?;
+
+        Ok(())
+    }
+
+    /// It returns the fixes applied so far, in input order. Removed line
+    /// breaks are reported last.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// It reads the top-level value. It returns `None` if the input is empty.
+    fn read_value(&mut self) -> Result<Option<Node>, Error> {
+        let mut containers: Vec<Container> = Vec::new();
+
+        loop {
+            let start = self.consumed_bytes();
+
+            let Some(event) = self.parser.next_whole_event()? else {
+                return Ok(None);
+            };
+
+            let token_length = self.consumed_bytes() - start;
+
+            let node = match event {
+                Event::Integer(integer) => {
+                    if integer.as_str() == "-0" {
+                        self.changes.push(Change::NegativeZero(path(&containers)));
+                    }
+                    Node::Integer(integer)
+                }
+                Event::Bytes(bytes) => {
+                    self.check_string_length(bytes.len(), token_length, path(&containers));
+                    Node::Bytes(bytes)
+                }
+                Event::BytesStart(_) | Event::BytesChunk(_) => {
+                    unreachable!("whole events have whole strings")
+                }
+                Event::Key(key) => {
+                    self.set_dict_key(&mut containers, key, token_length)?;
+                    continue;
+                }
+                Event::ListStart => {
+                    containers.push(Container::List(Vec::new()));
+                    continue;
+                }
+                Event::DictStart => {
+                    containers.push(Container::Dict {
+                        fields: BTreeMap::new(),
+                        key: None,
+                        last_key: None,
+                        drops_value: false,
+                        is_unsorted: false,
+                    });
+                    continue;
+                }
+                Event::End => match containers.pop() {
+                    Some(Container::List(items)) => Node::List(items),
+                    Some(Container::Dict { fields, .. }) => Node::Dict(fields),
+                    None => unreachable!("the parser only ends open lists or dictionaries"),
+                },
+            };
+
+            match containers.last_mut() {
+                None => return Ok(Some(node)),
+                Some(Container::List(items)) => items.push(node),
+                Some(Container::Dict {
+                    fields,
+                    key,
+                    drops_value,
+                    ..
+                }) => {
+                    if let Some(key) = key.take() {
+                        if !std::mem::take(drops_value) {
+                            fields.insert(key, node);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// It sets the key of the next field of the innermost dictionary,
+    /// applying the duplicate keys policy.
+    fn set_dict_key(
+        &mut self,
+        containers: &mut [Container],
+        new_key: Vec<u8>,
+        token_length: u64,
+    ) -> Result<(), Error> {
+        let dict_path = path(containers);
+
+        let mut field_segments = dict_path.segments().to_vec();
+        field_segments.push(PathSegment::Key(new_key.clone()));
+        let field_path = ValuePath::new(field_segments);
+
+        self.check_string_length(new_key.len(), token_length, field_path.clone());
+
+        let Some(Container::Dict {
+            fields,
+            key,
+            last_key,
+            drops_value,
+            is_unsorted,
+        }) = containers.last_mut()
+        else {
+            unreachable!("the parser only returns keys inside dictionaries")
+        };
+
+        if !*is_unsorted
+            && last_key
+                .as_ref()
+                .is_some_and(|last_key| new_key < *last_key)
+        {
+            *is_unsorted = true;
+            self.changes.push(Change::SortedDictKeys(dict_path));
+        }
+
+        if fields.contains_key(&new_key) {
+            match self.duplicate_keys {
+                DuplicateKeys::First => *drops_value = true,
+                DuplicateKeys::Last => {}
+                DuplicateKeys::Reject => {
+                    return Err(Error::DuplicateDictKey(
+                        ReadContext {
+                            byte: None,
+                            pos: self.parser.byte_reader.input_byte_counter(),
+                            latest_bytes: self.parser.byte_reader.captured_bytes(),
+                            path: field_path,
+                            state: None,
+                        },
+                        WriteContext {
+                            byte: None,
+                            pos: 0,
+                            latest_bytes: vec![],
+                        },
+                    ))
+                }
+            }
+
+            self.changes
+                .push(Change::RemovedDuplicateDictKey(field_path));
+        }
+
+        *last_key = Some(new_key.clone());
+        *key = Some(new_key);
+
+        Ok(())
+    }
+
+    /// It reports leading zeros when the string token is longer than its
+    /// canonical encoding.
+    fn check_string_length(&mut self, length: usize, token_length: u64, path: ValuePath) {
+        let canonical_length = length.to_string().len() + 1 + length;
+
+        if token_length > canonical_length as u64 {
+            self.changes.push(Change::LeadingZerosInStringLength(path));
+        }
+    }
+
+    /// It returns the number of bytes consumed from the input, without the
+    /// skipped line breaks.
+    fn consumed_bytes(&self) -> u64 {
+        self.parser.byte_reader.consumed_byte_counter() - self.parser.num_skipped_line_breaks
+    }
+}
+
+/// It returns the path of the next value.
+fn path(containers: &[Container]) -> ValuePath {
+    ValuePath::new(
+        containers
+            .iter()
+            .filter_map(|container| match container {
+                Container::List(items) => Some(PathSegment::Index(items.len())),
+                Container::Dict { key, .. } => key.clone().map(PathSegment::Key),
+            })
+            .collect(),
+    )
+}
+
+/// It writes the node with a stack of the unfinished lists and dictionaries
+/// instead of recursively, to avoid overflowing the stack with deeply nested
+/// input.
+fn write_node<W: Write>(
+    writer: &mut BencodeWriter<W>,
+    node: &Node,
+) -> Result<(), encoder::error::Error> {
+    let mut stack: Vec<Items<'_>> = Vec::new();
+
+    let mut next = Some(node);
+
+    loop {
+        if let Some(node) = next.take() {
+            match node {
+                Node::Integer(integer) => {
+                    writer.write_integer(integer)?;
+                }
+                Node::Bytes(bytes) => {
+                    writer.write_bytes(bytes)?;
+                }
+                Node::List(items) => {
+                    writer.begin_list()?;
+                    stack.push(Items::List(items.iter()));
+                }
+                Node::Dict(fields) => {
+                    writer.begin_dict()?;
+                    stack.push(Items::Dict(fields.iter()));
+                }
+            }
+        }
+
+        let Some(items) = stack.last_mut() else {
+            return Ok(());
+        };
+
+        next = match items {
+            Items::List(items) => items.next(),
+            Items::Dict(fields) => match fields.next() {
+                Some((key, value)) => {
+                    writer.key(key)?;
+                    Some(value)
+                }
+                None => None,
+            },
+        };
+
+        if next.is_none() {
+            writer.end()?;
+            stack.pop();
+        }
+    }
+}
+
+/// It converts the writer errors. Only I/O errors are possible, because the
+/// nodes are always valid canonical bencode.
+fn write_error(err: encoder::error::Error) -> Error {
+    match err {
+        encoder::error::Error::Io(err) => Error::Io(err),
+        err => unreachable!("canonical values are valid bencode: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{
+        canonical::{Canonicalizer, Change, DuplicateKeys},
+        error::Error,
+        path::{PathSegment, ValuePath},
+    };
+
+    /// It returns the canonical bencode and the changes.
+    fn canonicalize(
+        input: &[u8],
+        duplicate_keys: DuplicateKeys,
+    ) -> Result<(Vec<u8>, Vec<Change>), Error> {
+        let mut output = Vec::new();
+
+        let mut canonicalizer = Canonicalizer::new(input).duplicate_keys(duplicate_keys);
+
+        canonicalizer.write_bytes(&mut output)?;
+
+        Ok((output, canonicalizer.changes().to_vec()))
+    }
+
+    fn key(key: &str) -> PathSegment {
+        PathSegment::Key(key.as_bytes().to_vec())
+    }
+
+    mod it_should {
+        use crate::parsers::{
+            canonical::{
+                tests::{canonicalize, key},
+                Change, DuplicateKeys,
+            },
+            path::{PathSegment, ValuePath},
+        };
+
+        #[test]
+        fn not_change_canonical_bencode() {
+            for input in [
+                &b"i42e"[..],
+                b"i-42e",
+                b"4:spam",
+                b"0:",
+                b"li1e3:fooe",
+                b"d3:bar4:spam3:fooi42ee",
+                b"d1:ad1:bli1eeee",
+                b"d1:\xFFi1ee",
+            ] {
+                let (output, changes) = canonicalize(input, DuplicateKeys::default()).unwrap();
+
+                assert_eq!(output, input);
+                assert_eq!(changes, vec![]);
+            }
+        }
+
+        #[test]
+        fn write_nothing_for_empty_input() {
+            assert_eq!(
+                canonicalize(b"", DuplicateKeys::default()).unwrap(),
+                (vec![], vec![])
+            );
+        }
+
+        #[test]
+        fn sort_the_dictionary_keys_by_their_raw_bytes() {
+            let (output, changes) =
+                canonicalize(b"d1:\xFFi1e1:bi2e1:Bi3ee", DuplicateKeys::default()).unwrap();
+
+            assert_eq!(output, b"d1:Bi3e1:bi2e1:\xFFi1ee");
+            assert_eq!(changes, vec![Change::SortedDictKeys(ValuePath::default())]);
+        }
+
+        #[test]
+        fn sort_the_keys_of_nested_dictionaries() {
+            let (output, changes) =
+                canonicalize(b"d4:infold1:bi1e1:ai2eeee", DuplicateKeys::default()).unwrap();
+
+            assert_eq!(output, b"d4:infold1:ai2e1:bi1eeee");
+            assert_eq!(
+                changes,
+                vec![Change::SortedDictKeys(ValuePath::new(vec![
+                    key("info"),
+                    PathSegment::Index(0)
+                ]))]
+            );
+        }
+
+        #[test]
+        fn keep_the_last_value_of_duplicate_keys_by_default() {
+            let (output, changes) =
+                canonicalize(b"d1:ai1e1:bi2e1:ai3ee", DuplicateKeys::default()).unwrap();
+
+            assert_eq!(output, b"d1:ai3e1:bi2ee");
+            assert_eq!(
+                changes,
+                vec![
+                    Change::SortedDictKeys(ValuePath::default()),
+                    Change::RemovedDuplicateDictKey(ValuePath::new(vec![key("a")]))
+                ]
+            );
+        }
+
+        #[test]
+        fn keep_the_first_value_of_duplicate_keys() {
+            let (output, changes) =
+                canonicalize(b"d1:ai1e1:ad1:bi2eee", DuplicateKeys::First).unwrap();
+
+            assert_eq!(output, b"d1:ai1ee");
+            assert_eq!(
+                changes,
+                vec![Change::RemovedDuplicateDictKey(ValuePath::new(vec![key(
+                    "a"
+                )]))]
+            );
+        }
+
+        #[test]
+        fn write_negative_zero_as_zero() {
+            let (output, changes) = canonicalize(b"li1ei-0ee", DuplicateKeys::default()).unwrap();
+
+            assert_eq!(output, b"li1ei0ee");
+            assert_eq!(
+                changes,
+                vec![Change::NegativeZero(ValuePath::new(vec![
+                    PathSegment::Index(1)
+                ]))]
+            );
+        }
+
+        #[test]
+        fn remove_leading_zeros_in_string_lengths() {
+            let (output, changes) =
+                canonicalize(b"d003:foo0003:bare", DuplicateKeys::default()).unwrap();
+
+            assert_eq!(output, b"d3:foo3:bare");
+            assert_eq!(
+                changes,
+                vec![
+                    Change::LeadingZerosInStringLength(ValuePath::new(vec![key("foo")])),
+                    Change::LeadingZerosInStringLength(ValuePath::new(vec![key("foo")]))
+                ]
+            );
+        }
+
+        #[test]
+        fn remove_the_line_breaks() {
+            let (output, changes) =
+                canonicalize(b"\nl\n1:a\n\n1:be\n", DuplicateKeys::default()).unwrap();
+
+            assert_eq!(output, b"l1:a1:be");
+            assert_eq!(changes, vec![Change::RemovedLineBreaks(5)]);
+        }
+
+        #[test]
+        fn keep_integers_that_do_not_fit_into_an_i64() {
+            let input = format!("i{}1e", i64::MAX);
+
+            let (output, changes) =
+                canonicalize(input.as_bytes(), DuplicateKeys::default()).unwrap();
+
+            assert_eq!(output, input.as_bytes());
+            assert_eq!(changes, vec![]);
+        }
+
+        #[test]
+        fn describe_the_changes() {
+            let changes = [
+                Change::SortedDictKeys(ValuePath::default()),
+                Change::RemovedDuplicateDictKey(ValuePath::new(vec![key("info"), key("name")])),
+                Change::NegativeZero(ValuePath::new(vec![PathSegment::Index(2)])),
+                Change::LeadingZerosInStringLength(ValuePath::new(vec![key("a")])),
+                Change::RemovedLineBreaks(2),
+            ];
+
+            assert_eq!(
+                changes.iter().map(Change::to_string).collect::<Vec<_>>(),
+                [
+                    "sorted the dictionary keys at the top-level value",
+                    "removed the duplicate dictionary key `info.name`",
+                    "negative zero written as `0` at `[2]`",
+                    "removed leading zeros from the string length at `a`",
+                    "removed 2 line breaks",
+                ]
+            );
+        }
+
+        #[test]
+        fn write_deeply_nested_values_without_overflowing_the_stack() {
+            let input = [
+                b"l".repeat(100_000),
+                b"d1:ai-0ee".to_vec(),
+                b"e".repeat(100_000),
+            ]
+            .concat();
+
+            let (output, changes) = canonicalize(&input, DuplicateKeys::default()).unwrap();
+
+            assert_eq!(
+                output,
+                [
+                    b"l".repeat(100_000),
+                    b"d1:ai0ee".to_vec(),
+                    b"e".repeat(100_000)
+                ]
+                .concat()
+            );
+            assert_eq!(changes.len(), 1);
+        }
+    }
+
+    mod it_should_fail {
+        use crate::parsers::{
+            canonical::{
+                tests::{canonicalize, key},
+                DuplicateKeys,
+            },
+            error::Error,
+            path::ValuePath,
+        };
+
+        #[test]
+        fn rejecting_duplicate_keys() {
+            let result = canonicalize(b"d1:ai1e1:ai2ee", DuplicateKeys::Reject);
+
+            let Err(err @ Error::DuplicateDictKey(..)) = result else {
+                panic!("expected a duplicate key error, got {result:?}");
+            };
+
+            assert_eq!(err.path(), Some(&ValuePath::new(vec![key("a")])));
+        }
+
+        #[test]
+        fn when_there_is_more_than_one_top_level_value() {
+            assert!(matches!(
+                canonicalize(b"i1ei2e", DuplicateKeys::default()),
+                Err(Error::TrailingData(..))
+            ));
+        }
+
+        #[test]
+        fn when_the_input_is_invalid() {
+            assert!(matches!(
+                canonicalize(b"d1:ai1e", DuplicateKeys::default()),
+                Err(Error::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn it_should_use_the_parser_limits() {
+        use crate::parsers::options::BencodeParserBuilder;
+
+        let mut output = Vec::new();
+
+        let result = Canonicalizer::with_options(
+            &b"llleee"[..],
+            BencodeParserBuilder::default()
+                .max_depth(2)
+                .options()
+                .clone(),
+        )
+        .write_bytes(&mut output);
+
+        assert!(matches!(result, Err(Error::MaxDepthExceeded(..))));
+    }
+
+    #[test]
+    fn it_should_keep_the_path_of_values_after_a_duplicate_key() {
+        let (_, changes) = canonicalize(b"d1:ai1e1:ali-0eee", DuplicateKeys::First).unwrap();
+
+        assert_eq!(
+            changes[1],
+            Change::NegativeZero(ValuePath::new(vec![key("a"), PathSegment::Index(0)]))
+        );
+    }
+}
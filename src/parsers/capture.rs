@@ -0,0 +1,55 @@
+//! Values whose raw bytes are captured while they are parsed.
+//!
+//! The parser can hash, and optionally keep, the exact raw bencoded bytes of
+//! the values selected by a [`KeyPath`], in the same pass used to convert the
+//! input to JSON. For example, to get the info hash of a torrent:
+//!
+//! ```rust
+//! use torrust_bencode2json::parsers::options::BencodeParserBuilder;
+//!
+//! let mut output = String::new();
+//!
+//! let mut parser = BencodeParserBuilder::default()
+//!     .capture("info")
+//!     .build(&b"d4:infod4:name4:spamee"[..]);
+//!
+//! parser.write_str(&mut output).unwrap();
+//!
+//! let info = &parser.captured_values()[0];
+//!
+//! assert_eq!(info.path.to_string(), "info");
+//! assert_eq!(info.len, 14);
+//! ```
+use super::path::KeyPath;
+use crate::rw::recording::Recorded;
+
+/// The raw bytes of a value selected by a [`KeyPath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedValue {
+    /// The path of the value.
+    pub path: KeyPath,
+
+    /// Number of raw bytes of the value.
+    pub len: u64,
+
+    /// SHA-1 hash of the raw bytes.
+    pub sha1: [u8; 20],
+
+    /// SHA-256 hash of the raw bytes.
+    pub sha256: [u8; 32],
+
+    /// The raw bytes, if the parser was asked to keep them.
+    pub bytes: Option<Vec<u8>>,
+}
+
+impl CapturedValue {
+    pub(crate) fn new(path: KeyPath, recorded: Recorded) -> Self {
+        Self {
+            path,
+            len: recorded.len,
+            sha1: recorded.sha1,
+            sha256: recorded.sha256,
+            bytes: recorded.bytes,
+        }
+    }
+}
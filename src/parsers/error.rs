@@ -5,11 +5,13 @@ use std::{
     io,
 };
 
+use derive_more::derive::Display;
+use serde_json::json;
 use thiserror::Error;
 
-use crate::rw;
+use crate::rw::{self, writer::Writer};
 
-use super::BencodeType;
+use super::{path::ValuePath, stack::State, BencodeType};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -35,6 +37,9 @@ pub enum Error {
     #[error("Leading zeros in integers are not allowed, for example b'i00e'; {0}; {1}")]
     LeadingZerosInIntegersNotAllowed(ReadContext, WriteContext),
 
+    #[error("Integer does not fit into a 64-bit signed integer; {0}; {1}")]
+    IntegerOverflow(ReadContext, WriteContext),
+
     // Strings
     #[error("Invalid string length byte, expected a digit; {0}; {1}")]
     InvalidStringLengthByte(ReadContext, WriteContext),
@@ -45,6 +50,9 @@ pub enum Error {
     #[error("Unexpected end of input parsing string value; {0}; {1}")]
     UnexpectedEndOfInputParsingStringValue(ReadContext, WriteContext),
 
+    #[error("String length does not fit into the platform's usize; {0}; {1}")]
+    StringLengthOverflow(ReadContext, WriteContext),
+
     // Lists
     #[error(
         "Unexpected end of input parsing list. Expecting first list item or list end; {0}; {1}"
@@ -79,6 +87,453 @@ pub enum Error {
         "Unexpected end of list or dict. No matching start for the list or dict end: {0}, {1}"
     )]
     NoMatchingStartForListOrDictEnd(ReadContext, WriteContext),
+
+    // Canonical encoding (strict mode)
+    #[error("Negative zero is not allowed in canonical bencode, for example b'i-0e'; {0}; {1}")]
+    NegativeZeroNotAllowed(ReadContext, WriteContext),
+
+    #[error("Leading zeros in string lengths are not allowed in canonical bencode, for example b'03:abc'; {0}; {1}")]
+    LeadingZerosInStringLengthNotAllowed(ReadContext, WriteContext),
+
+    #[error("Dictionary keys must be sorted by their raw bytes in canonical bencode; {0}; {1}")]
+    UnsortedDictKeys(ReadContext, WriteContext),
+
+    #[error("Duplicate dictionary keys are not allowed in canonical bencode; {0}; {1}")]
+    DuplicateDictKey(ReadContext, WriteContext),
+
+    #[error("Line breaks between values are not allowed in canonical bencode; {0}; {1}")]
+    LineBreakNotAllowed(ReadContext, WriteContext),
+
+    // Limits
+    #[error("Maximum nesting depth of lists and dictionaries exceeded; {0}; {1}")]
+    MaxDepthExceeded(ReadContext, WriteContext),
+
+    #[error("Maximum string length exceeded; {0}; {1}")]
+    MaxStringLengthExceeded(ReadContext, WriteContext),
+
+    #[error("Maximum number of list items or dictionary fields exceeded; {0}; {1}")]
+    MaxContainerItemsExceeded(ReadContext, WriteContext),
+
+    #[error("Maximum input size exceeded; {0}; {1}")]
+    MaxInputBytesExceeded(ReadContext, WriteContext),
+
+    // Values
+    #[error("Unexpected trailing data after the bencoded value; {0}; {1}")]
+    TrailingData(ReadContext, WriteContext),
+
+    #[error("Invalid UTF-8 after the text already written for a streamed string; {0}; {1}")]
+    InvalidUtf8InStreamedString(ReadContext, WriteContext),
+
+    #[error("Deserialization error: {0}")]
+    Deserialize(String),
+}
+
+impl Error {
+    /// It returns the reader context, if the error has one.
+    #[must_use]
+    pub fn read_context(&self) -> Option<&ReadContext> {
+        match self {
+            Self::ReadByteAfterPeekingDoesMatchPeekedByte(read_context, _)
+            | Self::UnrecognizedFirstBencodeValueByte(read_context, _)
+            | Self::UnexpectedByteParsingInteger(read_context, _)
+            | Self::UnexpectedEndOfInputParsingInteger(read_context, _)
+            | Self::LeadingZerosInIntegersNotAllowed(read_context, _)
+            | Self::IntegerOverflow(read_context, _)
+            | Self::InvalidStringLengthByte(read_context, _)
+            | Self::UnexpectedEndOfInputParsingStringLength(read_context, _)
+            | Self::UnexpectedEndOfInputParsingStringValue(read_context, _)
+            | Self::StringLengthOverflow(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingFirstListItemOrEnd(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingNextListItem(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingDictFieldValue(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(read_context, _)
+            | Self::PrematureEndOfDict(read_context, _)
+            | Self::NoMatchingStartForListOrDictEnd(read_context, _)
+            | Self::NegativeZeroNotAllowed(read_context, _)
+            | Self::LeadingZerosInStringLengthNotAllowed(read_context, _)
+            | Self::UnsortedDictKeys(read_context, _)
+            | Self::DuplicateDictKey(read_context, _)
+            | Self::LineBreakNotAllowed(read_context, _)
+            | Self::MaxDepthExceeded(read_context, _)
+            | Self::MaxStringLengthExceeded(read_context, _)
+            | Self::MaxContainerItemsExceeded(read_context, _)
+            | Self::MaxInputBytesExceeded(read_context, _)
+            | Self::TrailingData(read_context, _)
+            | Self::InvalidUtf8InStreamedString(read_context, _)
+            | Self::ExpectedStringForDictKeyGot(_, read_context, _) => Some(read_context),
+            Self::Io(_) | Self::Rw(_) | Self::Deserialize(_) => None,
+        }
+    }
+
+    /// It returns the path of the value being parsed when the error occurred,
+    /// if the error has a reader context.
+    #[must_use]
+    pub fn path(&self) -> Option<&ValuePath> {
+        self.read_context().map(|read_context| &read_context.path)
+    }
+
+    /// It sets the path of the value being parsed in the reader context, if
+    /// the error has one.
+    #[must_use]
+    pub(crate) fn with_path(mut self, path: ValuePath) -> Self {
+        if let Some(read_context) = self.read_context_mut() {
+            read_context.path = path;
+        }
+
+        self
+    }
+
+    /// It sets the parser state before the token that caused the error in the
+    /// reader context, if the error has one.
+    #[must_use]
+    pub(crate) fn with_state(mut self, state: State) -> Self {
+        if let Some(read_context) = self.read_context_mut() {
+            read_context.state = Some(state);
+        }
+
+        self
+    }
+
+    /// It sets the output position and the latest output bytes of the writer
+    /// context from the given writer, if the error has one. The offending
+    /// byte is kept.
+    #[must_use]
+    pub(crate) fn with_output<W: Writer>(mut self, writer: &W) -> Self {
+        if let Some(write_context) = self.write_context_mut() {
+            write_context.pos = writer.output_byte_counter();
+            write_context.latest_bytes = writer.captured_bytes();
+        }
+
+        self
+    }
+
+    fn read_context_mut(&mut self) -> Option<&mut ReadContext> {
+        match self {
+            Self::ReadByteAfterPeekingDoesMatchPeekedByte(read_context, _)
+            | Self::UnrecognizedFirstBencodeValueByte(read_context, _)
+            | Self::UnexpectedByteParsingInteger(read_context, _)
+            | Self::UnexpectedEndOfInputParsingInteger(read_context, _)
+            | Self::LeadingZerosInIntegersNotAllowed(read_context, _)
+            | Self::IntegerOverflow(read_context, _)
+            | Self::InvalidStringLengthByte(read_context, _)
+            | Self::UnexpectedEndOfInputParsingStringLength(read_context, _)
+            | Self::UnexpectedEndOfInputParsingStringValue(read_context, _)
+            | Self::StringLengthOverflow(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingFirstListItemOrEnd(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingNextListItem(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingDictFieldValue(read_context, _)
+            | Self::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(read_context, _)
+            | Self::PrematureEndOfDict(read_context, _)
+            | Self::NoMatchingStartForListOrDictEnd(read_context, _)
+            | Self::NegativeZeroNotAllowed(read_context, _)
+            | Self::LeadingZerosInStringLengthNotAllowed(read_context, _)
+            | Self::UnsortedDictKeys(read_context, _)
+            | Self::DuplicateDictKey(read_context, _)
+            | Self::LineBreakNotAllowed(read_context, _)
+            | Self::MaxDepthExceeded(read_context, _)
+            | Self::MaxStringLengthExceeded(read_context, _)
+            | Self::MaxContainerItemsExceeded(read_context, _)
+            | Self::MaxInputBytesExceeded(read_context, _)
+            | Self::TrailingData(read_context, _)
+            | Self::InvalidUtf8InStreamedString(read_context, _)
+            | Self::ExpectedStringForDictKeyGot(_, read_context, _) => Some(read_context),
+            Self::Io(_) | Self::Rw(_) | Self::Deserialize(_) => None,
+        }
+    }
+
+    /// It returns a stable identifier of the error, the snake case name of
+    /// the variant, for example `unexpected_byte_parsing_integer`. Unlike the
+    /// message, it does not change between versions.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "io",
+            Self::Rw(..) => "rw",
+            Self::ReadByteAfterPeekingDoesMatchPeekedByte(..) => {
+                "read_byte_after_peeking_does_match_peeked_byte"
+            }
+            Self::UnrecognizedFirstBencodeValueByte(..) => "unrecognized_first_bencode_value_byte",
+            Self::UnexpectedByteParsingInteger(..) => "unexpected_byte_parsing_integer",
+            Self::UnexpectedEndOfInputParsingInteger(..) => {
+                "unexpected_end_of_input_parsing_integer"
+            }
+            Self::LeadingZerosInIntegersNotAllowed(..) => "leading_zeros_in_integers_not_allowed",
+            Self::IntegerOverflow(..) => "integer_overflow",
+            Self::InvalidStringLengthByte(..) => "invalid_string_length_byte",
+            Self::UnexpectedEndOfInputParsingStringLength(..) => {
+                "unexpected_end_of_input_parsing_string_length"
+            }
+            Self::UnexpectedEndOfInputParsingStringValue(..) => {
+                "unexpected_end_of_input_parsing_string_value"
+            }
+            Self::StringLengthOverflow(..) => "string_length_overflow",
+            Self::UnexpectedEndOfInputExpectingFirstListItemOrEnd(..) => {
+                "unexpected_end_of_input_expecting_first_list_item_or_end"
+            }
+            Self::UnexpectedEndOfInputExpectingNextListItem(..) => {
+                "unexpected_end_of_input_expecting_next_list_item"
+            }
+            Self::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(..) => {
+                "unexpected_end_of_input_expecting_first_dict_field_or_end"
+            }
+            Self::UnexpectedEndOfInputExpectingDictFieldValue(..) => {
+                "unexpected_end_of_input_expecting_dict_field_value"
+            }
+            Self::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(..) => {
+                "unexpected_end_of_input_expecting_dict_field_key_or_end"
+            }
+            Self::PrematureEndOfDict(..) => "premature_end_of_dict",
+            Self::ExpectedStringForDictKeyGot(..) => "expected_string_for_dict_key_got",
+            Self::NoMatchingStartForListOrDictEnd(..) => "no_matching_start_for_list_or_dict_end",
+            Self::NegativeZeroNotAllowed(..) => "negative_zero_not_allowed",
+            Self::LeadingZerosInStringLengthNotAllowed(..) => {
+                "leading_zeros_in_string_length_not_allowed"
+            }
+            Self::UnsortedDictKeys(..) => "unsorted_dict_keys",
+            Self::DuplicateDictKey(..) => "duplicate_dict_key",
+            Self::LineBreakNotAllowed(..) => "line_break_not_allowed",
+            Self::MaxDepthExceeded(..) => "max_depth_exceeded",
+            Self::MaxStringLengthExceeded(..) => "max_string_length_exceeded",
+            Self::MaxContainerItemsExceeded(..) => "max_container_items_exceeded",
+            Self::MaxInputBytesExceeded(..) => "max_input_bytes_exceeded",
+            Self::TrailingData(..) => "trailing_data",
+            Self::InvalidUtf8InStreamedString(..) => "invalid_utf8_in_streamed_string",
+            Self::Deserialize(..) => "deserialize",
+        }
+    }
+
+    /// It returns the category of the error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(_) | Self::Rw(_) => ErrorKind::Io,
+            Self::MaxDepthExceeded(..)
+            | Self::MaxStringLengthExceeded(..)
+            | Self::MaxContainerItemsExceeded(..)
+            | Self::MaxInputBytesExceeded(..) => ErrorKind::Limit,
+            _ => ErrorKind::Syntax,
+        }
+    }
+
+    /// It returns the tokens the parser expected where the error happened,
+    /// for example ``["`0`-`9`", "`e`"]`` for an invalid byte in an integer.
+    /// It's empty when the error is not about an unexpected token.
+    #[must_use]
+    pub fn expected_tokens(&self) -> &'static [&'static str] {
+        match self {
+            Self::UnexpectedByteParsingInteger(..)
+            | Self::UnexpectedEndOfInputParsingInteger(..) => &["`0`-`9`", "`e`"],
+            Self::InvalidStringLengthByte(..)
+            | Self::UnexpectedEndOfInputParsingStringLength(..) => &["`0`-`9`", "`:`"],
+            Self::UnexpectedEndOfInputParsingStringValue(..) => &["string bytes"],
+            Self::TrailingData(..) => &["end of input"],
+            Self::UnexpectedEndOfInputExpectingFirstListItemOrEnd(..) => {
+                State::ExpectingFirstListItemOrEnd.expected_tokens()
+            }
+            Self::UnexpectedEndOfInputExpectingNextListItem(..) => {
+                State::ExpectingNextListItem.expected_tokens()
+            }
+            Self::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(..) => {
+                State::ExpectingFirstDictFieldOrEnd.expected_tokens()
+            }
+            Self::UnexpectedEndOfInputExpectingDictFieldValue(..) => {
+                State::ExpectingDictFieldValue.expected_tokens()
+            }
+            Self::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(..) => {
+                State::ExpectingDictFieldKeyOrEnd.expected_tokens()
+            }
+            Self::UnrecognizedFirstBencodeValueByte(read_context, _)
+            | Self::LineBreakNotAllowed(read_context, _)
+            | Self::PrematureEndOfDict(read_context, _)
+            | Self::NoMatchingStartForListOrDictEnd(read_context, _)
+            | Self::ExpectedStringForDictKeyGot(_, read_context, _) => read_context
+                .state
+                .as_ref()
+                .map_or(&[], State::expected_tokens),
+            _ => &[],
+        }
+    }
+
+    /// It returns `true` if the input ended before the value was finished.
+    #[must_use]
+    pub fn is_unexpected_end_of_input(&self) -> bool {
+        matches!(
+            self,
+            Self::UnexpectedEndOfInputParsingInteger(..)
+                | Self::UnexpectedEndOfInputParsingStringLength(..)
+                | Self::UnexpectedEndOfInputParsingStringValue(..)
+                | Self::UnexpectedEndOfInputExpectingFirstListItemOrEnd(..)
+                | Self::UnexpectedEndOfInputExpectingNextListItem(..)
+                | Self::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(..)
+                | Self::UnexpectedEndOfInputExpectingDictFieldValue(..)
+                | Self::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(..)
+        )
+    }
+
+    /// It returns the writer context, if the error has one.
+    #[must_use]
+    pub fn write_context(&self) -> Option<&WriteContext> {
+        match self {
+            Self::ReadByteAfterPeekingDoesMatchPeekedByte(_, write_context)
+            | Self::UnrecognizedFirstBencodeValueByte(_, write_context)
+            | Self::UnexpectedByteParsingInteger(_, write_context)
+            | Self::UnexpectedEndOfInputParsingInteger(_, write_context)
+            | Self::LeadingZerosInIntegersNotAllowed(_, write_context)
+            | Self::IntegerOverflow(_, write_context)
+            | Self::InvalidStringLengthByte(_, write_context)
+            | Self::UnexpectedEndOfInputParsingStringLength(_, write_context)
+            | Self::UnexpectedEndOfInputParsingStringValue(_, write_context)
+            | Self::StringLengthOverflow(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingFirstListItemOrEnd(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingNextListItem(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingDictFieldValue(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(_, write_context)
+            | Self::PrematureEndOfDict(_, write_context)
+            | Self::ExpectedStringForDictKeyGot(_, _, write_context)
+            | Self::NoMatchingStartForListOrDictEnd(_, write_context)
+            | Self::NegativeZeroNotAllowed(_, write_context)
+            | Self::LeadingZerosInStringLengthNotAllowed(_, write_context)
+            | Self::UnsortedDictKeys(_, write_context)
+            | Self::DuplicateDictKey(_, write_context)
+            | Self::LineBreakNotAllowed(_, write_context)
+            | Self::MaxDepthExceeded(_, write_context)
+            | Self::MaxStringLengthExceeded(_, write_context)
+            | Self::MaxContainerItemsExceeded(_, write_context)
+            | Self::MaxInputBytesExceeded(_, write_context)
+            | Self::TrailingData(_, write_context)
+            | Self::InvalidUtf8InStreamedString(_, write_context) => Some(write_context),
+            Self::Io(_) | Self::Rw(_) | Self::Deserialize(_) => None,
+        }
+    }
+
+    fn write_context_mut(&mut self) -> Option<&mut WriteContext> {
+        match self {
+            Self::ReadByteAfterPeekingDoesMatchPeekedByte(_, write_context)
+            | Self::UnrecognizedFirstBencodeValueByte(_, write_context)
+            | Self::UnexpectedByteParsingInteger(_, write_context)
+            | Self::UnexpectedEndOfInputParsingInteger(_, write_context)
+            | Self::LeadingZerosInIntegersNotAllowed(_, write_context)
+            | Self::IntegerOverflow(_, write_context)
+            | Self::InvalidStringLengthByte(_, write_context)
+            | Self::UnexpectedEndOfInputParsingStringLength(_, write_context)
+            | Self::UnexpectedEndOfInputParsingStringValue(_, write_context)
+            | Self::StringLengthOverflow(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingFirstListItemOrEnd(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingNextListItem(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingDictFieldValue(_, write_context)
+            | Self::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(_, write_context)
+            | Self::PrematureEndOfDict(_, write_context)
+            | Self::ExpectedStringForDictKeyGot(_, _, write_context)
+            | Self::NoMatchingStartForListOrDictEnd(_, write_context)
+            | Self::NegativeZeroNotAllowed(_, write_context)
+            | Self::LeadingZerosInStringLengthNotAllowed(_, write_context)
+            | Self::UnsortedDictKeys(_, write_context)
+            | Self::DuplicateDictKey(_, write_context)
+            | Self::LineBreakNotAllowed(_, write_context)
+            | Self::MaxDepthExceeded(_, write_context)
+            | Self::MaxStringLengthExceeded(_, write_context)
+            | Self::MaxContainerItemsExceeded(_, write_context)
+            | Self::MaxInputBytesExceeded(_, write_context)
+            | Self::TrailingData(_, write_context)
+            | Self::InvalidUtf8InStreamedString(_, write_context) => Some(write_context),
+            Self::Io(_) | Self::Rw(_) | Self::Deserialize(_) => None,
+        }
+    }
+
+    /// It returns the error as a JSON object with the [`code`](Self::code),
+    /// the [`kind`](Self::kind), the message and, when the error has them,
+    /// the contexts:
+    ///
+    /// ```json
+    /// {
+    ///   "code": "unexpected_byte_parsing_integer",
+    ///   "input": {"byte": 120, "latest_bytes": [108, 105, 49, 120], "path": "[0]", "pos": 4},
+    ///   "kind": "syntax",
+    ///   "message": "Unexpected byte parsing integer; read context: ...",
+    ///   "output": {"byte": 120, "latest_bytes": [49], "pos": 1}
+    /// }
+    /// ```
+    ///
+    /// Fields are sorted by name. The `path` is empty for top-level values.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.report(true)
+    }
+
+    /// It returns the JSON object of [`to_json`](Self::to_json) without the
+    /// `output` context, also removed from the message. It's meant for
+    /// errors of parsers whose output is discarded, for example when the
+    /// input is only validated.
+    #[must_use]
+    pub fn to_json_without_output(&self) -> String {
+        self.report(false)
+    }
+
+    /// It returns the message without the writer context. It's meant for
+    /// errors of parsers whose output is discarded, for example when the
+    /// input is only validated.
+    #[must_use]
+    pub fn to_string_without_output(&self) -> String {
+        let message = self.to_string();
+
+        match self.write_context() {
+            Some(write_context) => message
+                .strip_suffix(&format!("; {write_context}"))
+                .map_or_else(|| message.clone(), ToString::to_string),
+            None => message,
+        }
+    }
+
+    fn report(&self, with_output: bool) -> String {
+        let mut report = json!({
+            "code": self.code(),
+            "kind": self.kind().to_string(),
+            "message": if with_output { self.to_string() } else { self.to_string_without_output() },
+        });
+
+        if let Some(read_context) = self.read_context() {
+            report["input"] = json!({
+                "pos": read_context.pos,
+                "byte": read_context.byte,
+                "path": read_context.path.to_string(),
+                "latest_bytes": read_context.latest_bytes,
+            });
+        }
+
+        match self.write_context() {
+            Some(write_context) if with_output => {
+                report["output"] = json!({
+                    "pos": write_context.pos,
+                    "byte": write_context.byte,
+                    "latest_bytes": write_context.latest_bytes,
+                });
+            }
+            _ => {}
+        }
+
+        report.to_string()
+    }
+}
+
+/// The category of an [`Error`](enum@Error).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ErrorKind {
+    /// It can't read from the input or write to the output.
+    #[display("io")]
+    Io,
+
+    /// The input is not valid bencode, or it's not accepted with the parser
+    /// options.
+    #[display("syntax")]
+    Syntax,
+
+    /// The input exceeds one of the limits for untrusted input.
+    #[display("limit")]
+    Limit,
 }
 
 /// The reader context when the error ocurred.
@@ -92,6 +547,14 @@ pub struct ReadContext {
 
     /// The latest bytes read from input.
     pub latest_bytes: Vec<u8>,
+
+    /// The path of the value being parsed, for example
+    /// `info.files[12].path[0]`.
+    pub path: ValuePath,
+
+    /// The parser state before the token that caused the error, if known. It
+    /// determines the tokens that were expected.
+    pub state: Option<State>,
 }
 
 impl fmt::Display for ReadContext {
@@ -103,11 +566,13 @@ impl fmt::Display for ReadContext {
             Some(byte) => write!(f, " byte `{}` (char: `{}`),", byte, byte as char)?,
         }
 
-        write!(
-            f,
-            " input pos {}, latest input bytes dump: {:?}",
-            self.pos, self.latest_bytes
-        )?;
+        write!(f, " input pos {},", self.pos)?;
+
+        if !self.path.is_empty() {
+            write!(f, " path `{}`,", self.path)?;
+        }
+
+        write!(f, " latest input bytes dump: {:?}", self.latest_bytes)?;
 
         if let Ok(utf8_string) = str::from_utf8(&self.latest_bytes) {
             write!(f, " (UTF-8 string: `{utf8_string}`)")?;
@@ -155,9 +620,128 @@ impl fmt::Display for WriteContext {
 
 #[cfg(test)]
 mod tests {
+    mod for_error {
+        use std::io;
+
+        use crate::parsers::{
+            error::{Error, ErrorKind},
+            BencodeParser,
+        };
+
+        fn parse_error(input: &[u8]) -> Error {
+            let mut output = String::new();
+
+            BencodeParser::new(input)
+                .write_str(&mut output)
+                .unwrap_err()
+        }
+
+        #[test]
+        fn it_should_have_a_stable_code() {
+            assert_eq!(
+                parse_error(b"li1ei2xe").code(),
+                "unexpected_byte_parsing_integer"
+            );
+            assert_eq!(Error::Io(io::Error::other("broken pipe")).code(), "io");
+        }
+
+        #[test]
+        fn it_should_have_a_kind() {
+            assert_eq!(parse_error(b"x").kind(), ErrorKind::Syntax);
+            assert_eq!(
+                Error::Io(io::Error::other("broken pipe")).kind(),
+                ErrorKind::Io
+            );
+        }
+
+        #[test]
+        fn it_should_list_the_tokens_expected_by_the_token_kind() {
+            assert_eq!(parse_error(b"i12xe").expected_tokens(), ["`0`-`9`", "`e`"]);
+            assert_eq!(parse_error(b"1x:a").expected_tokens(), ["`0`-`9`", "`:`"]);
+            assert_eq!(parse_error(b"3:ab").expected_tokens(), ["string bytes"]);
+        }
+
+        #[test]
+        fn it_should_list_the_tokens_expected_in_the_parser_state() {
+            assert_eq!(parse_error(b"dxe").expected_tokens(), ["`0`-`9`", "`e`"]);
+            assert_eq!(
+                parse_error(b"li1ex").expected_tokens(),
+                ["`i`", "`l`", "`d`", "`0`-`9`", "`e`"]
+            );
+            assert_eq!(
+                parse_error(b"d3:foo").expected_tokens(),
+                ["`i`", "`l`", "`d`", "`0`-`9`"]
+            );
+        }
+
+        #[test]
+        fn it_should_not_list_expected_tokens_for_invalid_values() {
+            assert!(parse_error(b"i00e").expected_tokens().is_empty());
+        }
+
+        #[test]
+        fn it_should_be_converted_to_a_json_object_with_structured_fields() {
+            let json: serde_json::Value =
+                serde_json::from_str(&parse_error(b"li1ei2xe").to_json()).unwrap();
+
+            assert_eq!(json["code"], "unexpected_byte_parsing_integer");
+            assert_eq!(json["kind"], "syntax");
+            assert!(json["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Unexpected byte parsing integer"));
+            assert_eq!(json["input"]["pos"], 7);
+            assert_eq!(json["input"]["byte"], b'x');
+            assert_eq!(json["input"]["path"], "[1]");
+            assert_eq!(
+                json["input"]["latest_bytes"],
+                serde_json::json!(b"li1ei2x".to_vec())
+            );
+            assert_eq!(
+                json["output"]["latest_bytes"],
+                serde_json::json!(b"[1".to_vec())
+            );
+        }
+
+        #[test]
+        fn it_should_leave_out_the_output_context_when_the_output_is_discarded() {
+            let error = parse_error(b"li1ei2xe");
+
+            assert!(error.to_string().contains("write context"));
+            assert!(error
+                .to_string_without_output()
+                .ends_with("latest input bytes dump: [108, 105, 49, 101, 105, 50, 120] (UTF-8 string: `li1ei2x`)"));
+
+            let json: serde_json::Value =
+                serde_json::from_str(&error.to_json_without_output()).unwrap();
+
+            assert_eq!(json["message"], error.to_string_without_output());
+            assert_eq!(json["input"]["pos"], 7);
+            assert!(json.get("output").is_none());
+        }
+
+        #[test]
+        fn errors_without_contexts_should_only_have_the_code_kind_and_message() {
+            let json: serde_json::Value =
+                serde_json::from_str(&Error::Io(io::Error::other("broken pipe")).to_json())
+                    .unwrap();
+
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "code": "io",
+                    "kind": "io",
+                    "message": "I/O error: broken pipe",
+                })
+            );
+        }
+    }
 
     mod for_read_context {
-        use crate::parsers::error::ReadContext;
+        use crate::parsers::{
+            error::ReadContext,
+            path::{PathSegment, ValuePath},
+        };
 
         #[test]
         fn it_should_display_the_read_context() {
@@ -165,6 +749,8 @@ mod tests {
                 byte: Some(b'a'),
                 pos: 10,
                 latest_bytes: vec![b'a', b'b', b'c'],
+                path: ValuePath::default(),
+                state: None,
             };
 
             assert_eq!( read_context.to_string(),"read context: byte `97` (char: `a`), input pos 10, latest input bytes dump: [97, 98, 99] (UTF-8 string: `abc`)");
@@ -176,6 +762,8 @@ mod tests {
                 byte: None,
                 pos: 10,
                 latest_bytes: vec![b'a', b'b', b'c'],
+                path: ValuePath::default(),
+                state: None,
             };
 
             assert_eq!(read_context.to_string(), "read context: input pos 10, latest input bytes dump: [97, 98, 99] (UTF-8 string: `abc`)");
@@ -187,6 +775,8 @@ mod tests {
                 byte: None,
                 pos: 10,
                 latest_bytes: vec![b'\xFF', b'\xFE'],
+                path: ValuePath::default(),
+                state: None,
             };
 
             assert_eq!(
@@ -194,6 +784,22 @@ mod tests {
                 "read context: input pos 10, latest input bytes dump: [255, 254]"
             );
         }
+
+        #[test]
+        fn it_should_display_the_path_of_the_value_being_parsed() {
+            let read_context = ReadContext {
+                byte: None,
+                pos: 10,
+                latest_bytes: vec![b'a'],
+                path: ValuePath::new(vec![
+                    PathSegment::Key(b"files".to_vec()),
+                    PathSegment::Index(12),
+                ]),
+                state: None,
+            };
+
+            assert_eq!(read_context.to_string(), "read context: input pos 10, path `files[12]`, latest input bytes dump: [97] (UTF-8 string: `a`)");
+        }
     }
 
     mod for_write_context {
@@ -0,0 +1,333 @@
+//! Events produced by the pull-based parser API.
+//!
+//! The [`BencodeParser`] can be used as a tokenizer. Instead of writing JSON,
+//! it returns one [`Event`] for each token in the input:
+//!
+//! ```text
+//! d3:fooli1ei2eee -> DictStart, Key("foo"), ListStart, Integer(1), Integer(2), End, End
+//! ```
+//!
+//! Events are validated with the same state machine used to generate JSON, so
+//! consumers always receive well-formed sequences of events.
+//!
+//! Strings longer than the
+//! [`string_buffer_size`](super::options::ParserOptions::string_buffer_size)
+//! are returned in chunks of that size, so they are not kept in memory:
+//!
+//! ```text
+//! 9:egg bacon -> BytesStart(9), BytesChunk("egg b"), BytesChunk("acon")
+//! ```
+//!
+//! Other strings, and dictionary keys, are returned whole in one event. Use
+//! [`max_string_length`](super::options::ParserOptions::max_string_length) to
+//! bound the memory used for untrusted input.
+use std::{fmt, io::Read};
+
+use super::{error::Error, BencodeParser};
+
+/// A token of the bencoded input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The beginning of a list.
+    ListStart,
+
+    /// The beginning of a dictionary.
+    DictStart,
+
+    /// The raw bytes of a dictionary field key.
+    Key(Vec<u8>),
+
+    /// An integer.
+    Integer(Integer),
+
+    /// The raw bytes of a string that is not a dictionary field key. Strings
+    /// longer than the
+    /// [`string_buffer_size`](super::options::ParserOptions::string_buffer_size)
+    /// are returned with [`BytesStart`](Self::BytesStart) and
+    /// [`BytesChunk`](Self::BytesChunk) instead.
+    Bytes(Vec<u8>),
+
+    /// The beginning of a string that is not a dictionary field key, longer
+    /// than the
+    /// [`string_buffer_size`](super::options::ParserOptions::string_buffer_size).
+    /// It has the length of the string, whose raw bytes are returned by the
+    /// next [`BytesChunk`](Self::BytesChunk) events.
+    BytesStart(usize),
+
+    /// The next raw bytes of the string begun with
+    /// [`BytesStart`](Self::BytesStart), up to the `string_buffer_size`. The
+    /// string ends with the chunk completing its length.
+    BytesChunk(Vec<u8>),
+
+    /// The end of the current list or dictionary.
+    End,
+}
+
+/// A bencoded integer.
+///
+/// Bencoded integers have no size limit, so the original digits are kept. Use
+/// [`Integer::to_i64`] to get the value when it fits into an `i64`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Integer {
+    digits: String,
+}
+
+impl Integer {
+    pub(crate) fn new(digits: String) -> Self {
+        Self { digits }
+    }
+
+    /// It returns the integer digits, including the minus sign for negative
+    /// integers.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.digits
+    }
+
+    /// It returns the integer if it fits into an `i64`.
+    #[must_use]
+    pub fn to_i64(&self) -> Option<i64> {
+        self.digits.parse().ok()
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.digits)
+    }
+}
+
+/// An iterator over the events of a [`BencodeParser`].
+///
+/// It stops after the first error.
+pub struct Events<'a, R: Read> {
+    parser: &'a mut BencodeParser<R>,
+    failed: bool,
+}
+
+impl<'a, R: Read> Events<'a, R> {
+    pub(crate) fn new(parser: &'a mut BencodeParser<R>) -> Self {
+        Self {
+            parser,
+            failed: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Events<'_, R> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.parser.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{error::Error, BencodeParser};
+
+    use super::{Event, Integer};
+
+    fn events(input: &[u8]) -> Vec<Result<Event, Error>> {
+        BencodeParser::new(input).events().collect()
+    }
+
+    fn valid_events(input: &[u8]) -> Vec<Event> {
+        BencodeParser::new(input)
+            .events()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn integer(digits: &str) -> Event {
+        Event::Integer(Integer::new(digits.to_string()))
+    }
+
+    mod it_should_produce_events_for {
+        use crate::parsers::{
+            event::{
+                tests::{integer, valid_events},
+                Event,
+            },
+            options::BencodeParserBuilder,
+        };
+
+        #[test]
+        fn integers() {
+            assert_eq!(valid_events(b"i-42e"), vec![integer("-42")]);
+        }
+
+        #[test]
+        fn strings() {
+            assert_eq!(
+                valid_events(b"2:\xFF\xFE"),
+                vec![Event::Bytes(b"\xFF\xFE".to_vec())]
+            );
+        }
+
+        #[test]
+        fn lists() {
+            assert_eq!(
+                valid_events(b"li1e4:spame"),
+                vec![
+                    Event::ListStart,
+                    integer("1"),
+                    Event::Bytes(b"spam".to_vec()),
+                    Event::End
+                ]
+            );
+        }
+
+        #[test]
+        fn dictionaries_distinguishing_keys_from_string_values() {
+            assert_eq!(
+                valid_events(b"d3:foo3:bar3:bazli1eee"),
+                vec![
+                    Event::DictStart,
+                    Event::Key(b"foo".to_vec()),
+                    Event::Bytes(b"bar".to_vec()),
+                    Event::Key(b"baz".to_vec()),
+                    Event::ListStart,
+                    integer("1"),
+                    Event::End,
+                    Event::End
+                ]
+            );
+        }
+
+        #[test]
+        fn many_top_level_values_ignoring_line_breaks() {
+            assert_eq!(
+                valid_events(b"i1e\n4:spam\n"),
+                vec![integer("1"), Event::Bytes(b"spam".to_vec())]
+            );
+        }
+
+        #[test]
+        fn long_strings_in_chunks() {
+            let events: Vec<Event> = BencodeParserBuilder::default()
+                .string_buffer_size(4)
+                .build(&b"l9:egg bacon4:spame"[..])
+                .events()
+                .collect::<Result<_, _>>()
+                .unwrap();
+
+            assert_eq!(
+                events,
+                vec![
+                    Event::ListStart,
+                    Event::BytesStart(9),
+                    Event::BytesChunk(b"egg ".to_vec()),
+                    Event::BytesChunk(b"baco".to_vec()),
+                    Event::BytesChunk(b"n".to_vec()),
+                    Event::Bytes(b"spam".to_vec()),
+                    Event::End
+                ]
+            );
+        }
+
+        #[test]
+        fn an_empty_input() {
+            assert_eq!(valid_events(b""), vec![]);
+        }
+    }
+
+    mod the_integer {
+        use crate::parsers::event::Integer;
+
+        #[test]
+        fn it_should_keep_the_original_digits() {
+            let big_integer = format!("{}1", i64::MAX);
+
+            let integer = Integer::new(big_integer.clone());
+
+            assert_eq!(integer.as_str(), big_integer);
+            assert_eq!(integer.to_string(), big_integer);
+        }
+
+        #[test]
+        fn it_should_be_converted_into_an_i64_when_it_fits() {
+            assert_eq!(Integer::new("-42".to_string()).to_i64(), Some(-42));
+            assert_eq!(Integer::new(format!("{}1", i64::MAX)).to_i64(), None);
+        }
+    }
+
+    mod it_should_stop_after_the_first_error {
+        use crate::parsers::{
+            error::Error,
+            event::{tests::events, Event},
+            options::BencodeParserBuilder,
+        };
+
+        #[test]
+        fn with_dictionary_keys_that_are_not_strings() {
+            let events = events(b"di1ei2ee");
+
+            assert_eq!(events.len(), 2);
+            assert!(matches!(events[0], Ok(Event::DictStart)));
+            assert!(matches!(
+                events[1],
+                Err(Error::ExpectedStringForDictKeyGot(..))
+            ));
+        }
+
+        #[test]
+        fn with_unfinished_lists() {
+            let events = events(b"li1e");
+
+            assert_eq!(events.len(), 3);
+            assert!(matches!(
+                events[2],
+                Err(Error::UnexpectedEndOfInputExpectingNextListItem(..))
+            ));
+        }
+
+        #[test]
+        fn with_unfinished_chunked_strings() {
+            let events: Vec<_> = BencodeParserBuilder::default()
+                .string_buffer_size(2)
+                .build(&b"5:spa"[..])
+                .events()
+                .collect();
+
+            assert_eq!(events.len(), 3);
+            assert!(matches!(events[0], Ok(Event::BytesStart(5))));
+            assert!(matches!(events[1], Ok(Event::BytesChunk(_))));
+            assert!(matches!(
+                events[2],
+                Err(Error::UnexpectedEndOfInputParsingStringValue(..))
+            ));
+        }
+
+        #[test]
+        fn with_unrecognized_bytes() {
+            let events = events(b"l1:ax");
+
+            assert_eq!(events.len(), 3);
+            assert!(matches!(
+                events[2],
+                Err(Error::UnrecognizedFirstBencodeValueByte(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn it_should_allow_pulling_events_one_by_one() {
+        let mut parser = BencodeParser::new(&b"le"[..]);
+
+        assert_eq!(parser.next_event().unwrap(), Some(Event::ListStart));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::End));
+        assert_eq!(parser.next_event().unwrap(), None);
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+}
@@ -7,124 +7,211 @@ use crate::rw::{byte_reader::ByteReader, writer::Writer};
 
 use super::{
     error::{Error, ReadContext, WriteContext},
+    options::ParserOptions,
+    path::ValuePath,
     BENCODE_END_INTEGER,
 };
 
 /// The current state parsing the integer.
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 #[allow(clippy::enum_variant_names)]
 enum StateExpecting {
-    Start,          // S
+    #[default]
+    Start, // S
     DigitOrSign,    // DoS
     DigitAfterSign, // DaS
     DigitOrEnd,     // DoE
 }
 
-/// It parses an integer bencoded value.
-///
-/// # Errors
-///
-/// Will return an error if it can't read from the input or write to the
-/// output.
-///
-/// # Panics
-///
-/// Will panic if we reach the end of the input without completing the integer
-/// (without reaching the end of the integer `e`).
-pub fn parse<R: Read, W: Writer>(reader: &mut ByteReader<R>, writer: &mut W) -> Result<(), Error> {
-    let mut state = StateExpecting::Start;
-    let mut first_digit_is_zero = false;
+/// The meaning of a byte accepted by the integer [`StateMachine`].
+#[derive(Debug, PartialEq)]
+pub enum Step {
+    /// The integer begin byte `i`. It's not part of the integer value.
+    Begin,
 
-    loop {
-        let byte = next_byte(reader, writer)?;
+    /// A byte of the integer value: a digit or the minus sign.
+    Digit,
+
+    /// The integer end byte `e`. It's not part of the integer value.
+    End,
+}
+
+/// Bytes rejected by the integer [`StateMachine`].
+#[derive(Debug, PartialEq)]
+pub enum InvalidByte {
+    /// The byte is not allowed in the current state.
+    Unexpected,
+
+    /// A digit after a leading zero, for example the second `0` in `i00e`.
+    LeadingZero,
+}
+
+impl InvalidByte {
+    /// It converts the invalid byte into a parser error with the given
+    /// contexts.
+    #[must_use]
+    pub fn into_error(self, read_context: ReadContext, write_context: WriteContext) -> Error {
+        match self {
+            InvalidByte::Unexpected => {
+                Error::UnexpectedByteParsingInteger(read_context, write_context)
+            }
+            InvalidByte::LeadingZero => {
+                Error::LeadingZerosInIntegersNotAllowed(read_context, write_context)
+            }
+        }
+    }
+}
 
+/// The integer validation rules.
+///
+/// It receives the bencoded integer bytes one by one, including the begin
+/// and end bytes. It does not read or write anything, so it can be shared by
+/// parsers reading from different sources.
+#[derive(Debug, Default)]
+pub struct StateMachine {
+    state: StateExpecting,
+    first_digit_is_zero: bool,
+    negative: bool,
+    num_digits: usize,
+
+    /// The integer value received so far, if it fits into an `i64`.
+    value: i64,
+    overflow: bool,
+}
+
+impl StateMachine {
+    /// It validates the next integer byte.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the byte is not allowed in the current state.
+    pub fn feed(&mut self, byte: u8) -> Result<Step, InvalidByte> {
         let char = byte as char;
 
-        state = match state {
+        match self.state {
             StateExpecting::Start => {
                 // Discard the 'i' byte
-                StateExpecting::DigitOrSign
+                self.state = StateExpecting::DigitOrSign;
+                Ok(Step::Begin)
             }
             StateExpecting::DigitOrSign => {
                 if char == '-' {
-                    writer.write_byte(byte)?;
-
-                    StateExpecting::DigitAfterSign
+                    self.negative = true;
+                    self.state = StateExpecting::DigitAfterSign;
+                    Ok(Step::Digit)
                 } else if char.is_ascii_digit() {
-                    writer.write_byte(byte)?;
-
-                    if char == '0' {
-                        first_digit_is_zero = true;
-                    }
-
-                    StateExpecting::DigitOrEnd
+                    self.first_digit_is_zero = char == '0';
+                    self.add_digit(byte);
+                    self.state = StateExpecting::DigitOrEnd;
+                    Ok(Step::Digit)
                 } else {
-                    return Err(Error::UnexpectedByteParsingInteger(
-                        ReadContext {
-                            byte: Some(byte),
-                            pos: reader.input_byte_counter(),
-                            latest_bytes: reader.captured_bytes(),
-                        },
-                        WriteContext {
-                            byte: Some(byte),
-                            pos: writer.output_byte_counter(),
-                            latest_bytes: writer.captured_bytes(),
-                        },
-                    ));
+                    Err(InvalidByte::Unexpected)
                 }
             }
             StateExpecting::DigitAfterSign => {
                 if char.is_ascii_digit() {
-                    writer.write_byte(byte)?;
-
-                    if char == '0' {
-                        first_digit_is_zero = true;
-                    }
-
-                    StateExpecting::DigitOrEnd
+                    self.first_digit_is_zero = char == '0';
+                    self.add_digit(byte);
+                    self.state = StateExpecting::DigitOrEnd;
+                    Ok(Step::Digit)
                 } else {
-                    return Err(Error::UnexpectedByteParsingInteger(
-                        ReadContext {
-                            byte: Some(byte),
-                            pos: reader.input_byte_counter(),
-                            latest_bytes: reader.captured_bytes(),
-                        },
-                        WriteContext {
-                            byte: Some(byte),
-                            pos: writer.output_byte_counter(),
-                            latest_bytes: writer.captured_bytes(),
-                        },
-                    ));
+                    Err(InvalidByte::Unexpected)
                 }
             }
             StateExpecting::DigitOrEnd => {
                 if char.is_ascii_digit() {
-                    writer.write_byte(byte)?;
-
-                    if char == '0' && first_digit_is_zero {
-                        return Err(Error::LeadingZerosInIntegersNotAllowed(
-                            ReadContext {
-                                byte: Some(byte),
-                                pos: reader.input_byte_counter(),
-                                latest_bytes: reader.captured_bytes(),
-                            },
-                            WriteContext {
-                                byte: Some(byte),
-                                pos: writer.output_byte_counter(),
-                                latest_bytes: writer.captured_bytes(),
-                            },
-                        ));
+                    if char == '0' && self.first_digit_is_zero {
+                        return Err(InvalidByte::LeadingZero);
                     }
 
-                    StateExpecting::DigitOrEnd
+                    self.add_digit(byte);
+                    Ok(Step::Digit)
                 } else if byte == BENCODE_END_INTEGER {
-                    return Ok(());
+                    Ok(Step::End)
                 } else {
-                    return Err(Error::UnexpectedByteParsingInteger(
+                    Err(InvalidByte::Unexpected)
+                }
+            }
+        }
+    }
+
+    /// It returns `true` if the integer received so far fits into an `i64`.
+    #[must_use]
+    pub fn fits_i64(&self) -> bool {
+        !self.overflow
+    }
+
+    /// It adds a new digit to the integer value, checking if it still fits
+    /// into an `i64`.
+    fn add_digit(&mut self, byte: u8) {
+        let digit = i64::from(byte - b'0');
+
+        self.num_digits += 1;
+
+        let value = self.value.checked_mul(10).and_then(|value| {
+            if self.negative {
+                value.checked_sub(digit)
+            } else {
+                value.checked_add(digit)
+            }
+        });
+
+        match value {
+            Some(value) => self.value = value,
+            None => self.overflow = true,
+        }
+    }
+
+    /// It returns `true` if the integer received so far is a negative zero:
+    /// `-0`.
+    #[must_use]
+    pub fn is_negative_zero(&self) -> bool {
+        self.negative && self.first_digit_is_zero && self.num_digits == 1
+    }
+
+    /// It returns `true` if the integer received so far has leading zeros.
+    /// Only a leading zero followed by another zero is rejected while
+    /// feeding bytes, so `01` is accepted unless it's checked.
+    #[must_use]
+    pub fn has_leading_zeros(&self) -> bool {
+        self.first_digit_is_zero && self.num_digits > 1
+    }
+}
+
+/// It parses an integer bencoded value.
+///
+/// # Errors
+///
+/// Will return an error if it can't read from the input or write to the
+/// output.
+///
+/// # Panics
+///
+/// Will panic if we reach the end of the input without completing the integer
+/// (without reaching the end of the integer `e`).
+pub fn parse<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &mut W,
+    options: &ParserOptions,
+) -> Result<(), Error> {
+    let mut state_machine = StateMachine::default();
+
+    loop {
+        let byte = next_byte(reader, writer)?;
+
+        match state_machine.feed(byte) {
+            Ok(Step::Begin) => {}
+            Ok(Step::Digit) => {
+                writer.write_byte(byte)?;
+
+                if options.i64_integers && !state_machine.fits_i64() {
+                    return Err(Error::IntegerOverflow(
                         ReadContext {
                             byte: Some(byte),
                             pos: reader.input_byte_counter(),
                             latest_bytes: reader.captured_bytes(),
+                            path: ValuePath::default(),
+                            state: None,
                         },
                         WriteContext {
                             byte: Some(byte),
@@ -134,10 +221,74 @@ pub fn parse<R: Read, W: Writer>(reader: &mut ByteReader<R>, writer: &mut W) ->
                     ));
                 }
             }
-        };
+            Ok(Step::End) => {
+                if options.strict {
+                    check_canonical(&state_machine, byte, reader, writer)?;
+                }
+
+                return Ok(());
+            }
+            Err(invalid_byte) => {
+                if invalid_byte == InvalidByte::LeadingZero {
+                    // The leading zero is part of the output before failing
+                    writer.write_byte(byte)?;
+                }
+
+                return Err(invalid_byte.into_error(
+                    ReadContext {
+                        byte: Some(byte),
+                        pos: reader.input_byte_counter(),
+                        latest_bytes: reader.captured_bytes(),
+                        path: ValuePath::default(),
+                        state: None,
+                    },
+                    WriteContext {
+                        byte: Some(byte),
+                        pos: writer.output_byte_counter(),
+                        latest_bytes: writer.captured_bytes(),
+                    },
+                ));
+            }
+        }
     }
 }
 
+/// It checks that the finished integer uses the canonical encoding: no
+/// negative zero and no leading zeros.
+///
+/// # Errors
+///
+/// Will return an error if the integer is not canonical.
+fn check_canonical<R: Read, W: Writer>(
+    state_machine: &StateMachine,
+    byte: u8,
+    reader: &ByteReader<R>,
+    writer: &W,
+) -> Result<(), Error> {
+    let error = if state_machine.is_negative_zero() {
+        Error::NegativeZeroNotAllowed
+    } else if state_machine.has_leading_zeros() {
+        Error::LeadingZerosInIntegersNotAllowed
+    } else {
+        return Ok(());
+    };
+
+    Err(error(
+        ReadContext {
+            byte: Some(byte),
+            pos: reader.input_byte_counter(),
+            latest_bytes: reader.captured_bytes(),
+            path: ValuePath::default(),
+            state: None,
+        },
+        WriteContext {
+            byte: None,
+            pos: writer.output_byte_counter(),
+            latest_bytes: writer.captured_bytes(),
+        },
+    ))
+}
+
 /// It reads the next byte from the input.
 ///
 /// # Errors
@@ -153,6 +304,8 @@ fn next_byte<R: Read, W: Writer>(reader: &mut ByteReader<R>, writer: &W) -> Resu
                         byte: None,
                         pos: reader.input_byte_counter(),
                         latest_bytes: reader.captured_bytes(),
+                        path: ValuePath::default(),
+                        state: None,
                     },
                     WriteContext {
                         byte: None,
@@ -169,7 +322,7 @@ fn next_byte<R: Read, W: Writer>(reader: &mut ByteReader<R>, writer: &W) -> Resu
 #[cfg(test)]
 mod tests {
     use crate::{
-        parsers::{error::Error, integer::parse},
+        parsers::{error::Error, integer::parse, options::ParserOptions},
         rw::{byte_reader::ByteReader, string_writer::StringWriter},
     };
 
@@ -195,7 +348,7 @@ mod tests {
 
         let mut writer = StringWriter::new(output);
 
-        parse(&mut reader, &mut writer)
+        parse(&mut reader, &mut writer, &ParserOptions::default())
     }
 
     mod for_helpers {
@@ -232,6 +385,78 @@ mod tests {
         assert_eq!(bencode_to_json_unchecked(b"i-1e"), "-1".to_string());
     }
 
+    mod the_state_machine {
+        use crate::parsers::integer::{InvalidByte, StateMachine, Step};
+
+        fn feed_all(bytes: &[u8]) -> Result<Vec<Step>, InvalidByte> {
+            let mut state_machine = StateMachine::default();
+
+            bytes.iter().map(|byte| state_machine.feed(*byte)).collect()
+        }
+
+        #[test]
+        fn should_classify_the_integer_bytes() {
+            assert_eq!(
+                feed_all(b"i-10e").unwrap(),
+                vec![
+                    Step::Begin,
+                    Step::Digit,
+                    Step::Digit,
+                    Step::Digit,
+                    Step::End
+                ]
+            );
+        }
+
+        #[test]
+        fn should_reject_leading_zeros() {
+            assert_eq!(feed_all(b"i-00e").unwrap_err(), InvalidByte::LeadingZero);
+        }
+
+        #[test]
+        fn should_reject_unexpected_bytes() {
+            assert_eq!(feed_all(b"i1-e").unwrap_err(), InvalidByte::Unexpected);
+        }
+
+        fn state_machine_after(bytes: &[u8]) -> StateMachine {
+            let mut state_machine = StateMachine::default();
+
+            for byte in bytes {
+                state_machine.feed(*byte).unwrap();
+            }
+
+            state_machine
+        }
+
+        #[test]
+        fn should_check_if_the_integer_fits_into_an_i64() {
+            let max = format!("i{}e", i64::MAX);
+            let min = format!("i{}e", i64::MIN);
+            let above_max = format!("i{}e", i128::from(i64::MAX) + 1);
+            let below_min = format!("i{}e", i128::from(i64::MIN) - 1);
+
+            assert!(state_machine_after(max.as_bytes()).fits_i64());
+            assert!(state_machine_after(min.as_bytes()).fits_i64());
+            assert!(!state_machine_after(above_max.as_bytes()).fits_i64());
+            assert!(!state_machine_after(below_min.as_bytes()).fits_i64());
+        }
+
+        #[test]
+        fn should_detect_negative_zeros() {
+            assert!(state_machine_after(b"i-0e").is_negative_zero());
+            assert!(!state_machine_after(b"i0e").is_negative_zero());
+            assert!(!state_machine_after(b"i-10e").is_negative_zero());
+        }
+
+        #[test]
+        fn should_detect_leading_zeros_not_rejected_while_feeding_bytes() {
+            assert!(state_machine_after(b"i01e").has_leading_zeros());
+            assert!(state_machine_after(b"i-01e").has_leading_zeros());
+            assert!(!state_machine_after(b"i0e").has_leading_zeros());
+            assert!(!state_machine_after(b"i10e").has_leading_zeros());
+        }
+    }
+
     mod it_should_fail {
         use std::io::{self, Read};
 
@@ -239,6 +464,7 @@ mod tests {
             parsers::{
                 error::Error,
                 integer::{parse, tests::try_bencode_to_json},
+                options::ParserOptions,
             },
             rw::{byte_reader::ByteReader, string_writer::StringWriter},
         };
@@ -353,7 +579,7 @@ mod tests {
             let mut output = String::new();
             let mut writer = StringWriter::new(&mut output);
 
-            let result = parse(&mut reader, &mut writer);
+            let result = parse(&mut reader, &mut writer, &ParserOptions::default());
 
             assert!(matches!(result, Err(Error::Io(_))));
         }
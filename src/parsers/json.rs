@@ -0,0 +1,666 @@
+//! JSON consumer of the parser events.
+//!
+//! It writes the JSON value corresponding to a sequence of events. It uses its
+//! own [`Stack`] to know which delimiters have to be written before each value,
+//! and how deep values have to be indented when pretty-printing.
+use super::{
+    error::Error,
+    event::Event,
+    options::{MultipleValues, ParserOptions},
+    recovery::CORRUPTION_MARKER,
+    stack::{Stack, State},
+    string,
+    string_stream::StringStream,
+    torrent::{self, InfoHash, INFO_HASH_FIELD},
+    tracker, BencodeType,
+};
+use crate::rw::{string_writer::StringWriter, writer::Writer};
+
+const JSON_ARRAY_BEGIN: u8 = b'[';
+const JSON_ARRAY_ITEMS_SEPARATOR: u8 = b',';
+const JSON_ARRAY_END: u8 = b']';
+
+const JSON_OBJ_BEGIN: u8 = b'{';
+const JSON_OBJ_FIELDS_SEPARATOR: u8 = b',';
+const JSON_OBJ_FIELD_KEY_VALUE_SEPARATOR: u8 = b':';
+const JSON_OBJ_END: u8 = b'}';
+
+const JSON_NULL: &str = "null";
+
+const JSON_PRETTY_KEY_VALUE_SEPARATOR: &str = ": ";
+
+const NDJSON_LINE_END: u8 = b'\n';
+const PRETTY_LINE_BREAK: u8 = b'\n';
+
+/// It writes parser events to the output as JSON.
+///
+/// Events must come from the [`BencodeParser`](super::BencodeParser), which
+/// only produces valid sequences of events.
+#[derive(Debug)]
+pub(crate) struct JsonEmitter {
+    stack: Stack,
+    options: ParserOptions,
+    num_top_level_values: usize,
+    /// The key of each open dictionary, or `None` for lists and dictionaries
+    /// without keys yet.
+    path: Vec<Option<Vec<u8>>>,
+    /// The info hash to add to the top-level dictionary in torrent mode.
+    info_hash: Option<InfoHash>,
+    /// The top-level dictionary already has an info hash field, so the
+    /// info hash is not added.
+    has_info_hash_field: bool,
+    /// The fields of the open dictionaries when keys are sorted, with their
+    /// raw key and their JSON. The key is `None` for the corruption marker
+    /// field, which is written after the sorted fields. The JSON of the
+    /// innermost dictionary is written to the last field, instead of the
+    /// output, until it ends.
+    sorted_dicts: Vec<Vec<(Option<Vec<u8>>, String)>>,
+}
+
+impl JsonEmitter {
+    pub fn new(options: ParserOptions) -> Self {
+        Self::with_path(options, Vec::new())
+    }
+
+    /// It creates an emitter for a value nested in the given path, so known
+    /// fields inside the value are still recognized.
+    pub fn with_path(options: ParserOptions, path: Vec<Option<Vec<u8>>>) -> Self {
+        Self {
+            stack: Stack::default(),
+            options,
+            num_top_level_values: 0,
+            path,
+            info_hash: None,
+            has_info_hash_field: false,
+            sorted_dicts: Vec::new(),
+        }
+    }
+
+    /// It sets the info hash added to the top-level dictionary when it ends.
+    pub fn set_info_hash(&mut self, info_hash: InfoHash) {
+        self.info_hash = Some(info_hash);
+    }
+
+    /// It writes what is needed before the first top-level value.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn begin<W: Writer>(&mut self, writer: &mut W) -> Result<(), Error> {
+        if self.options.multiple_values == MultipleValues::Array {
+            writer.write_byte(JSON_ARRAY_BEGIN)?;
+        }
+
+        Ok(())
+    }
+
+    /// It writes what is needed after the last top-level value.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn finish<W: Writer>(&mut self, writer: &mut W) -> Result<(), Error> {
+        if self.options.multiple_values == MultipleValues::Array {
+            if self.num_top_level_values > 0 {
+                self.write_line_break(0, writer)?;
+            }
+            writer.write_byte(JSON_ARRAY_END)?;
+        }
+
+        Ok(())
+    }
+
+    /// It returns `true` when there is no list or dictionary still open, that
+    /// is, when the last event completed a top-level value.
+    pub fn is_at_top_level(&self) -> bool {
+        self.stack.peek() == State::Initial
+    }
+
+    /// It returns `true` when at least one top-level value has been written.
+    pub fn has_top_level_values(&self) -> bool {
+        self.num_top_level_values > 0
+    }
+
+    /// It writes the JSON for one event, including the delimiters needed
+    /// before it.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn write_event<W: Writer>(&mut self, event: Event, writer: &mut W) -> Result<(), Error> {
+        match event {
+            Event::Integer(integer) => {
+                self.begin_value(BencodeType::Integer, writer)?;
+                self.write_str(integer.as_str(), writer)?;
+            }
+            Event::Key(bytes) => {
+                if let Some(fields) = self.sorted_dicts.last_mut() {
+                    fields.push((Some(bytes.clone()), String::new()));
+                }
+
+                self.begin_value(BencodeType::String, writer)?;
+
+                if self.path.len() == 1 && bytes == INFO_HASH_FIELD.as_bytes() {
+                    self.has_info_hash_field = true;
+                }
+
+                match self.known_key_json(&bytes) {
+                    Some(json) => self.write_str(&json, writer)?,
+                    None => self.write_string(bytes.clone(), true, writer)?,
+                }
+
+                *self
+                    .path
+                    .last_mut()
+                    .expect("dictionary keys are only parsed inside dictionaries") = Some(bytes);
+            }
+            Event::Bytes(bytes) => {
+                self.begin_value(BencodeType::String, writer)?;
+
+                match self.known_value_json(&bytes) {
+                    Some(json) => self.write_str(&json, writer)?,
+                    None => self.write_string(bytes, false, writer)?,
+                }
+            }
+            Event::BytesStart(_) | Event::BytesChunk(_) => {
+                unreachable!("strings are written in chunks without events")
+            }
+            Event::ListStart => {
+                self.begin_value(BencodeType::List, writer)?;
+                self.write_byte(JSON_ARRAY_BEGIN, writer)?;
+                self.stack.push(State::ExpectingFirstListItemOrEnd);
+                self.path.push(None);
+            }
+            Event::DictStart => {
+                if self.path.is_empty() {
+                    self.has_info_hash_field = false;
+                }
+
+                self.begin_value(BencodeType::Dict, writer)?;
+                self.write_byte(JSON_OBJ_BEGIN, writer)?;
+                self.stack.push(State::ExpectingFirstDictFieldOrEnd);
+                self.path.push(None);
+
+                if self.sorts_keys() {
+                    self.sorted_dicts.push(Vec::new());
+                }
+            }
+            Event::End => {
+                let popped_state = self
+                    .stack
+                    .end_list_or_dict()
+                    .expect("the parser only produces valid sequences of events");
+
+                self.path.pop();
+
+                match popped_state {
+                    State::ExpectingFirstListItemOrEnd | State::ExpectingNextListItem => {
+                        if popped_state == State::ExpectingNextListItem {
+                            self.write_line_break(self.depth(), writer)?;
+                        }
+                        self.write_byte(JSON_ARRAY_END, writer)?;
+                    }
+                    _ => {
+                        let is_empty = popped_state == State::ExpectingFirstDictFieldOrEnd;
+                        self.end_dict(is_empty, writer)?;
+                    }
+                }
+            }
+        }
+
+        if self.is_at_top_level() {
+            self.end_top_level_value(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// It writes the delimiters needed before a string value written in
+    /// chunks, and returns the stream converting the chunks into JSON.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn begin_streamed_string<W: Writer>(
+        &mut self,
+        length: usize,
+        writer: &mut W,
+    ) -> Result<StringStream, Error> {
+        self.begin_value(BencodeType::String, writer)?;
+
+        let layout = self.layout();
+
+        let hash_len = if self.options.torrent {
+            torrent::hash_list_len(&self.path)
+        } else {
+            None
+        };
+
+        Ok(match hash_len {
+            Some(hash_len) if length.is_multiple_of(hash_len) => {
+                StringStream::hash_list(&self.options, layout, hash_len)
+            }
+            _ => StringStream::new(&self.options, layout),
+        })
+    }
+
+    /// It writes the JSON of the next chunk of a streamed string.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn write_chunk<W: Writer>(&mut self, json: &str, writer: &mut W) -> Result<(), Error> {
+        self.write_str(json, writer)
+    }
+
+    /// It writes the end of a streamed string.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn end_streamed_string<W: Writer>(
+        &mut self,
+        json: &str,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        self.write_str(json, writer)?;
+
+        if self.is_at_top_level() {
+            self.end_top_level_value(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// It writes the [`CORRUPTION_MARKER`] as the next value, or as the next
+    /// key with a `null` value inside dictionaries expecting a key. That field
+    /// is always the last one, even when keys are sorted.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn write_corruption_marker<W: Writer>(&mut self, writer: &mut W) -> Result<(), Error> {
+        let marker = serde_json::to_string(CORRUPTION_MARKER).expect("a string is valid JSON");
+
+        if matches!(
+            self.stack.peek(),
+            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd
+        ) {
+            self.write_event(Event::Key(CORRUPTION_MARKER.as_bytes().to_vec()), writer)?;
+
+            if let Some((key, _json)) = self
+                .sorted_dicts
+                .last_mut()
+                .and_then(|fields| fields.last_mut())
+            {
+                *key = None;
+            }
+
+            self.begin_value(BencodeType::Integer, writer)?;
+            return self.write_str(JSON_NULL, writer);
+        }
+
+        self.begin_value(BencodeType::String, writer)?;
+        self.write_str(&marker, writer)?;
+
+        if self.is_at_top_level() {
+            self.end_top_level_value(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// It ends all the open lists and dictionaries.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the writer can't write to the output.
+    pub fn close_containers<W: Writer>(&mut self, writer: &mut W) -> Result<(), Error> {
+        while !self.is_at_top_level() {
+            self.write_event(Event::End, writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// It writes the end of a dictionary, including its sorted fields and the
+    /// info hash, if any.
+    fn end_dict<W: Writer>(&mut self, mut is_empty: bool, writer: &mut W) -> Result<(), Error> {
+        if self.sorts_keys() {
+            let mut fields = self
+                .sorted_dicts
+                .pop()
+                .expect("each dictionary has its sorted fields");
+
+            fields.sort_by(|(key, _), (other_key, _)| {
+                (key.is_none(), key).cmp(&(other_key.is_none(), other_key))
+            });
+
+            for (i, (_key, json)) in fields.iter().enumerate() {
+                if i > 0 {
+                    self.write_byte(JSON_OBJ_FIELDS_SEPARATOR, writer)?;
+                }
+                self.write_str(json, writer)?;
+            }
+        }
+
+        if self.path.is_empty() && self.write_info_hash(is_empty, writer)? {
+            is_empty = false;
+        }
+
+        if !is_empty {
+            self.write_line_break(self.depth(), writer)?;
+        }
+
+        self.write_byte(JSON_OBJ_END, writer)
+    }
+
+    /// It returns the JSON for a dictionary key if it's a known binary field
+    /// of the enabled modes.
+    fn known_key_json(&self, key: &[u8]) -> Option<String> {
+        let dict_path = &self.path[..self.path.len() - 1];
+
+        if self.options.torrent {
+            if let Some(json) = torrent::key_json(dict_path, key) {
+                return Some(json);
+            }
+        }
+
+        if self.options.tracker_response {
+            return tracker::key_json(dict_path, key);
+        }
+
+        None
+    }
+
+    /// It returns the JSON for a string value if it's a known binary field of
+    /// the enabled modes.
+    fn known_value_json(&self, bytes: &[u8]) -> Option<String> {
+        if self.options.torrent {
+            if let Some(json) = torrent::value_json(&self.path, bytes, &self.layout()) {
+                return Some(json);
+            }
+        }
+
+        if self.options.tracker_response {
+            return tracker::value_json(&self.path, bytes, &self.layout());
+        }
+
+        None
+    }
+
+    /// It updates the stack state and writes the delimiters needed before a
+    /// new value.
+    fn begin_value<W: Writer>(
+        &mut self,
+        bencode_type: BencodeType,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        let previous_state = self
+            .stack
+            .begin_value(bencode_type)
+            .expect("the parser only produces valid sequences of events");
+
+        match previous_state {
+            State::Initial => {
+                if self.options.multiple_values == MultipleValues::Array {
+                    if self.num_top_level_values > 0 {
+                        writer.write_byte(JSON_ARRAY_ITEMS_SEPARATOR)?;
+                    }
+                    self.write_line_break(1, writer)?;
+                }
+            }
+            State::ExpectingFirstListItemOrEnd | State::ExpectingFirstDictFieldOrEnd => {
+                self.write_line_break(self.depth(), writer)?;
+            }
+            State::ExpectingNextListItem => {
+                self.write_byte(JSON_ARRAY_ITEMS_SEPARATOR, writer)?;
+                self.write_line_break(self.depth(), writer)?;
+            }
+            State::ExpectingDictFieldValue => {
+                self.write_key_value_separator(writer)?;
+            }
+            State::ExpectingDictFieldKeyOrEnd => {
+                // Separators between sorted fields are written when the
+                // dictionary ends
+                if !self.sorts_keys() {
+                    self.write_byte(JSON_OBJ_FIELDS_SEPARATOR, writer)?;
+                }
+                self.write_line_break(self.depth(), writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// It writes the separator between a dictionary key and its value.
+    fn write_key_value_separator<W: Writer>(&mut self, writer: &mut W) -> Result<(), Error> {
+        if self.options.indent.is_some() {
+            self.write_str(JSON_PRETTY_KEY_VALUE_SEPARATOR, writer)
+        } else {
+            self.write_byte(JSON_OBJ_FIELD_KEY_VALUE_SEPARATOR, writer)
+        }
+    }
+
+    /// It returns `true` if dictionary fields have to be sorted. In strict
+    /// mode, the parser already rejects unsorted keys.
+    fn sorts_keys(&self) -> bool {
+        self.options.sort_keys && !self.options.strict
+    }
+
+    /// It returns the indentation level of the current value.
+    fn depth(&self) -> usize {
+        self.stack.depth() + usize::from(self.options.multiple_values == MultipleValues::Array)
+    }
+
+    /// It writes a line break followed by the indentation for the given
+    /// level, only when pretty-printing.
+    fn write_line_break<W: Writer>(&mut self, depth: usize, writer: &mut W) -> Result<(), Error> {
+        let Some(indent) = self.options.indent else {
+            return Ok(());
+        };
+
+        self.write_byte(PRETTY_LINE_BREAK, writer)?;
+        self.write_str(&" ".repeat(indent * depth), writer)
+    }
+
+    /// It returns the layout of the arrays and objects written for a string
+    /// at the current level.
+    fn layout(&self) -> Layout {
+        Layout::new(self.options.indent, self.depth())
+    }
+
+    /// It writes a string, as a dictionary key or as a value.
+    fn write_string<W: Writer>(
+        &mut self,
+        bytes: Vec<u8>,
+        is_key: bool,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        if self.options.indent.is_none() && self.sorted_dicts.is_empty() {
+            return if is_key {
+                string::write_json_key(bytes, writer, &self.options)
+            } else {
+                string::write_json(bytes, writer, &self.options)
+            };
+        }
+
+        let json = if is_key {
+            let mut json = String::new();
+
+            string::write_json_key(bytes, &mut StringWriter::new(&mut json), &self.options)?;
+
+            json
+        } else {
+            string::json_with_layout(bytes, &self.options, &self.layout())
+        };
+
+        self.write_str(&json, writer)
+    }
+
+    /// It writes a string to the innermost dictionary with sorted keys, if
+    /// any, or to the output.
+    fn write_str<W: Writer>(&mut self, json: &str, writer: &mut W) -> Result<(), Error> {
+        match self.sorted_field() {
+            Some(field) => field.push_str(json),
+            None => writer.write_str(json)?,
+        }
+
+        Ok(())
+    }
+
+    /// It writes a byte to the innermost dictionary with sorted keys, if any,
+    /// or to the output.
+    fn write_byte<W: Writer>(&mut self, byte: u8, writer: &mut W) -> Result<(), Error> {
+        match self.sorted_field() {
+            Some(field) => field.push(byte as char),
+            None => writer.write_byte(byte)?,
+        }
+
+        Ok(())
+    }
+
+    /// It returns the JSON of the current field of the innermost dictionary
+    /// with sorted keys.
+    fn sorted_field(&mut self) -> Option<&mut String> {
+        self.sorted_dicts
+            .last_mut()
+            .and_then(|fields| fields.last_mut())
+            .map(|(_key, json)| json)
+    }
+
+    /// It writes the info hash field at the end of the top-level dictionary,
+    /// if there is one. It returns `true` if it was written.
+    ///
+    /// It's not written if the dictionary already has an info hash field, so
+    /// the JSON keys are unique.
+    fn write_info_hash<W: Writer>(
+        &mut self,
+        is_empty: bool,
+        writer: &mut W,
+    ) -> Result<bool, Error> {
+        let Some(info_hash) = self.info_hash.take() else {
+            return Ok(false);
+        };
+
+        if self.has_info_hash_field {
+            return Ok(false);
+        }
+
+        if !is_empty {
+            self.write_byte(JSON_OBJ_FIELDS_SEPARATOR, writer)?;
+        }
+
+        let depth = self.depth() + 1;
+
+        self.write_line_break(depth, writer)?;
+        self.write_str(&format!(r#""{INFO_HASH_FIELD}""#), writer)?;
+        self.write_key_value_separator(writer)?;
+        self.write_str(
+            &info_hash.json(&Layout::new(self.options.indent, depth)),
+            writer,
+        )?;
+
+        Ok(true)
+    }
+
+    /// It writes what is needed after a complete top-level value.
+    fn end_top_level_value<W: Writer>(&mut self, writer: &mut W) -> Result<(), Error> {
+        self.num_top_level_values += 1;
+
+        if self.options.multiple_values == MultipleValues::Ndjson {
+            writer.write_byte(NDJSON_LINE_END)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The whitespace and separators written inside the arrays and objects of
+/// one level. Line breaks are empty for compact JSON.
+#[derive(Debug, Clone)]
+pub(crate) struct Layout {
+    indent: Option<usize>,
+
+    depth: usize,
+
+    /// Written before each item.
+    pub item_break: String,
+
+    /// Written before the end of a non-empty array or object.
+    pub end_break: String,
+
+    /// Written between the key and the value of an object field.
+    pub key_value_separator: &'static str,
+}
+
+impl Layout {
+    /// It returns the layout of the arrays and objects at the given level,
+    /// indented with the given number of spaces, or compact if `None`.
+    pub fn new(indent: Option<usize>, depth: usize) -> Self {
+        let Some(indent_size) = indent else {
+            return Self {
+                indent,
+                depth,
+                item_break: String::new(),
+                end_break: String::new(),
+                key_value_separator: ":",
+            };
+        };
+
+        Self {
+            indent,
+            depth,
+            item_break: format!("\n{}", " ".repeat(indent_size * (depth + 1))),
+            end_break: format!("\n{}", " ".repeat(indent_size * depth)),
+            key_value_separator: JSON_PRETTY_KEY_VALUE_SEPARATOR,
+        }
+    }
+
+    /// It returns the layout of compact JSON.
+    pub fn compact() -> Self {
+        Self::new(None, 0)
+    }
+
+    /// It returns the layout of the arrays and objects nested in this level.
+    pub fn nested(&self) -> Self {
+        Self::new(self.indent, self.depth + 1)
+    }
+
+    /// It returns a JSON array with the given JSON items.
+    pub fn array(&self, items: impl IntoIterator<Item = String>) -> String {
+        self.container('[', items, ']')
+    }
+
+    /// It returns a JSON object with the given fields. The keys are written
+    /// as they are, so they must not need escaping.
+    pub fn object<'a>(&self, fields: impl IntoIterator<Item = (&'a str, String)>) -> String {
+        let fields = fields
+            .into_iter()
+            .map(|(key, value)| format!("\"{key}\"{}{value}", self.key_value_separator));
+
+        self.container('{', fields, '}')
+    }
+
+    fn container(&self, begin: char, items: impl IntoIterator<Item = String>, end: char) -> String {
+        let mut json = String::from(begin);
+        let mut is_empty = true;
+
+        for item in items {
+            if !is_empty {
+                json.push(',');
+            }
+            json.push_str(&self.item_break);
+            json.push_str(&item);
+            is_empty = false;
+        }
+
+        if !is_empty {
+            json.push_str(&self.end_break);
+        }
+
+        json.push(end);
+
+        json
+    }
+}
@@ -1,21 +1,49 @@
 //! Parsers, including the main parser and the parsers for the basic types
 //! (integer and string)
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+pub mod borrowed;
+pub mod canonical;
+pub mod capture;
 pub mod error;
+pub mod event;
 pub mod integer;
+mod json;
+pub mod options;
+pub mod path;
+pub mod push;
+pub mod query;
+pub mod recovery;
+pub mod render;
 pub mod stack;
 pub mod string;
+mod string_stream;
+pub mod torrent;
+pub mod tracker;
 
 use std::{
+    cmp::Ordering,
     fmt::Write as FmtWrite,
     io::{self, Read, Write as IoWrite},
 };
 
+use capture::CapturedValue;
 use derive_more::derive::Display;
 use error::{ReadContext, WriteContext};
+use event::{Event, Events, Integer};
+use json::JsonEmitter;
+use options::{ErrorRecovery, MultipleValues, ParserOptions};
+use path::{KeyPath, PathSegment, ValuePath};
+use recovery::Diagnostic;
 use stack::{Stack, State};
-
-use crate::rw::{
-    byte_reader::ByteReader, byte_writer::ByteWriter, string_writer::StringWriter, writer::Writer,
+use torrent::InfoHash;
+
+use crate::{
+    rw::{
+        byte_reader::ByteReader, byte_writer::ByteWriter, null_writer::NullWriter,
+        string_writer::StringWriter, writer::Writer,
+    },
+    value::{builder::Builder, Value, DEFAULT_MAX_DEPTH},
 };
 
 // Bencoded reserved bytes
@@ -25,7 +53,7 @@ const BENCODE_BEGIN_LIST: u8 = b'l';
 const BENCODE_BEGIN_DICT: u8 = b'd';
 const BENCODE_END_LIST_OR_DICT: u8 = b'e';
 
-#[derive(Debug, PartialEq, Display)]
+#[derive(Debug, PartialEq, Clone, Copy, Display)]
 pub enum BencodeType {
     Integer,
     String,
@@ -36,27 +64,175 @@ pub enum BencodeType {
 pub struct BencodeParser<R: Read> {
     byte_reader: ByteReader<R>,
     num_processed_tokens: u64,
+    num_skipped_line_breaks: u64,
     stack: Stack,
+    options: ParserOptions,
+
+    /// The open lists and dictionaries, from the outermost to the innermost.
+    containers: Vec<Container>,
+
+    /// The values whose raw bytes are being recorded, from the outermost to
+    /// the innermost.
+    recorded_values: Vec<RecordedValue>,
+
+    /// The values selected by the captured paths, once they are complete.
+    captured_values: Vec<CapturedValue>,
+
+    /// The errors recovered while writing the JSON.
+    diagnostics: Vec<Diagnostic>,
+
+    /// A value began in the token being parsed. When an error is recovered
+    /// at the top level, the corruption marker is only written for such a
+    /// value, not after a complete top-level value.
+    has_begun_value: bool,
+
+    /// Bytes left of the string being returned in chunks by
+    /// [`BencodeParser::next_event`].
+    streamed_string_left: usize,
 }
 
-impl<R: Read> BencodeParser<R> {
-    const JSON_ARRAY_BEGIN: u8 = b'[';
-    const JSON_ARRAY_ITEMS_SEPARATOR: u8 = b',';
-    const JSON_ARRAY_END: u8 = b']';
+/// A value whose raw bytes are being recorded.
+#[derive(Debug)]
+struct RecordedValue {
+    /// Why the value is recorded.
+    purpose: RecordingPurpose,
+
+    /// Length of the path of the value. The value is complete when the
+    /// parser goes back to this depth.
+    depth: usize,
+}
+
+#[derive(Debug)]
+enum RecordingPurpose {
+    /// The `info` dictionary, in torrent mode.
+    InfoHash,
+
+    /// A value selected by one of the captured paths.
+    Capture(KeyPath),
+}
+
+/// A token read from the input: an event, or the length of a string value
+/// that is written in chunks while it's read. See
+/// [`ParserOptions::string_buffer_size`].
+#[derive(Debug)]
+enum Token {
+    Event(Event),
+    String(usize),
+}
+
+impl From<Event> for Token {
+    fn from(event: Event) -> Self {
+        Self::Event(event)
+    }
+}
+
+/// The JSON being written, kept between tokens.
+enum Output {
+    /// The whole input is written as JSON.
+    Json(JsonEmitter),
+
+    /// Only the values selected by the queries are written, one JSON value
+    /// per line.
+    Selected {
+        options: ParserOptions,
+
+        /// The emitter of the selected value being written, if any.
+        emitter: Option<JsonEmitter>,
+    },
+}
+
+impl Output {
+    /// It finishes the JSON.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if it can't write to the output.
+    fn finish<W: Writer>(&mut self, writer: &mut W) -> Result<(), error::Error> {
+        match self {
+            Output::Json(emitter) => emitter.finish(writer),
+            Output::Selected { .. } => Ok(()),
+        }
+    }
+}
+
+/// An open list or dictionary.
+#[derive(Debug, Default)]
+struct Container {
+    /// `true` for dictionaries.
+    is_dict: bool,
+
+    /// Number of list items or dictionary fields.
+    items: usize,
+
+    /// The latest dictionary key. It's `None` while the next key is parsed.
+    last_key: Option<Vec<u8>>,
+}
 
-    const JSON_OBJ_BEGIN: u8 = b'{';
-    const JSON_OBJ_FIELDS_SEPARATOR: u8 = b',';
-    const JSON_OBJ_FIELD_KEY_VALUE_SEPARATOR: u8 = b':';
-    const JSON_OBJ_END: u8 = b'}';
+impl Container {
+    /// It returns the path segment of the current item, if there is one.
+    /// When `next_item` is `true`, it's the segment of the next list item,
+    /// which has not begun yet.
+    fn current_segment(&self, next_item: bool) -> Option<PathSegment> {
+        if self.is_dict {
+            self.last_key.clone().map(PathSegment::Key)
+        } else if next_item {
+            Some(PathSegment::Index(self.items))
+        } else {
+            self.items.checked_sub(1).map(PathSegment::Index)
+        }
+    }
+}
 
+impl<R: Read> BencodeParser<R> {
     pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// It creates a parser with custom options. See also the
+    /// [`BencodeParserBuilder`](options::BencodeParserBuilder).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        let mut byte_reader = ByteReader::with_capture_size(reader, options.capture_size);
+        byte_reader.set_max_input_bytes(options.max_input_bytes);
+
         BencodeParser {
-            byte_reader: ByteReader::new(reader),
+            byte_reader,
             num_processed_tokens: 1,
+            num_skipped_line_breaks: 0,
             stack: Stack::default(),
+            options,
+            containers: vec![],
+            recorded_values: vec![],
+            captured_values: vec![],
+            diagnostics: vec![],
+            has_begun_value: false,
+            streamed_string_left: 0,
         }
     }
 
+    /// It creates a parser that only accepts canonical bencode.
+    ///
+    /// Besides the validations done by the default lenient parser, it rejects:
+    ///
+    /// - Negative zero: `i-0e`.
+    /// - Leading zeros in string lengths: `03:abc`.
+    /// - Dictionary keys that are not sorted by their raw bytes, or duplicate.
+    /// - Line breaks before, after or between values.
+    ///
+    /// The info-hash of a torrent is only meaningful for canonical bencode.
+    pub fn new_strict(reader: R) -> Self {
+        Self::with_options(
+            reader,
+            ParserOptions {
+                strict: true,
+                ..ParserOptions::default()
+            },
+        )
+    }
+
     /// It parses a bencoded value read from input and writes the corresponding
     /// JSON UTF-8 string value to the output.
     ///
@@ -70,7 +246,7 @@ impl<R: Read> BencodeParser<R> {
     /// Will panic if receives a byte that isn't a valid begin or end of a
     /// bencoded type: integer, string, list or dictionary.
     pub fn write_str<W: FmtWrite>(&mut self, writer: W) -> Result<(), error::Error> {
-        let mut writer = StringWriter::new(writer);
+        let mut writer = StringWriter::with_capture_size(writer, self.options.capture_size);
         self.parse(&mut writer)
     }
 
@@ -87,7 +263,7 @@ impl<R: Read> BencodeParser<R> {
     /// Will panic if receives a byte that isn't a valid begin or end of a
     /// bencoded type: integer, string, list or dictionary.
     pub fn write_bytes<W: IoWrite>(&mut self, writer: W) -> Result<(), error::Error> {
-        let mut writer = ByteWriter::new(writer);
+        let mut writer = ByteWriter::with_capture_size(writer, self.options.capture_size);
         self.parse(&mut writer)
     }
 
@@ -101,1996 +277,4792 @@ impl<R: Read> BencodeParser<R> {
     /// - It can't read from the input or write to the output.
     /// - The input is invalid Bencode.
     fn parse<W: Writer>(&mut self, writer: &mut W) -> Result<(), error::Error> {
-        while let Some(peeked_byte) = Self::peek_byte(&mut self.byte_reader, writer)? {
-            match peeked_byte {
-                BENCODE_BEGIN_INTEGER => {
-                    self.begin_bencoded_value(BencodeType::Integer, writer)?;
-                    integer::parse(&mut self.byte_reader, writer)?;
-                }
-                b'0'..=b'9' => {
-                    self.begin_bencoded_value(BencodeType::String, writer)?;
-                    string::parse(&mut self.byte_reader, writer)?;
-                }
-                BENCODE_BEGIN_LIST => {
-                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
-                    self.begin_bencoded_value(BencodeType::List, writer)?;
-                    writer.write_byte(Self::JSON_ARRAY_BEGIN)?;
-                    self.stack.push(State::ExpectingFirstListItemOrEnd);
-                }
-                BENCODE_BEGIN_DICT => {
-                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
-                    self.begin_bencoded_value(BencodeType::Dict, writer)?;
-                    writer.write_byte(Self::JSON_OBJ_BEGIN)?;
-                    self.stack.push(State::ExpectingFirstDictFieldOrEnd);
-                }
-                BENCODE_END_LIST_OR_DICT => {
-                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
-                    self.end_list_or_dict(writer)?;
-                }
-                b'\n' => {
-                    // Ignore line breaks at the beginning, the end, or between values
-                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
-                }
-                _ => {
-                    return Err(error::Error::UnrecognizedFirstBencodeValueByte(
-                        ReadContext {
-                            byte: Some(peeked_byte),
-                            pos: self.byte_reader.input_byte_counter(),
-                            latest_bytes: self.byte_reader.captured_bytes(),
-                        },
-                        WriteContext {
-                            byte: Some(peeked_byte),
-                            pos: writer.output_byte_counter(),
-                            latest_bytes: writer.captured_bytes(),
-                        },
-                    ));
-                }
-            }
+        let mut output = self.begin_output(writer)?;
 
-            self.num_processed_tokens += 1;
+        while self.write_next(&mut output, writer)? {}
+
+        output.finish(writer)
+    }
+
+    /// It begins writing the JSON for the whole input, or for the values
+    /// selected by the queries in the options.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if it can't write to the output.
+    fn begin_output<W: Writer>(&self, writer: &mut W) -> Result<Output, error::Error> {
+        if !self.options.selected_paths.is_empty() {
+            return Ok(Output::Selected {
+                options: ParserOptions {
+                    multiple_values: MultipleValues::Concatenate,
+                    indent: None,
+                    ..self.options.clone()
+                },
+                emitter: None,
+            });
+        }
+
+        let mut options = self.options.clone();
+
+        // Concatenated values could not be told apart from the corruption
+        // marker
+        if options.error_recovery != ErrorRecovery::Fail
+            && options.multiple_values == MultipleValues::Concatenate
+        {
+            options.multiple_values = MultipleValues::Ndjson;
+        }
+
+        // Each value must fit on its line
+        if options.multiple_values == MultipleValues::Ndjson {
+            options.indent = None;
         }
 
-        self.check_bad_end_stack_state(writer)
+        let mut emitter = JsonEmitter::new(options);
+
+        emitter.begin(writer)?;
+
+        Ok(Output::Json(emitter))
     }
 
-    /// It reads the next byte from the input consuming it. It returns `None` if
-    /// the input has ended.
+    /// It parses the next token and writes it, recovering from errors when
+    /// the options allow it. It returns `false` when no more tokens must be
+    /// parsed.
     ///
     /// # Errors
     ///
-    /// Will return and errors if:
+    /// Will return an error if:
     ///
-    /// - It can't read from the input.
-    /// - The byte read is not the expected one (the previously peeked byte).
-    fn read_peeked_byte<W: Writer>(
-        peeked_byte: u8,
-        reader: &mut ByteReader<R>,
-        writer: &W,
-    ) -> Result<Option<u8>, error::Error> {
-        match reader.read_byte() {
-            Ok(byte) => {
-                if byte == peeked_byte {
-                    return Ok(Some(byte));
-                }
-                Err(error::Error::ReadByteAfterPeekingDoesMatchPeekedByte(
-                    ReadContext {
-                        byte: Some(byte),
-                        pos: reader.input_byte_counter(),
-                        latest_bytes: reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: Some(byte),
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ))
-            }
-            Err(err) => {
-                if err.kind() == io::ErrorKind::UnexpectedEof {
-                    return Ok(None);
-                }
-                Err(err.into())
-            }
+    /// - It can't read from the input or write to the output.
+    /// - The input is invalid Bencode.
+    fn write_next<W: Writer>(
+        &mut self,
+        output: &mut Output,
+        writer: &mut W,
+    ) -> Result<bool, error::Error> {
+        match self.write_next_output_token(output, writer) {
+            Ok(has_more_tokens) => Ok(has_more_tokens),
+            Err(err) => self.recover_output(err, output, writer),
         }
     }
 
-    /// It peeks the next byte from the input without consuming it. It returns
-    /// `None` if the input has ended.
+    /// It parses the next token and writes it to the JSON being written. It
+    /// returns `false` when no more tokens must be parsed.
     ///
     /// # Errors
     ///
-    /// Will return and errors if it can't read from the input.
-    fn peek_byte<W: Writer>(
-        reader: &mut ByteReader<R>,
-        _writer: &W,
-    ) -> Result<Option<u8>, error::Error> {
-        match reader.peek_byte() {
-            Ok(byte) => Ok(Some(byte)),
-            Err(err) => {
-                if err.kind() == io::ErrorKind::UnexpectedEof {
-                    return Ok(None);
-                }
-                Err(err.into())
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input or write to the output.
+    /// - The input is invalid Bencode.
+    fn write_next_output_token<W: Writer>(
+        &mut self,
+        output: &mut Output,
+        writer: &mut W,
+    ) -> Result<bool, error::Error> {
+        match output {
+            Output::Json(emitter) => self.write_next_token(emitter, writer),
+            Output::Selected { options, emitter } => {
+                self.write_next_selected_token(emitter, options, writer)
             }
         }
     }
 
-    /// It updates the stack state and prints the delimiters when needed.
-    ///
-    /// Called when the first byt of a bencoded value (integer, string, list or dict)
-    /// is received.
+    /// It recovers from an error in the input when the options allow it,
+    /// closing the JSON being written. It returns `true` if parsing goes on
+    /// with the next top-level value.
     ///
     /// # Errors
     ///
-    /// Will return an error if the writer can't write to the output.
-    pub fn begin_bencoded_value<W: Writer>(
+    /// Will return the error if it can't be recovered, or a new error if it
+    /// can't write to the output.
+    fn recover_output<W: Writer>(
         &mut self,
-        bencode_type: BencodeType,
+        err: error::Error,
+        output: &mut Output,
         writer: &mut W,
-    ) -> Result<(), error::Error> {
-        match self.stack.peek() {
-            State::Initial => {}
-            State::ExpectingFirstListItemOrEnd => {
-                self.stack.swap_top(State::ExpectingNextListItem);
-            }
-            State::ExpectingNextListItem => {
-                writer.write_byte(Self::JSON_ARRAY_ITEMS_SEPARATOR)?;
-            }
-            State::ExpectingFirstDictFieldOrEnd => {
-                if bencode_type != BencodeType::String {
-                    return Err(error::Error::ExpectedStringForDictKeyGot(
-                        bencode_type,
-                        ReadContext {
-                            byte: None,
-                            pos: self.byte_reader.input_byte_counter(),
-                            latest_bytes: self.byte_reader.captured_bytes(),
-                        },
-                        WriteContext {
-                            byte: None,
-                            pos: writer.output_byte_counter(),
-                            latest_bytes: writer.captured_bytes(),
-                        },
-                    ));
+    ) -> Result<bool, error::Error> {
+        match output {
+            Output::Json(emitter) => self.recover(err, Some(emitter), writer),
+            Output::Selected { emitter, .. } => {
+                let has_more_tokens = self.recover(err, emitter.as_mut(), writer)?;
+
+                if emitter.take().is_some() {
+                    writer.write_byte(b'\n')?;
                 }
 
-                self.stack.swap_top(State::ExpectingDictFieldValue);
+                Ok(has_more_tokens)
             }
-            State::ExpectingDictFieldValue => {
-                writer.write_byte(Self::JSON_OBJ_FIELD_KEY_VALUE_SEPARATOR)?;
+        }
+    }
 
-                self.stack.swap_top(State::ExpectingDictFieldKeyOrEnd);
-            }
-            State::ExpectingDictFieldKeyOrEnd => {
-                if bencode_type != BencodeType::String {
-                    return Err(error::Error::ExpectedStringForDictKeyGot(
-                        bencode_type,
-                        ReadContext {
-                            byte: None,
-                            pos: self.byte_reader.input_byte_counter(),
-                            latest_bytes: self.byte_reader.captured_bytes(),
-                        },
-                        WriteContext {
-                            byte: None,
-                            pos: writer.output_byte_counter(),
-                            latest_bytes: writer.captured_bytes(),
-                        },
-                    ));
-                }
+    /// It parses the next token and writes it. It returns `false` when no
+    /// more tokens must be parsed.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input or write to the output.
+    /// - The input is invalid Bencode.
+    fn write_next_token<W: Writer>(
+        &mut self,
+        emitter: &mut JsonEmitter,
+        writer: &mut W,
+    ) -> Result<bool, error::Error> {
+        let Some(token) = self.read_token(writer, false)? else {
+            return Ok(false);
+        };
 
-                writer.write_byte(Self::JSON_OBJ_FIELDS_SEPARATOR)?;
+        let is_key = matches!(token, Token::Event(Event::Key(_)));
 
-                self.stack.swap_top(State::ExpectingDictFieldValue);
-            }
+        match token {
+            Token::Event(event) => emitter.write_event(event, writer)?,
+            Token::String(length) => self.write_streamed_string(length, emitter, writer)?,
         }
 
-        Ok(())
+        if let Some(info_hash) = self.update_recordings(is_key) {
+            emitter.set_info_hash(info_hash);
+        }
+
+        Ok(!self.is_top_level_value_complete(writer)?)
     }
 
-    /// It updates the stack state and prints the delimiters when needed.
-    ///
-    /// Called when the end of list or dictionary byte is received. End of
-    /// integers or strings are processed while parsing them.
+    /// It parses the next token and writes it if it's inside a selected
+    /// value. It returns `false` when no more tokens must be parsed.
     ///
     /// # Errors
     ///
-    /// Will return an error if the writer can't write to the output.
-    ///
-    /// # Panics
+    /// Will return an error if:
     ///
-    /// Will panic if the end of bencoded value (list or dictionary) was not
-    /// expected.
-    pub fn end_list_or_dict<W: Writer>(&mut self, writer: &mut W) -> Result<(), error::Error> {
-        match self.stack.peek() {
-            State::ExpectingFirstListItemOrEnd | State::ExpectingNextListItem => {
-                writer.write_byte(Self::JSON_ARRAY_END)?;
-                self.stack.pop();
+    /// - It can't read from the input or write to the output.
+    /// - The input is invalid Bencode.
+    fn write_next_selected_token<W: Writer>(
+        &mut self,
+        selected: &mut Option<JsonEmitter>,
+        options: &ParserOptions,
+        writer: &mut W,
+    ) -> Result<bool, error::Error> {
+        let Some(token) = self.read_token(writer, true)? else {
+            return Ok(false);
+        };
+
+        let is_key = matches!(token, Token::Event(Event::Key(_)));
+
+        if selected.is_none() && self.is_selected_value(&token) {
+            let mut path = self.key_path();
+
+            if matches!(token, Token::Event(Event::ListStart | Event::DictStart)) {
+                path.pop();
             }
-            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd => {
-                writer.write_byte(Self::JSON_OBJ_END)?;
-                self.stack.pop();
+
+            *selected = Some(JsonEmitter::with_path(options.clone(), path));
+        }
+
+        if let Some(emitter) = selected.as_mut() {
+            match token {
+                Token::Event(event) => emitter.write_event(event, writer)?,
+                Token::String(length) => self.write_streamed_string(length, emitter, writer)?,
             }
-            State::ExpectingDictFieldValue => {
-                return Err(error::Error::PrematureEndOfDict(
-                    ReadContext {
-                        byte: None,
-                        pos: self.byte_reader.input_byte_counter(),
-                        latest_bytes: self.byte_reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: None,
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ))
+        }
+
+        let info_hash = self.update_recordings(is_key);
+
+        if let Some(emitter) = selected.as_mut() {
+            if let Some(info_hash) = info_hash {
+                emitter.set_info_hash(info_hash);
             }
-            State::Initial => {
-                return Err(error::Error::NoMatchingStartForListOrDictEnd(
-                    ReadContext {
-                        byte: None,
-                        pos: self.byte_reader.input_byte_counter(),
-                        latest_bytes: self.byte_reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: None,
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ))
+
+            if emitter.is_at_top_level() {
+                writer.write_byte(b'\n')?;
+                *selected = None;
             }
         }
 
-        Ok(())
+        Ok(!self.is_top_level_value_complete(writer)?)
     }
 
-    /// It checks if the stack state is correct at the end of the parsing.
-    ///
-    /// That could happen, for example, when bencode values are not finished.
+    /// It recovers from an error in the input when the options allow it. It
+    /// closes the JSON being written, if any, and returns `true` if parsing
+    /// goes on with the next top-level value.
     ///
     /// # Errors
     ///
-    /// Will return an error if the stack state is not correct.
-    fn check_bad_end_stack_state<W: Writer>(&self, writer: &W) -> Result<(), error::Error> {
-        match self.stack.peek() {
-            State::Initial => Ok(()),
-            State::ExpectingFirstListItemOrEnd => Err(
-                error::Error::UnexpectedEndOfInputExpectingFirstListItemOrEnd(
-                    ReadContext {
-                        byte: None,
-                        pos: self.byte_reader.input_byte_counter(),
-                        latest_bytes: self.byte_reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: None,
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ),
-            ),
-            State::ExpectingNextListItem => {
-                Err(error::Error::UnexpectedEndOfInputExpectingNextListItem(
-                    ReadContext {
-                        byte: None,
-                        pos: self.byte_reader.input_byte_counter(),
-                        latest_bytes: self.byte_reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: None,
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ))
-            }
-            State::ExpectingFirstDictFieldOrEnd => Err(
-                error::Error::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(
-                    ReadContext {
-                        byte: None,
-                        pos: self.byte_reader.input_byte_counter(),
-                        latest_bytes: self.byte_reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: None,
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ),
-            ),
-            State::ExpectingDictFieldValue => {
-                Err(error::Error::UnexpectedEndOfInputExpectingDictFieldValue(
-                    ReadContext {
-                        byte: None,
-                        pos: self.byte_reader.input_byte_counter(),
-                        latest_bytes: self.byte_reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: None,
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ))
-            }
-            State::ExpectingDictFieldKeyOrEnd => Err(
-                error::Error::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(
-                    ReadContext {
-                        byte: None,
-                        pos: self.byte_reader.input_byte_counter(),
-                        latest_bytes: self.byte_reader.captured_bytes(),
-                    },
-                    WriteContext {
-                        byte: None,
-                        pos: writer.output_byte_counter(),
-                        latest_bytes: writer.captured_bytes(),
-                    },
-                ),
-            ),
+    /// Will return the error if it can't be recovered, or a new error if it
+    /// can't write to the output.
+    fn recover<W: Writer>(
+        &mut self,
+        err: error::Error,
+        emitter: Option<&mut JsonEmitter>,
+        writer: &mut W,
+    ) -> Result<bool, error::Error> {
+        if self.options.error_recovery == ErrorRecovery::Fail || err.read_context().is_none() {
+            return Err(err);
         }
-    }
-}
-
-#[cfg(test)]
-mod tests {
-
-    use std::io::{self, Read};
-
-    use crate::{parsers::BencodeParser, test::bencode_to_json_unchecked, try_bencode_to_json};
 
-    mod it_should_allow_writing {
-        use crate::parsers::BencodeParser;
+        if let Some(emitter) = emitter {
+            // The marker is not written after a complete top-level value for
+            // trailing data or bytes that don't begin a value
+            let is_value_corrupt = !emitter.is_at_top_level()
+                || (!matches!(err, error::Error::TrailingData(_, _))
+                    && (self.has_begun_value || !emitter.has_top_level_values()));
 
-        #[test]
-        fn to_any_type_implementing_io_write_trait() {
-            let mut output = Vec::new();
+            if is_value_corrupt {
+                emitter.write_corruption_marker(writer)?;
+            }
+            emitter.close_containers(writer)?;
+        }
 
-            let mut parser = BencodeParser::new(&b"i0e"[..]);
+        self.reset();
 
-            parser
-                .write_bytes(&mut output)
-                .expect("Bencode to JSON conversion failed");
+        let resumed_at = match err {
+            error::Error::MaxInputBytesExceeded(_, _) => None,
+            _ if self.resynchronizes() => self.resynchronize(writer)?,
+            _ => None,
+        };
 
-            assert_eq!(output, vec!(b'0'));
-        }
+        self.diagnostics.push(Diagnostic {
+            error: err,
+            resumed_at,
+        });
 
-        #[test]
-        fn writing_to_any_type_implementing_fmt_write_trait() {
-            let mut output = String::new();
+        Ok(resumed_at.is_some())
+    }
 
-            let mut parser = BencodeParser::new(&b"i0e"[..]);
+    /// It returns `true` if parsing goes on with the next top-level value
+    /// after an error. It never does when only one value is allowed, because
+    /// the next value would be written without a separator.
+    fn resynchronizes(&self) -> bool {
+        self.options.error_recovery == ErrorRecovery::Resynchronize
+            && self.options.multiple_values != MultipleValues::Reject
+    }
 
-            parser
-                .write_str(&mut output)
-                .expect("Bencode to JSON conversion failed");
+    /// It forgets the open lists and dictionaries, and the values being
+    /// recorded, to parse the next top-level value.
+    fn reset(&mut self) {
+        self.stack = Stack::default();
+        self.containers.clear();
 
-            assert_eq!(output, "0".to_string());
+        while self.recorded_values.pop().is_some() {
+            self.byte_reader.stop_recording();
         }
     }
 
-    #[test]
-    fn it_should_allow_reading_from_an_empty_input() {
-        struct EmptyReader;
-
-        impl Read for EmptyReader {
-            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
-                Err(io::Error::new(
-                    io::ErrorKind::UnexpectedEof,
-                    "Unexpected EOF",
-                ))
-            }
-        }
+    /// It skips bytes until the next one that can begin a bencoded value. It
+    /// returns its position, or `None` if the input ends before.
+    ///
+    /// At least one byte is skipped when parsing resumed at the same position
+    /// after the previous error, so it never gets stuck.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if it can't read from the input.
+    fn resynchronize<W: Writer>(&mut self, writer: &W) -> Result<Option<u64>, error::Error> {
+        let previous_resume = self
+            .diagnostics
+            .last()
+            .and_then(|diagnostic| diagnostic.resumed_at);
 
-        let mut output = String::new();
+        while let Some(peeked_byte) = Self::peek_byte(&mut self.byte_reader, writer)? {
+            let pos = self.byte_reader.input_byte_counter();
 
-        let mut parser = BencodeParser::new(EmptyReader);
+            if recovery::is_value_begin(peeked_byte) && previous_resume != Some(pos) {
+                return Ok(Some(pos));
+            }
 
-        parser.write_str(&mut output).unwrap();
+            let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
+        }
 
-        assert_eq!(output, "");
+        Ok(None)
     }
 
-    mod it_should_allow_special_bencode_cases {
+    /// It returns `true` if the token begins a value selected by one of the
+    /// queries.
+    fn is_selected_value(&self, token: &Token) -> bool {
+        if matches!(token, Token::Event(Event::Key(_) | Event::End)) {
+            return false;
+        }
 
-        use crate::{parsers::BencodeParser, test::bencode_to_json_unchecked};
+        let path = self.path();
 
-        #[test]
-        fn an_empty_input() {
-            let mut output = String::new();
+        self.options
+            .selected_paths
+            .iter()
+            .any(|query| query.matches(&path))
+    }
 
-            let mut parser = BencodeParser::new(&b""[..]);
+    /// It returns `true` if the string being parsed is not written to the
+    /// output because it's not inside a selected value.
+    fn is_skipped_string(&self) -> bool {
+        if self.options.selected_paths.is_empty() {
+            return false;
+        }
 
-            parser
-                .write_str(&mut output)
-                .expect("Bencode to JSON conversion failed");
+        let path = self.path();
 
-            assert_eq!(output, String::new());
-        }
+        !self
+            .options
+            .selected_paths
+            .iter()
+            .any(|query| query.contains(&path))
+    }
 
-        #[test]
-        fn line_breaks_at_the_beginning_of_the_input_stream() {
-            assert_eq!(bencode_to_json_unchecked(b"\ni0e"), "0".to_string());
+    /// It returns `true` if a string value of the given length is written in
+    /// chunks instead of being kept in memory. Known binary fields converted
+    /// as a whole are always kept in memory.
+    fn is_streamed_string(&self, length: usize) -> bool {
+        // Recovered errors replace the whole string with the corruption
+        // marker, so it can't be partially written
+        if self.options.error_recovery != ErrorRecovery::Fail
+            || self
+                .options
+                .string_buffer_size
+                .is_none_or(|string_buffer_size| length <= string_buffer_size)
+        {
+            return false;
         }
 
-        #[test]
-        fn line_breaks_at_the_end_of_the_input_stream() {
-            assert_eq!(bencode_to_json_unchecked(b"i0e\n"), "0".to_string());
-        }
+        let path = self.key_path();
 
-        #[test]
-        fn line_breaks_between_bencoded_values() {
-            assert_eq!(
-                bencode_to_json_unchecked(b"li0e\ni1ee"),
-                "[0,1]".to_string()
-            );
-        }
+        !(self.options.tracker_response && tracker::is_known_value_path(&path)
+            || self.options.torrent && torrent::is_hash_path(&path))
     }
 
-    mod it_should_fail {
-        use std::io::{self, Read};
+    /// It reads a string value in chunks of the string buffer size, writing
+    /// the JSON of each chunk as soon as it's read.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input or write to the output.
+    /// - The string contains invalid UTF-8 that can't be written after the
+    ///   text already written. See [`options::StreamedInvalidUtf8`].
+    fn write_streamed_string<W: Writer>(
+        &mut self,
+        length: usize,
+        emitter: &mut JsonEmitter,
+        writer: &mut W,
+    ) -> Result<(), error::Error> {
+        let string_buffer_size = self
+            .options
+            .string_buffer_size
+            .expect("only strings longer than the buffer are streamed");
 
-        use crate::{
-            parsers::{error::Error, BencodeParser},
-            try_bencode_to_json,
-        };
+        let mut stream = emitter.begin_streamed_string(length, writer)?;
 
-        #[test]
-        fn when_there_is_a_problem_reading_from_input() {
-            struct FaultyReader;
+        let mut buffer = vec![0; string_buffer_size];
+        let mut remaining = length;
 
-            impl Read for FaultyReader {
-                fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
-                    Err(io::Error::new(
-                        io::ErrorKind::PermissionDenied,
-                        "Permission denied",
-                    ))
-                }
-            }
+        while remaining > 0 {
+            let chunk = &mut buffer[..remaining.min(string_buffer_size)];
 
-            let mut output = String::new();
+            string::read_chunk(&mut self.byte_reader, writer, chunk)
+                .map_err(|err| self.input_limit_error(err, writer).with_path(self.path()))?;
 
-            let mut parser = BencodeParser::new(FaultyReader);
+            remaining -= chunk.len();
 
-            let result = parser.write_str(&mut output);
+            let json = stream
+                .write(chunk)
+                .ok_or_else(|| self.invalid_utf8_error(writer))?;
 
-            assert!(matches!(result, Err(Error::Io(_))));
+            emitter.write_chunk(&json, writer)?;
         }
 
-        #[test]
-        fn when_it_cannot_recognized_the_fist_byte_of_a_new_bencoded_value() {
-            let invalid_bencoded_value = b"a";
+        let json = stream
+            .finish()
+            .ok_or_else(|| self.invalid_utf8_error(writer))?;
 
-            let result = try_bencode_to_json(invalid_bencoded_value);
+        emitter.end_streamed_string(&json, writer)
+    }
 
-            assert!(matches!(
-                result,
-                Err(Error::UnrecognizedFirstBencodeValueByte { .. })
-            ));
+    /// It returns the error for invalid UTF-8 in a streamed string.
+    fn invalid_utf8_error<W: Writer>(&self, writer: &W) -> error::Error {
+        error::Error::InvalidUtf8InStreamedString(
+            ReadContext {
+                byte: None,
+                pos: self.byte_reader.input_byte_counter(),
+                latest_bytes: self.byte_reader.captured_bytes(),
+                state: None,
+                path: self.path(),
+            },
+            WriteContext {
+                byte: None,
+                pos: writer.output_byte_counter(),
+                latest_bytes: writer.captured_bytes(),
+            },
+        )
+    }
+
+    /// It returns `true` when the latest event completed a top-level value
+    /// and no more values must be parsed. In that case, it checks there is
+    /// no trailing data.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if there is trailing data and multiple top-level
+    /// values are rejected.
+    fn is_top_level_value_complete<W: Writer>(&mut self, writer: &W) -> Result<bool, error::Error> {
+        if self.options.multiple_values != MultipleValues::Reject
+            || self.stack.peek() != State::Initial
+        {
+            return Ok(false);
         }
 
-        #[test]
-        fn when_it_reaches_the_end_of_the_input_without_finishing_parsing_a_valid_bencoded_value() {
-            let integer_with_missing_end_byte = b"i42";
+        self.check_trailing_data(writer)
+            .map_err(|err| self.input_limit_error(err, writer))?;
 
-            let result = try_bencode_to_json(integer_with_missing_end_byte);
+        Ok(true)
+    }
 
-            assert!(matches!(
-                result,
-                Err(Error::UnexpectedEndOfInputParsingInteger { .. })
-            ));
-        }
+    /// It returns the values selected by the captured paths, in the order
+    /// they were completed.
+    ///
+    /// They are available after writing the JSON, or reading the values or
+    /// events. See [`capture`].
+    pub fn captured_values(&self) -> &[CapturedValue] {
+        &self.captured_values
     }
 
-    mod integers {
-        use crate::test::bencode_to_json_unchecked;
+    /// It returns the errors recovered while writing the JSON, in the order
+    /// they were found. See [`recovery`].
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
 
-        #[test]
-        fn zero() {
-            assert_eq!(bencode_to_json_unchecked(b"i0e"), "0".to_string());
+    /// It returns the path of the value of the latest event:
+    ///
+    /// - For a dictionary key, the path of its value.
+    /// - For the start or the end of a list or dictionary, the path of the
+    ///   list or dictionary.
+    /// - For other events, the path of the integer or string.
+    ///
+    /// Errors include the path of the value being parsed when they occurred. See
+    /// [`Error::path`](error::Error::path).
+    #[must_use]
+    pub fn path(&self) -> ValuePath {
+        self.value_path(false)
+    }
+
+    /// It returns the path of the current value. When `next_item` is `true`,
+    /// the path points to the next item of the innermost list instead.
+    fn value_path(&self, next_item: bool) -> ValuePath {
+        let innermost = self.containers.len().saturating_sub(1);
+
+        ValuePath::new(
+            self.containers
+                .iter()
+                .enumerate()
+                .filter_map(|(depth, container)| {
+                    container.current_segment(next_item && depth == innermost)
+                })
+                .collect(),
+        )
+    }
+
+    /// It returns the key of each open dictionary, `None` for lists and
+    /// dictionaries without keys yet.
+    fn key_path(&self) -> Vec<Option<Vec<u8>>> {
+        self.containers
+            .iter()
+            .map(|container| container.last_key.clone())
+            .collect()
+    }
+
+    /// It starts or stops recording raw bytes after an event. It returns the
+    /// info hash when the `info` dictionary is complete.
+    fn update_recordings(&mut self, is_key: bool) -> Option<InfoHash> {
+        if is_key {
+            if self.options.torrent || !self.options.captured_paths.is_empty() {
+                self.start_recordings(&self.key_path());
+            }
+            None
+        } else {
+            self.stop_recordings()
         }
+    }
 
-        #[test]
-        fn one_digit_integer() {
-            assert_eq!(bencode_to_json_unchecked(b"i1e"), "1".to_string());
+    /// It starts recording the raw bytes of the value of the dictionary key
+    /// just parsed, if it's selected.
+    fn start_recordings(&mut self, path: &[Option<Vec<u8>>]) {
+        if self.options.torrent && torrent::is_info_path(path) {
+            self.byte_reader.start_recording(false);
+            self.recorded_values.push(RecordedValue {
+                purpose: RecordingPurpose::InfoHash,
+                depth: path.len(),
+            });
         }
 
-        #[test]
-        fn two_digits_integer() {
-            assert_eq!(bencode_to_json_unchecked(b"i42e"), "42".to_string());
+        for captured_path in &self.options.captured_paths {
+            if captured_path.matches(path) {
+                self.byte_reader
+                    .start_recording(self.options.capture_raw_bytes);
+                self.recorded_values.push(RecordedValue {
+                    purpose: RecordingPurpose::Capture(captured_path.clone()),
+                    depth: path.len(),
+                });
+            }
         }
+    }
 
-        #[test]
-        fn negative_integer() {
-            assert_eq!(bencode_to_json_unchecked(b"i-1e"), "-1".to_string());
+    /// It stops recording the raw bytes of the values completed by the
+    /// latest event. It returns the info hash if the `info` dictionary is one
+    /// of them.
+    fn stop_recordings(&mut self) -> Option<InfoHash> {
+        let mut info_hash = None;
+
+        while self
+            .recorded_values
+            .last()
+            .is_some_and(|recorded_value| recorded_value.depth == self.containers.len())
+        {
+            let recorded_value = self
+                .recorded_values
+                .pop()
+                .expect("there is a recorded value");
+
+            let recorded = self
+                .byte_reader
+                .stop_recording()
+                .expect("each recorded value has a recording");
+
+            match recorded_value.purpose {
+                RecordingPurpose::InfoHash => {
+                    info_hash = Some(InfoHash {
+                        v1: recorded.sha1,
+                        v2: recorded.sha256,
+                    });
+                }
+                RecordingPurpose::Capture(path) => {
+                    self.captured_values
+                        .push(CapturedValue::new(path, recorded));
+                }
+            }
         }
 
-        #[test]
-        fn positive_integer_greater_than_i64_max() {
-            let big_positive_integer = i64::MAX.to_string() + "1";
+        info_hash
+    }
 
-            let bencoded_big_positive_integer = format!("i{big_positive_integer}e");
+    /// It parses the next token of the input and returns the corresponding
+    /// [`Event`]. It returns `None` if the input has ended.
+    ///
+    /// Strings longer than the
+    /// [`string_buffer_size`](ParserOptions::string_buffer_size) are returned
+    /// in chunks, with [`Event::BytesStart`] and [`Event::BytesChunk`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input.
+    /// - The input is invalid Bencode.
+    pub fn next_event(&mut self) -> Result<Option<Event>, error::Error> {
+        let writer = NullWriter;
+
+        let event = if self.streamed_string_left > 0 {
+            Some(self.read_string_chunk(&writer)?)
+        } else {
+            match self.read_token(&writer, false)? {
+                Some(Token::Event(event)) => Some(event),
+                Some(Token::String(length)) => {
+                    self.streamed_string_left = length;
+                    Some(Event::BytesStart(length))
+                }
+                None => None,
+            }
+        };
 
-            assert_eq!(
-                bencode_to_json_unchecked(bencoded_big_positive_integer.as_bytes()),
-                big_positive_integer
-            );
+        // The recordings end with the last chunk of the string
+        if self.streamed_string_left == 0 {
+            if let Some(event) = &event {
+                // The info hash is only written to the JSON
+                let _info_hash = self.update_recordings(matches!(event, Event::Key(_)));
+            }
         }
 
-        #[test]
-        fn negative_integer_smaller_than_i64_min() {
-            let big_negative_integer = i64::MIN.to_string() + "1";
-
-            let bencoded_big_negative_integer = format!("i{big_negative_integer}e");
+        Ok(event)
+    }
 
-            assert_eq!(
-                bencode_to_json_unchecked(bencoded_big_negative_integer.as_bytes()),
-                big_negative_integer
-            );
+    /// It parses the next token of the input and returns the corresponding
+    /// [`Event`], with the whole strings. It returns `None` if the input has
+    /// ended.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input.
+    /// - The input is invalid Bencode.
+    pub(crate) fn next_whole_event(&mut self) -> Result<Option<Event>, error::Error> {
+        let event = self.read_event(&NullWriter)?;
+
+        if let Some(event) = &event {
+            // The info hash is only written to the JSON
+            let _info_hash = self.update_recordings(matches!(event, Event::Key(_)));
         }
 
-        mod should_fail {
-            use crate::{parsers::error::Error, try_bencode_to_json};
+        Ok(event)
+    }
 
-            #[test]
-            fn when_it_finds_an_invalid_byte() {
-                let int_with_invalid_byte = b"iae";
+    /// It reads the next chunk of the string being returned in chunks.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the input ends before the chunk is filled.
+    fn read_string_chunk<W: Writer>(&mut self, writer: &W) -> Result<Event, error::Error> {
+        let string_buffer_size = self
+            .options
+            .string_buffer_size
+            .expect("only strings longer than the buffer are streamed");
 
-                let result = try_bencode_to_json(int_with_invalid_byte);
+        let mut chunk = vec![0; self.streamed_string_left.min(string_buffer_size)];
 
-                assert!(matches!(
-                    result,
-                    Err(Error::UnexpectedByteParsingInteger { .. })
-                ));
-            }
+        string::read_chunk(&mut self.byte_reader, writer, &mut chunk)
+            .map_err(|err| self.input_limit_error(err, writer).with_path(self.path()))?;
 
-            #[test]
-            fn with_duplicate_sign() {
-                let int_with_invalid_byte = b"i--42e";
+        self.streamed_string_left -= chunk.len();
 
-                let result = try_bencode_to_json(int_with_invalid_byte);
+        Ok(Event::BytesChunk(chunk))
+    }
 
-                assert!(matches!(
-                    result,
-                    Err(Error::UnexpectedByteParsingInteger { .. })
-                ));
+    /// It returns an iterator over the [`Event`]s of the input.
+    ///
+    /// The iterator stops after the first error.
+    pub fn events(&mut self) -> Events<'_, R> {
+        Events::new(self)
+    }
+
+    /// It parses the next bencoded value read from input and returns it as an
+    /// owned [`Value`] tree. It returns `None` if the input has ended.
+    ///
+    /// The nesting depth is limited to [`DEFAULT_MAX_DEPTH`] unless the
+    /// options set another limit.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input.
+    /// - The input is invalid Bencode.
+    /// - The input exceeds one of the limits.
+    /// - An integer does not fit into an `i64`.
+    pub fn read_value(&mut self) -> Result<Option<Value>, error::Error> {
+        let max_depth = self.options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+
+        self.build_value(max_depth)
+    }
+
+    /// It builds the [`Value`] tree of the next bencoded value, with lists and
+    /// dictionaries nested up to `max_depth` levels.
+    fn build_value(&mut self, max_depth: usize) -> Result<Option<Value>, error::Error> {
+        let mut builder = Builder::<Value>::default();
+
+        while let Some(event) = self.next_whole_event()? {
+            let value = match event {
+                Event::Integer(integer) => Some(Value::Integer(
+                    self.integer_to_i64(&integer)
+                        .map_err(|err| err.with_path(self.path()))?,
+                )),
+                Event::Key(bytes) => {
+                    builder.set_dict_field_key(bytes);
+                    None
+                }
+                Event::Bytes(bytes) => Some(Value::Bytes(bytes)),
+                Event::BytesStart(_) | Event::BytesChunk(_) => {
+                    unreachable!("whole events have whole strings")
+                }
+                Event::ListStart => {
+                    builder.begin_list();
+                    None
+                }
+                Event::DictStart => {
+                    builder.begin_dict();
+                    None
+                }
+                Event::End => Some(builder.end_list_or_dict()),
+            };
+
+            if self.containers.len() > max_depth {
+                return Err(self.max_depth_exceeded(&NullWriter));
+            }
+
+            if let Some(value) = value {
+                if let Some(top_level_value) = builder.add(value) {
+                    return Ok(Some(top_level_value));
+                }
             }
         }
+
+        Ok(None)
     }
 
-    mod strings {
-        use crate::{
-            test::{bencode_to_json_unchecked, bencoded_string_with_repeated_byte},
-            to_bencode,
-        };
+    /// It checks that there are no more bencoded values in the input, apart
+    /// from the ignored line breaks.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input.
+    /// - There is more data in the input.
+    pub fn expect_end_of_input(&mut self) -> Result<(), error::Error> {
+        let writer = NullWriter;
 
-        #[test]
-        fn length_can_contain_leading_zeros() {
-            assert_eq!(bencode_to_json_unchecked(b"00:"), r#""""#.to_string());
-        }
+        self.check_trailing_data(&writer)
+            .map_err(|err| self.input_limit_error(err, &writer))
+    }
 
-        #[test]
-        fn empty_string() {
-            assert_eq!(bencode_to_json_unchecked(b"0:"), r#""""#.to_string());
+    /// It updates the stack state and prints the delimiters when needed.
+    ///
+    /// Called when the first byte of a bencoded value (integer, string, list
+    /// or dict) is received.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the new value is not allowed in the current
+    /// state or the writer can't write to the output.
+    #[deprecated(
+        since = "0.1.0",
+        note = "use `next_event` to get the parsed values, or `write_bytes` and `write_str` to get the JSON output"
+    )]
+    pub fn begin_bencoded_value<W: Writer>(
+        &mut self,
+        bencode_type: BencodeType,
+        writer: &mut W,
+    ) -> Result<(), error::Error> {
+        match self.update_stack_on_value_begin(bencode_type, writer)? {
+            State::Initial
+            | State::ExpectingFirstListItemOrEnd
+            | State::ExpectingFirstDictFieldOrEnd => {}
+            State::ExpectingNextListItem | State::ExpectingDictFieldKeyOrEnd => {
+                writer.write_byte(b',')?;
+            }
+            State::ExpectingDictFieldValue => {
+                writer.write_byte(b':')?;
+            }
         }
 
-        #[test]
-        fn utf8() {
-            assert_eq!(
-                bencode_to_json_unchecked(b"4:spam"),
-                r#""spam""#.to_string()
-            );
-        }
+        Ok(())
+    }
 
-        #[test]
-        fn non_utf8() {
-            assert_eq!(
-                bencode_to_json_unchecked(b"4:\xFF\xFE\xFD\xFC"),
-                r#""<hex>fffefdfc</hex>""#.to_string()
-            );
+    /// It updates the stack state and prints the delimiters when needed.
+    ///
+    /// Called when the end of list or dictionary byte is received. End of
+    /// integers or strings are processed while parsing them.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the end was not expected or the writer can't
+    /// write to the output.
+    #[deprecated(
+        since = "0.1.0",
+        note = "use `next_event` to get the parsed values, or `write_bytes` and `write_str` to get the JSON output"
+    )]
+    pub fn end_list_or_dict<W: Writer>(&mut self, writer: &mut W) -> Result<(), error::Error> {
+        match self.update_stack_on_list_or_dict_end(writer)? {
+            State::ExpectingFirstListItemOrEnd | State::ExpectingNextListItem => {
+                writer.write_byte(b']')?;
+            }
+            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd => {
+                writer.write_byte(b'}')?;
+            }
+            // The stack rejects the end in these states.
+            State::Initial | State::ExpectingDictFieldValue => {}
         }
 
-        #[test]
-        fn big_utf8_string() {
-            let big_string = "a".repeat(1_000_000);
+        Ok(())
+    }
 
-            assert_eq!(
-                bencode_to_json_unchecked(&to_bencode(&big_string)),
-                format!(r#""{big_string}""#)
-            );
+    /// It fails if there is more data in the input, apart from the ignored
+    /// line breaks.
+    fn check_trailing_data<W: Writer>(&mut self, writer: &W) -> Result<(), error::Error> {
+        while let Some(peeked_byte) = Self::peek_byte(&mut self.byte_reader, writer)? {
+            let error = match peeked_byte {
+                b'\n' if self.options.strict => error::Error::LineBreakNotAllowed,
+                b'\n' if self.options.skip_newlines => {
+                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
+                    self.num_skipped_line_breaks += 1;
+                    continue;
+                }
+                _ => error::Error::TrailingData,
+            };
+
+            return Err(error(
+                ReadContext {
+                    byte: Some(peeked_byte),
+                    pos: self.byte_reader.input_byte_counter(),
+                    latest_bytes: self.byte_reader.captured_bytes(),
+                    path: ValuePath::default(),
+                    state: None,
+                },
+                WriteContext {
+                    byte: None,
+                    pos: writer.output_byte_counter(),
+                    latest_bytes: writer.captured_bytes(),
+                },
+            ));
         }
 
-        #[test]
-        fn big_non_utf8_string() {
-            let big_non_utf8_string = bencoded_string_with_repeated_byte(b'\xFF', 1_000_000);
+        Ok(())
+    }
 
-            let expected = format!(r#""<hex>{}</hex>""#, "ff".repeat(1_000_000));
+    /// It parses the next token of the input and returns the corresponding
+    /// event. It returns `None` if the input has ended.
+    ///
+    /// The writer is only used to build the error contexts.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input.
+    /// - The input is invalid Bencode.
+    /// - The input exceeds one of the limits.
+    fn read_event<W: Writer>(&mut self, writer: &W) -> Result<Option<Event>, error::Error> {
+        match self.read_token(writer, false)? {
+            Some(Token::Event(event)) => Ok(Some(event)),
+            Some(Token::String(length)) => {
+                let bytes = string::read_bytes(&mut self.byte_reader, writer, length)
+                    .map_err(|err| self.input_limit_error(err, writer).with_path(self.path()))?;
 
-            assert_eq!(bencode_to_json_unchecked(&big_non_utf8_string), expected);
+                Ok(Some(Event::Bytes(bytes)))
+            }
+            None => Ok(None),
         }
+    }
 
-        #[test]
-        fn ending_with_bencode_end_char() {
-            assert_eq!(bencode_to_json_unchecked(b"1:e"), r#""e""#.to_string());
-        }
+    /// It parses the next token of the input. Long strings are returned as
+    /// [`Token::String`] before reading their value.
+    ///
+    /// Only the JSON output of the selected values skips the strings outside
+    /// them, returning empty [`Event::Bytes`]. The events and the value trees
+    /// always have the whole strings.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input.
+    /// - The input is invalid Bencode.
+    /// - The input exceeds one of the limits.
+    fn read_token<W: Writer>(
+        &mut self,
+        writer: &W,
+        skip_unselected_strings: bool,
+    ) -> Result<Option<Token>, error::Error> {
+        self.has_begun_value = false;
+
+        let depth = self.containers.len();
+        let items = self.containers.last().map(|container| container.items);
+        let state = self.stack.peek();
+
+        self.read_next_token(writer, skip_unselected_strings)
+            .map_err(|err| {
+                // Errors before a new list item begins belong to that item
+                let next_item = self.containers.len() == depth
+                    && self.containers.last().map(|container| container.items) == items;
+
+                self.input_limit_error(err, writer)
+                    .with_path(self.value_path(next_item))
+                    .with_state(state)
+            })
+    }
 
-        #[test]
-        fn containing_a_reserved_char() {
-            assert_eq!(bencode_to_json_unchecked(b"1:i"), r#""i""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:l"), r#""l""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:d"), r#""d""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:l"), r#""l""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:e"), r#""e""#.to_string());
+    fn read_next_token<W: Writer>(
+        &mut self,
+        writer: &W,
+        skip_unselected_strings: bool,
+    ) -> Result<Option<Token>, error::Error> {
+        while let Some(peeked_byte) = Self::peek_byte(&mut self.byte_reader, writer)? {
+            let token = match peeked_byte {
+                BENCODE_BEGIN_INTEGER => {
+                    self.begin_value(BencodeType::Integer, writer)?;
+                    Event::Integer(self.read_integer(writer)?).into()
+                }
+                b'0'..=b'9' => {
+                    let previous_state = self.begin_value(BencodeType::String, writer)?;
+
+                    match previous_state {
+                        State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd => {
+                            Event::Key(self.read_dict_key(writer)?).into()
+                        }
+                        _ if skip_unselected_strings && self.is_skipped_string() => {
+                            string::skip_bytes(&mut self.byte_reader, writer, &self.options)?;
+                            Event::Bytes(Vec::new()).into()
+                        }
+                        _ => {
+                            let length =
+                                string::parse_length(&mut self.byte_reader, writer, &self.options)?;
+
+                            if self.is_streamed_string(length) {
+                                Token::String(length)
+                            } else {
+                                Event::Bytes(string::read_bytes(
+                                    &mut self.byte_reader,
+                                    writer,
+                                    length,
+                                )?)
+                                .into()
+                            }
+                        }
+                    }
+                }
+                BENCODE_BEGIN_LIST => {
+                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
+                    self.begin_value(BencodeType::List, writer)?;
+                    self.begin_container(false, writer)?;
+                    self.stack.push(State::ExpectingFirstListItemOrEnd);
+                    Event::ListStart.into()
+                }
+                BENCODE_BEGIN_DICT => {
+                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
+                    self.begin_value(BencodeType::Dict, writer)?;
+                    self.begin_container(true, writer)?;
+                    self.stack.push(State::ExpectingFirstDictFieldOrEnd);
+                    Event::DictStart.into()
+                }
+                BENCODE_END_LIST_OR_DICT => {
+                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
+                    self.update_stack_on_list_or_dict_end(writer)?;
+                    self.containers.pop();
+                    Event::End.into()
+                }
+                b'\n' if self.options.strict => {
+                    return Err(error::Error::LineBreakNotAllowed(
+                        ReadContext {
+                            byte: Some(peeked_byte),
+                            pos: self.byte_reader.input_byte_counter(),
+                            latest_bytes: self.byte_reader.captured_bytes(),
+                            path: ValuePath::default(),
+                            state: None,
+                        },
+                        WriteContext {
+                            byte: Some(peeked_byte),
+                            pos: writer.output_byte_counter(),
+                            latest_bytes: writer.captured_bytes(),
+                        },
+                    ));
+                }
+                b'\n' if self.options.skip_newlines => {
+                    // Ignore line breaks at the beginning, the end, or between values
+                    let _byte = Self::read_peeked_byte(peeked_byte, &mut self.byte_reader, writer)?;
+                    self.num_skipped_line_breaks += 1;
+                    continue;
+                }
+                _ => {
+                    return Err(error::Error::UnrecognizedFirstBencodeValueByte(
+                        ReadContext {
+                            byte: Some(peeked_byte),
+                            pos: self.byte_reader.input_byte_counter(),
+                            latest_bytes: self.byte_reader.captured_bytes(),
+                            path: ValuePath::default(),
+                            state: None,
+                        },
+                        WriteContext {
+                            byte: Some(peeked_byte),
+                            pos: writer.output_byte_counter(),
+                            latest_bytes: writer.captured_bytes(),
+                        },
+                    ));
+                }
+            };
+
+            self.num_processed_tokens += 1;
+
+            return Ok(Some(token));
         }
 
-        #[test]
-        fn containing_a_digit() {
-            assert_eq!(bencode_to_json_unchecked(b"1:0"), r#""0""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:1"), r#""1""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:2"), r#""2""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:3"), r#""3""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:4"), r#""4""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:5"), r#""5""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:6"), r#""6""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:7"), r#""7""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:8"), r#""8""#.to_string());
-            assert_eq!(bencode_to_json_unchecked(b"1:9"), r#""9""#.to_string());
+        self.check_bad_end_stack_state(writer)?;
+
+        Ok(None)
+    }
+
+    /// It parses a bencoded integer keeping its digits.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the integer is invalid.
+    fn read_integer<W: Writer>(&mut self, writer: &W) -> Result<Integer, error::Error> {
+        let mut digits = String::new();
+
+        // The digits are written to a scratch buffer, so the writer context
+        // of the errors must come from the real output.
+        integer::parse(
+            &mut self.byte_reader,
+            &mut StringWriter::with_capture_size(&mut digits, self.options.capture_size),
+            &self.options,
+        )
+        .map_err(|err| err.with_output(writer))?;
+
+        Ok(Integer::new(digits))
+    }
+
+    /// It parses a key of the current dictionary. In strict mode, it also
+    /// checks the key order.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the key is invalid or, in strict mode, not
+    /// greater than the previous key.
+    fn read_dict_key<W: Writer>(&mut self, writer: &W) -> Result<Vec<u8>, error::Error> {
+        let previous_key = self.current_container().last_key.take();
+
+        let key = string::parse_bytes(&mut self.byte_reader, writer, &self.options)?;
+
+        self.current_container().last_key = Some(key.clone());
+
+        if self.options.strict {
+            self.check_dict_key_order(previous_key.as_deref(), &key, writer)?;
         }
 
-        mod should_escape_json {
-            use crate::{parsers::tests::bencode_to_json_unchecked, to_bencode};
+        Ok(key)
+    }
+
+    /// It returns the innermost open list or dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is no open list or dictionary.
+    fn current_container(&mut self) -> &mut Container {
+        self.containers
+            .last_mut()
+            .expect("items are only parsed inside lists or dictionaries")
+    }
+
+    /// It checks that the new key of the current dictionary is greater than
+    /// the previous one, so keys are sorted and unique.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the key is not greater than the previous key.
+    fn check_dict_key_order<W: Writer>(
+        &self,
+        previous_key: Option<&[u8]>,
+        key: &[u8],
+        writer: &W,
+    ) -> Result<(), error::Error> {
+        let ordering = previous_key.map(|previous_key| key.cmp(previous_key));
+
+        let error = match ordering {
+            None | Some(Ordering::Greater) => return Ok(()),
+            Some(Ordering::Less) => error::Error::UnsortedDictKeys,
+            Some(Ordering::Equal) => error::Error::DuplicateDictKey,
+        };
+
+        Err(error(
+            ReadContext {
+                byte: None,
+                pos: self.byte_reader.input_byte_counter(),
+                latest_bytes: self.byte_reader.captured_bytes(),
+                path: ValuePath::default(),
+                state: None,
+            },
+            WriteContext {
+                byte: None,
+                pos: writer.output_byte_counter(),
+                latest_bytes: writer.captured_bytes(),
+            },
+        ))
+    }
+
+    /// It updates the stack state when the first byte of a new bencoded value
+    /// is received, and counts the value as a new item of the current list or
+    /// dictionary. It returns the previous state.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the new value is not allowed in the current
+    /// state, or the current list or dictionary has too many items.
+    fn begin_value<W: Writer>(
+        &mut self,
+        bencode_type: BencodeType,
+        writer: &W,
+    ) -> Result<State, error::Error> {
+        let previous_state = self.update_stack_on_value_begin(bencode_type, writer)?;
+
+        self.has_begun_value = true;
+
+        let is_new_item = match previous_state {
+            State::Initial | State::ExpectingDictFieldValue => false,
+            State::ExpectingFirstListItemOrEnd
+            | State::ExpectingNextListItem
+            | State::ExpectingFirstDictFieldOrEnd
+            | State::ExpectingDictFieldKeyOrEnd => true,
+        };
+
+        if is_new_item {
+            let container = self.current_container();
+
+            container.items += 1;
+
+            let items = container.items;
+
+            if self
+                .options
+                .max_container_items
+                .is_some_and(|max_container_items| items > max_container_items)
+            {
+                return Err(error::Error::MaxContainerItemsExceeded(
+                    ReadContext {
+                        byte: None,
+                        pos: self.byte_reader.input_byte_counter(),
+                        latest_bytes: self.byte_reader.captured_bytes(),
+                        path: ValuePath::default(),
+                        state: None,
+                    },
+                    WriteContext {
+                        byte: None,
+                        pos: writer.output_byte_counter(),
+                        latest_bytes: writer.captured_bytes(),
+                    },
+                ));
+            }
+        }
+
+        Ok(previous_state)
+    }
+
+    /// It opens a new list or dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the maximum nesting depth is exceeded.
+    fn begin_container<W: Writer>(
+        &mut self,
+        is_dict: bool,
+        writer: &W,
+    ) -> Result<(), error::Error> {
+        if self
+            .options
+            .max_depth
+            .is_some_and(|max_depth| self.containers.len() >= max_depth)
+        {
+            return Err(self.max_depth_exceeded(writer));
+        }
+
+        self.containers.push(Container {
+            is_dict,
+            ..Container::default()
+        });
+
+        Ok(())
+    }
+
+    /// It returns the error for a list or dictionary nested too deeply.
+    fn max_depth_exceeded<W: Writer>(&self, writer: &W) -> error::Error {
+        error::Error::MaxDepthExceeded(
+            ReadContext {
+                byte: None,
+                pos: self.byte_reader.input_byte_counter(),
+                latest_bytes: self.byte_reader.captured_bytes(),
+                path: ValuePath::default(),
+                state: None,
+            },
+            WriteContext {
+                byte: None,
+                pos: writer.output_byte_counter(),
+                latest_bytes: writer.captured_bytes(),
+            },
+        )
+    }
+
+    /// It replaces the error returned when reading beyond the maximum input
+    /// size with a specific error.
+    fn input_limit_error<W: Writer>(&self, err: error::Error, writer: &W) -> error::Error {
+        if !(matches!(err, error::Error::Io(_)) && self.byte_reader.input_limit_exceeded()) {
+            return err;
+        }
+
+        error::Error::MaxInputBytesExceeded(
+            ReadContext {
+                byte: None,
+                pos: self.byte_reader.input_byte_counter(),
+                latest_bytes: self.byte_reader.captured_bytes(),
+                path: ValuePath::default(),
+                state: None,
+            },
+            WriteContext {
+                byte: None,
+                pos: writer.output_byte_counter(),
+                latest_bytes: writer.captured_bytes(),
+            },
+        )
+    }
+
+    /// It converts a parsed integer into an `i64`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the integer does not fit into an `i64`.
+    fn integer_to_i64(&self, integer: &Integer) -> Result<i64, error::Error> {
+        let writer = NullWriter;
+
+        integer.to_i64().ok_or_else(|| {
+            error::Error::IntegerOverflow(
+                ReadContext {
+                    byte: None,
+                    pos: self.byte_reader.input_byte_counter(),
+                    latest_bytes: self.byte_reader.captured_bytes(),
+                    path: ValuePath::default(),
+                    state: None,
+                },
+                WriteContext {
+                    byte: None,
+                    pos: writer.output_byte_counter(),
+                    latest_bytes: writer.captured_bytes(),
+                },
+            )
+        })
+    }
+
+    /// It reads the next byte from the input consuming it. It returns `None` if
+    /// the input has ended.
+    ///
+    /// # Errors
+    ///
+    /// Will return and errors if:
+    ///
+    /// - It can't read from the input.
+    /// - The byte read is not the expected one (the previously peeked byte).
+    fn read_peeked_byte<W: Writer>(
+        peeked_byte: u8,
+        reader: &mut ByteReader<R>,
+        writer: &W,
+    ) -> Result<Option<u8>, error::Error> {
+        match reader.read_byte() {
+            Ok(byte) => {
+                if byte == peeked_byte {
+                    return Ok(Some(byte));
+                }
+                Err(error::Error::ReadByteAfterPeekingDoesMatchPeekedByte(
+                    ReadContext {
+                        byte: Some(byte),
+                        pos: reader.input_byte_counter(),
+                        latest_bytes: reader.captured_bytes(),
+                        path: ValuePath::default(),
+                        state: None,
+                    },
+                    WriteContext {
+                        byte: Some(byte),
+                        pos: writer.output_byte_counter(),
+                        latest_bytes: writer.captured_bytes(),
+                    },
+                ))
+            }
+            Err(err) => {
+                if err.kind() == io::ErrorKind::UnexpectedEof {
+                    return Ok(None);
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    /// It peeks the next byte from the input without consuming it. It returns
+    /// `None` if the input has ended.
+    ///
+    /// # Errors
+    ///
+    /// Will return and errors if it can't read from the input.
+    fn peek_byte<W: Writer>(
+        reader: &mut ByteReader<R>,
+        _writer: &W,
+    ) -> Result<Option<u8>, error::Error> {
+        match reader.peek_byte() {
+            Ok(byte) => Ok(Some(byte)),
+            Err(err) => {
+                if err.kind() == io::ErrorKind::UnexpectedEof {
+                    return Ok(None);
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    /// It updates the stack state when the first byte of a new bencoded value
+    /// is received. It returns the previous state.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the new value is not allowed in the current
+    /// state.
+    fn update_stack_on_value_begin<W: Writer>(
+        &mut self,
+        bencode_type: BencodeType,
+        writer: &W,
+    ) -> Result<State, error::Error> {
+        self.stack.begin_value(bencode_type).map_err(|err| {
+            err.into_error(
+                ReadContext {
+                    byte: None,
+                    pos: self.byte_reader.input_byte_counter(),
+                    latest_bytes: self.byte_reader.captured_bytes(),
+                    path: ValuePath::default(),
+                    state: None,
+                },
+                WriteContext {
+                    byte: None,
+                    pos: writer.output_byte_counter(),
+                    latest_bytes: writer.captured_bytes(),
+                },
+            )
+        })
+    }
+
+    /// It updates the stack state when the end of a list or dictionary is
+    /// received. It returns the popped state.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the end was not expected.
+    fn update_stack_on_list_or_dict_end<W: Writer>(
+        &mut self,
+        writer: &W,
+    ) -> Result<State, error::Error> {
+        self.stack.end_list_or_dict().map_err(|err| {
+            err.into_error(
+                ReadContext {
+                    byte: None,
+                    pos: self.byte_reader.input_byte_counter(),
+                    latest_bytes: self.byte_reader.captured_bytes(),
+                    path: ValuePath::default(),
+                    state: None,
+                },
+                WriteContext {
+                    byte: None,
+                    pos: writer.output_byte_counter(),
+                    latest_bytes: writer.captured_bytes(),
+                },
+            )
+        })
+    }
+
+    /// It checks if the stack state is correct at the end of the parsing.
+    ///
+    /// That could happen, for example, when bencode values are not finished.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the stack state is not correct.
+    fn check_bad_end_stack_state<W: Writer>(&self, writer: &W) -> Result<(), error::Error> {
+        self.stack.end_of_input().map_err(|err| {
+            err.into_error(
+                ReadContext {
+                    byte: None,
+                    pos: self.byte_reader.input_byte_counter(),
+                    latest_bytes: self.byte_reader.captured_bytes(),
+                    path: ValuePath::default(),
+                    state: None,
+                },
+                WriteContext {
+                    byte: None,
+                    pos: writer.output_byte_counter(),
+                    latest_bytes: writer.captured_bytes(),
+                },
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::{self, Read};
+
+    use crate::{parsers::BencodeParser, test::bencode_to_json_unchecked, try_bencode_to_json};
+
+    mod it_should_allow_writing {
+        use crate::parsers::BencodeParser;
+
+        #[test]
+        fn to_any_type_implementing_io_write_trait() {
+            let mut output = Vec::new();
+
+            let mut parser = BencodeParser::new(&b"i0e"[..]);
+
+            parser
+                .write_bytes(&mut output)
+                .expect("Bencode to JSON conversion failed");
+
+            assert_eq!(output, vec!(b'0'));
+        }
+
+        #[test]
+        fn writing_to_any_type_implementing_fmt_write_trait() {
+            let mut output = String::new();
+
+            let mut parser = BencodeParser::new(&b"i0e"[..]);
+
+            parser
+                .write_str(&mut output)
+                .expect("Bencode to JSON conversion failed");
+
+            assert_eq!(output, "0".to_string());
+        }
+    }
+
+    #[test]
+    fn it_should_allow_reading_from_an_empty_input() {
+        struct EmptyReader;
+
+        impl Read for EmptyReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Unexpected EOF",
+                ))
+            }
+        }
+
+        let mut output = String::new();
+
+        let mut parser = BencodeParser::new(EmptyReader);
+
+        parser.write_str(&mut output).unwrap();
+
+        assert_eq!(output, "");
+    }
+
+    mod it_should_allow_special_bencode_cases {
+
+        use crate::{parsers::BencodeParser, test::bencode_to_json_unchecked};
+
+        #[test]
+        fn an_empty_input() {
+            let mut output = String::new();
+
+            let mut parser = BencodeParser::new(&b""[..]);
+
+            parser
+                .write_str(&mut output)
+                .expect("Bencode to JSON conversion failed");
+
+            assert_eq!(output, String::new());
+        }
+
+        #[test]
+        fn line_breaks_at_the_beginning_of_the_input_stream() {
+            assert_eq!(bencode_to_json_unchecked(b"\ni0e"), "0".to_string());
+        }
+
+        #[test]
+        fn line_breaks_at_the_end_of_the_input_stream() {
+            assert_eq!(bencode_to_json_unchecked(b"i0e\n"), "0".to_string());
+        }
+
+        #[test]
+        fn line_breaks_between_bencoded_values() {
+            assert_eq!(
+                bencode_to_json_unchecked(b"li0e\ni1ee"),
+                "[0,1]".to_string()
+            );
+        }
+    }
+
+    mod it_should_fail {
+        use std::io::{self, Read};
+
+        use crate::{
+            parsers::{error::Error, BencodeParser},
+            try_bencode_to_json,
+        };
+
+        #[test]
+        fn when_there_is_a_problem_reading_from_input() {
+            struct FaultyReader;
+
+            impl Read for FaultyReader {
+                fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                    Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "Permission denied",
+                    ))
+                }
+            }
+
+            let mut output = String::new();
+
+            let mut parser = BencodeParser::new(FaultyReader);
+
+            let result = parser.write_str(&mut output);
+
+            assert!(matches!(result, Err(Error::Io(_))));
+        }
+
+        #[test]
+        fn when_it_cannot_recognized_the_fist_byte_of_a_new_bencoded_value() {
+            let invalid_bencoded_value = b"a";
+
+            let result = try_bencode_to_json(invalid_bencoded_value);
+
+            assert!(matches!(
+                result,
+                Err(Error::UnrecognizedFirstBencodeValueByte { .. })
+            ));
+        }
+
+        #[test]
+        fn when_it_reaches_the_end_of_the_input_without_finishing_parsing_a_valid_bencoded_value() {
+            let integer_with_missing_end_byte = b"i42";
+
+            let result = try_bencode_to_json(integer_with_missing_end_byte);
+
+            assert!(matches!(
+                result,
+                Err(Error::UnexpectedEndOfInputParsingInteger { .. })
+            ));
+        }
+    }
+
+    #[allow(deprecated)]
+    mod deprecated_api {
+        use crate::{
+            parsers::{BencodeParser, BencodeType},
+            rw::string_writer::StringWriter,
+        };
+
+        #[test]
+        fn it_should_not_write_delimiters_for_a_top_level_value() {
+            let mut output = String::new();
+            let mut writer = StringWriter::new(&mut output);
+            let mut parser = BencodeParser::new(&b""[..]);
+
+            parser
+                .begin_bencoded_value(BencodeType::Integer, &mut writer)
+                .unwrap();
+
+            assert_eq!(output, "");
+        }
+
+        #[test]
+        fn it_should_fail_ending_a_list_or_dict_that_was_not_begun() {
+            let mut output = String::new();
+            let mut writer = StringWriter::new(&mut output);
+            let mut parser = BencodeParser::new(&b""[..]);
+
+            assert!(parser.end_list_or_dict(&mut writer).is_err());
+        }
+    }
+
+    mod integers {
+        use crate::test::bencode_to_json_unchecked;
+
+        #[test]
+        fn zero() {
+            assert_eq!(bencode_to_json_unchecked(b"i0e"), "0".to_string());
+        }
+
+        #[test]
+        fn one_digit_integer() {
+            assert_eq!(bencode_to_json_unchecked(b"i1e"), "1".to_string());
+        }
+
+        #[test]
+        fn two_digits_integer() {
+            assert_eq!(bencode_to_json_unchecked(b"i42e"), "42".to_string());
+        }
+
+        #[test]
+        fn negative_integer() {
+            assert_eq!(bencode_to_json_unchecked(b"i-1e"), "-1".to_string());
+        }
+
+        #[test]
+        fn positive_integer_greater_than_i64_max() {
+            let big_positive_integer = i64::MAX.to_string() + "1";
+
+            let bencoded_big_positive_integer = format!("i{big_positive_integer}e");
+
+            assert_eq!(
+                bencode_to_json_unchecked(bencoded_big_positive_integer.as_bytes()),
+                big_positive_integer
+            );
+        }
+
+        #[test]
+        fn negative_integer_smaller_than_i64_min() {
+            let big_negative_integer = i64::MIN.to_string() + "1";
+
+            let bencoded_big_negative_integer = format!("i{big_negative_integer}e");
+
+            assert_eq!(
+                bencode_to_json_unchecked(bencoded_big_negative_integer.as_bytes()),
+                big_negative_integer
+            );
+        }
+
+        mod should_fail {
+            use crate::{parsers::error::Error, try_bencode_to_json};
+
+            #[test]
+            fn when_it_finds_an_invalid_byte() {
+                let int_with_invalid_byte = b"iae";
+
+                let result = try_bencode_to_json(int_with_invalid_byte);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedByteParsingInteger { .. })
+                ));
+            }
+
+            #[test]
+            fn with_duplicate_sign() {
+                let int_with_invalid_byte = b"i--42e";
+
+                let result = try_bencode_to_json(int_with_invalid_byte);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedByteParsingInteger { .. })
+                ));
+            }
+
+            #[test]
+            fn reporting_the_output_written_before_the_invalid_integer() {
+                let list_with_invalid_int = b"li1ei2xe";
+
+                let error = try_bencode_to_json(list_with_invalid_int).unwrap_err();
+
+                let write_context = error.write_context().unwrap();
+
+                assert_eq!(write_context.byte, Some(b'x'));
+                assert_eq!(write_context.pos, 2);
+                assert_eq!(write_context.latest_bytes, b"[1".to_vec());
+            }
+        }
+    }
+
+    mod strings {
+        use crate::{
+            test::{bencode_to_json_unchecked, bencoded_string_with_repeated_byte},
+            to_bencode,
+        };
+
+        #[test]
+        fn length_can_contain_leading_zeros() {
+            assert_eq!(bencode_to_json_unchecked(b"00:"), r#""""#.to_string());
+        }
+
+        #[test]
+        fn empty_string() {
+            assert_eq!(bencode_to_json_unchecked(b"0:"), r#""""#.to_string());
+        }
+
+        #[test]
+        fn utf8() {
+            assert_eq!(
+                bencode_to_json_unchecked(b"4:spam"),
+                r#""spam""#.to_string()
+            );
+        }
+
+        #[test]
+        fn non_utf8() {
+            assert_eq!(
+                bencode_to_json_unchecked(b"4:\xFF\xFE\xFD\xFC"),
+                r#""<hex>fffefdfc</hex>""#.to_string()
+            );
+        }
+
+        #[test]
+        fn big_utf8_string() {
+            let big_string = "a".repeat(1_000_000);
+
+            assert_eq!(
+                bencode_to_json_unchecked(&to_bencode(&big_string)),
+                format!(r#""{big_string}""#)
+            );
+        }
+
+        #[test]
+        fn big_non_utf8_string() {
+            let big_non_utf8_string = bencoded_string_with_repeated_byte(b'\xFF', 1_000_000);
+
+            let expected = format!(r#""<hex>{}</hex>""#, "ff".repeat(1_000_000));
+
+            assert_eq!(bencode_to_json_unchecked(&big_non_utf8_string), expected);
+        }
+
+        #[test]
+        fn ending_with_bencode_end_char() {
+            assert_eq!(bencode_to_json_unchecked(b"1:e"), r#""e""#.to_string());
+        }
+
+        #[test]
+        fn containing_a_reserved_char() {
+            assert_eq!(bencode_to_json_unchecked(b"1:i"), r#""i""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:l"), r#""l""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:d"), r#""d""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:l"), r#""l""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:e"), r#""e""#.to_string());
+        }
+
+        #[test]
+        fn containing_a_digit() {
+            assert_eq!(bencode_to_json_unchecked(b"1:0"), r#""0""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:1"), r#""1""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:2"), r#""2""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:3"), r#""3""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:4"), r#""4""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:5"), r#""5""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:6"), r#""6""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:7"), r#""7""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:8"), r#""8""#.to_string());
+            assert_eq!(bencode_to_json_unchecked(b"1:9"), r#""9""#.to_string());
+        }
+
+        mod should_escape_json {
+            use crate::{parsers::tests::bencode_to_json_unchecked, to_bencode};
+
+            #[test]
+            fn containing_a_double_quote() {
+                assert_eq!(
+                    bencode_to_json_unchecked("1:\"".as_bytes()),
+                    r#""\"""#.to_string()
+                );
+            }
+
+            #[test]
+            fn containing_backslashes() {
+                assert_eq!(
+                    bencode_to_json_unchecked("1:\\".as_bytes()),
+                    r#""\\""#.to_string()
+                );
+            }
+
+            #[test]
+            fn containing_control_characters() {
+                assert_eq!(
+                    bencode_to_json_unchecked("1:\n".as_bytes()),
+                    r#""\n""#.to_string()
+                );
+                assert_eq!(
+                    bencode_to_json_unchecked("1:\r".as_bytes()),
+                    r#""\r""#.to_string()
+                );
+                assert_eq!(
+                    bencode_to_json_unchecked("1:\t".as_bytes()),
+                    r#""\t""#.to_string()
+                );
+            }
+
+            #[test]
+            fn containing_unicode_characters() {
+                assert_eq!(
+                    bencode_to_json_unchecked(&to_bencode("ñandú")),
+                    r#""ñandú""#.to_string()
+                );
+            }
+        }
+
+        mod it_should_fail_parsing_when {
+            use crate::parsers::{error::Error, tests::try_bencode_to_json};
+
+            #[test]
+            fn it_reaches_the_end_of_the_input_parsing_the_string_length() {
+                let incomplete_string_length = b"4";
+
+                let result = try_bencode_to_json(incomplete_string_length);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedEndOfInputParsingStringLength { .. })
+                ));
+            }
+
+            #[test]
+            fn it_reaches_the_end_of_the_input_parsing_the_string_value() {
+                let incomplete_string_value = b"4:123";
+
+                let result = try_bencode_to_json(incomplete_string_value);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedEndOfInputParsingStringValue { .. })
+                ));
+            }
+
+            #[test]
+            fn it_receives_a_non_digit_byte_in_the_string_length() {
+                let incomplete_string_value = b"4a:1234";
+
+                let result = try_bencode_to_json(incomplete_string_value);
+
+                assert!(matches!(result, Err(Error::InvalidStringLengthByte { .. })));
+            }
+        }
+    }
+
+    mod lists {
+        use crate::{
+            parsers::tests::bencode_to_json_unchecked,
+            test::{generate_n_nested_empty_bencoded_lists, generate_n_nested_empty_json_arrays},
+        };
+
+        #[test]
+        fn empty_list() {
+            assert_eq!(bencode_to_json_unchecked(b"le"), "[]".to_string());
+        }
+
+        #[test]
+        fn one_nested_empty_list() {
+            assert_eq!(bencode_to_json_unchecked(b"llee"), "[[]]".to_string());
+        }
+
+        #[test]
+        fn two_nested_empty_list() {
+            assert_eq!(bencode_to_json_unchecked(b"llleee"), "[[[]]]".to_string());
+        }
+
+        #[test]
+        fn many_nested_empty_list() {
+            assert_eq!(
+                bencode_to_json_unchecked(&generate_n_nested_empty_bencoded_lists(100)),
+                generate_n_nested_empty_json_arrays(100)
+            );
+        }
+
+        mod with_one_item {
+            use crate::parsers::tests::bencode_to_json_unchecked;
+
+            #[test]
+            fn integer() {
+                assert_eq!(bencode_to_json_unchecked(b"li42ee"), "[42]".to_string());
+            }
+
+            #[test]
+            fn utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l4:spame"),
+                    r#"["spam"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l4:\xFF\xFE\xFD\xFCe"),
+                    r#"["<hex>fffefdfc</hex>"]"#.to_string()
+                );
+            }
+
+            mod of_type_list {
+                use crate::parsers::tests::bencode_to_json_unchecked;
+
+                #[test]
+                fn two_nested_empty_list() {
+                    assert_eq!(bencode_to_json_unchecked(b"llee"), "[[]]".to_string());
+                }
+
+                #[test]
+                fn three_nested_empty_lists() {
+                    assert_eq!(bencode_to_json_unchecked(b"llleee"), "[[[]]]".to_string());
+                }
+
+                #[test]
+                fn one_nested_list_which_contains_one_integer() {
+                    assert_eq!(bencode_to_json_unchecked(b"lli42eee"), "[[42]]".to_string());
+                }
+
+                #[test]
+                fn one_nested_list_which_contains_two_integers() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"lli42ei43eee"),
+                        "[[42,43]]".to_string()
+                    );
+                }
+
+                #[test]
+                fn one_nested_list_which_contains_one_utf_8_string() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ll4:spamee"),
+                        r#"[["spam"]]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn one_nested_list_which_contains_two_utf_8_strings() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ll5:alice3:bobee"),
+                        r#"[["alice","bob"]]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn one_nested_list_which_contains_one_non_utf_8_string() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ll4:\xFF\xFE\xFD\xFCee"),
+                        r#"[["<hex>fffefdfc</hex>"]]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn one_nested_list_which_contains_two_non_utf_8_string() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ll2:\xFF\xFE2:\xFD\xFCee"),
+                        r#"[["<hex>fffe</hex>","<hex>fdfc</hex>"]]"#.to_string()
+                    );
+                }
+            }
+
+            mod of_type_dict {
+                use crate::parsers::tests::bencode_to_json_unchecked;
+
+                #[test]
+                fn empty() {
+                    assert_eq!(bencode_to_json_unchecked(b"ldee"), "[{}]".to_string());
+                }
+
+                #[test]
+                fn with_one_field() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ld3:foo3:baree"),
+                        r#"[{"foo":"bar"}]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn with_two_fields() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ld3:bar4:spam3:fooi42eee"),
+                        r#"[{"bar":"spam","foo":42}]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn with_nested_empty_dict() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ld3:foodeee"),
+                        r#"[{"foo":{}}]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn with_two_nested_empty_dicts() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ld3:food3:foodeeee"),
+                        r#"[{"foo":{"foo":{}}}]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn with_nested_dict_with_one_field() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ld3:food3:foo3:bareee"),
+                        r#"[{"foo":{"foo":"bar"}}]"#.to_string()
+                    );
+                }
+
+                #[test]
+                fn with_nested_dict_with_two_fields() {
+                    assert_eq!(
+                        bencode_to_json_unchecked(b"ld3:food3:foo3:bar3:fooi42eeee"),
+                        r#"[{"foo":{"foo":"bar","foo":42}}]"#.to_string()
+                    );
+                }
+            }
+        }
+
+        mod with_two_items_of_the_same_type {
+            use crate::parsers::tests::bencode_to_json_unchecked;
+
+            #[test]
+            fn two_integers() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"li42ei43ee"),
+                    "[42,43]".to_string()
+                );
+            }
+
+            #[test]
+            fn two_utf8_strings() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l5:alice3:bobe"),
+                    r#"["alice","bob"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_non_utf8_strings() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l2:\xFF\xFE2:\xFD\xFCe"),
+                    r#"["<hex>fffe</hex>","<hex>fdfc</hex>"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_empty_lists() {
+                assert_eq!(bencode_to_json_unchecked(b"llelee"), r"[[],[]]".to_string());
+            }
+
+            #[test]
+            fn two_empty_dicts() {
+                assert_eq!(bencode_to_json_unchecked(b"ldedee"), r"[{},{}]".to_string());
+            }
+
+            #[test]
+            fn two_lists_with_one_item() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lli42eeli42eee"),
+                    r"[[42],[42]]".to_string()
+                );
+            }
+
+            #[test]
+            fn two_dicts_with_one_item() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ld3:fooi42eed3:fooi42eee"),
+                    r#"[{"foo":42},{"foo":42}]"#.to_string()
+                );
+            }
+        }
+
+        mod with_two_items_of_different_types {
+            use crate::parsers::tests::bencode_to_json_unchecked;
+
+            #[test]
+            fn integer_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"li42e5:alicee"),
+                    r#"[42,"alice"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"li42e2:\xFF\xFEe"),
+                    r#"[42,"<hex>fffe</hex>"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"li42elee"),
+                    r"[42,[]]".to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"li42eli43eee"),
+                    r"[42,[43]]".to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"li42edee"),
+                    r"[42,{}]".to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"li42ed3:fooi42eee"),
+                    r#"[42,{"foo":42}]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l5:alicei42ee"),
+                    r#"["alice",42]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l5:alice1:\xFFe"),
+                    r#"["alice","<hex>ff</hex>"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l5:alicelee"),
+                    r#"["alice",[]]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l5:aliceli42eee"),
+                    r#"["alice",[42]]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l5:alicedee"),
+                    r#"["alice",{}]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l5:aliced3:fooi42eee"),
+                    r#"["alice",{"foo":42}]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l1:\xFFi42ee"),
+                    r#"["<hex>ff</hex>",42]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l1:\xFF3:fooe"),
+                    r#"["<hex>ff</hex>","foo"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l1:\xFFlee"),
+                    r#"["<hex>ff</hex>",[]]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l1:\xFFli42eee"),
+                    r#"["<hex>ff</hex>",[42]]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l1:\xFFdee"),
+                    r#"["<hex>ff</hex>",{}]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l1:\xFFd3:fooi42eee"),
+                    r#"["<hex>ff</hex>",{"foo":42}]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_list_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"llei42ee"),
+                    r"[[],42]".to_string()
+                );
+            }
+
+            #[test]
+            fn empty_list_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lle3:fooe"),
+                    r#"[[],"foo"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_list_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lle1:\xFFe"),
+                    r#"[[],"<hex>ff</hex>"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_list_and_empty_dict() {
+                assert_eq!(bencode_to_json_unchecked(b"lledee"), r"[[],{}]".to_string());
+            }
+
+            #[test]
+            fn empty_list_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lled3:fooi42eee"),
+                    r#"[[],{"foo":42}]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn list_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lli42eei43ee"),
+                    r"[[42],43]".to_string()
+                );
+            }
+
+            #[test]
+            fn list_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lli42ee3:fooe"),
+                    r#"[[42],"foo"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn list_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lli42ee1:\xFFe"),
+                    r#"[[42],"<hex>ff</hex>"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn list_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lli42eedee"),
+                    r"[[42],{}]".to_string()
+                );
+            }
+
+            #[test]
+            fn list_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lli42eed3:fooi43eee"),
+                    r#"[[42],{"foo":43}]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_dict_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ldei42ee"),
+                    r"[{},42]".to_string()
+                );
+            }
+
+            #[test]
+            fn empty_dict_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lde3:fooe"),
+                    r#"[{},"foo"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_dict_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"lde1:\xFFe"),
+                    r#"[{},"<hex>ff</hex>"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_dict_and_empty_list() {
+                assert_eq!(bencode_to_json_unchecked(b"ldelee"), r"[{},[]]".to_string());
+            }
+
+            #[test]
+            fn empty_dict_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ldeli42eee"),
+                    r"[{},[42]]".to_string()
+                );
+            }
+
+            #[test]
+            fn dict_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ld3:fooi42eei43ee"),
+                    r#"[{"foo":42},43]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn dict_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ld3:fooi42ee3:fooe"),
+                    r#"[{"foo":42},"foo"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn dict_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ld3:fooi42ee1:\xFFe"),
+                    r#"[{"foo":42},"<hex>ff</hex>"]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn dict_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ld3:fooi42eelee"),
+                    r#"[{"foo":42},[]]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn dict_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"ld3:fooi42eeli43eee"),
+                    r#"[{"foo":42},[43]]"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_an_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"l2:\xFF\xFEi42ee"),
+                    r#"["<hex>fffe</hex>",42]"#.to_string()
+                );
+            }
+        }
+
+        mod should_fail {
+            use crate::{parsers::error::Error, try_bencode_to_json};
+
+            #[test]
+            fn when_an_empty_list_does_not_have_the_matching_close_byte() {
+                let list_without_closing_list_byte = b"l";
+
+                let result = try_bencode_to_json(list_without_closing_list_byte);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedEndOfInputExpectingFirstListItemOrEnd { .. })
+                ));
+            }
+
+            #[test]
+            fn when_a_list_does_not_have_the_matching_close_byte() {
+                let list_without_closing_list_byte = b"li42e";
+
+                let result = try_bencode_to_json(list_without_closing_list_byte);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedEndOfInputExpectingNextListItem { .. })
+                ));
+            }
+
+            #[test]
+            fn when_it_receives_an_end_list_byte_without_the_matching_open_byte() {
+                let end_list_byte_without_start = b"e";
+
+                let result = try_bencode_to_json(end_list_byte_without_start);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::NoMatchingStartForListOrDictEnd { .. })
+                ));
+            }
+        }
+    }
+
+    mod dictionary {
+        use crate::{
+            parsers::tests::bencode_to_json_unchecked,
+            test::{
+                generate_n_nested_empty_bencoded_dictionaries, generate_n_nested_empty_json_objects,
+            },
+        };
+
+        #[test]
+        fn empty_dictionary() {
+            assert_eq!(bencode_to_json_unchecked(b"de"), "{}".to_string());
+        }
+
+        #[test]
+        fn one_nested_empty_dictionary() {
+            assert_eq!(
+                bencode_to_json_unchecked(b"d3:foodee"),
+                r#"{"foo":{}}"#.to_string()
+            );
+        }
+
+        #[test]
+        fn two_nested_empty_dictionaries() {
+            assert_eq!(
+                bencode_to_json_unchecked(b"d3:food3:foodeee"),
+                r#"{"foo":{"foo":{}}}"#.to_string()
+            );
+        }
+
+        #[test]
+        fn many_nested_empty_dictionaries() {
+            assert_eq!(
+                bencode_to_json_unchecked(&generate_n_nested_empty_bencoded_dictionaries(100)),
+                generate_n_nested_empty_json_objects(100)
+            );
+        }
+
+        mod with_a_key {
+            use crate::parsers::tests::bencode_to_json_unchecked;
+
+            #[test]
+            fn starting_with_a_digit() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d4:1fooi42ee"),
+                    r#"{"1foo":42}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn which_is_not_a_utf_8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d2:\xFF\xFEi42ee"),
+                    r#"{"<hex>fffe</hex>":42}"#.to_string()
+                );
+            }
+        }
+
+        mod with_one_field {
+            use crate::parsers::tests::bencode_to_json_unchecked;
+
+            #[test]
+            fn integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:fooi42ee"),
+                    r#"{"foo":42}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar4:spame"),
+                    r#"{"bar":"spam"}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar2:\xFF\xFEe"),
+                    r#"{"bar":"<hex>fffe</hex>"}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barlee"),
+                    r#"{"bar":[]}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bardee"),
+                    r#"{"bar":{}}"#.to_string()
+                );
+            }
+        }
+
+        mod with_two_fields_of_the_same_type {
+            use crate::parsers::tests::bencode_to_json_unchecked;
+
+            #[test]
+            fn two_integers() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bari42e3:fooi43ee"),
+                    r#"{"bar":42,"foo":43}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_empty_utf8_strings() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar0:3:foo0:e"),
+                    r#"{"bar":"","foo":""}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_utf8_strings() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar4:spam3:foo5:alicee"),
+                    r#"{"bar":"spam","foo":"alice"}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_non_utf8_strings() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foo1:\xFEe"),
+                    r#"{"bar":"<hex>ff</hex>","foo":"<hex>fe</hex>"}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_empty_lists() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barle3:foolee"),
+                    r#"{"bar":[],"foo":[]}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_empty_dicts() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barde3:foodee"),
+                    r#"{"bar":{},"foo":{}}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_lists() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barli42ee3:fooli43eee"),
+                    r#"{"bar":[42],"foo":[43]}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn two_dicts() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bard3:bardee3:food3:foodeee"),
+                    r#"{"bar":{"bar":{}},"foo":{"foo":{}}}"#.to_string()
+                );
+            }
+        }
+
+        mod with_two_fields_of_different_type {
+            use crate::test::bencode_to_json_unchecked;
+
+            #[test]
+            fn integer_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bari42e3:foo5:alicee"),
+                    r#"{"bar":42,"foo":"alice"}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bari42e3:foo1:\xFFe"),
+                    r#"{"bar":42,"foo":"<hex>ff</hex>"}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bari42e3:foolee"),
+                    r#"{"bar":42,"foo":[]}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bari42e3:fooli43eee"),
+                    r#"{"bar":42,"foo":[43]}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bari42e3:foodee"),
+                    r#"{"bar":42,"foo":{}}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn integer_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bari42e3:food3:fooi43eee"),
+                    r#"{"bar":42,"foo":{"foo":43}}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar5:alice3:fooi43ee"),
+                    r#"{"bar":"alice","foo":43}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar5:alice3:foo1:\xFFe"),
+                    r#"{"bar":"alice","foo":"<hex>ff</hex>"}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar5:alice3:foolee"),
+                    r#"{"bar":"alice","foo":[]}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar5:alice3:fooli42eee"),
+                    r#"{"bar":"alice","foo":[42]}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar5:alice3:foodee"),
+                    r#"{"bar":"alice","foo":{}}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn utf8_string_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar5:alice3:food3:fooi42eee"),
+                    r#"{"bar":"alice","foo":{"foo":42}}"#.to_string()
+                );
+            }
+
+            #[test]
+            fn non_utf8_string_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:fooi43ee"),
+                    r#"{"bar":"<hex>ff</hex>","foo":43}"#.to_string()
+                );
+            }
 
             #[test]
-            fn containing_a_double_quote() {
+            fn non_utf8_string_and_non_utf8_string() {
                 assert_eq!(
-                    bencode_to_json_unchecked("1:\"".as_bytes()),
-                    r#""\"""#.to_string()
+                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foo1:\xFFe"),
+                    r#"{"bar":"<hex>ff</hex>","foo":"<hex>ff</hex>"}"#.to_string()
                 );
             }
 
             #[test]
-            fn containing_backslashes() {
+            fn non_utf8_string_and_empty_list() {
                 assert_eq!(
-                    bencode_to_json_unchecked("1:\\".as_bytes()),
-                    r#""\\""#.to_string()
+                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foolee"),
+                    r#"{"bar":"<hex>ff</hex>","foo":[]}"#.to_string()
                 );
             }
 
             #[test]
-            fn containing_control_characters() {
+            fn non_utf8_string_and_list() {
                 assert_eq!(
-                    bencode_to_json_unchecked("1:\n".as_bytes()),
-                    r#""\n""#.to_string()
+                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:fooli42eee"),
+                    r#"{"bar":"<hex>ff</hex>","foo":[42]}"#.to_string()
                 );
+            }
+
+            #[test]
+            fn non_utf8_string_and_empty_dict() {
                 assert_eq!(
-                    bencode_to_json_unchecked("1:\r".as_bytes()),
-                    r#""\r""#.to_string()
+                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foodee"),
+                    r#"{"bar":"<hex>ff</hex>","foo":{}}"#.to_string()
                 );
+            }
+
+            #[test]
+            fn non_utf8_string_and_dict() {
                 assert_eq!(
-                    bencode_to_json_unchecked("1:\t".as_bytes()),
-                    r#""\t""#.to_string()
+                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:food3:fooi42eee"),
+                    r#"{"bar":"<hex>ff</hex>","foo":{"foo":42}}"#.to_string()
                 );
             }
 
             #[test]
-            fn containing_unicode_characters() {
+            fn empty_list_and_integer() {
                 assert_eq!(
-                    bencode_to_json_unchecked(&to_bencode("ñandú")),
-                    r#""ñandú""#.to_string()
+                    bencode_to_json_unchecked(b"d3:barle3:fooi42ee"),
+                    r#"{"bar":[],"foo":42}"#.to_string()
                 );
             }
-        }
-
-        mod it_should_fail_parsing_when {
-            use crate::parsers::{error::Error, tests::try_bencode_to_json};
 
             #[test]
-            fn it_reaches_the_end_of_the_input_parsing_the_string_length() {
-                let incomplete_string_length = b"4";
-
-                let result = try_bencode_to_json(incomplete_string_length);
-
-                assert!(matches!(
-                    result,
-                    Err(Error::UnexpectedEndOfInputParsingStringLength { .. })
-                ));
+            fn empty_list_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barle3:foo5:alicee"),
+                    r#"{"bar":[],"foo":"alice"}"#.to_string()
+                );
             }
 
             #[test]
-            fn it_reaches_the_end_of_the_input_parsing_the_string_value() {
-                let incomplete_string_value = b"4:123";
-
-                let result = try_bencode_to_json(incomplete_string_value);
-
-                assert!(matches!(
-                    result,
-                    Err(Error::UnexpectedEndOfInputParsingStringValue { .. })
-                ));
+            fn empty_list_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barle3:foo1:\xFFe"),
+                    r#"{"bar":[],"foo":"<hex>ff</hex>"}"#.to_string()
+                );
             }
 
             #[test]
-            fn it_receives_a_non_digit_byte_in_the_string_length() {
-                let incomplete_string_value = b"4a:1234";
-
-                let result = try_bencode_to_json(incomplete_string_value);
-
-                assert!(matches!(result, Err(Error::InvalidStringLengthByte { .. })));
+            fn empty_list_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barle3:foodee"),
+                    r#"{"bar":[],"foo":{}}"#.to_string()
+                );
             }
-        }
-    }
-
-    mod lists {
-        use crate::{
-            parsers::tests::bencode_to_json_unchecked,
-            test::{generate_n_nested_empty_bencoded_lists, generate_n_nested_empty_json_arrays},
-        };
-
-        #[test]
-        fn empty_list() {
-            assert_eq!(bencode_to_json_unchecked(b"le"), "[]".to_string());
-        }
 
-        #[test]
-        fn one_nested_empty_list() {
-            assert_eq!(bencode_to_json_unchecked(b"llee"), "[[]]".to_string());
-        }
+            #[test]
+            fn empty_list_and_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barle3:food3:foo5:aliceee"),
+                    r#"{"bar":[],"foo":{"foo":"alice"}}"#.to_string()
+                );
+            }
 
-        #[test]
-        fn two_nested_empty_list() {
-            assert_eq!(bencode_to_json_unchecked(b"llleee"), "[[[]]]".to_string());
-        }
+            #[test]
+            fn list_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barli42ee3:fooi42ee"),
+                    r#"{"bar":[42],"foo":42}"#.to_string()
+                );
+            }
 
-        #[test]
-        fn many_nested_empty_list() {
-            assert_eq!(
-                bencode_to_json_unchecked(&generate_n_nested_empty_bencoded_lists(100)),
-                generate_n_nested_empty_json_arrays(100)
-            );
-        }
+            #[test]
+            fn list_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barli42ee3:foo5:alicee"),
+                    r#"{"bar":[42],"foo":"alice"}"#.to_string()
+                );
+            }
 
-        mod with_one_item {
-            use crate::parsers::tests::bencode_to_json_unchecked;
+            #[test]
+            fn list_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barli42ee3:foo1:\xFFe"),
+                    r#"{"bar":[42],"foo":"<hex>ff</hex>"}"#.to_string()
+                );
+            }
 
             #[test]
-            fn integer() {
-                assert_eq!(bencode_to_json_unchecked(b"li42ee"), "[42]".to_string());
+            fn list_and_empty_dict() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barli42ee3:foodee"),
+                    r#"{"bar":[42],"foo":{}}"#.to_string()
+                );
             }
 
             #[test]
-            fn utf8_string() {
+            fn list_and_dict() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"l4:spame"),
-                    r#"["spam"]"#.to_string()
+                    bencode_to_json_unchecked(b"d3:barli42ee3:food3:foo5:aliceee"),
+                    r#"{"bar":[42],"foo":{"foo":"alice"}}"#.to_string()
                 );
             }
 
             #[test]
-            fn non_utf8_string() {
+            fn empty_dict_and_integer() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"l4:\xFF\xFE\xFD\xFCe"),
-                    r#"["<hex>fffefdfc</hex>"]"#.to_string()
+                    bencode_to_json_unchecked(b"d3:barde3:fooi42ee"),
+                    r#"{"bar":{},"foo":42}"#.to_string()
                 );
             }
 
-            mod of_type_list {
-                use crate::parsers::tests::bencode_to_json_unchecked;
+            #[test]
+            fn empty_dict_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barde3:foo5:alicee"),
+                    r#"{"bar":{},"foo":"alice"}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn two_nested_empty_list() {
-                    assert_eq!(bencode_to_json_unchecked(b"llee"), "[[]]".to_string());
-                }
+            #[test]
+            fn empty_dict_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barde3:foo1:\xFFe"),
+                    r#"{"bar":{},"foo":"<hex>ff</hex>"}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn three_nested_empty_lists() {
-                    assert_eq!(bencode_to_json_unchecked(b"llleee"), "[[[]]]".to_string());
-                }
+            #[test]
+            fn empty_dict_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barde3:foolee"),
+                    r#"{"bar":{},"foo":[]}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn one_nested_list_which_contains_one_integer() {
-                    assert_eq!(bencode_to_json_unchecked(b"lli42eee"), "[[42]]".to_string());
-                }
+            #[test]
+            fn empty_dict_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:barde3:fooli42eee"),
+                    r#"{"bar":{},"foo":[42]}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn one_nested_list_which_contains_two_integers() {
-                    assert_eq!(
-                        bencode_to_json_unchecked(b"lli42ei43eee"),
-                        "[[42,43]]".to_string()
-                    );
-                }
+            #[test]
+            fn dict_and_integer() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:fooi43ee"),
+                    r#"{"bar":{"bar":42},"foo":43}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn one_nested_list_which_contains_one_utf_8_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked(b"ll4:spamee"),
-                        r#"[["spam"]]"#.to_string()
-                    );
-                }
+            #[test]
+            fn dict_and_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:foo5:alicee"),
+                    r#"{"bar":{"bar":42},"foo":"alice"}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn one_nested_list_which_contains_two_utf_8_strings() {
-                    assert_eq!(
-                        bencode_to_json_unchecked(b"ll5:alice3:bobee"),
-                        r#"[["alice","bob"]]"#.to_string()
-                    );
-                }
+            #[test]
+            fn dict_and_non_utf8_string() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:foo1:\xFFe"),
+                    r#"{"bar":{"bar":42},"foo":"<hex>ff</hex>"}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn one_nested_list_which_contains_one_non_utf_8_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked(b"ll4:\xFF\xFE\xFD\xFCee"),
-                        r#"[["<hex>fffefdfc</hex>"]]"#.to_string()
-                    );
-                }
+            #[test]
+            fn dict_and_empty_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:foolee"),
+                    r#"{"bar":{"bar":42},"foo":[]}"#.to_string()
+                );
+            }
 
-                #[test]
-                fn one_nested_list_which_contains_two_non_utf_8_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked(b"ll2:\xFF\xFE2:\xFD\xFCee"),
-                        r#"[["<hex>fffe</hex>","<hex>fdfc</hex>"]]"#.to_string()
-                    );
-                }
+            #[test]
+            fn dict_and_list() {
+                assert_eq!(
+                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:fooli42eee"),
+                    r#"{"bar":{"bar":42},"foo":[42]}"#.to_string()
+                );
             }
+        }
 
-            mod of_type_dict {
+        mod should_escape_json {
+
+            mod in_field_keys {
                 use crate::parsers::tests::bencode_to_json_unchecked;
 
-                #[test]
-                fn empty() {
-                    assert_eq!(bencode_to_json_unchecked(b"ldee"), "[{}]".to_string());
-                }
+                // Only one especial char is tested. The string parser contains
+                // other tests for the rest of the special chars that need to be
+                // escaped.
 
                 #[test]
-                fn with_one_field() {
+                fn containing_a_line_break_at_the_beginning_of_the_string() {
                     assert_eq!(
-                        bencode_to_json_unchecked(b"ld3:foo3:baree"),
-                        r#"[{"foo":"bar"}]"#.to_string()
+                        bencode_to_json_unchecked("d4:\nfoo3:bare".as_bytes()),
+                        r#"{"\nfoo":"bar"}"#.to_string()
                     );
                 }
 
                 #[test]
-                fn with_two_fields() {
+                fn containing_a_line_break_in_the_middle_of_the_string() {
                     assert_eq!(
-                        bencode_to_json_unchecked(b"ld3:bar4:spam3:fooi42eee"),
-                        r#"[{"bar":"spam","foo":42}]"#.to_string()
+                        bencode_to_json_unchecked("d4:f\noo3:bare".as_bytes()),
+                        r#"{"f\noo":"bar"}"#.to_string()
                     );
                 }
 
                 #[test]
-                fn with_nested_empty_dict() {
+                fn containing_a_line_break_at_the_end_of_the_string() {
                     assert_eq!(
-                        bencode_to_json_unchecked(b"ld3:foodeee"),
-                        r#"[{"foo":{}}]"#.to_string()
+                        bencode_to_json_unchecked("d4:foo\n3:bare".as_bytes()),
+                        r#"{"foo\n":"bar"}"#.to_string()
                     );
                 }
+            }
+
+            mod in_field_values {
+                use crate::parsers::tests::bencode_to_json_unchecked;
 
                 #[test]
-                fn with_two_nested_empty_dicts() {
+                fn containing_a_line_break_at_the_beginning_of_the_string() {
                     assert_eq!(
-                        bencode_to_json_unchecked(b"ld3:food3:foodeeee"),
-                        r#"[{"foo":{"foo":{}}}]"#.to_string()
+                        bencode_to_json_unchecked("d3:foo4:\nbare".as_bytes()),
+                        r#"{"foo":"\nbar"}"#.to_string()
                     );
                 }
 
                 #[test]
-                fn with_nested_dict_with_one_field() {
+                fn containing_a_line_break_in_the_middle_of_the_string() {
                     assert_eq!(
-                        bencode_to_json_unchecked(b"ld3:food3:foo3:bareee"),
-                        r#"[{"foo":{"foo":"bar"}}]"#.to_string()
+                        bencode_to_json_unchecked("d3:foo4:ba\nre".as_bytes()),
+                        r#"{"foo":"ba\nr"}"#.to_string()
                     );
                 }
 
                 #[test]
-                fn with_nested_dict_with_two_fields() {
+                fn containing_a_line_break_at_the_end_of_the_string() {
                     assert_eq!(
-                        bencode_to_json_unchecked(b"ld3:food3:foo3:bar3:fooi42eeee"),
-                        r#"[{"foo":{"foo":"bar","foo":42}}]"#.to_string()
+                        bencode_to_json_unchecked("d3:foo4:bar\ne".as_bytes()),
+                        r#"{"foo":"bar\n"}"#.to_string()
                     );
                 }
             }
         }
 
-        mod with_two_items_of_the_same_type {
-            use crate::parsers::tests::bencode_to_json_unchecked;
+        mod should_fail {
+            use crate::{parsers::error::Error, try_bencode_to_json};
 
             #[test]
-            fn two_integers() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"li42ei43ee"),
-                    "[42,43]".to_string()
-                );
-            }
+            fn when_an_empty_dict_does_not_have_the_matching_close_byte() {
+                let dict_without_closing_dict_byte = b"d";
 
-            #[test]
-            fn two_utf8_strings() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l5:alice3:bobe"),
-                    r#"["alice","bob"]"#.to_string()
-                );
+                let result = try_bencode_to_json(dict_without_closing_dict_byte);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd { .. })
+                ));
             }
 
             #[test]
-            fn two_non_utf8_strings() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l2:\xFF\xFE2:\xFD\xFCe"),
-                    r#"["<hex>fffe</hex>","<hex>fdfc</hex>"]"#.to_string()
-                );
+            fn when_a_dict_field_does_not_have_the_value() {
+                let dict_without_closing_dict_byte = b"d3:foo";
+
+                let result = try_bencode_to_json(dict_without_closing_dict_byte);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedEndOfInputExpectingDictFieldValue { .. })
+                ));
             }
 
             #[test]
-            fn two_empty_lists() {
-                assert_eq!(bencode_to_json_unchecked(b"llelee"), r"[[],[]]".to_string());
+            fn when_a_dict_does_not_have_the_matching_close_byte() {
+                let dict_without_closing_dict_byte = b"d3:fooi42e";
+
+                let result = try_bencode_to_json(dict_without_closing_dict_byte);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd { .. })
+                ));
             }
 
             #[test]
-            fn two_empty_dicts() {
-                assert_eq!(bencode_to_json_unchecked(b"ldedee"), r"[{},{}]".to_string());
+            fn when_it_receives_an_end_dict_byte_without_the_matching_open_byte() {
+                let end_dict_byte_without_start = b"e";
+
+                let result = try_bencode_to_json(end_dict_byte_without_start);
+
+                assert!(matches!(
+                    result,
+                    Err(Error::NoMatchingStartForListOrDictEnd { .. })
+                ));
             }
 
             #[test]
-            fn two_lists_with_one_item() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lli42eeli42eee"),
-                    r"[[42],[42]]".to_string()
-                );
+            fn when_it_receives_a_premature_end_dict_byte() {
+                let dict_with_missing_key_value = b"d3:fooe";
+
+                let result = try_bencode_to_json(dict_with_missing_key_value);
+
+                assert!(matches!(result, Err(Error::PrematureEndOfDict { .. })));
             }
 
             #[test]
-            fn two_dicts_with_one_item() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ld3:fooi42eed3:fooi42eee"),
-                    r#"[{"foo":42},{"foo":42}]"#.to_string()
-                );
-            }
-        }
+            fn when_the_first_field_value_is_empty() {
+                let dict_with_missing_key_value = b"d3:fooe";
 
-        mod with_two_items_of_different_types {
-            use crate::parsers::tests::bencode_to_json_unchecked;
+                let result = try_bencode_to_json(dict_with_missing_key_value);
 
-            #[test]
-            fn integer_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"li42e5:alicee"),
-                    r#"[42,"alice"]"#.to_string()
-                );
+                assert!(matches!(result, Err(Error::PrematureEndOfDict { .. })));
             }
 
             #[test]
-            fn integer_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"li42e2:\xFF\xFEe"),
-                    r#"[42,"<hex>fffe</hex>"]"#.to_string()
-                );
+            fn when_the_second_field_value_is_empty() {
+                let dict_with_missing_key_value = b"d3:foo3:bar3:fooe";
+
+                let result = try_bencode_to_json(dict_with_missing_key_value);
+
+                assert!(matches!(result, Err(Error::PrematureEndOfDict { .. })));
             }
 
-            #[test]
-            fn integer_and_empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"li42elee"),
-                    r"[42,[]]".to_string()
-                );
+            mod when_the_field_key_is_not_a_string_for_example {
+                use crate::parsers::error::Error;
+                use crate::parsers::BencodeType;
+                use crate::try_bencode_to_json;
+
+                #[test]
+                fn when_the_key_in_the_first_dict_field_is_an_integer() {
+                    let field_with_integer_key = b"di42ei43ee";
+
+                    let result = try_bencode_to_json(field_with_integer_key);
+
+                    assert!(matches!(
+                        result,
+                        Err(Error::ExpectedStringForDictKeyGot(
+                            BencodeType::Integer,
+                            _,
+                            _
+                        ))
+                    ));
+                }
+
+                #[test]
+                fn when_the_key_in_the_second_dict_field_is_an_integer() {
+                    let field_with_integer_key = b"d3:foo3:bari42ei43ee";
+
+                    let result = try_bencode_to_json(field_with_integer_key);
+
+                    assert!(matches!(
+                        result,
+                        Err(Error::ExpectedStringForDictKeyGot(
+                            BencodeType::Integer,
+                            _,
+                            _
+                        ))
+                    ));
+                }
+
+                #[test]
+                fn when_the_key_in_the_first_dict_field_is_a_list() {
+                    let field_with_list_key = b"dlei42ee";
+
+                    let result = try_bencode_to_json(field_with_list_key);
+
+                    assert!(matches!(
+                        result,
+                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::List, _, _))
+                    ));
+                }
+
+                #[test]
+                fn when_the_key_in_the_second_dict_field_is_a_list() {
+                    let field_with_list_key = b"d3:foo3:barlei42ee";
+
+                    let result = try_bencode_to_json(field_with_list_key);
+
+                    assert!(matches!(
+                        result,
+                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::List, _, _))
+                    ));
+                }
+
+                #[test]
+                fn when_the_key_in_the_first_dict_field_is_a_dict() {
+                    let field_with_list_key = b"ddei42ee";
+
+                    let result = try_bencode_to_json(field_with_list_key);
+
+                    assert!(matches!(
+                        result,
+                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::Dict, _, _))
+                    ));
+                }
+
+                #[test]
+                fn when_the_key_in_the_second_dict_field_is_a_dict() {
+                    let field_with_list_key = b"d3:foo3:bardei42ee";
+
+                    let result = try_bencode_to_json(field_with_list_key);
+
+                    assert!(matches!(
+                        result,
+                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::Dict, _, _))
+                    ));
+                }
             }
+        }
+    }
 
-            #[test]
-            fn integer_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"li42eli43eee"),
-                    r"[42,[43]]".to_string()
-                );
-            }
+    mod strict_mode {
+        use crate::parsers::{error::Error, BencodeParser};
 
-            #[test]
-            fn integer_and_empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"li42edee"),
-                    r"[42,{}]".to_string()
-                );
-            }
+        fn strict_bencode_to_json(input: &[u8]) -> Result<String, Error> {
+            let mut output = String::new();
 
-            #[test]
-            fn integer_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"li42ed3:fooi42eee"),
-                    r#"[42,{"foo":42}]"#.to_string()
-                );
-            }
+            BencodeParser::new_strict(input).write_str(&mut output)?;
 
-            #[test]
-            fn utf8_string_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l5:alicei42ee"),
-                    r#"["alice",42]"#.to_string()
-                );
-            }
+            Ok(output)
+        }
 
-            #[test]
-            fn utf8_string_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l5:alice1:\xFFe"),
-                    r#"["alice","<hex>ff</hex>"]"#.to_string()
-                );
-            }
+        mod it_should_accept {
+            use crate::parsers::tests::strict_mode::strict_bencode_to_json;
 
             #[test]
-            fn utf8_string_and_empty_list() {
+            fn canonical_bencode() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"l5:alicelee"),
-                    r#"["alice",[]]"#.to_string()
+                    strict_bencode_to_json(b"d1:ai0e1:bl0:i-1ee2:bad1:xdeee").unwrap(),
+                    r#"{"a":0,"b":["",-1],"ba":{"x":{}}}"#
                 );
             }
 
             #[test]
-            fn utf8_string_and_list() {
+            fn the_same_keys_in_different_dictionaries() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"l5:aliceli42eee"),
-                    r#"["alice",[42]]"#.to_string()
+                    strict_bencode_to_json(b"d1:ad1:ai1eee").unwrap(),
+                    r#"{"a":{"a":1}}"#
                 );
             }
 
             #[test]
-            fn utf8_string_and_empty_dict() {
+            fn dictionaries_after_nested_empty_lists() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"l5:alicedee"),
-                    r#"["alice",{}]"#.to_string()
+                    strict_bencode_to_json(b"d1:ale1:bi1ee").unwrap(),
+                    r#"{"a":[],"b":1}"#
                 );
             }
+        }
 
-            #[test]
-            fn utf8_string_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l5:aliced3:fooi42eee"),
-                    r#"["alice",{"foo":42}]"#.to_string()
-                );
-            }
+        mod it_should_reject {
+            use crate::parsers::{error::Error, tests::strict_mode::strict_bencode_to_json};
 
             #[test]
-            fn non_utf8_string_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l1:\xFFi42ee"),
-                    r#"["<hex>ff</hex>",42]"#.to_string()
-                );
+            fn negative_zero() {
+                assert!(matches!(
+                    strict_bencode_to_json(b"i-0e"),
+                    Err(Error::NegativeZeroNotAllowed(..))
+                ));
             }
 
             #[test]
-            fn non_utf8_string_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l1:\xFF3:fooe"),
-                    r#"["<hex>ff</hex>","foo"]"#.to_string()
-                );
+            fn leading_zeros_in_integers() {
+                assert!(matches!(
+                    strict_bencode_to_json(b"i01e"),
+                    Err(Error::LeadingZerosInIntegersNotAllowed(..))
+                ));
+                assert!(matches!(
+                    strict_bencode_to_json(b"i-01e"),
+                    Err(Error::LeadingZerosInIntegersNotAllowed(..))
+                ));
             }
 
             #[test]
-            fn non_utf8_string_and_empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l1:\xFFlee"),
-                    r#"["<hex>ff</hex>",[]]"#.to_string()
-                );
+            fn leading_zeros_in_string_lengths() {
+                assert!(matches!(
+                    strict_bencode_to_json(b"03:abc"),
+                    Err(Error::LeadingZerosInStringLengthNotAllowed(..))
+                ));
             }
 
             #[test]
-            fn non_utf8_string_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l1:\xFFli42eee"),
-                    r#"["<hex>ff</hex>",[42]]"#.to_string()
-                );
+            fn unsorted_dictionary_keys() {
+                assert!(matches!(
+                    strict_bencode_to_json(b"d1:bi1e1:ai2ee"),
+                    Err(Error::UnsortedDictKeys(..))
+                ));
             }
 
             #[test]
-            fn non_utf8_string_and_empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l1:\xFFdee"),
-                    r#"["<hex>ff</hex>",{}]"#.to_string()
-                );
+            fn duplicate_dictionary_keys() {
+                assert!(matches!(
+                    strict_bencode_to_json(b"d1:ai1e1:ai2ee"),
+                    Err(Error::DuplicateDictKey(..))
+                ));
             }
 
             #[test]
-            fn non_utf8_string_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l1:\xFFd3:fooi42eee"),
-                    r#"["<hex>ff</hex>",{"foo":42}]"#.to_string()
-                );
+            fn keys_sorted_as_strings_instead_of_raw_bytes() {
+                // "\xFF" > "b" comparing raw bytes
+                assert!(matches!(
+                    strict_bencode_to_json(b"d1:\xFFi1e1:bi2ee"),
+                    Err(Error::UnsortedDictKeys(..))
+                ));
             }
 
             #[test]
-            fn empty_list_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"llei42ee"),
-                    r"[[],42]".to_string()
-                );
+            fn line_breaks() {
+                assert!(matches!(
+                    strict_bencode_to_json(b"i1e\n"),
+                    Err(Error::LineBreakNotAllowed(..))
+                ));
             }
+        }
 
-            #[test]
-            fn empty_list_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lle3:fooe"),
-                    r#"[[],"foo"]"#.to_string()
-                );
-            }
+        #[test]
+        fn the_lenient_mode_should_be_the_default() {
+            assert_eq!(
+                crate::try_bencode_to_json(b"d1:bi-0e1:a03:abce\n").unwrap(),
+                r#"{"b":-0,"a":"abc"}"#
+            );
+        }
+    }
 
-            #[test]
-            fn empty_list_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lle1:\xFFe"),
-                    r#"[[],"<hex>ff</hex>"]"#.to_string()
-                );
-            }
+    mod with_options {
+        use crate::parsers::{error::Error, options::BencodeParserBuilder};
 
-            #[test]
-            fn empty_list_and_empty_dict() {
-                assert_eq!(bencode_to_json_unchecked(b"lledee"), r"[[],{}]".to_string());
-            }
+        fn bencode_to_json(builder: BencodeParserBuilder, input: &[u8]) -> Result<String, Error> {
+            let mut output = String::new();
 
-            #[test]
-            fn empty_list_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lled3:fooi42eee"),
-                    r#"[[],{"foo":42}]"#.to_string()
-                );
-            }
+            builder.build(input).write_str(&mut output)?;
 
-            #[test]
-            fn list_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lli42eei43ee"),
-                    r"[[42],43]".to_string()
-                );
-            }
+            Ok(output)
+        }
 
-            #[test]
-            fn list_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lli42ee3:fooe"),
-                    r#"[[42],"foo"]"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_limit_the_captured_bytes_in_the_error_contexts() {
+            let result = bencode_to_json(BencodeParserBuilder::default().capture_size(2), b"li1ex");
 
-            #[test]
-            fn list_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lli42ee1:\xFFe"),
-                    r#"[[42],"<hex>ff</hex>"]"#.to_string()
-                );
+            match result {
+                Err(Error::UnrecognizedFirstBencodeValueByte(read_context, write_context)) => {
+                    assert_eq!(read_context.latest_bytes, b"ex");
+                    assert_eq!(write_context.latest_bytes, b"[1");
+                }
+                _ => panic!("unexpected result: {result:?}"),
             }
+        }
 
-            #[test]
-            fn list_and_empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lli42eedee"),
-                    r"[[42],{}]".to_string()
-                );
-            }
+        #[test]
+        fn it_should_reject_line_breaks_when_they_are_not_skipped() {
+            let result = bencode_to_json(
+                BencodeParserBuilder::default().skip_newlines(false),
+                b"i1e\n",
+            );
 
-            #[test]
-            fn list_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lli42eed3:fooi43eee"),
-                    r#"[[42],{"foo":43}]"#.to_string()
-                );
-            }
+            assert!(matches!(
+                result,
+                Err(Error::UnrecognizedFirstBencodeValueByte(..))
+            ));
+        }
 
-            #[test]
-            fn empty_dict_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ldei42ee"),
-                    r"[{},42]".to_string()
-                );
-            }
+        #[test]
+        fn it_should_reject_integers_that_do_not_fit_into_an_i64_when_required() {
+            let builder = || BencodeParserBuilder::default().i64_integers(true);
 
-            #[test]
-            fn empty_dict_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lde3:fooe"),
-                    r#"[{},"foo"]"#.to_string()
-                );
-            }
+            let max = format!("i{}e", i64::MAX);
+            let above_max = format!("i{}e", i128::from(i64::MAX) + 1);
+            let below_min = format!("i{}e", i128::from(i64::MIN) - 1);
 
-            #[test]
-            fn empty_dict_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"lde1:\xFFe"),
-                    r#"[{},"<hex>ff</hex>"]"#.to_string()
-                );
-            }
+            assert_eq!(
+                bencode_to_json(builder(), max.as_bytes()).unwrap(),
+                i64::MAX.to_string()
+            );
+            assert!(matches!(
+                bencode_to_json(builder(), above_max.as_bytes()),
+                Err(Error::IntegerOverflow(..))
+            ));
+            assert!(matches!(
+                bencode_to_json(builder(), below_min.as_bytes()),
+                Err(Error::IntegerOverflow(..))
+            ));
+        }
 
-            #[test]
-            fn empty_dict_and_empty_list() {
-                assert_eq!(bencode_to_json_unchecked(b"ldelee"), r"[{},[]]".to_string());
-            }
+        #[test]
+        fn it_should_only_accept_canonical_bencode_in_strict_mode() {
+            assert!(matches!(
+                bencode_to_json(BencodeParserBuilder::default().strict(true), b"i-0e"),
+                Err(Error::NegativeZeroNotAllowed(..))
+            ));
+        }
+    }
 
-            #[test]
-            fn empty_dict_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ldeli42eee"),
-                    r"[{},[42]]".to_string()
-                );
-            }
+    mod byte_string_encodings {
+        use crate::parsers::options::{BencodeParserBuilder, ByteStringEncoding};
 
-            #[test]
-            fn dict_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ld3:fooi42eei43ee"),
-                    r#"[{"foo":42},43]"#.to_string()
-                );
-            }
+        /// A dictionary with non UTF-8 bytes in a key and a value, a key
+        /// starting with `$` and a UTF-8 value containing a backslash.
+        const INPUT: &[u8] = b"d2:\xFF\xFEl1:a2:\xFD\xFCe5:$spam3:a\\be";
 
-            #[test]
-            fn dict_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ld3:fooi42ee3:fooe"),
-                    r#"[{"foo":42},"foo"]"#.to_string()
-                );
-            }
+        fn bencode_to_json(encoding: ByteStringEncoding) -> String {
+            let mut output = String::new();
+
+            BencodeParserBuilder::default()
+                .byte_string_encoding(encoding)
+                .build(INPUT)
+                .write_str(&mut output)
+                .unwrap();
+
+            output
+        }
+
+        #[test]
+        fn hex() {
+            assert_eq!(
+                bencode_to_json(ByteStringEncoding::Hex),
+                r#"{"<hex>fffe</hex>":["a","<hex>fdfc</hex>"],"$spam":"a\\b"}"#
+            );
+        }
+
+        #[test]
+        fn base64() {
+            assert_eq!(
+                bencode_to_json(ByteStringEncoding::Base64),
+                r#"{"<base64>//4=</base64>":["a","<base64>/fw=</base64>"],"$spam":"a\\b"}"#
+            );
+        }
 
-            #[test]
-            fn dict_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ld3:fooi42ee1:\xFFe"),
-                    r#"[{"foo":42},"<hex>ff</hex>"]"#.to_string()
-                );
-            }
+        #[test]
+        fn lossy() {
+            assert_eq!(
+                bencode_to_json(ByteStringEncoding::Lossy),
+                "{\"\u{FFFD}\u{FFFD}\":[\"a\",\"\u{FFFD}\u{FFFD}\"],\"$spam\":\"a\\\\b\"}"
+            );
+        }
 
-            #[test]
-            fn dict_and_empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ld3:fooi42eelee"),
-                    r#"[{"foo":42},[]]"#.to_string()
-                );
-            }
+        #[test]
+        fn escaped() {
+            assert_eq!(
+                bencode_to_json(ByteStringEncoding::Escaped),
+                r#"{"\\xff\\xfe":["a","\\xfd\\xfc"],"$spam":"a\\\\b"}"#
+            );
+        }
 
-            #[test]
-            fn dict_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"ld3:fooi42eeli43eee"),
-                    r#"[{"foo":42},[43]]"#.to_string()
-                );
-            }
+        #[test]
+        fn tagged() {
+            assert_eq!(
+                bencode_to_json(ByteStringEncoding::Tagged),
+                r#"{"\\xff\\xfe":["a",{"$bytes":"/fw="}],"$$spam":"a\\b"}"#
+            );
+        }
 
-            #[test]
-            fn non_utf8_string_and_an_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"l2:\xFF\xFEi42ee"),
-                    r#"["<hex>fffe</hex>",42]"#.to_string()
-                );
-            }
+        #[test]
+        fn array() {
+            assert_eq!(
+                bencode_to_json(ByteStringEncoding::Array),
+                r#"{"\\xff\\xfe":["a",[253,252]],"$spam":"a\\b"}"#
+            );
         }
+    }
 
-        mod should_fail {
-            use crate::{parsers::error::Error, try_bencode_to_json};
+    mod torrent_mode {
+        use crate::parsers::options::BencodeParserBuilder;
 
-            #[test]
-            fn when_an_empty_list_does_not_have_the_matching_close_byte() {
-                let list_without_closing_list_byte = b"l";
+        fn torrent_to_json(input: &[u8]) -> String {
+            let mut output = String::new();
 
-                let result = try_bencode_to_json(list_without_closing_list_byte);
+            BencodeParserBuilder::default()
+                .torrent(true)
+                .build(input)
+                .write_str(&mut output)
+                .unwrap();
 
-                assert!(matches!(
-                    result,
-                    Err(Error::UnexpectedEndOfInputExpectingFirstListItemOrEnd { .. })
-                ));
-            }
+            output
+        }
 
-            #[test]
-            fn when_a_list_does_not_have_the_matching_close_byte() {
-                let list_without_closing_list_byte = b"li42e";
+        #[test]
+        fn it_should_write_the_pieces_as_sha1_hashes_and_add_the_info_hash() {
+            let info = [
+                &b"d6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:"[..],
+                &[0xAA; 20],
+                b"e",
+            ]
+            .concat();
 
-                let result = try_bencode_to_json(list_without_closing_list_byte);
+            let torrent = [&b"d8:announce4:spam4:info"[..], &info, b"e"].concat();
 
-                assert!(matches!(
-                    result,
-                    Err(Error::UnexpectedEndOfInputExpectingNextListItem { .. })
-                ));
-            }
+            assert_eq!(
+                torrent_to_json(&torrent),
+                format!(
+                    r#"{{"announce":"spam","info":{{"length":1,"name":"a","piece length":16384,"pieces":["{}"]}},"info_hash":{{"v1":"aec9dbf72c7d2f249b9744b3549a064d316a904b","v2":"ee3e215cf12affebf79499a7b61e3fbe7f893605ad3356652a4d666166a062b4"}}}}"#,
+                    "aa".repeat(20)
+                )
+            );
+        }
 
-            #[test]
-            fn when_it_receives_an_end_list_byte_without_the_matching_open_byte() {
-                let end_list_byte_without_start = b"e";
+        #[test]
+        fn it_should_not_add_the_info_hash_when_the_torrent_already_has_an_info_hash_field() {
+            assert_eq!(
+                torrent_to_json(b"d4:infod4:name1:ae9:info_hashi1ee"),
+                r#"{"info":{"name":"a"},"info_hash":1}"#
+            );
+        }
 
-                let result = try_bencode_to_json(end_list_byte_without_start);
+        #[test]
+        fn it_should_write_the_v2_hashes_as_sha256_hashes() {
+            let torrent = [
+                &b"d4:infod9:file treed1:ad0:d11:pieces root32:"[..],
+                &[0xCC; 32],
+                b"eeee12:piece layersd32:",
+                &[0xCC; 32],
+                b"64:",
+                &[[0xDD; 32], [0xEE; 32]].concat(),
+                b"ee",
+            ]
+            .concat();
+
+            let json = torrent_to_json(&torrent);
+
+            assert!(json.starts_with(&format!(
+                r#"{{"info":{{"file tree":{{"a":{{"":{{"pieces root":"{cc}"}}}}}}}},"piece layers":{{"{cc}":["{dd}","{ee}"]}},"info_hash":"#,
+                cc = "cc".repeat(32),
+                dd = "dd".repeat(32),
+                ee = "ee".repeat(32)
+            )));
+        }
 
-                assert!(matches!(
-                    result,
-                    Err(Error::NoMatchingStartForListOrDictEnd { .. })
-                ));
-            }
+        #[test]
+        fn it_should_only_hash_the_top_level_info_dictionary() {
+            assert_eq!(torrent_to_json(b"d1:ad4:infodeee"), r#"{"a":{"info":{}}}"#);
+        }
+
+        #[test]
+        fn it_should_not_change_anything_when_it_is_disabled() {
+            assert_eq!(
+                crate::try_bencode_to_json(b"d4:infod6:pieces1:\xAAee").unwrap(),
+                r#"{"info":{"pieces":"<hex>aa</hex>"}}"#
+            );
         }
     }
 
-    mod dictionary {
-        use crate::{
-            parsers::tests::bencode_to_json_unchecked,
-            test::{
-                generate_n_nested_empty_bencoded_dictionaries, generate_n_nested_empty_json_objects,
-            },
-        };
+    mod tracker_response_mode {
+        use crate::parsers::options::BencodeParserBuilder;
 
-        #[test]
-        fn empty_dictionary() {
-            assert_eq!(bencode_to_json_unchecked(b"de"), "{}".to_string());
+        fn tracker_response_to_json(input: &[u8]) -> String {
+            let mut output = String::new();
+
+            BencodeParserBuilder::default()
+                .tracker_response(true)
+                .build(input)
+                .write_str(&mut output)
+                .unwrap();
+
+            output
         }
 
         #[test]
-        fn one_nested_empty_dictionary() {
+        fn it_should_decode_the_compact_peers_of_an_announce_response() {
+            let mut peers6 = vec![0; 15];
+            peers6.extend([1, 0x1A, 0xE1]);
+
+            let response = [
+                &b"d8:intervali1800e5:peers6:"[..],
+                &[1, 2, 3, 4, 0x1A, 0xE1],
+                b"6:peers618:",
+                &peers6,
+                b"e",
+            ]
+            .concat();
+
             assert_eq!(
-                bencode_to_json_unchecked(b"d3:foodee"),
-                r#"{"foo":{}}"#.to_string()
+                tracker_response_to_json(&response),
+                r#"{"interval":1800,"peers":[{"ip":"1.2.3.4","port":6881}],"peers6":[{"ip":"::1","port":6881}]}"#
             );
         }
 
         #[test]
-        fn two_nested_empty_dictionaries() {
+        fn it_should_not_change_non_compact_peer_lists() {
             assert_eq!(
-                bencode_to_json_unchecked(b"d3:food3:foodeee"),
-                r#"{"foo":{"foo":{}}}"#.to_string()
+                tracker_response_to_json(b"d5:peersld2:ip7:1.2.3.44:porti6881eeee"),
+                r#"{"peers":[{"ip":"1.2.3.4","port":6881}]}"#
             );
         }
 
         #[test]
-        fn many_nested_empty_dictionaries() {
+        fn it_should_write_the_info_hashes_of_a_scrape_response_as_hex() {
+            let response = [
+                &b"d5:filesd20:"[..],
+                &[0xAB; 20],
+                b"d8:completei5e10:downloadedi50e10:incompletei10eeee",
+            ]
+            .concat();
+
             assert_eq!(
-                bencode_to_json_unchecked(&generate_n_nested_empty_bencoded_dictionaries(100)),
-                generate_n_nested_empty_json_objects(100)
+                tracker_response_to_json(&response),
+                format!(
+                    r#"{{"files":{{"{}":{{"complete":5,"downloaded":50,"incomplete":10}}}}}}"#,
+                    "ab".repeat(20)
+                )
             );
         }
+    }
 
-        mod with_a_key {
-            use crate::parsers::tests::bencode_to_json_unchecked;
+    mod captured_values {
+        use crate::parsers::{
+            capture::CapturedValue, options::BencodeParserBuilder, torrent::InfoHash,
+        };
 
-            #[test]
-            fn starting_with_a_digit() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d4:1fooi42ee"),
-                    r#"{"1foo":42}"#.to_string()
-                );
-            }
+        const INFO: &[u8] = b"d6:lengthi1e4:name4:spame";
 
-            #[test]
-            fn which_is_not_a_utf_8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d2:\xFF\xFEi42ee"),
-                    r#"{"<hex>fffe</hex>":42}"#.to_string()
-                );
-            }
+        fn torrent() -> Vec<u8> {
+            [&b"d8:announce4:spam4:info"[..], INFO, b"5:nodeslee"].concat()
         }
 
-        mod with_one_field {
-            use crate::parsers::tests::bencode_to_json_unchecked;
+        fn capture(builder: BencodeParserBuilder, input: &[u8]) -> (String, Vec<CapturedValue>) {
+            let mut output = String::new();
 
-            #[test]
-            fn integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:fooi42ee"),
-                    r#"{"foo":42}"#.to_string()
-                );
-            }
+            let mut parser = builder.build(input);
 
-            #[test]
-            fn utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar4:spame"),
-                    r#"{"bar":"spam"}"#.to_string()
-                );
-            }
+            parser.write_str(&mut output).unwrap();
 
-            #[test]
-            fn non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar2:\xFF\xFEe"),
-                    r#"{"bar":"<hex>fffe</hex>"}"#.to_string()
-                );
-            }
+            (output, parser.captured_values().to_vec())
+        }
 
-            #[test]
-            fn empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barlee"),
-                    r#"{"bar":[]}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_hash_the_raw_bytes_of_the_selected_values_while_writing_the_json() {
+            let (json, captured_values) =
+                capture(BencodeParserBuilder::default().capture("info"), &torrent());
 
-            #[test]
-            fn empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bardee"),
-                    r#"{"bar":{}}"#.to_string()
-                );
-            }
+            let info_hash = InfoHash::from_info_bytes(INFO);
+
+            assert_eq!(
+                json,
+                r#"{"announce":"spam","info":{"length":1,"name":"spam"},"nodes":[]}"#
+            );
+            assert_eq!(captured_values.len(), 1);
+            assert_eq!(captured_values[0].path.to_string(), "info");
+            assert_eq!(captured_values[0].len, INFO.len() as u64);
+            assert_eq!(captured_values[0].sha1, info_hash.v1);
+            assert_eq!(captured_values[0].sha256, info_hash.v2);
+            assert_eq!(captured_values[0].bytes, None);
         }
 
-        mod with_two_fields_of_the_same_type {
-            use crate::parsers::tests::bencode_to_json_unchecked;
+        #[test]
+        fn it_should_keep_the_raw_bytes_when_required() {
+            let (_json, captured_values) = capture(
+                BencodeParserBuilder::default()
+                    .capture("info")
+                    .capture_raw_bytes(true),
+                &torrent(),
+            );
 
-            #[test]
-            fn two_integers() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bari42e3:fooi43ee"),
-                    r#"{"bar":42,"foo":43}"#.to_string()
-                );
-            }
+            assert_eq!(captured_values[0].bytes, Some(INFO.to_vec()));
+        }
 
-            #[test]
-            fn two_empty_utf8_strings() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar0:3:foo0:e"),
-                    r#"{"bar":"","foo":""}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_capture_nested_values() {
+            let (_json, captured_values) = capture(
+                BencodeParserBuilder::default()
+                    .capture("info")
+                    .capture("info.name")
+                    .capture("nodes")
+                    .capture_raw_bytes(true),
+                &torrent(),
+            );
 
-            #[test]
-            fn two_utf8_strings() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar4:spam3:foo5:alicee"),
-                    r#"{"bar":"spam","foo":"alice"}"#.to_string()
-                );
-            }
+            let captured: Vec<(String, Vec<u8>)> = captured_values
+                .into_iter()
+                .map(|value| (value.path.to_string(), value.bytes.unwrap()))
+                .collect();
 
-            #[test]
-            fn two_non_utf8_strings() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foo1:\xFEe"),
-                    r#"{"bar":"<hex>ff</hex>","foo":"<hex>fe</hex>"}"#.to_string()
-                );
-            }
+            assert_eq!(
+                captured,
+                vec![
+                    ("info.name".to_string(), b"4:spam".to_vec()),
+                    ("info".to_string(), INFO.to_vec()),
+                    ("nodes".to_string(), b"le".to_vec()),
+                ]
+            );
+        }
 
-            #[test]
-            fn two_empty_lists() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barle3:foolee"),
-                    r#"{"bar":[],"foo":[]}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_capture_the_selected_values_of_every_top_level_value() {
+            let (_json, captured_values) = capture(
+                BencodeParserBuilder::default()
+                    .capture("a")
+                    .capture_raw_bytes(true),
+                b"d1:ai1eed1:bi2eed1:ai3ee",
+            );
 
-            #[test]
-            fn two_empty_dicts() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barde3:foodee"),
-                    r#"{"bar":{},"foo":{}}"#.to_string()
-                );
-            }
+            let captured: Vec<Vec<u8>> = captured_values
+                .into_iter()
+                .map(|value| value.bytes.unwrap())
+                .collect();
 
-            #[test]
-            fn two_lists() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barli42ee3:fooli43eee"),
-                    r#"{"bar":[42],"foo":[43]}"#.to_string()
-                );
-            }
+            assert_eq!(captured, vec![b"i1e".to_vec(), b"i3e".to_vec()]);
+        }
+
+        #[test]
+        fn it_should_not_capture_values_inside_lists() {
+            let (_json, captured_values) =
+                capture(BencodeParserBuilder::default().capture("a"), b"ld1:ai1eee");
+
+            assert!(captured_values.is_empty());
+        }
+
+        #[test]
+        fn it_should_capture_values_in_torrent_mode() {
+            let (json, captured_values) = capture(
+                BencodeParserBuilder::default()
+                    .torrent(true)
+                    .capture("info"),
+                &torrent(),
+            );
+
+            let info_hash = InfoHash::from_info_bytes(INFO);
+
+            assert!(json.ends_with(&format!(
+                r#""info_hash":{{"v1":"{}","v2":"{}"}}}}"#,
+                hex::encode(info_hash.v1),
+                hex::encode(info_hash.v2)
+            )));
+            assert_eq!(captured_values[0].sha1, info_hash.v1);
+        }
+
+        #[test]
+        fn it_should_capture_values_while_reading_the_values() {
+            let torrent = torrent();
 
-            #[test]
-            fn two_dicts() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bard3:bardee3:food3:foodeee"),
-                    r#"{"bar":{"bar":{}},"foo":{"foo":{}}}"#.to_string()
-                );
-            }
+            let mut parser = BencodeParserBuilder::default()
+                .capture("info")
+                .build(&torrent[..]);
+
+            let _value = parser.read_value().unwrap();
+
+            assert_eq!(
+                parser.captured_values()[0].sha1,
+                InfoHash::from_info_bytes(INFO).v1
+            );
         }
+    }
 
-        mod with_two_fields_of_different_type {
-            use crate::test::bencode_to_json_unchecked;
+    mod multiple_top_level_values {
+        use crate::parsers::{
+            error::Error,
+            options::{BencodeParserBuilder, MultipleValues},
+        };
 
-            #[test]
-            fn integer_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bari42e3:foo5:alicee"),
-                    r#"{"bar":42,"foo":"alice"}"#.to_string()
-                );
-            }
+        fn bencode_to_json(multiple_values: MultipleValues, input: &[u8]) -> Result<String, Error> {
+            let mut output = String::new();
 
-            #[test]
-            fn integer_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bari42e3:foo1:\xFFe"),
-                    r#"{"bar":42,"foo":"<hex>ff</hex>"}"#.to_string()
-                );
-            }
+            BencodeParserBuilder::default()
+                .multiple_values(multiple_values)
+                .build(input)
+                .write_str(&mut output)?;
 
-            #[test]
-            fn integer_and_empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bari42e3:foolee"),
-                    r#"{"bar":42,"foo":[]}"#.to_string()
-                );
-            }
+            Ok(output)
+        }
 
-            #[test]
-            fn integer_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bari42e3:fooli43eee"),
-                    r#"{"bar":42,"foo":[43]}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_concatenate_them_by_default() {
+            assert_eq!(
+                bencode_to_json(MultipleValues::default(), b"i1ei2e").unwrap(),
+                "12"
+            );
+        }
 
-            #[test]
-            fn integer_and_empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bari42e3:foodee"),
-                    r#"{"bar":42,"foo":{}}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_write_one_json_document_per_line_in_ndjson_mode() {
+            assert_eq!(
+                bencode_to_json(MultipleValues::Ndjson, b"i1eli2eed1:ai3ee\n4:spam").unwrap(),
+                "1\n[2]\n{\"a\":3}\n\"spam\"\n"
+            );
+        }
 
-            #[test]
-            fn integer_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bari42e3:food3:fooi43eee"),
-                    r#"{"bar":42,"foo":{"foo":43}}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_write_nothing_for_an_empty_input_in_ndjson_mode() {
+            assert_eq!(bencode_to_json(MultipleValues::Ndjson, b"").unwrap(), "");
+        }
 
-            #[test]
-            fn utf8_string_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar5:alice3:fooi43ee"),
-                    r#"{"bar":"alice","foo":43}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_wrap_them_in_a_json_array_in_array_mode() {
+            assert_eq!(
+                bencode_to_json(MultipleValues::Array, b"i1eli2eed1:ai3ee\n4:spam").unwrap(),
+                "[1,[2],{\"a\":3},\"spam\"]"
+            );
+        }
 
-            #[test]
-            fn utf8_string_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar5:alice3:foo1:\xFFe"),
-                    r#"{"bar":"alice","foo":"<hex>ff</hex>"}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_write_an_empty_json_array_for_an_empty_input_in_array_mode() {
+            assert_eq!(bencode_to_json(MultipleValues::Array, b"").unwrap(), "[]");
+        }
 
-            #[test]
-            fn utf8_string_and_empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar5:alice3:foolee"),
-                    r#"{"bar":"alice","foo":[]}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_accept_a_single_value_in_reject_mode() {
+            assert_eq!(
+                bencode_to_json(MultipleValues::Reject, b"li1ee\n").unwrap(),
+                "[1]"
+            );
+        }
 
-            #[test]
-            fn utf8_string_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar5:alice3:fooli42eee"),
-                    r#"{"bar":"alice","foo":[42]}"#.to_string()
-                );
+        #[test]
+        fn it_should_reject_trailing_data_after_the_first_value_in_reject_mode() {
+            let result = bencode_to_json(MultipleValues::Reject, b"i1ei2e");
+
+            match result {
+                Err(Error::TrailingData(read_context, write_context)) => {
+                    assert_eq!(read_context.byte, Some(b'i'));
+                    assert_eq!(read_context.pos, 4);
+                    assert_eq!(write_context.latest_bytes, b"1");
+                }
+                _ => panic!("unexpected result: {result:?}"),
             }
+        }
 
-            #[test]
-            fn utf8_string_and_empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar5:alice3:foodee"),
-                    r#"{"bar":"alice","foo":{}}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_reject_a_trailing_line_break_in_reject_and_strict_mode() {
+            let mut output = String::new();
 
-            #[test]
-            fn utf8_string_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar5:alice3:food3:fooi42eee"),
-                    r#"{"bar":"alice","foo":{"foo":42}}"#.to_string()
-                );
-            }
+            let result = BencodeParserBuilder::default()
+                .multiple_values(MultipleValues::Reject)
+                .strict(true)
+                .build(&b"i1e\n"[..])
+                .write_str(&mut output);
 
-            #[test]
-            fn non_utf8_string_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:fooi43ee"),
-                    r#"{"bar":"<hex>ff</hex>","foo":43}"#.to_string()
-                );
-            }
+            assert!(matches!(result, Err(Error::LineBreakNotAllowed(..))));
+        }
 
-            #[test]
-            fn non_utf8_string_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foo1:\xFFe"),
-                    r#"{"bar":"<hex>ff</hex>","foo":"<hex>ff</hex>"}"#.to_string()
-                );
-            }
+        #[test]
+        fn it_should_reject_a_trailing_line_break_in_reject_mode_when_line_breaks_are_not_skipped()
+        {
+            let mut output = String::new();
 
-            #[test]
-            fn non_utf8_string_and_empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foolee"),
-                    r#"{"bar":"<hex>ff</hex>","foo":[]}"#.to_string()
-                );
-            }
+            let result = BencodeParserBuilder::default()
+                .multiple_values(MultipleValues::Reject)
+                .skip_newlines(false)
+                .build(&b"i1e\n"[..])
+                .write_str(&mut output);
 
-            #[test]
-            fn non_utf8_string_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:fooli42eee"),
-                    r#"{"bar":"<hex>ff</hex>","foo":[42]}"#.to_string()
-                );
-            }
+            assert!(matches!(result, Err(Error::TrailingData(..))));
+        }
+    }
 
-            #[test]
-            fn non_utf8_string_and_empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:foodee"),
-                    r#"{"bar":"<hex>ff</hex>","foo":{}}"#.to_string()
-                );
-            }
+    mod limits {
+        use crate::parsers::{error::Error, options::BencodeParserBuilder};
 
-            #[test]
-            fn non_utf8_string_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bar1:\xFF3:food3:fooi42eee"),
-                    r#"{"bar":"<hex>ff</hex>","foo":{"foo":42}}"#.to_string()
-                );
-            }
+        fn bencode_to_json(builder: BencodeParserBuilder, input: &[u8]) -> Result<String, Error> {
+            let mut output = String::new();
 
-            #[test]
-            fn empty_list_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barle3:fooi42ee"),
-                    r#"{"bar":[],"foo":42}"#.to_string()
-                );
-            }
+            builder.build(input).write_str(&mut output)?;
 
-            #[test]
-            fn empty_list_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barle3:foo5:alicee"),
-                    r#"{"bar":[],"foo":"alice"}"#.to_string()
-                );
-            }
+            Ok(output)
+        }
 
-            #[test]
-            fn empty_list_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barle3:foo1:\xFFe"),
-                    r#"{"bar":[],"foo":"<hex>ff</hex>"}"#.to_string()
-                );
-            }
+        mod max_depth {
+            use crate::parsers::{
+                error::Error, options::BencodeParserBuilder, tests::limits::bencode_to_json,
+            };
 
             #[test]
-            fn empty_list_and_empty_dict() {
+            fn it_should_allow_values_up_to_the_maximum_depth() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barle3:foodee"),
-                    r#"{"bar":[],"foo":{}}"#.to_string()
+                    bencode_to_json(BencodeParserBuilder::default().max_depth(2), b"ld1:ai1eee")
+                        .unwrap(),
+                    r#"[{"a":1}]"#
                 );
             }
 
             #[test]
-            fn empty_list_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barle3:food3:foo5:aliceee"),
-                    r#"{"bar":[],"foo":{"foo":"alice"}}"#.to_string()
-                );
-            }
+            fn it_should_reject_values_nested_deeper() {
+                let deeply_nested_lists = format!("{}{}", "l".repeat(10_000), "e".repeat(10_000));
 
-            #[test]
-            fn list_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barli42ee3:fooi42ee"),
-                    r#"{"bar":[42],"foo":42}"#.to_string()
-                );
+                assert!(matches!(
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_depth(100),
+                        deeply_nested_lists.as_bytes()
+                    ),
+                    Err(Error::MaxDepthExceeded(..))
+                ));
             }
 
             #[test]
-            fn list_and_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barli42ee3:foo5:alicee"),
-                    r#"{"bar":[42],"foo":"alice"}"#.to_string()
+            fn it_should_not_count_closed_lists_or_dictionaries() {
+                assert!(
+                    bencode_to_json(BencodeParserBuilder::default().max_depth(2), b"llelelee")
+                        .is_ok()
                 );
             }
 
             #[test]
-            fn list_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barli42ee3:foo1:\xFFe"),
-                    r#"{"bar":[42],"foo":"<hex>ff</hex>"}"#.to_string()
-                );
-            }
+            fn it_should_not_keep_the_default_value_tree_limit_after_reading_a_value() {
+                let depth = crate::value::DEFAULT_MAX_DEPTH + 1;
+                let deeply_nested_lists = format!("{}{}", "l".repeat(depth), "e".repeat(depth));
 
-            #[test]
-            fn list_and_empty_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barli42ee3:foodee"),
-                    r#"{"bar":[42],"foo":{}}"#.to_string()
-                );
-            }
+                let mut parser =
+                    BencodeParserBuilder::default().build(deeply_nested_lists.as_bytes());
 
-            #[test]
-            fn list_and_dict() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barli42ee3:food3:foo5:aliceee"),
-                    r#"{"bar":[42],"foo":{"foo":"alice"}}"#.to_string()
-                );
+                assert!(matches!(
+                    parser.read_value(),
+                    Err(Error::MaxDepthExceeded(..))
+                ));
+                assert_eq!(parser.options.max_depth, None);
             }
+        }
 
-            #[test]
-            fn empty_dict_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barde3:fooi42ee"),
-                    r#"{"bar":{},"foo":42}"#.to_string()
-                );
-            }
+        mod max_string_length {
+            use crate::parsers::{
+                error::Error, options::BencodeParserBuilder, tests::limits::bencode_to_json,
+            };
 
             #[test]
-            fn empty_dict_and_utf8_string() {
+            fn it_should_allow_strings_up_to_the_maximum_length() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barde3:foo5:alicee"),
-                    r#"{"bar":{},"foo":"alice"}"#.to_string()
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_string_length(4),
+                        b"4:spam"
+                    )
+                    .unwrap(),
+                    r#""spam""#
                 );
             }
 
             #[test]
-            fn empty_dict_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barde3:foo1:\xFFe"),
-                    r#"{"bar":{},"foo":"<hex>ff</hex>"}"#.to_string()
-                );
+            fn it_should_reject_longer_strings_before_reading_them() {
+                // Only the length is in the input
+                assert!(matches!(
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_string_length(1024),
+                        b"5000000000:"
+                    ),
+                    Err(Error::MaxStringLengthExceeded(..))
+                ));
+            }
+
+            #[test]
+            fn it_should_apply_to_dictionary_keys() {
+                assert!(matches!(
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_string_length(2),
+                        b"d3:fooi1ee"
+                    ),
+                    Err(Error::MaxStringLengthExceeded(..))
+                ));
             }
+        }
+
+        mod max_container_items {
+            use crate::parsers::{
+                error::Error, options::BencodeParserBuilder, tests::limits::bencode_to_json,
+            };
 
             #[test]
-            fn empty_dict_and_empty_list() {
+            fn it_should_allow_containers_up_to_the_maximum_number_of_items() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barde3:foolee"),
-                    r#"{"bar":{},"foo":[]}"#.to_string()
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_container_items(2),
+                        b"li1eli1ei2eee"
+                    )
+                    .unwrap(),
+                    "[1,[1,2]]"
                 );
             }
 
             #[test]
-            fn empty_dict_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:barde3:fooli42eee"),
-                    r#"{"bar":{},"foo":[42]}"#.to_string()
-                );
+            fn it_should_reject_lists_with_more_items() {
+                assert!(matches!(
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_container_items(2),
+                        b"li1ei2ei3ee"
+                    ),
+                    Err(Error::MaxContainerItemsExceeded(..))
+                ));
             }
 
             #[test]
-            fn dict_and_integer() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:fooi43ee"),
-                    r#"{"bar":{"bar":42},"foo":43}"#.to_string()
-                );
+            fn it_should_count_dictionary_fields_instead_of_keys_and_values() {
+                let builder = || BencodeParserBuilder::default().max_container_items(2);
+
+                assert!(bencode_to_json(builder(), b"d1:ai1e1:bi2ee").is_ok());
+                assert!(matches!(
+                    bencode_to_json(builder(), b"d1:ai1e1:bi2e1:ci3ee"),
+                    Err(Error::MaxContainerItemsExceeded(..))
+                ));
             }
+        }
+
+        mod max_input_bytes {
+            use crate::parsers::{
+                error::Error, options::BencodeParserBuilder, tests::limits::bencode_to_json,
+            };
 
             #[test]
-            fn dict_and_utf8_string() {
+            fn it_should_allow_inputs_up_to_the_maximum_size() {
                 assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:foo5:alicee"),
-                    r#"{"bar":{"bar":42},"foo":"alice"}"#.to_string()
+                    bencode_to_json(BencodeParserBuilder::default().max_input_bytes(4), b"i42e")
+                        .unwrap(),
+                    "42"
                 );
             }
 
             #[test]
-            fn dict_and_non_utf8_string() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:foo1:\xFFe"),
-                    r#"{"bar":{"bar":42},"foo":"<hex>ff</hex>"}"#.to_string()
-                );
+            fn it_should_reject_bigger_inputs() {
+                assert!(matches!(
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_input_bytes(4),
+                        b"li42ee"
+                    ),
+                    Err(Error::MaxInputBytesExceeded(..))
+                ));
             }
 
             #[test]
-            fn dict_and_empty_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:foolee"),
-                    r#"{"bar":{"bar":42},"foo":[]}"#.to_string()
-                );
+            fn it_should_stop_reading_in_the_middle_of_a_string() {
+                assert!(matches!(
+                    bencode_to_json(
+                        BencodeParserBuilder::default().max_input_bytes(4),
+                        b"6:foobar"
+                    ),
+                    Err(Error::MaxInputBytesExceeded(..))
+                ));
             }
 
             #[test]
-            fn dict_and_list() {
-                assert_eq!(
-                    bencode_to_json_unchecked(b"d3:bard3:bari42ee3:fooli42eee"),
-                    r#"{"bar":{"bar":42},"foo":[42]}"#.to_string()
-                );
+            fn it_should_apply_when_checking_for_trailing_data() {
+                let mut parser = BencodeParserBuilder::default()
+                    .max_input_bytes(3)
+                    .build(&b"i1e\n"[..]);
+
+                parser.read_value().unwrap();
+
+                assert!(matches!(
+                    parser.expect_end_of_input(),
+                    Err(Error::MaxInputBytesExceeded(..))
+                ));
             }
         }
+    }
 
-        mod should_escape_json {
+    mod value_paths {
+        use crate::parsers::{
+            error::Error,
+            event::{Event, Integer},
+            path::{PathSegment, ValuePath},
+            BencodeParser,
+        };
 
-            mod in_field_keys {
-                use crate::parsers::tests::bencode_to_json_unchecked;
+        fn error_path(input: &[u8]) -> String {
+            let mut output = String::new();
 
-                // Only one especial char is tested. The string parser contains
-                // other tests for the rest of the special chars that need to be
-                // escaped.
+            let err = BencodeParser::new(input)
+                .write_str(&mut output)
+                .unwrap_err();
 
-                #[test]
-                fn containing_a_line_break_at_the_beginning_of_the_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked("d4:\nfoo3:bare".as_bytes()),
-                        r#"{"\nfoo":"bar"}"#.to_string()
-                    );
-                }
+            err.path().unwrap().to_string()
+        }
 
-                #[test]
-                fn containing_a_line_break_in_the_middle_of_the_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked("d4:f\noo3:bare".as_bytes()),
-                        r#"{"f\noo":"bar"}"#.to_string()
-                    );
-                }
+        #[test]
+        fn errors_should_include_the_path_of_the_value_being_parsed() {
+            assert_eq!(
+                error_path(b"d4:infod5:filesld6:lengthi1e4:pathl1:ax"),
+                "info.files[0].path[1]"
+            );
+        }
 
-                #[test]
-                fn containing_a_line_break_at_the_end_of_the_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked("d4:foo\n3:bare".as_bytes()),
-                        r#"{"foo\n":"bar"}"#.to_string()
-                    );
-                }
+        #[test]
+        fn errors_parsing_a_dictionary_key_should_include_the_path_of_the_dictionary() {
+            assert_eq!(error_path(b"d1:ad1:bi1e3:xy"), "a");
+        }
+
+        #[test]
+        fn errors_in_the_top_level_value_should_have_an_empty_path() {
+            assert_eq!(error_path(b"i1x"), "");
+        }
+
+        #[test]
+        fn the_error_message_should_include_the_path() {
+            let mut output = String::new();
+
+            let err = BencodeParser::new(&b"l1:ai00ee"[..])
+                .write_str(&mut output)
+                .unwrap_err();
+
+            assert!(err.to_string().contains("path `[1]`"));
+        }
+
+        #[test]
+        fn integer_overflow_errors_should_include_the_path_of_the_integer() {
+            let err = BencodeParser::new(&b"d1:ai99999999999999999999ee"[..])
+                .read_value()
+                .unwrap_err();
+
+            assert!(matches!(err, Error::IntegerOverflow(..)));
+            assert_eq!(err.path().unwrap().to_string(), "a");
+        }
+
+        #[test]
+        fn the_parser_should_return_the_path_of_the_latest_event() {
+            let mut parser = BencodeParser::new(&b"d1:ali1eee"[..]);
+
+            let mut paths = vec![];
+
+            while let Some(event) = parser.next_event().unwrap() {
+                paths.push((event, parser.path()));
             }
 
-            mod in_field_values {
-                use crate::parsers::tests::bencode_to_json_unchecked;
+            let a = PathSegment::Key(b"a".to_vec());
+
+            assert_eq!(
+                paths,
+                vec![
+                    (Event::DictStart, ValuePath::default()),
+                    (Event::Key(b"a".to_vec()), ValuePath::new(vec![a.clone()])),
+                    (Event::ListStart, ValuePath::new(vec![a.clone()])),
+                    (
+                        Event::Integer(Integer::new("1".to_string())),
+                        ValuePath::new(vec![a.clone(), PathSegment::Index(0)])
+                    ),
+                    (Event::End, ValuePath::new(vec![a])),
+                    (Event::End, ValuePath::default()),
+                ]
+            );
+        }
+    }
+
+    mod selected_values {
+        use crate::{
+            parsers::{error::Error, event::Event, options::BencodeParserBuilder, BencodeParser},
+            value::Value,
+        };
+
+        fn select(queries: &[&str], input: &[u8]) -> Result<String, Error> {
+            let mut output = String::new();
+
+            queries
+                .iter()
+                .fold(BencodeParserBuilder::default(), |builder, query| {
+                    builder.select(query.parse().unwrap())
+                })
+                .build(input)
+                .write_str(&mut output)?;
+
+            Ok(output)
+        }
+
+        const TORRENT: &[u8] =
+            b"d8:announce4:spam13:announce-listll1:aee4:infod5:filesld6:lengthi1e4:pathl1:xeed6:lengthi2e4:pathl1:yeee4:name3:eggee";
+
+        #[test]
+        fn it_should_only_write_the_selected_values() {
+            assert_eq!(select(&["info.name"], TORRENT).unwrap(), "\"egg\"\n");
+        }
+
+        #[test]
+        fn it_should_write_each_selected_value_on_its_own_line() {
+            assert_eq!(
+                select(&["info.name", "announce-list"], TORRENT).unwrap(),
+                "[[\"a\"]]\n\"egg\"\n"
+            );
+        }
+
+        #[test]
+        fn it_should_select_values_with_wildcards() {
+            assert_eq!(
+                select(&["info.files[*].length"], TORRENT).unwrap(),
+                "1\n2\n"
+            );
+            assert_eq!(
+                select(&["info.files[1].*"], TORRENT).unwrap(),
+                "2\n[\"y\"]\n"
+            );
+        }
+
+        #[test]
+        fn it_should_write_nothing_when_no_value_is_selected() {
+            assert_eq!(select(&["comment"], TORRENT).unwrap(), "");
+        }
+
+        #[test]
+        fn it_should_select_the_top_level_value_with_the_empty_query() {
+            assert_eq!(select(&[""], b"li1ee").unwrap(), "[1]\n");
+        }
+
+        #[test]
+        fn it_should_keep_rendering_known_fields_inside_the_selected_values() {
+            let mut output = String::new();
+
+            BencodeParserBuilder::default()
+                .torrent(true)
+                .select("info".parse().unwrap())
+                .build(&[&b"d4:infod6:pieces20:"[..], &[0xAA; 20], b"ee"].concat()[..])
+                .write_str(&mut output)
+                .unwrap();
+
+            assert_eq!(
+                output,
+                format!("{{\"pieces\":[\"{}\"]}}\n", "aa".repeat(20))
+            );
+        }
+
+        #[test]
+        fn it_should_still_validate_the_values_that_are_not_selected() {
+            assert!(matches!(
+                select(&["b"], b"d1:a3:xy"),
+                Err(Error::UnexpectedEndOfInputParsingStringValue(..))
+            ));
+        }
+
+        #[test]
+        fn strings_outside_the_selected_values_should_still_be_parsed_as_events() {
+            let mut parser = BencodeParserBuilder::default()
+                .select("b".parse().unwrap())
+                .build(&b"d1:a4:spam1:b3:egge"[..]);
+
+            let events: Vec<Event> = parser.events().map(Result::unwrap).collect();
+
+            assert_eq!(
+                events,
+                vec![
+                    Event::DictStart,
+                    Event::Key(b"a".to_vec()),
+                    Event::Bytes(b"spam".to_vec()),
+                    Event::Key(b"b".to_vec()),
+                    Event::Bytes(b"egg".to_vec()),
+                    Event::End,
+                ]
+            );
+        }
+
+        #[test]
+        fn strings_outside_the_selected_values_should_still_be_in_the_value_tree() {
+            let mut parser = BencodeParserBuilder::default()
+                .select("b".parse().unwrap())
+                .build(&b"d1:a4:spam1:b3:egge"[..]);
+
+            let value = parser.read_value().unwrap().unwrap();
+
+            assert_eq!(value.get(b"a").and_then(Value::as_str), Some("spam"));
+        }
+
+        #[test]
+        fn it_should_write_the_whole_input_without_queries() {
+            let mut output = String::new();
+
+            BencodeParser::new(&b"d1:ai1ee"[..])
+                .write_str(&mut output)
+                .unwrap();
+
+            assert_eq!(output, r#"{"a":1}"#);
+        }
+    }
+
+    mod pretty_printing {
+        use crate::parsers::options::{BencodeParserBuilder, MultipleValues};
+
+        fn to_json(builder: BencodeParserBuilder, input: &[u8]) -> String {
+            let mut output = String::new();
+
+            builder.build(input).write_str(&mut output).unwrap();
+
+            output
+        }
+
+        #[test]
+        fn it_should_indent_nested_lists_and_dictionaries() {
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default().indent(2),
+                    b"d3:fooli1ed3:bari42eeee"
+                ),
+                "{\n  \"foo\": [\n    1,\n    {\n      \"bar\": 42\n    }\n  ]\n}"
+            );
+        }
+
+        #[test]
+        fn it_should_write_empty_lists_and_dictionaries_on_one_line() {
+            assert_eq!(
+                to_json(BencodeParserBuilder::default().indent(2), b"d1:ade1:blee"),
+                "{\n  \"a\": {},\n  \"b\": []\n}"
+            );
+        }
+
+        #[test]
+        fn it_should_use_the_configured_indentation() {
+            assert_eq!(
+                to_json(BencodeParserBuilder::default().indent(4), b"li1ee"),
+                "[\n    1\n]"
+            );
+        }
+
+        #[test]
+        fn it_should_not_change_top_level_integers_and_strings() {
+            assert_eq!(
+                to_json(BencodeParserBuilder::default().indent(2), b"4:spam"),
+                r#""spam""#
+            );
+        }
+
+        #[test]
+        fn it_should_indent_the_values_of_known_fields() {
+            let input = [&b"d4:infod6:pieces20:"[..], &[0xAA; 20], b"ee"].concat();
+
+            let json = to_json(
+                BencodeParserBuilder::default().indent(2).torrent(true),
+                &input,
+            );
+
+            assert!(json.starts_with(&format!(
+                "{{\n  \"info\": {{\n    \"pieces\": [\n      \"{}\"\n    ]\n  }},\n  \"info_hash\": {{\n    \"v1\": ",
+                "aa".repeat(20)
+            )));
+            assert!(json.ends_with("\"\n  }\n}"));
+        }
+
+        #[test]
+        fn it_should_indent_the_items_of_the_json_array_with_several_top_level_values() {
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default()
+                        .indent(2)
+                        .multiple_values(MultipleValues::Array),
+                    b"i1eli2ee"
+                ),
+                "[\n  1,\n  [\n    2\n  ]\n]"
+            );
+        }
+
+        #[test]
+        fn it_should_not_pretty_print_values_written_one_per_line() {
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default()
+                        .indent(2)
+                        .multiple_values(MultipleValues::Ndjson),
+                    b"li1eed1:ai2ee"
+                ),
+                "[1]\n{\"a\":2}\n"
+            );
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default()
+                        .indent(2)
+                        .select("[*]".parse().unwrap()),
+                    b"lli1eed1:ai2eee"
+                ),
+                "[1]\n{\"a\":2}\n"
+            );
+        }
+
+        #[test]
+        fn it_should_pretty_print_to_bytes_too() {
+            let mut output = Vec::new();
+
+            BencodeParserBuilder::default()
+                .indent(2)
+                .build(&b"li1ee"[..])
+                .write_bytes(&mut output)
+                .unwrap();
+
+            assert_eq!(output, b"[\n  1\n]");
+        }
+
+        #[test]
+        fn it_should_sort_the_dictionary_keys_by_their_raw_bytes() {
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default().sort_keys(true),
+                    b"d1:bi1e1:ad1:yi1e1:xi2ee1:cli1ed1:qi1e1:pi2eeee"
+                ),
+                r#"{"a":{"x":2,"y":1},"b":1,"c":[1,{"p":2,"q":1}]}"#
+            );
+        }
+
+        #[test]
+        fn it_should_sort_the_dictionary_keys_while_pretty_printing() {
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default().indent(2).sort_keys(true),
+                    b"d1:bi1e1:ald1:yi1e1:xi2eeee"
+                ),
+                "{\n  \"a\": [\n    {\n      \"x\": 2,\n      \"y\": 1\n    }\n  ],\n  \"b\": 1\n}"
+            );
+        }
+
+        #[test]
+        fn it_should_add_the_info_hash_after_the_sorted_fields() {
+            let json = to_json(
+                BencodeParserBuilder::default()
+                    .torrent(true)
+                    .sort_keys(true),
+                b"d4:infod4:name4:spame8:announce3:egge",
+            );
+
+            assert!(
+                json.starts_with(r#"{"announce":"egg","info":{"name":"spam"},"info_hash":{"v1":"#)
+            );
+        }
+    }
+
+    mod streamed_strings {
+        use crate::{
+            parsers::{
+                error::Error,
+                event::Event,
+                options::{BencodeParserBuilder, ByteStringEncoding, StreamedInvalidUtf8},
+                BencodeParser,
+            },
+            value::Value,
+        };
+
+        fn to_json(builder: BencodeParserBuilder, input: &[u8]) -> Result<String, Error> {
+            let mut output = String::new();
 
-                #[test]
-                fn containing_a_line_break_at_the_beginning_of_the_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked("d3:foo4:\nbare".as_bytes()),
-                        r#"{"foo":"\nbar"}"#.to_string()
-                    );
-                }
+            builder.build(input).write_str(&mut output)?;
 
-                #[test]
-                fn containing_a_line_break_in_the_middle_of_the_string() {
-                    assert_eq!(
-                        bencode_to_json_unchecked("d3:foo4:ba\nre".as_bytes()),
-                        r#"{"foo":"ba\nr"}"#.to_string()
-                    );
+            Ok(output)
+        }
+
+        fn string(bytes: &[u8]) -> Vec<u8> {
+            [format!("{}:", bytes.len()).as_bytes(), bytes].concat()
+        }
+
+        fn builder(encoding: ByteStringEncoding, indent: Option<usize>) -> BencodeParserBuilder {
+            let builder = BencodeParserBuilder::default().byte_string_encoding(encoding);
+
+            match indent {
+                Some(indent) => builder.indent(indent),
+                None => builder,
+            }
+        }
+
+        #[test]
+        fn it_should_write_the_same_json_as_when_strings_are_kept_in_memory() {
+            let inputs = [
+                string(b"short"),
+                string("a text with \"quotes\", \\ and \n".as_bytes()),
+                string("ñandú, 🦀 and 中文 split between chunks".as_bytes()),
+                string(&[0xFF, 0xFE, b'a', b'\\', 0x00, 0x80, 0xC3]),
+                [&b"l"[..], &string(b"spam eggs"), &string(&[0xFF; 7]), b"e"].concat(),
+            ];
+
+            let encodings = [
+                ByteStringEncoding::Hex,
+                ByteStringEncoding::Base64,
+                ByteStringEncoding::Lossy,
+                ByteStringEncoding::Escaped,
+                ByteStringEncoding::Tagged,
+                ByteStringEncoding::Array,
+            ];
+
+            for input in &inputs {
+                for encoding in encodings {
+                    for indent in [None, Some(2)] {
+                        let expected = to_json(builder(encoding, indent), input).unwrap();
+
+                        for string_buffer_size in 1..=8 {
+                            assert_eq!(
+                                to_json(
+                                    builder(encoding, indent).string_buffer_size(string_buffer_size),
+                                    input
+                                )
+                                .unwrap(),
+                                expected,
+                                "input: {input:?}, encoding: {encoding:?}, buffer: {string_buffer_size}"
+                            );
+                        }
+                    }
                 }
+            }
+        }
 
-                #[test]
-                fn containing_a_line_break_at_the_end_of_the_string() {
+        #[test]
+        fn it_should_write_the_torrent_pieces_and_the_info_hash_as_when_they_are_kept_in_memory() {
+            let input = [
+                &b"d4:infod4:name4:spam6:pieces40:"[..],
+                &[0xAA; 20],
+                &[0xBB; 20],
+                b"ee",
+            ]
+            .concat();
+
+            for indent in [None, Some(2)] {
+                let expected = to_json(
+                    builder(ByteStringEncoding::Hex, indent).torrent(true),
+                    &input,
+                )
+                .unwrap();
+
+                for string_buffer_size in [1, 3, 20, 39] {
                     assert_eq!(
-                        bencode_to_json_unchecked("d3:foo4:bar\ne".as_bytes()),
-                        r#"{"foo":"bar\n"}"#.to_string()
+                        to_json(
+                            builder(ByteStringEncoding::Hex, indent)
+                                .torrent(true)
+                                .string_buffer_size(string_buffer_size),
+                            &input
+                        )
+                        .unwrap(),
+                        expected
                     );
                 }
             }
         }
 
-        mod should_fail {
-            use crate::{parsers::error::Error, try_bencode_to_json};
+        fn switching_encoding() -> BencodeParserBuilder {
+            BencodeParserBuilder::default()
+                .streamed_invalid_utf8(StreamedInvalidUtf8::SwitchEncoding)
+        }
 
-            #[test]
-            fn when_an_empty_dict_does_not_have_the_matching_close_byte() {
-                let dict_without_closing_dict_byte = b"d";
+        #[test]
+        fn it_should_switch_to_the_byte_string_encoding_when_invalid_utf8_follows_some_text() {
+            let input = string(b"abc\xFF\xFEghi");
 
-                let result = try_bencode_to_json(dict_without_closing_dict_byte);
+            assert_eq!(
+                to_json(switching_encoding().string_buffer_size(3), &input).unwrap(),
+                r#""abc<hex>fffe676869</hex>""#
+            );
+            assert_eq!(
+                to_json(
+                    switching_encoding()
+                        .byte_string_encoding(ByteStringEncoding::Base64)
+                        .string_buffer_size(3),
+                    &input
+                )
+                .unwrap(),
+                r#""abc<base64>//5naGk=</base64>""#
+            );
+        }
 
-                assert!(matches!(
-                    result,
-                    Err(Error::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd { .. })
-                ));
-            }
+        #[test]
+        fn it_should_switch_to_the_byte_string_encoding_when_the_string_ends_with_an_incomplete_utf8_sequence(
+        ) {
+            assert_eq!(
+                to_json(
+                    switching_encoding().string_buffer_size(2),
+                    &string(b"ab\xC3")
+                )
+                .unwrap(),
+                r#""ab<hex>c3</hex>""#
+            );
+        }
 
-            #[test]
-            fn when_a_dict_field_does_not_have_the_value() {
-                let dict_without_closing_dict_byte = b"d3:foo";
+        #[test]
+        fn it_should_replace_invalid_utf8_following_some_text_when_the_rule_is_lossy() {
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default()
+                        .string_buffer_size(3)
+                        .streamed_invalid_utf8(StreamedInvalidUtf8::Lossy),
+                    &string(b"abc\xFF\xFEghi")
+                )
+                .unwrap(),
+                "\"abc\u{FFFD}\u{FFFD}ghi\""
+            );
+        }
 
-                let result = try_bencode_to_json(dict_without_closing_dict_byte);
+        #[test]
+        fn it_should_fail_by_default_when_invalid_utf8_follows_some_text() {
+            for encoding in [
+                ByteStringEncoding::Hex,
+                ByteStringEncoding::Base64,
+                ByteStringEncoding::Tagged,
+                ByteStringEncoding::Array,
+            ] {
+                let result = to_json(
+                    BencodeParserBuilder::default()
+                        .byte_string_encoding(encoding)
+                        .string_buffer_size(3),
+                    &string(b"abc\xFF"),
+                );
 
                 assert!(matches!(
                     result,
-                    Err(Error::UnexpectedEndOfInputExpectingDictFieldValue { .. })
+                    Err(Error::InvalidUtf8InStreamedString(_, _))
                 ));
             }
+        }
 
-            #[test]
-            fn when_a_dict_does_not_have_the_matching_close_byte() {
-                let dict_without_closing_dict_byte = b"d3:fooi42e";
+        #[test]
+        fn it_should_fail_when_invalid_utf8_follows_some_text_and_the_rule_is_error() {
+            let err = to_json(
+                BencodeParserBuilder::default()
+                    .string_buffer_size(3)
+                    .streamed_invalid_utf8(StreamedInvalidUtf8::Error),
+                &[&b"d3:foo"[..], &string(b"abc\xFF\xFEghi"), b"e"].concat(),
+            )
+            .unwrap_err();
+
+            assert!(matches!(err, Error::InvalidUtf8InStreamedString(_, _)));
+            assert_eq!(err.path().unwrap().to_string(), "foo");
+        }
 
-                let result = try_bencode_to_json(dict_without_closing_dict_byte);
+        #[test]
+        fn it_should_fail_switching_encodings_that_do_not_write_json_strings() {
+            for encoding in [ByteStringEncoding::Tagged, ByteStringEncoding::Array] {
+                let result = to_json(
+                    switching_encoding()
+                        .byte_string_encoding(encoding)
+                        .string_buffer_size(3),
+                    &string(b"abc\xFF"),
+                );
 
                 assert!(matches!(
                     result,
-                    Err(Error::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd { .. })
+                    Err(Error::InvalidUtf8InStreamedString(_, _))
                 ));
             }
+        }
 
-            #[test]
-            fn when_it_receives_an_end_dict_byte_without_the_matching_open_byte() {
-                let end_dict_byte_without_start = b"e";
+        #[test]
+        fn errors_reading_a_streamed_string_should_include_its_path() {
+            let err = to_json(
+                BencodeParserBuilder::default().string_buffer_size(2),
+                b"d3:fool10:abc",
+            )
+            .unwrap_err();
 
-                let result = try_bencode_to_json(end_dict_byte_without_start);
+            assert!(matches!(
+                err,
+                Error::UnexpectedEndOfInputParsingStringValue(_, _)
+            ));
+            assert_eq!(err.path().unwrap().to_string(), "foo[0]");
+        }
 
-                assert!(matches!(
-                    result,
-                    Err(Error::NoMatchingStartForListOrDictEnd { .. })
-                ));
-            }
+        #[test]
+        fn it_should_stream_the_selected_values() {
+            assert_eq!(
+                to_json(
+                    BencodeParserBuilder::default()
+                        .string_buffer_size(2)
+                        .select("info.name".parse().unwrap()),
+                    b"d8:announce4:spam4:infod4:name9:egg baconee"
+                )
+                .unwrap(),
+                "\"egg bacon\"\n"
+            );
+        }
 
-            #[test]
-            fn when_it_receives_a_premature_end_dict_byte() {
-                let dict_with_missing_key_value = b"d3:fooe";
+        #[test]
+        fn events_should_return_the_string_in_chunks() {
+            let mut parser = BencodeParserBuilder::default()
+                .string_buffer_size(5)
+                .build(&b"9:egg bacon"[..]);
 
-                let result = try_bencode_to_json(dict_with_missing_key_value);
+            assert_eq!(parser.next_event().unwrap(), Some(Event::BytesStart(9)));
+            assert_eq!(
+                parser.next_event().unwrap(),
+                Some(Event::BytesChunk(b"egg b".to_vec()))
+            );
+            assert_eq!(
+                parser.next_event().unwrap(),
+                Some(Event::BytesChunk(b"acon".to_vec()))
+            );
+            assert_eq!(parser.next_event().unwrap(), None);
 
-                assert!(matches!(result, Err(Error::PrematureEndOfDict { .. })));
-            }
+            let mut parser = BencodeParser::new(&b"9:egg bacon"[..]);
 
-            #[test]
-            fn when_the_first_field_value_is_empty() {
-                let dict_with_missing_key_value = b"d3:fooe";
+            assert_eq!(
+                parser.next_event().unwrap(),
+                Some(Event::Bytes(b"egg bacon".to_vec()))
+            );
+        }
 
-                let result = try_bencode_to_json(dict_with_missing_key_value);
+        #[test]
+        fn values_should_still_contain_the_whole_string() {
+            let mut parser = BencodeParserBuilder::default()
+                .string_buffer_size(2)
+                .build(&b"l9:egg bacone"[..]);
 
-                assert!(matches!(result, Err(Error::PrematureEndOfDict { .. })));
-            }
+            assert_eq!(
+                parser.read_value().unwrap(),
+                Some(Value::List(vec![Value::Bytes(b"egg bacon".to_vec())]))
+            );
+        }
 
-            #[test]
-            fn when_the_second_field_value_is_empty() {
-                let dict_with_missing_key_value = b"d3:foo3:bar3:fooe";
+        #[test]
+        fn captured_values_should_end_with_the_last_chunk() {
+            let mut parser = BencodeParserBuilder::default()
+                .string_buffer_size(2)
+                .capture("a")
+                .build(&b"d1:a5:spamse"[..]);
 
-                let result = try_bencode_to_json(dict_with_missing_key_value);
+            let events: Vec<Event> = parser.events().map(Result::unwrap).collect();
 
-                assert!(matches!(result, Err(Error::PrematureEndOfDict { .. })));
-            }
+            assert_eq!(events.len(), 7);
+            assert_eq!(parser.captured_values().len(), 1);
+            assert_eq!(parser.captured_values()[0].len, 7);
+        }
+    }
 
-            mod when_the_field_key_is_not_a_string_for_example {
-                use crate::parsers::error::Error;
-                use crate::parsers::BencodeType;
-                use crate::try_bencode_to_json;
+    mod error_recovery {
+        use crate::parsers::{
+            error::Error,
+            options::{BencodeParserBuilder, ErrorRecovery, MultipleValues},
+            BencodeParser,
+        };
 
-                #[test]
-                fn when_the_key_in_the_first_dict_field_is_an_integer() {
-                    let field_with_integer_key = b"di42ei43ee";
+        fn recover<R: std::io::Read>(parser: &mut BencodeParser<R>) -> String {
+            let mut output = String::new();
 
-                    let result = try_bencode_to_json(field_with_integer_key);
+            parser.write_str(&mut output).unwrap();
 
-                    assert!(matches!(
-                        result,
-                        Err(Error::ExpectedStringForDictKeyGot(
-                            BencodeType::Integer,
-                            _,
-                            _
-                        ))
-                    ));
-                }
+            output
+        }
 
-                #[test]
-                fn when_the_key_in_the_second_dict_field_is_an_integer() {
-                    let field_with_integer_key = b"d3:foo3:bari42ei43ee";
+        fn close(input: &[u8]) -> String {
+            recover(
+                &mut BencodeParserBuilder::default()
+                    .error_recovery(ErrorRecovery::Close)
+                    .build(input),
+            )
+        }
 
-                    let result = try_bencode_to_json(field_with_integer_key);
+        #[test]
+        fn it_should_fail_by_default() {
+            let mut output = String::new();
 
-                    assert!(matches!(
-                        result,
-                        Err(Error::ExpectedStringForDictKeyGot(
-                            BencodeType::Integer,
-                            _,
-                            _
-                        ))
-                    ));
-                }
+            let result = BencodeParser::new(&b"li1ex"[..]).write_str(&mut output);
 
-                #[test]
-                fn when_the_key_in_the_first_dict_field_is_a_list() {
-                    let field_with_list_key = b"dlei42ee";
+            assert!(matches!(
+                result,
+                Err(Error::UnrecognizedFirstBencodeValueByte(_, _))
+            ));
+            assert_eq!(output, "[1");
+        }
 
-                    let result = try_bencode_to_json(field_with_list_key);
+        #[test]
+        fn it_should_replace_the_corrupt_value_with_a_marker_and_close_the_open_containers() {
+            assert_eq!(close(b"li1ex"), "[1,\"<corrupt>\"]\n");
+            assert_eq!(close(b"d1:ali1ei2x"), "{\"a\":[1,\"<corrupt>\"]}\n");
+            assert_eq!(close(b"d1:a5:sp"), "{\"a\":\"<corrupt>\"}\n");
+            assert_eq!(close(b"x"), "\"<corrupt>\"\n");
+        }
 
-                    assert!(matches!(
-                        result,
-                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::List, _, _))
-                    ));
-                }
+        #[test]
+        fn it_should_not_write_a_marker_after_a_complete_top_level_value() {
+            assert_eq!(close(b"i1ex"), "1\n");
+            assert_eq!(close(b"li1eee"), "[1]\n");
+            assert_eq!(close(b"i1ei2x"), "1\n\"<corrupt>\"\n");
+        }
 
-                #[test]
-                fn when_the_key_in_the_second_dict_field_is_a_list() {
-                    let field_with_list_key = b"d3:foo3:barlei42ee";
+        #[test]
+        fn it_should_write_the_marker_as_a_key_when_a_dictionary_key_is_expected() {
+            assert_eq!(close(b"d1:ai1ex"), "{\"a\":1,\"<corrupt>\":null}\n");
+            assert_eq!(close(b"di1e"), "{\"<corrupt>\":null}\n");
+        }
 
-                    let result = try_bencode_to_json(field_with_list_key);
+        #[test]
+        fn it_should_return_the_recovered_errors_as_diagnostics() {
+            let mut parser = BencodeParserBuilder::default()
+                .error_recovery(ErrorRecovery::Close)
+                .build(&b"d1:ali1ei2x1:bi3ee"[..]);
 
-                    assert!(matches!(
-                        result,
-                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::List, _, _))
-                    ));
-                }
+            assert_eq!(recover(&mut parser), "{\"a\":[1,\"<corrupt>\"]}\n");
 
-                #[test]
-                fn when_the_key_in_the_first_dict_field_is_a_dict() {
-                    let field_with_list_key = b"ddei42ee";
+            let diagnostics = parser.diagnostics();
 
-                    let result = try_bencode_to_json(field_with_list_key);
+            assert_eq!(diagnostics.len(), 1);
+            assert!(matches!(
+                diagnostics[0].error,
+                Error::UnexpectedByteParsingInteger(_, _)
+            ));
+            assert_eq!(diagnostics[0].error.path().unwrap().to_string(), "a[1]");
+            assert_eq!(diagnostics[0].resumed_at, None);
+        }
 
-                    assert!(matches!(
-                        result,
-                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::Dict, _, _))
-                    ));
-                }
+        #[test]
+        fn it_should_close_sorted_and_pretty_printed_dictionaries() {
+            assert_eq!(
+                recover(
+                    &mut BencodeParserBuilder::default()
+                        .error_recovery(ErrorRecovery::Close)
+                        .multiple_values(MultipleValues::Reject)
+                        .sort_keys(true)
+                        .indent(1)
+                        .build(&b"d1:bi1e1:ad"[..])
+                ),
+                "{\n \"a\": {\n  \"<corrupt>\": null\n },\n \"b\": 1\n}"
+            );
+        }
 
-                #[test]
-                fn when_the_key_in_the_second_dict_field_is_a_dict() {
-                    let field_with_list_key = b"d3:foo3:bardei42ee";
+        #[test]
+        fn it_should_write_each_recovered_value_on_its_line_even_when_pretty_printing() {
+            assert_eq!(
+                recover(
+                    &mut BencodeParserBuilder::default()
+                        .error_recovery(ErrorRecovery::Resynchronize)
+                        .indent(2)
+                        .build(&b"li1eexli2ee"[..])
+                ),
+                "[1]\n[2]\n"
+            );
+        }
 
-                    let result = try_bencode_to_json(field_with_list_key);
+        #[test]
+        fn it_should_write_the_marker_key_after_the_sorted_keys() {
+            assert_eq!(
+                recover(
+                    &mut BencodeParserBuilder::default()
+                        .error_recovery(ErrorRecovery::Close)
+                        .sort_keys(true)
+                        .build(&b"d1:~i1e1:ai2ex"[..])
+                ),
+                "{\"a\":2,\"~\":1,\"<corrupt>\":null}\n"
+            );
+        }
 
-                    assert!(matches!(
-                        result,
-                        Err(Error::ExpectedStringForDictKeyGot(BencodeType::Dict, _, _))
-                    ));
-                }
+        #[test]
+        fn it_should_resume_parsing_at_the_next_value_after_the_corrupt_bytes() {
+            let mut parser = BencodeParserBuilder::default()
+                .error_recovery(ErrorRecovery::Resynchronize)
+                .multiple_values(MultipleValues::Ndjson)
+                .build(&b"li1ex?!d1:ai2eexi3e"[..]);
+
+            assert_eq!(recover(&mut parser), "[1,\"<corrupt>\"]\n{\"a\":2}\n3\n");
+
+            let resumed_at: Vec<Option<u64>> = parser
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| diagnostic.resumed_at)
+                .collect();
+
+            assert_eq!(resumed_at.len(), 2);
+            assert!(resumed_at.iter().all(Option::is_some));
+        }
+
+        #[test]
+        fn it_should_write_one_value_per_line_when_resynchronizing_concatenated_values() {
+            let mut parser = BencodeParserBuilder::default()
+                .error_recovery(ErrorRecovery::Resynchronize)
+                .build(&b"li1eXi2ee"[..]);
+
+            assert_eq!(recover(&mut parser), "[1,\"<corrupt>\"]\n2\n");
+            assert_eq!(parser.diagnostics().len(), 2);
+        }
+
+        #[test]
+        fn it_should_stop_resynchronizing_at_the_end_of_the_input() {
+            let mut parser = BencodeParserBuilder::default()
+                .error_recovery(ErrorRecovery::Resynchronize)
+                .multiple_values(MultipleValues::Array)
+                .build(&b"li1e"[..]);
+
+            assert_eq!(recover(&mut parser), r#"[[1,"<corrupt>"]]"#);
+            assert_eq!(parser.diagnostics()[0].resumed_at, None);
+        }
+
+        #[test]
+        fn it_should_not_write_a_marker_for_trailing_data() {
+            let mut parser = BencodeParserBuilder::default()
+                .error_recovery(ErrorRecovery::Close)
+                .multiple_values(MultipleValues::Reject)
+                .build(&b"i1ei2e"[..]);
+
+            assert_eq!(recover(&mut parser), "1");
+            assert!(matches!(
+                parser.diagnostics()[0].error,
+                Error::TrailingData(_, _)
+            ));
+        }
+
+        #[test]
+        fn it_should_not_resume_parsing_when_only_one_value_is_allowed() {
+            for (input, expected_output) in [
+                (&b"i1ei2e"[..], "1"),
+                (&b"li1exi2e"[..], "[1,\"<corrupt>\"]"),
+                (&b"xi2ei3e"[..], "\"<corrupt>\""),
+            ] {
+                let mut parser = BencodeParserBuilder::default()
+                    .error_recovery(ErrorRecovery::Resynchronize)
+                    .multiple_values(MultipleValues::Reject)
+                    .build(input);
+
+                assert_eq!(recover(&mut parser), expected_output);
+                assert_eq!(parser.diagnostics().len(), 1);
+                assert_eq!(parser.diagnostics()[0].resumed_at, None);
             }
         }
+
+        #[test]
+        fn it_should_close_the_selected_values() {
+            assert_eq!(
+                recover(
+                    &mut BencodeParserBuilder::default()
+                        .error_recovery(ErrorRecovery::Close)
+                        .select("info".parse().unwrap())
+                        .build(&b"d4:infod4:name3:egg6:lengthi1x"[..])
+                ),
+                "{\"name\":\"egg\",\"length\":\"<corrupt>\"}\n"
+            );
+        }
+
+        #[test]
+        fn it_should_keep_long_strings_in_memory_to_replace_them_with_the_marker() {
+            assert_eq!(
+                recover(
+                    &mut BencodeParserBuilder::default()
+                        .error_recovery(ErrorRecovery::Close)
+                        .string_buffer_size(2)
+                        .build(&b"l9:egg ba"[..])
+                ),
+                "[\"<corrupt>\"]\n"
+            );
+        }
     }
 }
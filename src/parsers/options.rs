@@ -0,0 +1,594 @@
+//! Parser options.
+//!
+//! Options can be set per parser instance with the [`BencodeParserBuilder`]:
+//!
+//! ```rust
+//! use torrust_bencode2json::parsers::options::{BencodeParserBuilder, ByteStringEncoding};
+//!
+//! let mut output = String::new();
+//!
+//! BencodeParserBuilder::default()
+//!     .byte_string_encoding(ByteStringEncoding::Lossy)
+//!     .build(&b"2:\xFF\xFE"[..])
+//!     .write_str(&mut output)
+//!     .unwrap();
+//!
+//! assert_eq!(output, "\"\u{FFFD}\u{FFFD}\"");
+//! ```
+use std::io::Read;
+
+use crate::rw::DEFAULT_CAPTURE_SIZE;
+
+use super::{path::KeyPath, query::Query, BencodeParser};
+
+/// Settings for one parser instance.
+///
+/// The default options keep the original behavior of the parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ParserOptions {
+    /// Number of latest input and output bytes captured for the error
+    /// contexts. It must be greater than zero.
+    pub capture_size: usize,
+
+    /// Line breaks at the beginning, the end, or between values are ignored.
+    /// Otherwise, they are rejected as unrecognized bytes.
+    pub skip_newlines: bool,
+
+    /// How strings containing non UTF-8 bytes are written to JSON.
+    pub byte_string_encoding: ByteStringEncoding,
+
+    /// Only the canonical encoding is accepted. See
+    /// [`BencodeParser::new_strict`].
+    pub strict: bool,
+
+    /// Integers must fit into an `i64`, for consumers deserializing them into
+    /// native types. Bencoded integers have no size limit otherwise.
+    pub i64_integers: bool,
+
+    /// How several top-level values in the same input are written to JSON.
+    pub multiple_values: MultipleValues,
+
+    /// The input is a torrent file. The binary fields of the metainfo are
+    /// written as hashes and the info hash is added, unless the top-level
+    /// dictionary already has an `info_hash` field. See
+    /// [`torrent`](super::torrent).
+    pub torrent: bool,
+
+    /// The input is a tracker announce or scrape response. Compact peer lists
+    /// and other binary fields are decoded. See [`tracker`](super::tracker).
+    pub tracker_response: bool,
+
+    /// Paths of the values whose raw bytes are hashed while they are parsed.
+    /// See [`capture`](super::capture).
+    pub captured_paths: Vec<KeyPath>,
+
+    /// The raw bytes of the captured values are kept, besides hashing them.
+    pub capture_raw_bytes: bool,
+
+    /// Number of spaces used to indent pretty-printed JSON. The JSON is
+    /// compact if `None`, and also when the values are written one per line:
+    /// with [`MultipleValues::Ndjson`], with
+    /// [`selected_paths`](Self::selected_paths), or when errors are recovered
+    /// with [`MultipleValues::Concatenate`].
+    pub indent: Option<usize>,
+
+    /// Dictionary fields are written sorted by their raw keys, like in
+    /// canonical bencode. Each dictionary is kept in memory until it ends,
+    /// except in strict mode, where keys are already sorted. The dictionaries
+    /// nested in it are kept too, so for a torrent file the whole JSON is
+    /// kept in memory, including the strings written in chunks with
+    /// [`string_buffer_size`](Self::string_buffer_size).
+    pub sort_keys: bool,
+
+    /// Queries selecting the values written to the output, one per line. The
+    /// whole input is written if there are none. See [`query`](super::query).
+    pub selected_paths: Vec<Query>,
+
+    /// Strings longer than this are written in chunks of this size while
+    /// they are read, instead of being kept in memory. The events return them
+    /// in chunks too, with [`Event::BytesStart`](super::event::Event::BytesStart).
+    /// Every string is kept in memory if `None`, or when errors are
+    /// recovered. The chunks of the
+    /// strings inside dictionaries are still kept in memory when
+    /// [`sort_keys`](Self::sort_keys) is set without strict mode.
+    pub string_buffer_size: Option<usize>,
+
+    /// How invalid UTF-8 is written when it appears in a streamed string
+    /// after some text was already written.
+    pub streamed_invalid_utf8: StreamedInvalidUtf8,
+
+    /// What to do with the JSON written so far when the input is invalid.
+    pub error_recovery: ErrorRecovery,
+
+    /// Maximum number of nested lists and dictionaries. Unlimited if `None`.
+    pub max_depth: Option<usize>,
+
+    /// Maximum length of a single string. Unlimited if `None`.
+    pub max_string_length: Option<usize>,
+
+    /// Maximum number of items in a list, or fields in a dictionary.
+    /// Unlimited if `None`.
+    pub max_container_items: Option<usize>,
+
+    /// Maximum number of bytes read from the input. Unlimited if `None`.
+    pub max_input_bytes: Option<u64>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            capture_size: DEFAULT_CAPTURE_SIZE,
+            skip_newlines: true,
+            byte_string_encoding: ByteStringEncoding::default(),
+            strict: false,
+            i64_integers: false,
+            multiple_values: MultipleValues::default(),
+            torrent: false,
+            tracker_response: false,
+            captured_paths: Vec::new(),
+            capture_raw_bytes: false,
+            indent: None,
+            sort_keys: false,
+            selected_paths: Vec::new(),
+            string_buffer_size: None,
+            streamed_invalid_utf8: StreamedInvalidUtf8::default(),
+            error_recovery: ErrorRecovery::default(),
+            max_depth: None,
+            max_string_length: None,
+            max_container_items: None,
+            max_input_bytes: None,
+        }
+    }
+}
+
+/// How bencoded strings are written to JSON.
+///
+/// Bencoded strings are arbitrary byte strings, while JSON strings can only
+/// contain Unicode chars. Strings containing only valid UTF-8 are written as
+/// JSON strings by every encoding except [`Escaped`](Self::Escaped). The
+/// encodings differ in how they write the other strings.
+///
+/// Only [`Escaped`](Self::Escaped) and [`Tagged`](Self::Tagged) are
+/// unambiguous: the original bytes can always be recovered from the output
+/// with the [`JsonParser`](crate::json2bencode::JsonParser).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ByteStringEncoding {
+    /// Non UTF-8 strings are written as hexadecimal bytes: `"<hex>fffe</hex>"`.
+    ///
+    /// It's ambiguous because a UTF-8 string can contain the same text.
+    #[default]
+    Hex,
+
+    /// Non UTF-8 strings are written as base64 bytes: `"<base64>//4=</base64>"`.
+    ///
+    /// It's ambiguous because a UTF-8 string can contain the same text.
+    Base64,
+
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    ///
+    /// The original bytes can't be recovered.
+    Lossy,
+
+    /// Every string is written as a JSON string where backslashes are
+    /// doubled and each byte of the invalid UTF-8 sequences is written as
+    /// `\xNN`: `"\\xff\\xfe"` in the JSON text.
+    Escaped,
+
+    /// Non UTF-8 strings are written as objects with the base64 bytes:
+    /// `{"$bytes":"//4="}`.
+    ///
+    /// Dictionary keys must be JSON strings, so they are written with the
+    /// [`Escaped`](Self::Escaped) encoding, and keys starting with `$` get an
+    /// extra `$`. That way, a dictionary can't be confused with a tagged
+    /// string.
+    Tagged,
+
+    /// Non UTF-8 strings are written as arrays of bytes: `[255,254]`.
+    /// Dictionary keys are written with the [`Escaped`](Self::Escaped)
+    /// encoding.
+    ///
+    /// It's ambiguous because a list of integers has the same format.
+    Array,
+}
+
+/// How to handle inputs with more than one top-level value, for example
+/// `i1ei2e`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MultipleValues {
+    /// Values are written one after the other without any separator: `12`.
+    /// The output is not valid JSON when there is more than one value. When
+    /// errors are recovered, it's written as [`Ndjson`](Self::Ndjson).
+    #[default]
+    Concatenate,
+
+    /// Each value is written as a JSON document in its own line (NDJSON):
+    /// `1\n2\n`. The values are never pretty-printed.
+    Ndjson,
+
+    /// Values are written as the items of a JSON array: `[1,2]`. The output
+    /// is `[]` for an empty input.
+    Array,
+
+    /// Only one value is allowed. Any data after the first value is rejected
+    /// with a trailing data error.
+    Reject,
+}
+
+/// How invalid UTF-8 is written when it appears in the middle of a streamed
+/// string.
+///
+/// Streamed strings are written as text if their first chunk is valid UTF-8,
+/// and with the [`ByteStringEncoding`] otherwise. The text already written
+/// can't be undone when a later chunk contains invalid UTF-8, so the string
+/// can't be written like the same string kept in memory.
+///
+/// It does not apply to the [`Lossy`](ByteStringEncoding::Lossy) and
+/// [`Escaped`](ByteStringEncoding::Escaped) encodings, which write every
+/// string as text. With the other encodings, strings with invalid UTF-8 after
+/// valid text are rejected by default. Keep them in memory, with a bigger
+/// [`string_buffer_size`](BencodeParserBuilder::string_buffer_size), to write
+/// them like any other non UTF-8 string.
+///
+/// [`Error`](Self::Error) is the default, instead of
+/// [`SwitchEncoding`](Self::SwitchEncoding), because the output of the other
+/// rules can't be converted back into the input. Failing is the only rule that
+/// never writes a string that differs from the bencoded one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StreamedInvalidUtf8 {
+    /// The rest of the string is written with the
+    /// [`Hex`](ByteStringEncoding::Hex) or
+    /// [`Base64`](ByteStringEncoding::Base64) markers, inside the same JSON
+    /// string: `"text<hex>fffe</hex>"`. It fails with the other encodings.
+    ///
+    /// The markers can't be told apart from text, so the JSON can't be
+    /// converted back into the same bencoded string.
+    SwitchEncoding,
+
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    Lossy,
+
+    /// The string is rejected with an error.
+    #[default]
+    Error,
+}
+
+/// What to do with the JSON written so far when the input is invalid, for
+/// example, a truncated or damaged torrent file.
+///
+/// When errors are recovered, the JSON is well-formed: the value being parsed
+/// is replaced with the [`CORRUPTION_MARKER`](super::recovery::CORRUPTION_MARKER)
+/// and the open lists and dictionaries are closed. No marker is written for
+/// bytes found after a complete top-level value. Top-level values are
+/// separated even with [`MultipleValues::Concatenate`], which is written as
+/// [`MultipleValues::Ndjson`], because the marker could not be told apart
+/// from the values. The errors are returned as
+/// [`Diagnostic`](super::recovery::Diagnostic)s by
+/// [`BencodeParser::diagnostics`] instead of failing. Errors writing to the
+/// output, or reading from the input, are never recovered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorRecovery {
+    /// The first error is returned, leaving the JSON written so far
+    /// unfinished.
+    #[default]
+    Fail,
+
+    /// The JSON is closed at the first error and the rest of the input is
+    /// ignored.
+    Close,
+
+    /// The JSON is closed at each error, and parsing goes on with the next
+    /// top-level value found after skipping the corrupt bytes. The values
+    /// are written with the [`MultipleValues`] option. With
+    /// [`MultipleValues::Reject`], only one value is allowed, so it's like
+    /// [`Close`](Self::Close).
+    Resynchronize,
+}
+
+/// Builder for [`BencodeParser`]s with custom [`ParserOptions`].
+#[derive(Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct BencodeParserBuilder {
+    options: ParserOptions,
+}
+
+impl BencodeParserBuilder {
+    /// It sets the number of latest input and output bytes captured for the
+    /// error contexts.
+    #[must_use]
+    pub fn capture_size(mut self, capture_size: usize) -> Self {
+        self.options.capture_size = capture_size;
+        self
+    }
+
+    /// It sets whether line breaks between values are ignored.
+    #[must_use]
+    pub fn skip_newlines(mut self, skip_newlines: bool) -> Self {
+        self.options.skip_newlines = skip_newlines;
+        self
+    }
+
+    /// It sets how non UTF-8 strings are written to JSON.
+    #[must_use]
+    pub fn byte_string_encoding(mut self, byte_string_encoding: ByteStringEncoding) -> Self {
+        self.options.byte_string_encoding = byte_string_encoding;
+        self
+    }
+
+    /// It sets whether only canonical bencode is accepted.
+    #[must_use]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    /// It sets whether integers must fit into an `i64`.
+    #[must_use]
+    pub fn i64_integers(mut self, i64_integers: bool) -> Self {
+        self.options.i64_integers = i64_integers;
+        self
+    }
+
+    /// It sets how several top-level values are written to JSON.
+    #[must_use]
+    pub fn multiple_values(mut self, multiple_values: MultipleValues) -> Self {
+        self.options.multiple_values = multiple_values;
+        self
+    }
+
+    /// It sets whether the input is rendered as a torrent file.
+    #[must_use]
+    pub fn torrent(mut self, torrent: bool) -> Self {
+        self.options.torrent = torrent;
+        self
+    }
+
+    /// It sets whether the input is rendered as a tracker response.
+    #[must_use]
+    pub fn tracker_response(mut self, tracker_response: bool) -> Self {
+        self.options.tracker_response = tracker_response;
+        self
+    }
+
+    /// It adds a path of the values whose raw bytes are hashed while they
+    /// are parsed.
+    #[must_use]
+    pub fn capture(mut self, path: impl Into<KeyPath>) -> Self {
+        self.options.captured_paths.push(path.into());
+        self
+    }
+
+    /// It sets whether the raw bytes of the captured values are kept.
+    #[must_use]
+    pub fn capture_raw_bytes(mut self, capture_raw_bytes: bool) -> Self {
+        self.options.capture_raw_bytes = capture_raw_bytes;
+        self
+    }
+
+    /// It sets the number of spaces used to indent pretty-printed JSON. It's
+    /// ignored when the values are written one per line. See
+    /// [`ParserOptions::indent`].
+    #[must_use]
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.options.indent = Some(indent);
+        self
+    }
+
+    /// It sets whether dictionary fields are written sorted by their raw
+    /// keys. Unless strict mode is also set, each dictionary is kept in
+    /// memory until it ends, so the output is no longer streamed. See
+    /// [`ParserOptions::sort_keys`].
+    #[must_use]
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.options.sort_keys = sort_keys;
+        self
+    }
+
+    /// It adds a query selecting values written to the output.
+    ///
+    /// Only the selected values are written, one per line, so the
+    /// [`multiple_values`](Self::multiple_values) mode does not apply.
+    /// Strings outside them are skipped without keeping their bytes. It only
+    /// applies to the JSON output: the events and the value trees are not
+    /// filtered.
+    #[must_use]
+    pub fn select(mut self, query: Query) -> Self {
+        self.options.selected_paths.push(query);
+        self
+    }
+
+    /// It sets the size of the chunks long strings are written in. Strings
+    /// longer than this are not kept in memory.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the size is zero.
+    #[must_use]
+    pub fn string_buffer_size(mut self, string_buffer_size: usize) -> Self {
+        assert!(string_buffer_size > 0, "the string buffer can't be empty");
+        self.options.string_buffer_size = Some(string_buffer_size);
+        self
+    }
+
+    /// It sets how invalid UTF-8 is written when it appears in the middle of
+    /// a streamed string.
+    #[must_use]
+    pub fn streamed_invalid_utf8(mut self, streamed_invalid_utf8: StreamedInvalidUtf8) -> Self {
+        self.options.streamed_invalid_utf8 = streamed_invalid_utf8;
+        self
+    }
+
+    /// It sets what to do with the JSON written so far when the input is
+    /// invalid.
+    #[must_use]
+    pub fn error_recovery(mut self, error_recovery: ErrorRecovery) -> Self {
+        self.options.error_recovery = error_recovery;
+        self
+    }
+
+    /// It sets the maximum number of nested lists and dictionaries.
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = Some(max_depth);
+        self
+    }
+
+    /// It sets the maximum length of a single string.
+    #[must_use]
+    pub fn max_string_length(mut self, max_string_length: usize) -> Self {
+        self.options.max_string_length = Some(max_string_length);
+        self
+    }
+
+    /// It sets the maximum number of items in a list, or fields in a
+    /// dictionary.
+    #[must_use]
+    pub fn max_container_items(mut self, max_container_items: usize) -> Self {
+        self.options.max_container_items = Some(max_container_items);
+        self
+    }
+
+    /// It sets the maximum number of bytes read from the input.
+    #[must_use]
+    pub fn max_input_bytes(mut self, max_input_bytes: u64) -> Self {
+        self.options.max_input_bytes = Some(max_input_bytes);
+        self
+    }
+
+    /// It returns the options set so far.
+    #[must_use]
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// It builds a parser reading from the given input.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn build<R: Read>(self, reader: R) -> BencodeParser<R> {
+        BencodeParser::with_options(reader, self.options)
+    }
+
+    /// It builds a push parser, receiving the input in chunks. See
+    /// [`PushParser`](super::push::PushParser).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    #[must_use]
+    pub fn build_push(self) -> super::push::PushParser {
+        super::push::PushParser::with_options(self.options)
+    }
+
+    /// It builds a canonicalizer reading from the given input. See
+    /// [`Canonicalizer`](super::canonical::Canonicalizer).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn build_canonicalizer<R: Read>(self, reader: R) -> super::canonical::Canonicalizer<R> {
+        super::canonical::Canonicalizer::with_options(reader, self.options)
+    }
+
+    /// It builds an async parser reading from the given input.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    #[cfg(feature = "tokio")]
+    pub fn build_async<R: tokio::io::AsyncRead + Unpin>(
+        self,
+        reader: R,
+    ) -> super::asynchronous::AsyncBencodeParser<R> {
+        super::asynchronous::AsyncBencodeParser::with_options(reader, self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{
+        options::{
+            BencodeParserBuilder, ByteStringEncoding, ErrorRecovery, MultipleValues, ParserOptions,
+            StreamedInvalidUtf8,
+        },
+        path::KeyPath,
+    };
+
+    #[test]
+    fn the_default_options_should_keep_the_original_behavior() {
+        let options = ParserOptions::default();
+
+        assert_eq!(options.capture_size, 1024);
+        assert!(options.skip_newlines);
+        assert_eq!(options.byte_string_encoding, ByteStringEncoding::Hex);
+        assert!(!options.strict);
+        assert!(!options.i64_integers);
+        assert_eq!(options.multiple_values, MultipleValues::Concatenate);
+        assert!(!options.torrent);
+        assert!(!options.tracker_response);
+        assert!(options.captured_paths.is_empty());
+        assert!(!options.capture_raw_bytes);
+        assert_eq!(options.indent, None);
+        assert!(!options.sort_keys);
+        assert!(options.selected_paths.is_empty());
+        assert_eq!(options.string_buffer_size, None);
+        assert_eq!(options.streamed_invalid_utf8, StreamedInvalidUtf8::Error);
+        assert_eq!(options.error_recovery, ErrorRecovery::Fail);
+        assert_eq!(options.max_depth, None);
+        assert_eq!(options.max_string_length, None);
+        assert_eq!(options.max_container_items, None);
+        assert_eq!(options.max_input_bytes, None);
+    }
+
+    #[test]
+    fn the_builder_should_set_every_option() {
+        let builder = BencodeParserBuilder::default()
+            .capture_size(8)
+            .skip_newlines(false)
+            .byte_string_encoding(ByteStringEncoding::Tagged)
+            .strict(true)
+            .i64_integers(true)
+            .multiple_values(MultipleValues::Ndjson)
+            .torrent(true)
+            .tracker_response(true)
+            .capture("info")
+            .capture_raw_bytes(true)
+            .indent(4)
+            .sort_keys(true)
+            .select("info.name".parse().unwrap())
+            .string_buffer_size(5)
+            .streamed_invalid_utf8(StreamedInvalidUtf8::Error)
+            .error_recovery(ErrorRecovery::Resynchronize)
+            .max_depth(1)
+            .max_string_length(2)
+            .max_container_items(3)
+            .max_input_bytes(4);
+
+        assert_eq!(
+            builder.options(),
+            &ParserOptions {
+                capture_size: 8,
+                skip_newlines: false,
+                byte_string_encoding: ByteStringEncoding::Tagged,
+                strict: true,
+                i64_integers: true,
+                multiple_values: MultipleValues::Ndjson,
+                torrent: true,
+                tracker_response: true,
+                captured_paths: vec![KeyPath::from("info")],
+                capture_raw_bytes: true,
+                indent: Some(4),
+                sort_keys: true,
+                selected_paths: vec!["info.name".parse().unwrap()],
+                string_buffer_size: Some(5),
+                streamed_invalid_utf8: StreamedInvalidUtf8::Error,
+                error_recovery: ErrorRecovery::Resynchronize,
+                max_depth: Some(1),
+                max_string_length: Some(2),
+                max_container_items: Some(3),
+                max_input_bytes: Some(4),
+            }
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Paths to values inside the bencoded structure.
+//!
+//! - [`KeyPath`]: a path made only of dictionary keys, used to select values.
+//! - [`ValuePath`]: the position of a value, including list indexes, used to
+//!   report where an error happened.
+use std::fmt;
+
+/// A path to a value, made of the keys of the nested dictionaries containing
+/// it, starting from the top-level dictionary.
+///
+/// For example, the path `info.name` selects the value `"spam"` in
+/// `d4:infod4:name4:spamee`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct KeyPath {
+    keys: Vec<Vec<u8>>,
+}
+
+impl KeyPath {
+    #[must_use]
+    pub fn new(keys: Vec<Vec<u8>>) -> Self {
+        Self { keys }
+    }
+
+    /// It returns the keys, starting from the top-level dictionary.
+    #[must_use]
+    pub fn keys(&self) -> &[Vec<u8>] {
+        &self.keys
+    }
+
+    /// It returns `true` if the path of the keys of the open dictionaries is
+    /// this one. The path has `None` for lists.
+    pub(crate) fn matches(&self, path: &[Option<Vec<u8>>]) -> bool {
+        self.keys.len() == path.len()
+            && self
+                .keys
+                .iter()
+                .zip(path)
+                .all(|(key, path_key)| path_key.as_ref() == Some(key))
+    }
+}
+
+impl From<&str> for KeyPath {
+    /// It splits the keys by dots: `info.name`.
+    fn from(path: &str) -> Self {
+        Self::new(path.split('.').map(|key| key.as_bytes().to_vec()).collect())
+    }
+}
+
+impl fmt::Display for KeyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", String::from_utf8_lossy(key))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One step of a [`ValuePath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The value of a dictionary field.
+    Key(Vec<u8>),
+
+    /// The item of a list.
+    Index(usize),
+}
+
+/// The position of a value in the bencoded structure, for example
+/// `info.files[12].path[0]`. It's empty for the top-level value.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct ValuePath {
+    segments: Vec<PathSegment>,
+}
+
+impl ValuePath {
+    #[must_use]
+    pub fn new(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+
+    /// It returns the segments, starting from the top-level value.
+    #[must_use]
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// It returns `true` for the path of the top-level value.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+impl fmt::Display for ValuePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) => {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+                    write!(f, "{}", String::from_utf8_lossy(key))?;
+                }
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::path::{KeyPath, PathSegment, ValuePath};
+
+    #[test]
+    fn a_value_path_should_be_displayed_with_keys_separated_by_dots_and_indexes_in_brackets() {
+        let path = ValuePath::new(vec![
+            PathSegment::Key(b"info".to_vec()),
+            PathSegment::Key(b"files".to_vec()),
+            PathSegment::Index(12),
+            PathSegment::Key(b"path".to_vec()),
+            PathSegment::Index(0),
+        ]);
+
+        assert_eq!(path.to_string(), "info.files[12].path[0]");
+    }
+
+    #[test]
+    fn a_value_path_starting_with_a_list_index_should_be_displayed_without_a_leading_dot() {
+        let path = ValuePath::new(vec![PathSegment::Index(1), PathSegment::Key(b"a".to_vec())]);
+
+        assert_eq!(path.to_string(), "[1].a");
+    }
+
+    #[test]
+    fn the_value_path_of_the_top_level_value_should_be_empty() {
+        assert!(ValuePath::default().is_empty());
+        assert_eq!(ValuePath::default().to_string(), "");
+    }
+
+    #[test]
+    fn it_should_be_built_from_keys_separated_by_dots() {
+        assert_eq!(
+            KeyPath::from("info.piece length"),
+            KeyPath::new(vec![b"info".to_vec(), b"piece length".to_vec()])
+        );
+    }
+
+    #[test]
+    fn it_should_be_displayed_with_the_keys_separated_by_dots() {
+        assert_eq!(KeyPath::from("info.name").to_string(), "info.name");
+    }
+
+    #[test]
+    fn it_should_match_the_keys_of_the_open_dictionaries() {
+        let path = KeyPath::from("info.name");
+
+        assert!(path.matches(&[Some(b"info".to_vec()), Some(b"name".to_vec())]));
+        assert!(!path.matches(&[Some(b"info".to_vec())]));
+        assert!(!path.matches(&[Some(b"info".to_vec()), None]));
+        assert!(!path.matches(&[
+            Some(b"info".to_vec()),
+            Some(b"name".to_vec()),
+            Some(b"x".to_vec())
+        ]));
+    }
+}
@@ -0,0 +1,596 @@
+//! Push parser for input received in chunks.
+//!
+//! The [`BencodeParser`] pulls the bytes from a reader, so a short read in the
+//! middle of a token makes it fail with one of the `UnexpectedEndOfInput`
+//! errors. The [`PushParser`] receives the bytes with
+//! [`PushParser::feed`] instead. It writes the JSON for every complete token,
+//! and keeps the partial token and the parser state until more bytes arrive.
+//! The input only ends when [`PushParser::finish`] is called.
+//!
+//! ```rust
+//! use torrust_bencode2json::parsers::push::{PushParser, Status};
+//!
+//! let mut parser = PushParser::new();
+//!
+//! assert_eq!(parser.feed(b"d3:foold3:ba").unwrap(), Status::NeedMoreData);
+//! assert_eq!(parser.take_json(), br#"{"foo":[{"#);
+//!
+//! assert_eq!(parser.feed(b"ri42eeee").unwrap(), Status::NeedMoreData);
+//! parser.finish().unwrap();
+//!
+//! assert_eq!(parser.take_json(), br#""bar":42}]}"#);
+//! ```
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+
+use super::{
+    capture::CapturedValue,
+    error::Error,
+    integer,
+    options::{MultipleValues, ParserOptions},
+    recovery::{self, Diagnostic},
+    string, BencodeParser, Output, BENCODE_BEGIN_INTEGER,
+};
+use crate::rw::byte_writer::ByteWriter;
+
+/// What the parser needs after the bytes fed so far.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Status {
+    /// The bytes fed so far have been parsed, and more can follow.
+    NeedMoreData,
+
+    /// No more bytes are parsed, for example after the value when multiple
+    /// top-level values are rejected, or after an error when errors are
+    /// recovered closing the JSON. The next bytes are ignored.
+    Done,
+}
+
+pub struct PushParser {
+    /// The sync parser, reading the bytes fed so far.
+    parser: BencodeParser<Input>,
+
+    writer: ByteWriter<Vec<u8>>,
+
+    /// The JSON being written.
+    output: Output,
+
+    /// Bytes fed that the parser has not consumed yet.
+    lookahead: Vec<u8>,
+
+    /// The input position of the first lookahead byte.
+    lookahead_pos: u64,
+
+    /// It finds where the next token ends in the lookahead bytes.
+    scanner: TokenScanner,
+
+    /// An error waiting for the bytes where parsing resumes, when errors are
+    /// recovered with [`ErrorRecovery::Resynchronize`].
+    unrecovered_error: Option<Error>,
+
+    is_done: bool,
+
+    /// It failed with an error that was not recovered.
+    has_failed: bool,
+}
+
+/// The bytes fed to the push parser, waiting to be parsed.
+#[derive(Default)]
+struct Input {
+    bytes: VecDeque<u8>,
+
+    /// No more bytes will be fed.
+    is_finished: bool,
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.bytes.is_empty() && !self.is_finished {
+            // Tokens are only parsed once they are complete
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "the next token has not been fed yet",
+            ));
+        }
+
+        self.bytes.read(buf)
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushParser {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_options(ParserOptions::default())
+    }
+
+    /// It creates a parser with custom options. See also the
+    /// [`BencodeParserBuilder`](super::options::BencodeParserBuilder).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    #[must_use]
+    pub fn with_options(options: ParserOptions) -> Self {
+        let mut writer = ByteWriter::with_capture_size(Vec::new(), options.capture_size);
+        let parser = BencodeParser::with_options(Input::default(), options);
+
+        let output = parser
+            .begin_output(&mut writer)
+            .expect("writing to memory doesn't fail");
+
+        Self {
+            parser,
+            writer,
+            output,
+            lookahead: Vec::new(),
+            lookahead_pos: 0,
+            scanner: TokenScanner::default(),
+            unrecovered_error: None,
+            is_done: false,
+            has_failed: false,
+        }
+    }
+
+    /// It parses the next bytes of the input. The JSON written for the
+    /// complete tokens is available with [`PushParser::take_json`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the input is invalid Bencode. A partial token
+    /// at the end of the bytes is not an error: it waits for the next bytes.
+    /// After an error, the parser doesn't parse or write anything else.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Status, Error> {
+        if self.is_done {
+            return Ok(Status::Done);
+        }
+
+        self.parser.byte_reader.get_mut().bytes.extend(bytes);
+        self.lookahead.extend_from_slice(bytes);
+
+        self.parse_fed_bytes()
+    }
+
+    /// It ends the input, parsing the bytes left and finishing the JSON.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the input is invalid Bencode, for example if
+    /// it ends in the middle of a value.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        let input = self.parser.byte_reader.get_mut();
+
+        if input.is_finished || self.has_failed {
+            return Ok(());
+        }
+
+        input.is_finished = true;
+
+        self.parse_fed_bytes()?;
+
+        self.output.finish(&mut self.writer)
+    }
+
+    /// It returns the JSON written since the last call.
+    pub fn take_json(&mut self) -> Vec<u8> {
+        // Writing to memory doesn't fail
+        self.writer
+            .flushed_output()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// It returns the values selected by the captured paths. See
+    /// [`BencodeParser::captured_values`].
+    #[must_use]
+    pub fn captured_values(&self) -> &[CapturedValue] {
+        self.parser.captured_values()
+    }
+
+    /// It returns the errors recovered while writing the JSON. See
+    /// [`BencodeParser::diagnostics`].
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.parser.diagnostics()
+    }
+
+    /// It parses the tokens that have been fed completely.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the input is invalid Bencode.
+    fn parse_fed_bytes(&mut self) -> Result<Status, Error> {
+        while !self.is_done {
+            self.discard_consumed_bytes();
+
+            let is_finished = self.parser.byte_reader.get_mut().is_finished;
+
+            if let Some(err) = self.unrecovered_error.take() {
+                if !is_finished && !self.is_next_value_begin_fed() {
+                    self.unrecovered_error = Some(err);
+                    return Ok(Status::NeedMoreData);
+                }
+
+                self.recover(err)?;
+                continue;
+            }
+
+            if !is_finished && !self.is_next_token_fed() {
+                return Ok(Status::NeedMoreData);
+            }
+
+            self.scanner = TokenScanner::default();
+
+            match self
+                .parser
+                .write_next_output_token(&mut self.output, &mut self.writer)
+            {
+                Ok(has_more_tokens) => self.is_done = !has_more_tokens,
+                Err(err) if self.parser.resynchronizes() && err.read_context().is_some() => {
+                    // Parsing resumes at the next value, maybe not fed yet
+                    self.unrecovered_error = Some(err);
+                }
+                Err(err) => self.recover(err)?,
+            }
+        }
+
+        Ok(Status::Done)
+    }
+
+    /// It recovers from the error when the options allow it.
+    ///
+    /// # Errors
+    ///
+    /// Will return the error if it can't be recovered.
+    fn recover(&mut self, err: Error) -> Result<(), Error> {
+        self.scanner = TokenScanner::default();
+
+        match self
+            .parser
+            .recover_output(err, &mut self.output, &mut self.writer)
+        {
+            Ok(has_more_tokens) => {
+                self.is_done = !has_more_tokens;
+                Ok(())
+            }
+            Err(err) => {
+                self.is_done = true;
+                self.has_failed = true;
+                Err(err)
+            }
+        }
+    }
+
+    fn discard_consumed_bytes(&mut self) {
+        let consumed_byte_counter = self.parser.byte_reader.consumed_byte_counter();
+        let num_consumed = usize::try_from(consumed_byte_counter - self.lookahead_pos)
+            .expect("consumed bytes are in the lookahead");
+
+        self.lookahead.drain(..num_consumed);
+        self.lookahead_pos = consumed_byte_counter;
+    }
+
+    /// It returns `true` if the bytes read by the parser for the next token
+    /// have been fed.
+    fn is_next_token_fed(&mut self) -> bool {
+        if self.is_input_limit_fed() {
+            return true;
+        }
+
+        let Some(token_len) = self.scanner.scan(&self.lookahead, &self.parser.options) else {
+            return false;
+        };
+
+        let options = &self.parser.options;
+
+        // The next byte is peeked after a top-level value, looking for
+        // trailing data. Line breaks are only skipped in lenient mode.
+        options.multiple_values != MultipleValues::Reject
+            || self.lookahead[token_len..]
+                .iter()
+                .any(|byte| *byte != b'\n' || options.strict || !options.skip_newlines)
+    }
+
+    /// It returns `true` if the bytes skipped when resuming after an error
+    /// have been fed.
+    fn is_next_value_begin_fed(&self) -> bool {
+        // The first byte can be skipped to avoid resuming at the same
+        // position twice
+        self.is_input_limit_fed()
+            || self
+                .lookahead
+                .iter()
+                .skip(1)
+                .any(|byte| recovery::is_value_begin(*byte))
+    }
+
+    /// It returns `true` if the bytes fed exceed the input limit, so the
+    /// parser fails before needing more.
+    fn is_input_limit_fed(&self) -> bool {
+        self.parser
+            .options
+            .max_input_bytes
+            .is_some_and(|max_input_bytes| {
+                self.lookahead_pos + self.lookahead.len() as u64 > max_input_bytes
+            })
+    }
+}
+
+/// It finds where the next token ends in the bytes fed so far, using the
+/// integer and string length state machines. The state is kept between
+/// calls, so the bytes are only scanned once.
+#[derive(Debug, Default)]
+struct TokenScanner {
+    /// Number of bytes already scanned.
+    scanned: usize,
+
+    state: ScannerState,
+}
+
+#[derive(Debug, Default)]
+enum ScannerState {
+    /// Before the first byte of the token. Line breaks are skipped.
+    #[default]
+    Begin,
+
+    Integer(integer::StateMachine),
+
+    StringLength(string::Length),
+
+    /// The string value, ending at the given position.
+    StringValue(usize),
+
+    /// The token ends at the given position.
+    End(usize),
+}
+
+impl TokenScanner {
+    /// It returns the number of bytes the parser reads for the next token,
+    /// including the line breaks before it, or `None` if the token is not
+    /// complete.
+    fn scan(&mut self, bytes: &[u8], options: &ParserOptions) -> Option<usize> {
+        loop {
+            let pos = self.scanned;
+
+            self.state = match &mut self.state {
+                ScannerState::End(len) => return Some(*len),
+                ScannerState::StringValue(end) => {
+                    if bytes.len() < *end {
+                        return None;
+                    }
+                    ScannerState::End(*end)
+                }
+                ScannerState::Begin => match *bytes.get(pos)? {
+                    b'\n' => {
+                        self.scanned += 1;
+                        continue;
+                    }
+                    BENCODE_BEGIN_INTEGER => {
+                        ScannerState::Integer(integer::StateMachine::default())
+                    }
+                    b'0'..=b'9' => ScannerState::StringLength(string::Length::default()),
+                    _ => ScannerState::End(pos + 1),
+                },
+                ScannerState::Integer(state_machine) => {
+                    let byte = *bytes.get(pos)?;
+                    self.scanned += 1;
+
+                    match state_machine.feed(byte) {
+                        Ok(integer::Step::End) | Err(_) => ScannerState::End(pos + 1),
+                        Ok(_) => continue,
+                    }
+                }
+                ScannerState::StringLength(length) => {
+                    let byte = *bytes.get(pos)?;
+                    self.scanned += 1;
+
+                    match length.feed(byte) {
+                        Ok(None) => continue,
+                        // The length is checked before reading the value
+                        Ok(Some(length))
+                            if options
+                                .max_string_length
+                                .is_some_and(|max_string_length| length > max_string_length) =>
+                        {
+                            ScannerState::End(pos + 1)
+                        }
+                        Ok(Some(length)) => {
+                            ScannerState::StringValue((pos + 1).saturating_add(length))
+                        }
+                        Err(_) => ScannerState::End(pos + 1),
+                    }
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{
+        error::Error,
+        options::ParserOptions,
+        push::{PushParser, Status},
+    };
+
+    /// It feeds the input in chunks of the given size. It returns the JSON
+    /// and the error message, if any.
+    fn push_json(
+        input: &[u8],
+        chunk_size: usize,
+        options: &ParserOptions,
+    ) -> (String, Option<String>) {
+        let mut parser = PushParser::with_options(options.clone());
+        let mut output = Vec::new();
+
+        let result = feed_chunks(&mut parser, input, chunk_size, &mut output);
+
+        output.extend(parser.take_json());
+
+        (
+            String::from_utf8(output).unwrap(),
+            result.err().map(|err| err.to_string()),
+        )
+    }
+
+    fn feed_chunks(
+        parser: &mut PushParser,
+        input: &[u8],
+        chunk_size: usize,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        for chunk in input.chunks(chunk_size) {
+            let status = parser.feed(chunk)?;
+
+            output.extend(parser.take_json());
+
+            if status == Status::Done {
+                break;
+            }
+        }
+
+        parser.finish()
+    }
+
+    mod it_should {
+        use crate::{
+            parsers::{
+                error::Error,
+                options::{BencodeParserBuilder, ErrorRecovery, MultipleValues, ParserOptions},
+                push::{tests::push_json, PushParser, Status},
+            },
+            test::{sync_json, PARSER_INPUTS},
+        };
+
+        #[test]
+        fn write_the_same_json_and_errors_as_the_sync_parser_for_any_chunk_size() {
+            let options = [
+                BencodeParserBuilder::default(),
+                BencodeParserBuilder::default().strict(true),
+                BencodeParserBuilder::default().torrent(true),
+                BencodeParserBuilder::default().multiple_values(MultipleValues::Reject),
+                BencodeParserBuilder::default()
+                    .multiple_values(MultipleValues::Reject)
+                    .strict(true),
+                BencodeParserBuilder::default()
+                    .multiple_values(MultipleValues::Reject)
+                    .skip_newlines(false),
+                BencodeParserBuilder::default().multiple_values(MultipleValues::Array),
+                BencodeParserBuilder::default().string_buffer_size(2),
+                BencodeParserBuilder::default().max_string_length(3),
+                BencodeParserBuilder::default().max_input_bytes(5),
+                BencodeParserBuilder::default().error_recovery(ErrorRecovery::Close),
+                BencodeParserBuilder::default().error_recovery(ErrorRecovery::Resynchronize),
+                BencodeParserBuilder::default()
+                    .error_recovery(ErrorRecovery::Resynchronize)
+                    .multiple_values(MultipleValues::Reject),
+                BencodeParserBuilder::default().select("foo".parse().unwrap()),
+            ];
+
+            for builder in options {
+                for input in PARSER_INPUTS {
+                    let expected = sync_json(input, builder.options());
+
+                    for chunk_size in 1..=input.len().max(1) {
+                        assert_eq!(
+                            push_json(input, chunk_size, builder.options()),
+                            expected,
+                            "input: {input:?}, chunk size: {chunk_size}"
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn write_the_json_of_the_complete_tokens_before_the_input_ends() {
+            let mut parser = PushParser::new();
+
+            assert_eq!(parser.feed(b"li42ei4").unwrap(), Status::NeedMoreData);
+            assert_eq!(parser.take_json(), b"[42");
+
+            assert_eq!(parser.feed(b"3e").unwrap(), Status::NeedMoreData);
+            assert_eq!(parser.take_json(), b",43");
+        }
+
+        #[test]
+        fn wait_for_more_data_instead_of_failing_in_the_middle_of_a_token() {
+            let mut parser = PushParser::new();
+
+            assert_eq!(parser.feed(b"d3:foo8:sp").unwrap(), Status::NeedMoreData);
+            assert_eq!(parser.feed(b"am").unwrap(), Status::NeedMoreData);
+            assert_eq!(parser.feed(b" egge").unwrap(), Status::NeedMoreData);
+
+            parser.finish().unwrap();
+
+            assert_eq!(parser.take_json(), br#"{"foo":"spam egg"}"#);
+        }
+
+        #[test]
+        fn fail_when_the_input_finishes_in_the_middle_of_a_value() {
+            let mut parser = PushParser::new();
+
+            assert_eq!(parser.feed(b"i12").unwrap(), Status::NeedMoreData);
+
+            assert!(matches!(
+                parser.finish(),
+                Err(Error::UnexpectedEndOfInputParsingInteger(..))
+            ));
+        }
+
+        #[test]
+        fn be_done_after_the_only_value_allowed() {
+            let mut parser = BencodeParserBuilder::default()
+                .multiple_values(MultipleValues::Reject)
+                .build_push();
+
+            assert_eq!(parser.feed(b"i1e").unwrap(), Status::NeedMoreData);
+            assert!(parser.feed(b"i2e").is_err());
+            assert_eq!(parser.take_json(), b"1");
+        }
+
+        #[test]
+        fn keep_the_recovered_errors() {
+            let mut parser = BencodeParserBuilder::default()
+                .error_recovery(ErrorRecovery::Close)
+                .build_push();
+
+            assert_eq!(parser.feed(b"li1ex").unwrap(), Status::Done);
+            assert_eq!(parser.feed(b"i2e").unwrap(), Status::Done);
+
+            parser.finish().unwrap();
+
+            assert_eq!(parser.take_json(), b"[1,\"<corrupt>\"]\n");
+            assert_eq!(parser.diagnostics().len(), 1);
+        }
+
+        #[test]
+        fn not_parse_after_an_error() {
+            let mut parser = PushParser::new();
+
+            assert!(parser.feed(b"x").is_err());
+            assert_eq!(parser.feed(b"i1e").unwrap(), Status::Done);
+
+            parser.finish().unwrap();
+
+            assert!(parser.take_json().is_empty());
+        }
+
+        #[test]
+        fn parse_long_tokens_fed_byte_by_byte() {
+            let mut input = b"100000:".to_vec();
+            input.extend(vec![b'a'; 100_000]);
+
+            let (json, error) = push_json(&input, 1, &ParserOptions::default());
+
+            assert_eq!(json.len(), 100_002);
+            assert_eq!(error, None);
+        }
+    }
+}
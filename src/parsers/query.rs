@@ -0,0 +1,378 @@
+//! Queries selecting the values written to the output.
+//!
+//! A query is a simple path with:
+//!
+//! - Dictionary keys separated by dots: `info.name`.
+//! - List indexes in brackets: `announce-list[0]`.
+//! - Wildcards matching any key (`*`) or any index (`[*]`):
+//!   `info.files[*].length`.
+//! - Quoted keys in brackets, for keys that are empty or contain `.` or `[`,
+//!   or to match a literal `*` key: `info["a.b"]`. Quotes and backslashes in
+//!   quoted keys are escaped with a backslash: `["say \"hi\""]`.
+//!
+//! The empty query selects the top-level value.
+//!
+//! ```rust
+//! use torrust_bencode2json::parsers::options::BencodeParserBuilder;
+//!
+//! let mut output = String::new();
+//!
+//! BencodeParserBuilder::default()
+//!     .select("info.name".parse().unwrap())
+//!     .build(&b"d8:announce4:spam4:infod4:name3:eggee"[..])
+//!     .write_str(&mut output)
+//!     .unwrap();
+//!
+//! assert_eq!(output, "\"egg\"\n");
+//! ```
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use super::path::{PathSegment, ValuePath};
+
+const ANY: &str = "*";
+
+/// One step of a [`Query`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum QuerySegment {
+    /// The value of a dictionary field.
+    Key(Vec<u8>),
+
+    /// The value of any dictionary field.
+    AnyKey,
+
+    /// The item of a list.
+    Index(usize),
+
+    /// Any item of a list.
+    AnyIndex,
+}
+
+impl QuerySegment {
+    fn matches(&self, segment: &PathSegment) -> bool {
+        match (self, segment) {
+            (Self::Key(expected), PathSegment::Key(key)) => expected == key,
+            (Self::Index(expected), PathSegment::Index(index)) => expected == index,
+            (Self::AnyKey, PathSegment::Key(_)) | (Self::AnyIndex, PathSegment::Index(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A path selecting values, with optional wildcards.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Query {
+    segments: Vec<QuerySegment>,
+}
+
+impl Query {
+    #[must_use]
+    pub fn new(segments: Vec<QuerySegment>) -> Self {
+        Self { segments }
+    }
+
+    #[must_use]
+    pub fn segments(&self) -> &[QuerySegment] {
+        &self.segments
+    }
+
+    /// It returns `true` if the query selects the value in the path.
+    #[must_use]
+    pub fn matches(&self, path: &ValuePath) -> bool {
+        self.segments.len() == path.segments().len() && self.contains(path)
+    }
+
+    /// It returns `true` if the value in the path is selected by the query,
+    /// or it's inside a selected value.
+    #[must_use]
+    pub fn contains(&self, path: &ValuePath) -> bool {
+        self.segments.len() <= path.segments().len()
+            && self
+                .segments
+                .iter()
+                .zip(path.segments())
+                .all(|(query_segment, segment)| query_segment.matches(segment))
+    }
+}
+
+/// Error parsing a [`Query`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ParseQueryError {
+    #[error("Empty dictionary key in query `{0}`")]
+    EmptyKey(String),
+
+    #[error("Invalid list index in query `{0}`, expected `[<number>]` or `[*]`")]
+    InvalidIndex(String),
+
+    #[error(
+        "Invalid quoted key in query `{0}`, expected `[\"<key>\"]` with `\\\"` and `\\\\` escapes"
+    )]
+    InvalidQuotedKey(String),
+}
+
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut segments = vec![];
+
+        if query.is_empty() {
+            return Ok(Self::new(segments));
+        }
+
+        let mut rest = query;
+        let mut expecting_key = !query.starts_with('[');
+
+        loop {
+            if expecting_key {
+                let (key, tail) = rest.split_at(rest.find(['.', '[']).unwrap_or(rest.len()));
+
+                segments.push(match key {
+                    "" => return Err(ParseQueryError::EmptyKey(query.to_string())),
+                    ANY => QuerySegment::AnyKey,
+                    _ => QuerySegment::Key(key.as_bytes().to_vec()),
+                });
+
+                rest = tail;
+            }
+
+            if let Some(tail) = rest.strip_prefix('[') {
+                let (segment, tail) = parse_bracket(tail, query)?;
+
+                segments.push(segment);
+                rest = tail;
+                expecting_key = false;
+            } else if let Some(tail) = rest.strip_prefix('.') {
+                rest = tail;
+                expecting_key = true;
+            } else if rest.is_empty() {
+                return Ok(Self::new(segments));
+            } else {
+                return Err(ParseQueryError::InvalidIndex(query.to_string()));
+            }
+        }
+    }
+}
+
+/// It parses a list index or a quoted key, after the opening bracket. It
+/// returns the segment and the rest of the query after the closing bracket.
+fn parse_bracket<'a>(
+    brackets: &'a str,
+    query: &str,
+) -> Result<(QuerySegment, &'a str), ParseQueryError> {
+    if let Some(quoted) = brackets.strip_prefix('"') {
+        return parse_quoted_key(quoted)
+            .ok_or_else(|| ParseQueryError::InvalidQuotedKey(query.to_string()));
+    }
+
+    let Some((index, rest)) = brackets.split_once(']') else {
+        return Err(ParseQueryError::InvalidIndex(query.to_string()));
+    };
+
+    let segment = match index {
+        ANY => QuerySegment::AnyIndex,
+        _ => QuerySegment::Index(
+            index
+                .parse()
+                .map_err(|_| ParseQueryError::InvalidIndex(query.to_string()))?,
+        ),
+    };
+
+    Ok((segment, rest))
+}
+
+/// It parses a quoted key, after the opening quote, up to the closing
+/// bracket. It returns `None` if the key is not closed or it has an invalid
+/// escape.
+fn parse_quoted_key(quoted: &str) -> Option<(QuerySegment, &str)> {
+    let mut key = String::new();
+    let mut chars = quoted.char_indices();
+
+    loop {
+        match chars.next()? {
+            (i, '"') => {
+                let rest = quoted[i + 1..].strip_prefix(']')?;
+
+                return Some((QuerySegment::Key(key.into_bytes()), rest));
+            }
+            (_, '\\') => match chars.next()? {
+                (_, escaped @ ('"' | '\\')) => key.push(escaped),
+                _ => return None,
+            },
+            (_, c) => key.push(c),
+        }
+    }
+}
+
+/// It returns `true` if the key can only be written as a quoted key.
+fn needs_quotes(key: &[u8]) -> bool {
+    key.is_empty() || key == ANY.as_bytes() || key.iter().any(|byte| matches!(byte, b'.' | b'['))
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                QuerySegment::Key(key) if needs_quotes(key) => {}
+                QuerySegment::Key(_) | QuerySegment::AnyKey if i > 0 => write!(f, ".")?,
+                _ => {}
+            }
+
+            match segment {
+                QuerySegment::Key(key) if needs_quotes(key) => write!(
+                    f,
+                    "[\"{}\"]",
+                    String::from_utf8_lossy(key)
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"")
+                )?,
+                QuerySegment::Key(key) => write!(f, "{}", String::from_utf8_lossy(key))?,
+                QuerySegment::AnyKey => write!(f, "{ANY}")?,
+                QuerySegment::Index(index) => write!(f, "[{index}]")?,
+                QuerySegment::AnyIndex => write!(f, "[{ANY}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{
+        path::{PathSegment, ValuePath},
+        query::{ParseQueryError, Query, QuerySegment},
+    };
+
+    fn key(key: &str) -> QuerySegment {
+        QuerySegment::Key(key.as_bytes().to_vec())
+    }
+
+    fn path(segments: &[&str]) -> ValuePath {
+        ValuePath::new(
+            segments
+                .iter()
+                .map(|segment| match segment.parse() {
+                    Ok(index) => PathSegment::Index(index),
+                    Err(_) => PathSegment::Key(segment.as_bytes().to_vec()),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn it_should_parse_dotted_keys_list_indexes_and_wildcards() {
+        assert_eq!(
+            "info.files[*].path[0]".parse::<Query>().unwrap(),
+            Query::new(vec![
+                key("info"),
+                key("files"),
+                QuerySegment::AnyIndex,
+                key("path"),
+                QuerySegment::Index(0),
+            ])
+        );
+        assert_eq!(
+            "[1].*".parse::<Query>().unwrap(),
+            Query::new(vec![QuerySegment::Index(1), QuerySegment::AnyKey])
+        );
+    }
+
+    #[test]
+    fn the_empty_query_should_select_the_top_level_value() {
+        let query: Query = "".parse().unwrap();
+
+        assert!(query.segments().is_empty());
+        assert!(query.matches(&ValuePath::default()));
+    }
+
+    #[test]
+    fn it_should_be_displayed_as_it_is_parsed() {
+        for query in ["info.files[*].path[0]", "[1].*", "announce-list[0][1]"] {
+            assert_eq!(query.parse::<Query>().unwrap().to_string(), query);
+        }
+    }
+
+    #[test]
+    fn it_should_parse_quoted_keys() {
+        assert_eq!(
+            r#"info["a.b"]["c[0]"][0]"#.parse::<Query>().unwrap(),
+            Query::new(vec![
+                key("info"),
+                key("a.b"),
+                key("c[0]"),
+                QuerySegment::Index(0)
+            ])
+        );
+        assert_eq!(
+            r#"[""].["*"]"#.parse::<Query>(),
+            Err(ParseQueryError::EmptyKey(r#"[""].["*"]"#.to_string()))
+        );
+        assert_eq!(
+            r#"[""]["*"]["say \"hi\" \\o/"]"#.parse::<Query>().unwrap(),
+            Query::new(vec![key(""), key("*"), key(r#"say "hi" \o/"#)])
+        );
+    }
+
+    #[test]
+    fn it_should_quote_the_keys_that_cannot_be_written_unquoted() {
+        for query in [
+            r#"info["a.b"]"#,
+            r#"[""]["*"].*"#,
+            r#"a["b[0]"]["\"hi.\""].c"#,
+        ] {
+            assert_eq!(query.parse::<Query>().unwrap().to_string(), query);
+        }
+    }
+
+    #[test]
+    fn it_should_fail_parsing_invalid_quoted_keys() {
+        for query in [r#"["a"#, r#"["a""#, r#"["a\b"]"#, r#"["a"b]"#] {
+            assert_eq!(
+                query.parse::<Query>(),
+                Err(ParseQueryError::InvalidQuotedKey(query.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_fail_parsing_empty_keys() {
+        assert_eq!(
+            "info..name".parse::<Query>(),
+            Err(ParseQueryError::EmptyKey("info..name".to_string()))
+        );
+        assert!("info.".parse::<Query>().is_err());
+        assert!("a.[0]".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn it_should_fail_parsing_invalid_list_indexes() {
+        for query in ["a[x]", "a[0", "a[0]b", "a[-1]", "a[]"] {
+            assert_eq!(
+                query.parse::<Query>(),
+                Err(ParseQueryError::InvalidIndex(query.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_match_the_paths_of_the_selected_values() {
+        let query: Query = "info.files[*].length".parse().unwrap();
+
+        assert!(query.matches(&path(&["info", "files", "3", "length"])));
+        assert!(!query.matches(&path(&["info", "files", "3", "path"])));
+        assert!(!query.matches(&path(&["info", "files", "3"])));
+        assert!(!query.matches(&path(&["info", "files", "length", "length"])));
+    }
+
+    #[test]
+    fn it_should_contain_the_values_inside_the_selected_values() {
+        let query: Query = "info".parse().unwrap();
+
+        assert!(query.contains(&path(&["info"])));
+        assert!(query.contains(&path(&["info", "files", "0"])));
+        assert!(!query.contains(&path(&["announce"])));
+        assert!(!query.contains(&ValuePath::default()));
+    }
+}
@@ -0,0 +1,55 @@
+//! Partial output for invalid input.
+//!
+//! By default the parser stops at the first error, leaving the JSON written
+//! so far unfinished. With an [`ErrorRecovery`](super::options::ErrorRecovery)
+//! mode, it writes a marker where the corruption starts, closes the open lists
+//! and dictionaries, and keeps the errors as [`Diagnostic`]s. Concatenated
+//! top-level values are written one per line, as NDJSON. For example, to
+//! inspect a truncated torrent file:
+//!
+//! ```rust
+//! use torrust_bencode2json::parsers::options::{BencodeParserBuilder, ErrorRecovery};
+//!
+//! let mut output = String::new();
+//!
+//! let mut parser = BencodeParserBuilder::default()
+//!     .error_recovery(ErrorRecovery::Close)
+//!     .build(&b"d8:announce4:spam4:infod4:name3:eg"[..]);
+//!
+//! parser.write_str(&mut output).unwrap();
+//!
+//! assert_eq!(output, "{\"announce\":\"spam\",\"info\":{\"name\":\"<corrupt>\"}}\n");
+//!
+//! let diagnostic = &parser.diagnostics()[0];
+//!
+//! assert_eq!(diagnostic.error.path().unwrap().to_string(), "info.name");
+//! ```
+use super::error::Error;
+
+/// JSON value written instead of the value being parsed when an error is
+/// recovered. When the error happens where a dictionary key is expected, it's
+/// written as the key of a `null` value, after the other fields of the
+/// dictionary, even when keys are sorted.
+///
+/// It's a reserved key when errors are recovered. It's not checked against
+/// the keys of the input, so a dictionary that already has a `<corrupt>` key
+/// is written with that key twice.
+pub const CORRUPTION_MARKER: &str = "<corrupt>";
+
+/// An error recovered while writing the JSON.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// The error, with the position and the path where the corruption
+    /// starts.
+    pub error: Error,
+
+    /// The input position where parsing resumed after skipping the corrupt
+    /// bytes. `None` if the rest of the input was ignored.
+    pub resumed_at: Option<u64>,
+}
+
+/// It returns `true` if the byte can begin a bencoded value, so parsing can
+/// resume from it.
+pub(crate) fn is_value_begin(byte: u8) -> bool {
+    matches!(byte, b'i' | b'l' | b'd' | b'0'..=b'9')
+}
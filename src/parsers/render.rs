@@ -0,0 +1,320 @@
+//! Human-friendly error messages.
+//!
+//! The [`Display`](std::fmt::Display) of parser errors includes the latest
+//! bytes read as a list of decimal numbers, which is hard to read. The
+//! [`ErrorRenderer`] shows them in an `xxd`-style hex and ASCII dump, with a
+//! caret under the byte that caused the error and the tokens the parser
+//! expected:
+//!
+//! ```rust
+//! use torrust_bencode2json::{parsers::render::ErrorRenderer, try_bencode_to_json};
+//!
+//! let error = try_bencode_to_json(b"li1ex").unwrap_err();
+//!
+//! assert_eq!(
+//!     ErrorRenderer::default().render(&error),
+//!     "\
+//! error[unrecognized_first_bencode_value_byte]: Unrecognized first byte for new bencoded value
+//!  --> input pos 5, path `[1]`
+//! 00000000: 6c69 3165 78                             li1ex
+//!                     ^^                                 ^
+//! expected one of: `i`, `l`, `d`, `0`-`9`, `e`
+//! "
+//! );
+//! ```
+use std::fmt::Write;
+
+use super::error::{Error, ReadContext};
+
+/// Number of input bytes in each line of the dump.
+const BYTES_PER_LINE: u64 = 16;
+
+/// Width of the offset column, including the separator.
+const OFFSET_WIDTH: usize = 10;
+
+/// Width of the hex column: eight groups of two bytes separated by spaces.
+const HEX_WIDTH: usize = 39;
+
+/// Spaces between the hex and the ASCII columns.
+const COLUMN_GAP: usize = 2;
+
+const BOLD_RED: &str = "\x1b[1;31m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// It renders parser errors for humans.
+#[derive(Debug, Clone)]
+pub struct ErrorRenderer {
+    color: bool,
+    context_lines: usize,
+}
+
+impl Default for ErrorRenderer {
+    fn default() -> Self {
+        Self {
+            color: false,
+            context_lines: 4,
+        }
+    }
+}
+
+impl ErrorRenderer {
+    /// It highlights the error and the offending byte with ANSI colors.
+    #[must_use]
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// It sets the maximum number of dump lines, ending with the line of the
+    /// offending byte.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the number of lines is zero.
+    #[must_use]
+    pub fn context_lines(mut self, context_lines: usize) -> Self {
+        assert!(context_lines > 0, "the dump needs at least one line");
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// It returns the message for the error, ending with a line break.
+    ///
+    /// Errors without a reader context, like I/O errors, only have the first
+    /// line.
+    #[must_use]
+    pub fn render(&self, error: &Error) -> String {
+        let mut output = format!(
+            "{}: {}\n",
+            self.paint(BOLD_RED, &format!("error[{}]", error.code())),
+            summary(error)
+        );
+
+        let Some(read_context) = error.read_context() else {
+            return output;
+        };
+
+        let _ = write!(output, " --> input pos {}", read_context.pos);
+
+        if !read_context.path.is_empty() {
+            let _ = write!(output, ", path `{}`", read_context.path);
+        }
+
+        output.push('\n');
+
+        self.write_dump(&mut output, read_context, caret_offset(error, read_context));
+
+        match error.expected_tokens() {
+            [] => {}
+            [token] => {
+                let _ = writeln!(output, "expected: {token}");
+            }
+            tokens => {
+                let _ = writeln!(output, "expected one of: {}", tokens.join(", "));
+            }
+        }
+
+        output
+    }
+
+    /// It writes the hex and ASCII dump of the latest bytes read, followed by
+    /// the caret line.
+    fn write_dump(&self, output: &mut String, read_context: &ReadContext, caret: u64) {
+        let end = read_context.pos;
+        let start = end.saturating_sub(read_context.latest_bytes.len() as u64);
+
+        let caret_line = caret - caret % BYTES_PER_LINE;
+        let first_line = (start - start % BYTES_PER_LINE)
+            .max(caret_line.saturating_sub((self.context_lines as u64 - 1) * BYTES_PER_LINE));
+
+        let byte_at = |offset: u64| {
+            (start..end)
+                .contains(&offset)
+                .then(|| read_context.latest_bytes[usize::try_from(offset - start).unwrap()])
+        };
+
+        let lines = (first_line / BYTES_PER_LINE..=caret_line / BYTES_PER_LINE)
+            .map(|line| line * BYTES_PER_LINE);
+
+        for line in lines {
+            let mut hex = String::new();
+            let mut ascii = String::new();
+
+            for offset in line..line + BYTES_PER_LINE {
+                if offset > line && (offset - line) % 2 == 0 {
+                    hex.push(' ');
+                }
+
+                let Some(byte) = byte_at(offset) else {
+                    hex.push_str("  ");
+                    ascii.push(' ');
+                    continue;
+                };
+
+                let char = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+
+                if offset == caret {
+                    hex.push_str(&self.paint(BOLD_RED, &format!("{byte:02x}")));
+                    ascii.push_str(&self.paint(BOLD_RED, &char.to_string()));
+                } else {
+                    let _ = write!(hex, "{byte:02x}");
+                    ascii.push(char);
+                }
+            }
+
+            let line = format!(
+                "{}: {hex}{}{ascii}",
+                self.paint(CYAN, &format!("{line:08x}")),
+                " ".repeat(COLUMN_GAP)
+            );
+
+            let _ = writeln!(output, "{}", line.trim_end());
+        }
+
+        let column = usize::try_from(caret - caret_line).unwrap();
+        let hex_column = OFFSET_WIDTH + column * 2 + column / 2;
+        let ascii_column = OFFSET_WIDTH + HEX_WIDTH + COLUMN_GAP + column;
+
+        let _ = writeln!(
+            output,
+            "{}{}{}{}",
+            " ".repeat(hex_column),
+            self.paint(BOLD_RED, "^^"),
+            " ".repeat(ascii_column - hex_column - 2),
+            self.paint(BOLD_RED, "^")
+        );
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// It returns the error message without the reader and writer contexts.
+fn summary(error: &Error) -> String {
+    let message = error.to_string();
+
+    match message.split_once("read context:") {
+        Some((summary, _)) => summary.trim_end_matches([' ', ',', ';', ':']).to_string(),
+        None => message,
+    }
+}
+
+/// It returns the input position of the offending byte. When the input ended
+/// too early, it's the position after the last byte.
+fn caret_offset(error: &Error, read_context: &ReadContext) -> u64 {
+    if error.is_unexpected_end_of_input() {
+        read_context.pos
+    } else {
+        read_context.pos.saturating_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod the_error_renderer {
+        use crate::{
+            parsers::{options::BencodeParserBuilder, render::ErrorRenderer},
+            try_bencode_to_json,
+        };
+
+        #[test]
+        fn should_point_at_the_offending_byte() {
+            let error = try_bencode_to_json(b"i12xe").unwrap_err();
+
+            assert_eq!(
+                ErrorRenderer::default().render(&error),
+                "\
+error[unexpected_byte_parsing_integer]: Unexpected byte parsing integer
+ --> input pos 4
+00000000: 6931 3278                                i12x
+                 ^^                                   ^
+expected one of: `0`-`9`, `e`
+"
+            );
+        }
+
+        #[test]
+        fn should_point_after_the_last_byte_when_the_input_ends_too_early() {
+            let error = try_bencode_to_json(b"d3:foo").unwrap_err();
+
+            assert_eq!(
+                ErrorRenderer::default().render(&error),
+                "\
+error[unexpected_end_of_input_expecting_dict_field_value]: Unexpected end of input parsing dictionary. Expecting dictionary field value
+ --> input pos 6, path `foo`
+00000000: 6433 3a66 6f6f                           d3:foo
+                         ^^                              ^
+expected one of: `i`, `l`, `d`, `0`-`9`
+"
+            );
+        }
+
+        #[test]
+        fn should_only_dump_the_latest_lines() {
+            let mut input = b"l".to_vec();
+            input.extend(b"i1e".repeat(20));
+            input.push(b'x');
+
+            let error = try_bencode_to_json(&input).unwrap_err();
+
+            assert_eq!(
+                ErrorRenderer::default().context_lines(2).render(&error),
+                "\
+error[unrecognized_first_bencode_value_byte]: Unrecognized first byte for new bencoded value
+ --> input pos 62, path `[20]`
+00000020: 3165 6931 6569 3165 6931 6569 3165 6931  1ei1ei1ei1ei1ei1
+00000030: 6569 3165 6931 6569 3165 6931 6578       ei1ei1ei1ei1ex
+                                          ^^                    ^
+expected one of: `i`, `l`, `d`, `0`-`9`, `e`
+"
+            );
+        }
+
+        #[test]
+        fn should_replace_non_printable_bytes_with_dots() {
+            let mut output = String::new();
+
+            let error = BencodeParserBuilder::default()
+                .build(&b"l2:\x00\xffx"[..])
+                .write_str(&mut output)
+                .unwrap_err();
+
+            let rendered = ErrorRenderer::default().render(&error);
+
+            assert!(
+                rendered.contains("00000000: 6c32 3a00 ff78                           l2:..x\n")
+            );
+        }
+
+        #[test]
+        fn should_highlight_the_offending_byte_with_colors() {
+            let error = try_bencode_to_json(b"x").unwrap_err();
+
+            let rendered = ErrorRenderer::default().color(true).render(&error);
+
+            assert!(rendered
+                .starts_with("\x1b[1;31merror[unrecognized_first_bencode_value_byte]\x1b[0m"));
+            assert!(rendered.contains("\x1b[36m00000000\x1b[0m: \x1b[1;31m78\x1b[0m"));
+        }
+
+        #[test]
+        fn should_only_render_the_message_for_errors_without_a_read_context() {
+            let error = std::io::Error::other("broken pipe").into();
+
+            assert_eq!(
+                ErrorRenderer::default().render(&error),
+                "error[io]: I/O error: broken pipe\n"
+            );
+        }
+    }
+}
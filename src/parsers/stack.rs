@@ -2,6 +2,11 @@
 //! current parsing state.
 use std::fmt::Display;
 
+use super::{
+    error::{Error, ReadContext, WriteContext},
+    BencodeType,
+};
+
 /// Stack with containing states for nested Bencoded values.
 ///
 /// The stat has an immutable initial state.
@@ -34,6 +39,93 @@ pub enum State {
     ExpectingDictFieldKeyOrEnd,   // F
 }
 
+/// Errors updating the stack when the new bencoded value, or the end of a list
+/// or dictionary, is not allowed in the current state.
+#[derive(Debug, PartialEq)]
+pub enum TransitionError {
+    /// Dictionary field keys must be strings.
+    ExpectedStringForDictKey(BencodeType),
+
+    /// The dictionary ended without the value for the last field key.
+    PrematureEndOfDict,
+
+    /// There is no list or dictionary to end.
+    NoMatchingStartForListOrDictEnd,
+
+    /// The input ended in the given state, before finishing a list or
+    /// dictionary.
+    UnexpectedEndOfInput(State),
+}
+
+impl TransitionError {
+    /// It converts the transition error into a parser error with the given
+    /// contexts.
+    #[must_use]
+    pub fn into_error(self, read_context: ReadContext, write_context: WriteContext) -> Error {
+        match self {
+            TransitionError::ExpectedStringForDictKey(bencode_type) => {
+                Error::ExpectedStringForDictKeyGot(bencode_type, read_context, write_context)
+            }
+            TransitionError::PrematureEndOfDict => {
+                Error::PrematureEndOfDict(read_context, write_context)
+            }
+            TransitionError::NoMatchingStartForListOrDictEnd => {
+                Error::NoMatchingStartForListOrDictEnd(read_context, write_context)
+            }
+            TransitionError::UnexpectedEndOfInput(state) => match state {
+                State::Initial => unreachable!("the input can always end in the initial state"),
+                State::ExpectingFirstListItemOrEnd => {
+                    Error::UnexpectedEndOfInputExpectingFirstListItemOrEnd(
+                        read_context,
+                        write_context,
+                    )
+                }
+                State::ExpectingNextListItem => {
+                    Error::UnexpectedEndOfInputExpectingNextListItem(read_context, write_context)
+                }
+                State::ExpectingFirstDictFieldOrEnd => {
+                    Error::UnexpectedEndOfInputExpectingFirstDictFieldOrEnd(
+                        read_context,
+                        write_context,
+                    )
+                }
+                State::ExpectingDictFieldValue => {
+                    Error::UnexpectedEndOfInputExpectingDictFieldValue(read_context, write_context)
+                }
+                State::ExpectingDictFieldKeyOrEnd => {
+                    Error::UnexpectedEndOfInputExpectingDictFieldKeyOrEnd(
+                        read_context,
+                        write_context,
+                    )
+                }
+            },
+        }
+    }
+}
+
+/// Tokens that can begin a bencoded value.
+const VALUE_TOKENS: &[&str] = &["`i`", "`l`", "`d`", "`0`-`9`"];
+
+/// Tokens that can begin a list item or end the list.
+const LIST_ITEM_TOKENS: &[&str] = &["`i`", "`l`", "`d`", "`0`-`9`", "`e`"];
+
+/// Tokens that can begin a dictionary key or end the dictionary.
+const DICT_KEY_TOKENS: &[&str] = &["`0`-`9`", "`e`"];
+
+impl State {
+    /// It returns the tokens accepted in this state, for error messages.
+    #[must_use]
+    pub fn expected_tokens(&self) -> &'static [&'static str] {
+        match self {
+            State::Initial | State::ExpectingDictFieldValue => VALUE_TOKENS,
+            State::ExpectingFirstListItemOrEnd | State::ExpectingNextListItem => LIST_ITEM_TOKENS,
+            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd => {
+                DICT_KEY_TOKENS
+            }
+        }
+    }
+}
+
 impl Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let output = match self {
@@ -117,6 +209,85 @@ impl Stack {
         }
     }
 
+    /// It returns the number of open lists and dictionaries.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    /// It updates the top state when the first byte of a new bencoded value
+    /// (integer, string, list or dict) is received.
+    ///
+    /// It returns the state before the update. Callers can use it to know
+    /// whether the new value is a list item, a dictionary field key or a
+    /// dictionary field value.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if a dictionary field key is expected and the new
+    /// value is not a string.
+    pub fn begin_value(&mut self, bencode_type: BencodeType) -> Result<State, TransitionError> {
+        let previous_state = self.peek();
+
+        match previous_state {
+            State::Initial | State::ExpectingNextListItem => {}
+            State::ExpectingFirstListItemOrEnd => {
+                self.swap_top(State::ExpectingNextListItem);
+            }
+            State::ExpectingFirstDictFieldOrEnd | State::ExpectingDictFieldKeyOrEnd => {
+                if bencode_type != BencodeType::String {
+                    return Err(TransitionError::ExpectedStringForDictKey(bencode_type));
+                }
+
+                self.swap_top(State::ExpectingDictFieldValue);
+            }
+            State::ExpectingDictFieldValue => {
+                self.swap_top(State::ExpectingDictFieldKeyOrEnd);
+            }
+        }
+
+        Ok(previous_state)
+    }
+
+    /// It pops the top state when the end of a list or dictionary is received.
+    ///
+    /// It returns the popped state.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - The dictionary is expecting a field value.
+    /// - There is no list or dictionary to end.
+    pub fn end_list_or_dict(&mut self) -> Result<State, TransitionError> {
+        let previous_state = self.peek();
+
+        match previous_state {
+            State::ExpectingFirstListItemOrEnd
+            | State::ExpectingNextListItem
+            | State::ExpectingFirstDictFieldOrEnd
+            | State::ExpectingDictFieldKeyOrEnd => {
+                self.pop();
+            }
+            State::ExpectingDictFieldValue => return Err(TransitionError::PrematureEndOfDict),
+            State::Initial => return Err(TransitionError::NoMatchingStartForListOrDictEnd),
+        }
+
+        Ok(previous_state)
+    }
+
+    /// It checks the stack state when the input ends.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if there are unfinished lists or dictionaries.
+    pub fn end_of_input(&self) -> Result<(), TransitionError> {
+        match self.peek() {
+            State::Initial => Ok(()),
+            state => Err(TransitionError::UnexpectedEndOfInput(state)),
+        }
+    }
+
     /// Prevent from mutating the initial state.
     fn guard_immutable_initial_state(&self) {
         if let Some(top) = self.states.last() {
@@ -143,6 +314,22 @@ mod tests {
             assert_eq!(format!("{}", State::ExpectingDictFieldValue), "E");
             assert_eq!(format!("{}", State::ExpectingDictFieldKeyOrEnd), "F");
         }
+
+        #[test]
+        fn should_list_the_tokens_it_expects() {
+            assert_eq!(
+                State::Initial.expected_tokens(),
+                ["`i`", "`l`", "`d`", "`0`-`9`"]
+            );
+            assert_eq!(
+                State::ExpectingNextListItem.expected_tokens(),
+                ["`i`", "`l`", "`d`", "`0`-`9`", "`e`"]
+            );
+            assert_eq!(
+                State::ExpectingDictFieldKeyOrEnd.expected_tokens(),
+                ["`0`-`9`", "`e`"]
+            );
+        }
     }
 
     mod the_stack {
@@ -174,6 +361,17 @@ mod tests {
                 assert_eq!(stack.peek(), State::Initial);
             }
 
+            #[test]
+            fn return_the_number_of_open_lists_and_dictionaries() {
+                let mut stack = Stack::default();
+
+                assert_eq!(stack.depth(), 0);
+
+                stack.push(State::ExpectingFirstListItemOrEnd);
+
+                assert_eq!(stack.depth(), 1);
+            }
+
             #[test]
             fn allow_pushing_new_states() {
                 let mut stack = Stack::default();
@@ -215,6 +413,120 @@ mod tests {
                 Stack::default().swap_top(State::Initial);
             }
 
+            mod when_a_new_value_begins {
+                use crate::parsers::{
+                    stack::{Stack, State, TransitionError},
+                    BencodeType,
+                };
+
+                #[test]
+                fn return_the_previous_state() {
+                    let mut stack = Stack::default();
+
+                    stack.push(State::ExpectingFirstListItemOrEnd);
+
+                    assert_eq!(
+                        stack.begin_value(BencodeType::Integer),
+                        Ok(State::ExpectingFirstListItemOrEnd)
+                    );
+                }
+
+                #[test]
+                fn expect_the_next_list_item_after_the_first_one() {
+                    let mut stack = Stack::default();
+
+                    stack.push(State::ExpectingFirstListItemOrEnd);
+                    stack.begin_value(BencodeType::Integer).unwrap();
+
+                    assert_eq!(stack.peek(), State::ExpectingNextListItem);
+                }
+
+                #[test]
+                fn alternate_dictionary_field_keys_and_values() {
+                    let mut stack = Stack::default();
+
+                    stack.push(State::ExpectingFirstDictFieldOrEnd);
+
+                    stack.begin_value(BencodeType::String).unwrap();
+                    assert_eq!(stack.peek(), State::ExpectingDictFieldValue);
+
+                    stack.begin_value(BencodeType::Integer).unwrap();
+                    assert_eq!(stack.peek(), State::ExpectingDictFieldKeyOrEnd);
+
+                    stack.begin_value(BencodeType::String).unwrap();
+                    assert_eq!(stack.peek(), State::ExpectingDictFieldValue);
+                }
+
+                #[test]
+                fn not_allow_dictionary_field_keys_that_are_not_strings() {
+                    let mut stack = Stack::default();
+
+                    stack.push(State::ExpectingFirstDictFieldOrEnd);
+
+                    assert_eq!(
+                        stack.begin_value(BencodeType::List),
+                        Err(TransitionError::ExpectedStringForDictKey(BencodeType::List))
+                    );
+                }
+            }
+
+            mod when_a_list_or_dict_ends {
+                use crate::parsers::stack::{Stack, State, TransitionError};
+
+                #[test]
+                fn pop_and_return_the_top_state() {
+                    let mut stack = Stack::default();
+
+                    stack.push(State::ExpectingNextListItem);
+
+                    assert_eq!(stack.end_list_or_dict(), Ok(State::ExpectingNextListItem));
+                    assert_eq!(stack.peek(), State::Initial);
+                }
+
+                #[test]
+                fn not_allow_ending_a_dictionary_expecting_a_field_value() {
+                    let mut stack = Stack::default();
+
+                    stack.push(State::ExpectingDictFieldValue);
+
+                    assert_eq!(
+                        stack.end_list_or_dict(),
+                        Err(TransitionError::PrematureEndOfDict)
+                    );
+                }
+
+                #[test]
+                fn not_allow_ending_when_there_is_no_list_or_dict() {
+                    assert_eq!(
+                        Stack::default().end_list_or_dict(),
+                        Err(TransitionError::NoMatchingStartForListOrDictEnd)
+                    );
+                }
+            }
+
+            mod when_the_input_ends {
+                use crate::parsers::stack::{Stack, State, TransitionError};
+
+                #[test]
+                fn allow_ending_in_the_initial_state() {
+                    assert_eq!(Stack::default().end_of_input(), Ok(()));
+                }
+
+                #[test]
+                fn not_allow_ending_with_unfinished_lists_or_dictionaries() {
+                    let mut stack = Stack::default();
+
+                    stack.push(State::ExpectingNextListItem);
+
+                    assert_eq!(
+                        stack.end_of_input(),
+                        Err(TransitionError::UnexpectedEndOfInput(
+                            State::ExpectingNextListItem
+                        ))
+                    );
+                }
+            }
+
             mod be_displayed_with_single_letter_abbreviations_for_states {
 
                 use crate::parsers::stack::{Stack, State};
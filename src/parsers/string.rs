@@ -1,19 +1,33 @@
 //! Bencoded string parser.
 //!
 //! It reads bencoded bytes from the input and writes JSON bytes to the output.
-use std::io::{self, Read};
+use std::{
+    fmt::Write as _,
+    io::{self, Read},
+};
 
 use crate::rw::{byte_reader::ByteReader, writer::Writer};
 
-/* todo: Optimize UTF-8 conversion. Try to convert to string partially and stop
-    converting if we reach a point when input is not valid UTF-8 anymore. This
-    way we don't consume more memory and we can print the bytes directly to the
-    output from that point on.
-*/
-
 use core::str;
 
-use super::error::{Error, ReadContext, WriteContext};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use super::{
+    error::{Error, ReadContext, WriteContext},
+    json::Layout,
+    options::{ByteStringEncoding, ParserOptions},
+    path::ValuePath,
+};
+
+pub(crate) const HEX_PREFIX: &str = "<hex>";
+pub(crate) const HEX_SUFFIX: &str = "</hex>";
+
+pub(crate) const BASE64_PREFIX: &str = "<base64>";
+pub(crate) const BASE64_SUFFIX: &str = "</base64>";
+
+/// Key of the JSON objects used by the [`ByteStringEncoding::Tagged`]
+/// encoding.
+pub const TAGGED_BYTES_KEY: &str = "$bytes";
 
 /// It parses a string bencoded value.
 ///
@@ -25,9 +39,170 @@ use super::error::{Error, ReadContext, WriteContext};
 /// # Panics
 ///
 /// Will panic if we reach the end of the input without completing the string.
-pub fn parse<R: Read, W: Writer>(reader: &mut ByteReader<R>, writer: &mut W) -> Result<(), Error> {
+pub fn parse<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &mut W,
+    options: &ParserOptions,
+) -> Result<(), Error> {
     let mut string_parser = StringParser::default();
-    string_parser.parse(reader, writer)
+    string_parser.parse(reader, writer, options)
+}
+
+/// It parses a string bencoded value and returns its raw bytes, without
+/// writing anything to the output.
+///
+/// The writer is only used to build the error context.
+///
+/// # Errors
+///
+/// Will return an error if it can't read from the input, or the length has
+/// leading zeros in strict mode.
+pub fn parse_bytes<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &W,
+    options: &ParserOptions,
+) -> Result<Vec<u8>, Error> {
+    let value = read_value(reader, writer, options)?;
+
+    Ok(value.bytes)
+}
+
+/// It reads a bencoded string from input without keeping its value. It's used
+/// for strings that are not written to the output.
+///
+/// # Errors
+///
+/// Will return an error if it can't read from the input, or the length has
+/// leading zeros in strict mode.
+pub fn skip_bytes<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &W,
+    options: &ParserOptions,
+) -> Result<(), Error> {
+    let mut length = Length::default();
+
+    length.parse(reader, writer, options)?;
+
+    for _i in 1..=length.number {
+        Value::next_byte(reader, writer)?;
+    }
+
+    Ok(())
+}
+
+/// It parses the length of a bencoded string, including the `:` end byte. The
+/// value must be read next with [`read_bytes`] or [`read_chunk`].
+///
+/// # Errors
+///
+/// Will return an error if it can't read from the input, or the length has
+/// leading zeros in strict mode.
+pub fn parse_length<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &W,
+    options: &ParserOptions,
+) -> Result<usize, Error> {
+    let mut length = Length::default();
+
+    length.parse(reader, writer, options)?;
+
+    Ok(length.number)
+}
+
+/// It reads the value of a bencoded string whose length was already parsed.
+///
+/// # Errors
+///
+/// Will return an error if the input ends before the whole value.
+pub fn read_bytes<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &W,
+    length: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut value = Value::new(length);
+
+    value.parse(reader, writer)?;
+
+    Ok(value.bytes)
+}
+
+/// It reads the next part of the value of a bencoded string, filling the
+/// whole chunk.
+///
+/// # Errors
+///
+/// Will return an error if the input ends before the chunk is filled.
+pub fn read_chunk<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &W,
+    chunk: &mut [u8],
+) -> Result<(), Error> {
+    for byte in chunk {
+        *byte = Value::next_byte(reader, writer)?;
+    }
+
+    Ok(())
+}
+
+/// It writes the raw bytes of a bencoded string to the output as a JSON
+/// value.
+///
+/// If the bytes are not valid UTF-8 they are written with the
+/// [`ByteStringEncoding`] in the options. For example, with the default one,
+/// it writes the hexadecimal list of bytes in the format '<hex>fafb</hex>'.
+///
+/// # Errors
+///
+/// Will return an error if it can't write to the output.
+pub fn write_json<W: Writer>(
+    bytes: Vec<u8>,
+    writer: &mut W,
+    options: &ParserOptions,
+) -> Result<(), Error> {
+    let mut string_parser = StringParser::default();
+    string_parser.write(&Value::from(bytes), writer, options)
+}
+
+/// It returns the JSON for the raw bytes of a bencoded string value, like
+/// [`write_json`], writing the arrays and objects of the encoding with the
+/// layout.
+pub(crate) fn json_with_layout(bytes: Vec<u8>, options: &ParserOptions, layout: &Layout) -> String {
+    Value::from(bytes).json(options.byte_string_encoding, layout)
+}
+
+/// It writes the raw bytes of a bencoded dictionary key to the output as a
+/// JSON string.
+///
+/// Unlike values, keys are always written as JSON strings, even with the
+/// encodings writing non UTF-8 values as objects or arrays.
+///
+/// # Errors
+///
+/// Will return an error if it can't write to the output.
+pub fn write_json_key<W: Writer>(
+    bytes: Vec<u8>,
+    writer: &mut W,
+    options: &ParserOptions,
+) -> Result<(), Error> {
+    let mut string_parser = StringParser::default();
+    string_parser.write_key(&Value::from(bytes), writer, options)
+}
+
+/// It reads both parts of a bencoded string: the length and the value.
+fn read_value<R: Read, W: Writer>(
+    reader: &mut ByteReader<R>,
+    writer: &W,
+    options: &ParserOptions,
+) -> Result<Value, Error> {
+    let mut length = Length::default();
+
+    length.parse(reader, writer, options)?;
+
+    let mut value = Value::new(length.number);
+
+    value.parse(reader, writer)?;
+
+    Ok(value)
 }
 
 /// Strings bencode format have two parts: `length:value`.
@@ -37,8 +212,8 @@ pub fn parse<R: Read, W: Writer>(reader: &mut ByteReader<R>, writer: &mut W) ->
 #[derive(Default, Debug)]
 #[allow(clippy::module_name_repetitions)]
 struct StringParser {
-    /// The final parsed string.
-    parsed_value: String,
+    /// The JSON for the final parsed string.
+    json: String,
 }
 
 impl StringParser {
@@ -46,44 +221,77 @@ impl StringParser {
         &mut self,
         reader: &mut ByteReader<R>,
         writer: &mut W,
+        options: &ParserOptions,
     ) -> Result<(), Error> {
-        let mut length = Length::default();
+        let value = read_value(reader, writer, options)?;
 
-        length.parse(reader, writer)?;
+        self.write(&value, writer, options)
+    }
 
-        let mut value = Value::new(length.number);
+    fn write<W: Writer>(
+        &mut self,
+        value: &Value,
+        writer: &mut W,
+        options: &ParserOptions,
+    ) -> Result<(), Error> {
+        self.json = value.json(options.byte_string_encoding, &Layout::compact());
 
-        value.parse(reader, writer)?;
+        writer.write_str(&self.json)?;
 
-        self.parsed_value = value.utf8();
+        Ok(())
+    }
 
-        writer.write_str(&self.json())?;
+    fn write_key<W: Writer>(
+        &mut self,
+        value: &Value,
+        writer: &mut W,
+        options: &ParserOptions,
+    ) -> Result<(), Error> {
+        self.json = value.json_key(options.byte_string_encoding);
+
+        writer.write_str(&self.json)?;
 
         Ok(())
     }
+}
 
-    /// It returns the final parsed value as string.
-    ///
-    /// If the string contains non UTF-8 bytes it returns the hexadecimal list
-    /// of bytes in in the format '<hex>fa fb</hex>'  
-    fn parsed_value(&self) -> String {
-        self.parsed_value.clone()
-    }
+/// Bytes rejected while parsing the string length.
+#[derive(Debug, PartialEq)]
+pub enum InvalidByte {
+    /// The string length can only contain digits (0..9) before the `:`.
+    NonDigit,
+
+    /// The string length does not fit into a `usize`.
+    LengthOverflow,
+}
 
-    /// It serializes the parsed value into JSON.
+impl InvalidByte {
+    /// It converts the invalid byte into a parser error with the given
+    /// contexts.
     #[must_use]
-    fn json(&self) -> String {
-        serde_json::to_string(&self.parsed_value()).unwrap()
+    pub fn into_error(self, read_context: ReadContext, write_context: WriteContext) -> Error {
+        match self {
+            InvalidByte::NonDigit => Error::InvalidStringLengthByte(read_context, write_context),
+            InvalidByte::LengthOverflow => Error::StringLengthOverflow(read_context, write_context),
+        }
     }
 }
 
+/// The first part of a bencoded string: the length.
+///
+/// It receives the length bytes one by one, including the `:` end byte. It
+/// does not read or write anything, so it can be shared by parsers reading
+/// from different sources.
 #[derive(Default, Debug)]
-struct Length {
-    /// A list of parsed bytes. It's only for debugging.
-    bytes: Vec<u8>,
-
+pub struct Length {
     /// The parsed length at the current read digit.
     number: usize,
+
+    /// The number of digits received.
+    num_digits: usize,
+
+    /// The first digit is a zero.
+    first_digit_is_zero: bool,
 }
 
 impl Length {
@@ -93,16 +301,54 @@ impl Length {
         &mut self,
         reader: &mut ByteReader<R>,
         writer: &W,
+        options: &ParserOptions,
     ) -> Result<(), Error> {
         loop {
             let byte = Self::next_byte(reader, writer)?;
 
-            match byte {
-                Self::END_OF_STRING_LENGTH_BYTE => {
-                    break;
+            match self.feed(byte) {
+                Ok(Some(_length)) => break,
+                Ok(None) => {
+                    let error = if options.strict && self.has_leading_zeros() {
+                        Error::LeadingZerosInStringLengthNotAllowed
+                    } else if self.exceeds_max_string_length(options) {
+                        // Checked while parsing the length, before buffering
+                        // the string value.
+                        Error::MaxStringLengthExceeded
+                    } else {
+                        continue;
+                    };
+
+                    return Err(error(
+                        ReadContext {
+                            byte: Some(byte),
+                            pos: reader.input_byte_counter(),
+                            latest_bytes: reader.captured_bytes(),
+                            path: ValuePath::default(),
+                            state: None,
+                        },
+                        WriteContext {
+                            byte: Some(byte),
+                            pos: writer.output_byte_counter(),
+                            latest_bytes: writer.captured_bytes(),
+                        },
+                    ));
                 }
-                _ => {
-                    self.add_byte(byte, reader, writer)?;
+                Err(invalid_byte) => {
+                    return Err(invalid_byte.into_error(
+                        ReadContext {
+                            byte: Some(byte),
+                            pos: reader.input_byte_counter(),
+                            latest_bytes: reader.captured_bytes(),
+                            path: ValuePath::default(),
+                            state: None,
+                        },
+                        WriteContext {
+                            byte: Some(byte),
+                            pos: writer.output_byte_counter(),
+                            latest_bytes: writer.captured_bytes(),
+                        },
+                    ));
                 }
             }
         }
@@ -110,6 +356,50 @@ impl Length {
         Ok(())
     }
 
+    /// It adds the next string length byte.
+    ///
+    /// It returns the final length when it receives the `:` end byte.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the byte is not a digit (0..9) or the end byte,
+    /// or the length does not fit into a `usize`.
+    pub fn feed(&mut self, byte: u8) -> Result<Option<usize>, InvalidByte> {
+        if byte == Self::END_OF_STRING_LENGTH_BYTE {
+            return Ok(Some(self.number));
+        }
+
+        if !byte.is_ascii_digit() {
+            return Err(InvalidByte::NonDigit);
+        }
+
+        if self.num_digits == 0 {
+            self.first_digit_is_zero = byte == b'0';
+        }
+
+        self.num_digits += 1;
+
+        self.add_digit_to_length(Self::byte_to_digit(byte))?;
+
+        Ok(None)
+    }
+
+    /// It returns `true` if the length received so far is greater than the
+    /// maximum string length of the options.
+    #[must_use]
+    pub fn exceeds_max_string_length(&self, options: &ParserOptions) -> bool {
+        options
+            .max_string_length
+            .is_some_and(|max_string_length| self.number > max_string_length)
+    }
+
+    /// It returns `true` if the length received so far has leading zeros, for
+    /// example `03`. A single `0` is the canonical length of an empty string.
+    #[must_use]
+    pub fn has_leading_zeros(&self) -> bool {
+        self.first_digit_is_zero && self.num_digits > 1
+    }
+
     /// It reads the next byte from the input.
     ///
     /// # Errors
@@ -125,6 +415,8 @@ impl Length {
                             byte: None,
                             pos: reader.input_byte_counter(),
                             latest_bytes: reader.captured_bytes(),
+                            path: ValuePath::default(),
+                            state: None,
                         },
                         WriteContext {
                             byte: None,
@@ -138,47 +430,24 @@ impl Length {
         }
     }
 
-    /// It adds a new byte (digit) to the string length.
-    ///
-    /// # Errors
-    ///
-    /// Will return an error if the byte is not a digit (0..9).
-    fn add_byte<R: Read, W: Writer>(
-        &mut self,
-        byte: u8,
-        reader: &mut ByteReader<R>,
-        writer: &W,
-    ) -> Result<(), Error> {
-        if !byte.is_ascii_digit() {
-            return Err(Error::InvalidStringLengthByte(
-                ReadContext {
-                    byte: Some(byte),
-                    pos: reader.input_byte_counter(),
-                    latest_bytes: reader.captured_bytes(),
-                },
-                WriteContext {
-                    byte: Some(byte),
-                    pos: writer.output_byte_counter(),
-                    latest_bytes: writer.captured_bytes(),
-                },
-            ));
-        }
-
-        self.bytes.push(byte);
-
-        self.add_digit_to_length(Self::byte_to_digit(byte));
-
-        Ok(())
-    }
-
     /// It converts a byte containing an ASCII digit into a number `usize`.
     fn byte_to_digit(byte: u8) -> usize {
         (byte - b'0') as usize
     }
 
     /// It adds the new digit to the number.
-    fn add_digit_to_length(&mut self, digit: usize) {
-        self.number = (self.number * 10) + digit;
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the new number does not fit into a `usize`.
+    fn add_digit_to_length(&mut self, digit: usize) -> Result<(), InvalidByte> {
+        self.number = self
+            .number
+            .checked_mul(10)
+            .and_then(|number| number.checked_add(digit))
+            .ok_or(InvalidByte::LengthOverflow)?;
+
+        Ok(())
     }
 }
 
@@ -189,6 +458,16 @@ struct Value {
     bytes_counter: usize,
 }
 
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self {
+            length: bytes.len(),
+            bytes_counter: bytes.len(),
+            bytes,
+        }
+    }
+}
+
 impl Value {
     fn new(length: usize) -> Self {
         Self {
@@ -225,6 +504,8 @@ impl Value {
                             byte: None,
                             pos: reader.input_byte_counter(),
                             latest_bytes: reader.captured_bytes(),
+                            path: ValuePath::default(),
+                            state: None,
                         },
                         WriteContext {
                             byte: None,
@@ -243,28 +524,91 @@ impl Value {
         self.bytes_counter += 1;
     }
 
-    fn utf8(&self) -> String {
-        match str::from_utf8(&self.bytes) {
-            Ok(string) => {
-                // String only contains valid UTF-8 chars -> print it as it's
-                string.to_owned()
+    /// It returns the JSON for the string as a value.
+    fn json(&self, encoding: ByteStringEncoding, layout: &Layout) -> String {
+        if encoding == ByteStringEncoding::Escaped {
+            return json_string(&self.escaped());
+        }
+
+        if let Ok(string) = str::from_utf8(&self.bytes) {
+            // String only contains valid UTF-8 chars -> print it as it's
+            return json_string(string);
+        }
+
+        // String contains non valid UTF-8 chars -> print it encoded
+        match encoding {
+            ByteStringEncoding::Hex => json_string(&self.hex()),
+            ByteStringEncoding::Base64 => json_string(&self.base64()),
+            ByteStringEncoding::Lossy => json_string(&self.utf8_lossy()),
+            ByteStringEncoding::Escaped => json_string(&self.escaped()),
+            ByteStringEncoding::Tagged => {
+                layout.object([(TAGGED_BYTES_KEY, json_string(&BASE64.encode(&self.bytes)))])
             }
-            Err(_) => {
-                // String contains non valid UTF-8 chars -> print it as hex bytes
-                Self::bytes_to_hex(&self.bytes)
+            ByteStringEncoding::Array => layout.array(self.bytes.iter().map(u8::to_string)),
+        }
+    }
+
+    /// It returns the JSON for the string as a dictionary key.
+    fn json_key(&self, encoding: ByteStringEncoding) -> String {
+        match encoding {
+            ByteStringEncoding::Hex
+            | ByteStringEncoding::Base64
+            | ByteStringEncoding::Lossy
+            | ByteStringEncoding::Escaped => self.json(encoding, &Layout::compact()),
+            ByteStringEncoding::Tagged => {
+                let key = self.escaped();
+
+                if key.starts_with('$') {
+                    json_string(&format!("${key}"))
+                } else {
+                    json_string(&key)
+                }
             }
+            ByteStringEncoding::Array => json_string(&self.escaped()),
         }
     }
 
-    fn bytes_to_hex(data: &[u8]) -> String {
-        format!("<hex>{}</hex>", hex::encode(data))
+    fn utf8_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+
+    fn hex(&self) -> String {
+        format!("{HEX_PREFIX}{}{HEX_SUFFIX}", hex::encode(&self.bytes))
     }
+
+    fn base64(&self) -> String {
+        format!(
+            "{BASE64_PREFIX}{}{BASE64_SUFFIX}",
+            BASE64.encode(&self.bytes)
+        )
+    }
+
+    /// It doubles the backslashes and replaces each byte of the invalid UTF-8
+    /// sequences with `\xNN`.
+    fn escaped(&self) -> String {
+        let mut escaped = String::with_capacity(self.bytes.len());
+
+        for chunk in self.bytes.utf8_chunks() {
+            escaped.push_str(&chunk.valid().replace('\\', "\\\\"));
+
+            for byte in chunk.invalid() {
+                write!(escaped, "\\x{byte:02x}").expect("writing to a string never fails");
+            }
+        }
+
+        escaped
+    }
+}
+
+/// It serializes a Rust string into a JSON string.
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).expect("a string is valid JSON")
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        parsers::error::Error,
+        parsers::{error::Error, options::ParserOptions},
         rw::{byte_reader::ByteReader, string_writer::StringWriter},
     };
 
@@ -292,7 +636,7 @@ mod tests {
 
         let mut writer = StringWriter::new(output);
 
-        parse(&mut reader, &mut writer)
+        parse(&mut reader, &mut writer, &ParserOptions::default())
     }
 
     mod for_helpers {
@@ -366,6 +710,112 @@ mod tests {
         assert_eq!(bencode_to_json_unchecked(b"1:9"), r#""9""#.to_string());
     }
 
+    mod parsing_raw_bytes {
+        use crate::{
+            parsers::{options::ParserOptions, string::parse_bytes},
+            rw::{byte_reader::ByteReader, string_writer::StringWriter},
+        };
+
+        #[test]
+        fn it_should_return_the_string_bytes_without_writing_them() {
+            let mut reader = ByteReader::new(&b"2:\xFF\xFE"[..]);
+
+            let mut output = String::new();
+            let writer = StringWriter::new(&mut output);
+
+            assert_eq!(
+                parse_bytes(&mut reader, &writer, &ParserOptions::default()).unwrap(),
+                b"\xFF\xFE"
+            );
+
+            drop(writer);
+
+            assert_eq!(output, "");
+        }
+    }
+
+    mod skipping_raw_bytes {
+        use crate::{
+            parsers::{error::Error, options::ParserOptions, string::skip_bytes},
+            rw::{byte_reader::ByteReader, string_writer::StringWriter},
+        };
+
+        #[test]
+        fn it_should_consume_the_whole_string() {
+            let mut reader = ByteReader::new(&b"4:spami1e"[..]);
+
+            let mut output = String::new();
+            let writer = StringWriter::new(&mut output);
+
+            skip_bytes(&mut reader, &writer, &ParserOptions::default()).unwrap();
+
+            assert_eq!(reader.input_byte_counter(), 6);
+        }
+
+        #[test]
+        fn it_should_fail_when_the_string_is_shorter_than_its_length() {
+            let mut reader = ByteReader::new(&b"4:sp"[..]);
+
+            let mut output = String::new();
+            let writer = StringWriter::new(&mut output);
+
+            assert!(matches!(
+                skip_bytes(&mut reader, &writer, &ParserOptions::default()),
+                Err(Error::UnexpectedEndOfInputParsingStringValue(..))
+            ));
+        }
+    }
+
+    mod the_length {
+        use crate::parsers::string::{InvalidByte, Length};
+
+        #[test]
+        fn should_return_the_length_when_it_receives_the_end_byte() {
+            let mut length = Length::default();
+
+            assert_eq!(length.feed(b'1'), Ok(None));
+            assert_eq!(length.feed(b'2'), Ok(None));
+            assert_eq!(length.feed(b':'), Ok(Some(12)));
+        }
+
+        #[test]
+        fn should_reject_non_digit_bytes() {
+            assert_eq!(Length::default().feed(b'a'), Err(InvalidByte::NonDigit));
+        }
+
+        #[test]
+        fn should_reject_lengths_that_do_not_fit_into_a_usize() {
+            let mut length = Length::default();
+
+            for byte in usize::MAX.to_string().bytes() {
+                assert_eq!(length.feed(byte), Ok(None));
+            }
+
+            assert_eq!(length.feed(b'0'), Err(InvalidByte::LengthOverflow));
+        }
+
+        #[test]
+        fn should_detect_leading_zeros() {
+            let mut length = Length::default();
+
+            length.feed(b'0').unwrap();
+            assert!(!length.has_leading_zeros());
+
+            length.feed(b'3').unwrap();
+            assert!(length.has_leading_zeros());
+        }
+
+        #[test]
+        fn should_not_detect_leading_zeros_when_the_first_digit_is_not_zero() {
+            let mut length = Length::default();
+
+            length.feed(b'1').unwrap();
+            length.feed(b'0').unwrap();
+
+            assert!(!length.has_leading_zeros());
+        }
+    }
+
     mod should_escape_json {
         use crate::{test::bencode_to_json_unchecked, to_bencode};
 
@@ -424,6 +874,7 @@ mod tests {
         use crate::{
             parsers::{
                 error::Error,
+                options::ParserOptions,
                 string::{parse, tests::try_bencode_to_json},
             },
             rw::{byte_reader::ByteReader, string_writer::StringWriter},
@@ -453,6 +904,15 @@ mod tests {
             ));
         }
 
+        #[test]
+        fn the_string_length_does_not_fit_into_a_usize() {
+            let huge_length = format!("{}0:", usize::MAX);
+
+            let result = try_bencode_to_json(huge_length.as_bytes());
+
+            assert!(matches!(result, Err(Error::StringLengthOverflow { .. })));
+        }
+
         #[test]
         fn it_receives_a_non_digit_byte_in_the_string_length() {
             let incomplete_string_value = b"4a:1234";
@@ -517,7 +977,7 @@ mod tests {
             let mut output = String::new();
             let mut writer = StringWriter::new(&mut output);
 
-            let result = parse(&mut reader, &mut writer);
+            let result = parse(&mut reader, &mut writer, &ParserOptions::default());
 
             assert!(matches!(result, Err(Error::Io(_))));
         }
@@ -529,7 +989,7 @@ mod tests {
             let mut output = String::new();
             let mut writer = StringWriter::new(&mut output);
 
-            let result = parse(&mut reader, &mut writer);
+            let result = parse(&mut reader, &mut writer, &ParserOptions::default());
 
             assert!(matches!(result, Err(Error::Io(_))));
         }
@@ -0,0 +1,399 @@
+//! JSON encoder for strings written in chunks while they are read.
+//!
+//! Long strings are not kept in memory. Each chunk is converted to JSON as
+//! soon as it's read, so the encoder only keeps the few bytes that can't be
+//! converted yet: the beginning of an incomplete UTF-8 sequence, or the bytes
+//! left to complete a base64 block or a hash.
+//!
+//! The first chunk decides how the string is written: as text if it's valid
+//! UTF-8, or with the [`ByteStringEncoding`] otherwise. If invalid UTF-8
+//! appears after writing text, the [`StreamedInvalidUtf8`] rule applies.
+use core::str;
+use std::fmt::Write as _;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use super::{
+    json::Layout,
+    options::{ByteStringEncoding, ParserOptions, StreamedInvalidUtf8},
+    string::{BASE64_PREFIX, BASE64_SUFFIX, HEX_PREFIX, HEX_SUFFIX, TAGGED_BYTES_KEY},
+};
+
+/// Number of bytes encoded together in base64.
+const BASE64_BLOCK_LEN: usize = 3;
+
+/// How the chunks are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Valid UTF-8 text.
+    Text,
+
+    /// Text with invalid UTF-8 sequences replaced with U+FFFD.
+    Lossy,
+
+    /// Text with doubled backslashes and invalid bytes as `\xNN`.
+    Escaped,
+
+    /// Hexadecimal bytes, inside a JSON string.
+    Hex,
+
+    /// Base64 bytes, inside a JSON string.
+    Base64,
+
+    /// A JSON array with one number per byte.
+    Array,
+
+    /// A JSON array of hexadecimal hashes of the given length.
+    HashList(usize),
+}
+
+/// It converts the chunks of a string into JSON.
+#[derive(Debug)]
+pub(crate) struct StringStream {
+    encoding: ByteStringEncoding,
+    invalid_utf8: StreamedInvalidUtf8,
+    layout: Layout,
+    mode: Mode,
+    /// The beginning of the JSON value was already written.
+    is_started: bool,
+    /// Bytes received but not converted yet.
+    pending: Vec<u8>,
+    /// Number of items written in array modes.
+    num_items: usize,
+    /// The JSON written after the last chunk.
+    end: String,
+}
+
+impl StringStream {
+    pub fn new(options: &ParserOptions, layout: Layout) -> Self {
+        Self {
+            encoding: options.byte_string_encoding,
+            invalid_utf8: options.streamed_invalid_utf8,
+            layout,
+            mode: Mode::Text,
+            is_started: false,
+            pending: Vec::new(),
+            num_items: 0,
+            end: String::new(),
+        }
+    }
+
+    /// It creates a stream writing the string as a list of hexadecimal
+    /// hashes. The string length must be a multiple of the hash length.
+    pub fn hash_list(options: &ParserOptions, layout: Layout, hash_len: usize) -> Self {
+        let mut stream = Self::new(options, layout);
+        stream.mode = Mode::HashList(hash_len);
+        stream
+    }
+
+    /// It returns the JSON for the next chunk, including the beginning of the
+    /// JSON value for the first chunk.
+    ///
+    /// It returns `None` if the chunk has invalid UTF-8 that can't be written
+    /// after the text already written.
+    pub fn write(&mut self, chunk: &[u8]) -> Option<String> {
+        let mut json = self.begin(chunk);
+
+        self.write_chunk(chunk, &mut json)?;
+
+        Some(json)
+    }
+
+    /// It returns the JSON after the last chunk.
+    ///
+    /// It returns `None` if the string ends with an incomplete UTF-8 sequence
+    /// that can't be written after the text already written.
+    pub fn finish(&mut self) -> Option<String> {
+        let mut json = self.begin(&[]);
+
+        let pending = std::mem::take(&mut self.pending);
+
+        match self.mode {
+            Mode::Text | Mode::Lossy | Mode::Escaped if !pending.is_empty() => {
+                // The string ends in the middle of a UTF-8 sequence
+                self.write_invalid_utf8(&pending, &[], &mut json)?;
+            }
+            Mode::Base64 => json.push_str(&BASE64.encode(&pending)),
+            _ => {}
+        }
+
+        if matches!(self.mode, Mode::Array | Mode::HashList(_)) && self.num_items > 0 {
+            json.push_str(&self.layout.end_break);
+        }
+
+        json.push_str(&self.end);
+
+        Some(json)
+    }
+
+    /// It decides how the string is written and returns the beginning of the
+    /// JSON value. It returns nothing after the first chunk.
+    fn begin(&mut self, first_chunk: &[u8]) -> String {
+        if self.is_started {
+            return String::new();
+        }
+
+        self.is_started = true;
+
+        if let Mode::HashList(_) = self.mode {
+            self.end = "]".to_string();
+            return "[".to_string();
+        }
+
+        let is_text = match str::from_utf8(first_chunk) {
+            Ok(_) => true,
+            // The chunk can end in the middle of a UTF-8 sequence
+            Err(err) => err.error_len().is_none(),
+        };
+
+        let (mode, begin, end) = match self.encoding {
+            ByteStringEncoding::Escaped => (Mode::Escaped, "\"".to_string(), "\"".to_string()),
+            ByteStringEncoding::Lossy => (Mode::Lossy, "\"".to_string(), "\"".to_string()),
+            _ if is_text => (Mode::Text, "\"".to_string(), "\"".to_string()),
+            ByteStringEncoding::Hex => (
+                Mode::Hex,
+                format!("\"{HEX_PREFIX}"),
+                format!("{HEX_SUFFIX}\""),
+            ),
+            ByteStringEncoding::Base64 => (
+                Mode::Base64,
+                format!("\"{BASE64_PREFIX}"),
+                format!("{BASE64_SUFFIX}\""),
+            ),
+            ByteStringEncoding::Tagged => (
+                Mode::Base64,
+                format!(
+                    "{{{}\"{TAGGED_BYTES_KEY}\"{}\"",
+                    self.layout.item_break, self.layout.key_value_separator
+                ),
+                format!("\"{}}}", self.layout.end_break),
+            ),
+            ByteStringEncoding::Array => (Mode::Array, "[".to_string(), "]".to_string()),
+        };
+
+        self.mode = mode;
+        self.end = end;
+
+        begin
+    }
+
+    /// It writes a chunk in the current mode, which can change if invalid
+    /// UTF-8 is found after writing text.
+    fn write_chunk(&mut self, chunk: &[u8], json: &mut String) -> Option<()> {
+        match self.mode {
+            Mode::Text | Mode::Lossy | Mode::Escaped => {
+                let mut bytes = std::mem::take(&mut self.pending);
+                bytes.extend_from_slice(chunk);
+                self.write_text(&bytes, json)
+            }
+            Mode::Hex => {
+                json.push_str(&hex::encode(chunk));
+                Some(())
+            }
+            Mode::Base64 => {
+                self.pending.extend_from_slice(chunk);
+                let len = self.pending.len() - self.pending.len() % BASE64_BLOCK_LEN;
+                json.push_str(&BASE64.encode(&self.pending[..len]));
+                self.pending.drain(..len);
+                Some(())
+            }
+            Mode::Array => {
+                for byte in chunk {
+                    self.write_item_separator(json);
+                    write!(json, "{byte}").expect("writing to a string never fails");
+                }
+                Some(())
+            }
+            Mode::HashList(hash_len) => {
+                self.write_hashes(chunk, hash_len, json);
+                Some(())
+            }
+        }
+    }
+
+    /// It writes the valid UTF-8 text and handles the invalid sequences. An
+    /// incomplete sequence at the end is kept until the next chunk.
+    fn write_text(&mut self, bytes: &[u8], json: &mut String) -> Option<()> {
+        let mut rest = bytes;
+
+        loop {
+            let err = match str::from_utf8(rest) {
+                Ok(text) => {
+                    self.write_valid_text(text, json);
+                    return Some(());
+                }
+                Err(err) => err,
+            };
+
+            let (valid, after) = rest.split_at(err.valid_up_to());
+
+            self.write_valid_text(
+                str::from_utf8(valid).expect("the bytes up to the error are valid UTF-8"),
+                json,
+            );
+
+            let Some(error_len) = err.error_len() else {
+                self.pending = after.to_vec();
+                return Some(());
+            };
+
+            let (invalid, remaining) = after.split_at(error_len);
+
+            if !self.write_invalid_utf8(invalid, remaining, json)? {
+                return Some(());
+            }
+
+            rest = remaining;
+        }
+    }
+
+    /// It writes an invalid UTF-8 sequence. It returns `true` if the text
+    /// after it is still written as text, or `false` if the remaining bytes
+    /// were already written with the byte string encoding.
+    fn write_invalid_utf8(
+        &mut self,
+        invalid: &[u8],
+        remaining: &[u8],
+        json: &mut String,
+    ) -> Option<bool> {
+        match self.mode {
+            Mode::Lossy => json.push(char::REPLACEMENT_CHARACTER),
+            Mode::Escaped => {
+                for byte in invalid {
+                    write!(json, "\\\\x{byte:02x}").expect("writing to a string never fails");
+                }
+            }
+            _ => match (self.invalid_utf8, self.encoding) {
+                (StreamedInvalidUtf8::Lossy, _) => json.push(char::REPLACEMENT_CHARACTER),
+                (StreamedInvalidUtf8::SwitchEncoding, ByteStringEncoding::Hex) => {
+                    json.push_str(HEX_PREFIX);
+                    self.mode = Mode::Hex;
+                    self.end = format!("{HEX_SUFFIX}\"");
+                    self.write_chunk(&[invalid, remaining].concat(), json)?;
+                    return Some(false);
+                }
+                (StreamedInvalidUtf8::SwitchEncoding, ByteStringEncoding::Base64) => {
+                    json.push_str(BASE64_PREFIX);
+                    self.mode = Mode::Base64;
+                    self.end = format!("{BASE64_SUFFIX}\"");
+                    self.write_chunk(&[invalid, remaining].concat(), json)?;
+                    return Some(false);
+                }
+                _ => return None,
+            },
+        }
+
+        Some(true)
+    }
+
+    fn write_valid_text(&self, text: &str, json: &mut String) {
+        let escaped_text = if self.mode == Mode::Escaped {
+            json_chars(&text.replace('\\', "\\\\"))
+        } else {
+            json_chars(text)
+        };
+
+        json.push_str(&escaped_text);
+    }
+
+    fn write_hashes(&mut self, chunk: &[u8], hash_len: usize, json: &mut String) {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(chunk);
+
+        let len = bytes.len() - bytes.len() % hash_len;
+
+        for hash in bytes[..len].chunks(hash_len) {
+            self.write_item_separator(json);
+            write!(json, "\"{}\"", hex::encode(hash)).expect("writing to a string never fails");
+        }
+
+        self.pending = bytes.split_off(len);
+    }
+
+    fn write_item_separator(&mut self, json: &mut String) {
+        if self.num_items > 0 {
+            json.push(',');
+        }
+        self.num_items += 1;
+        json.push_str(&self.layout.item_break);
+    }
+}
+
+/// It escapes a text for a JSON string, without the quotes.
+fn json_chars(text: &str) -> String {
+    let json = serde_json::to_string(text).expect("a string is valid JSON");
+
+    json[1..json.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{
+        json::Layout,
+        options::{ByteStringEncoding, ParserOptions},
+        string_stream::StringStream,
+    };
+
+    fn compact() -> Layout {
+        Layout::compact()
+    }
+
+    fn options(encoding: ByteStringEncoding) -> ParserOptions {
+        ParserOptions {
+            byte_string_encoding: encoding,
+            ..ParserOptions::default()
+        }
+    }
+
+    fn write_chunks(mut stream: StringStream, chunks: &[&[u8]]) -> Vec<String> {
+        let mut json: Vec<String> = chunks
+            .iter()
+            .map(|chunk| stream.write(chunk).unwrap())
+            .collect();
+
+        json.push(stream.finish().unwrap());
+
+        json
+    }
+
+    #[test]
+    fn it_should_keep_incomplete_utf8_sequences_until_the_next_chunk() {
+        let stream = StringStream::new(&ParserOptions::default(), compact());
+
+        assert_eq!(
+            write_chunks(stream, &[b"a\xC3", b"\xB1b"]),
+            vec!["\"a", "ñb", "\""]
+        );
+    }
+
+    #[test]
+    fn it_should_keep_the_bytes_of_incomplete_base64_blocks_until_the_next_chunk() {
+        let stream = StringStream::new(&options(ByteStringEncoding::Base64), compact());
+
+        assert_eq!(
+            write_chunks(stream, &[b"\xFF\xFE", b"\xFD\xFC"]),
+            vec!["\"<base64>", "//79", "/A==</base64>\""]
+        );
+    }
+
+    #[test]
+    fn it_should_write_each_hash_when_all_its_bytes_are_received() {
+        let stream = StringStream::hash_list(&ParserOptions::default(), compact(), 2);
+
+        assert_eq!(
+            write_chunks(stream, &[b"\xAA", b"\xAA\xBB\xBB"]),
+            vec!["[", "\"aaaa\",\"bbbb\"", "]"]
+        );
+    }
+
+    #[test]
+    fn it_should_write_byte_arrays_with_the_layout() {
+        let stream =
+            StringStream::new(&options(ByteStringEncoding::Array), Layout::new(Some(2), 0));
+
+        assert_eq!(
+            write_chunks(stream, &[b"\xFF", b"\xFE"]).concat(),
+            "[\n  255,\n  254\n]"
+        );
+    }
+}
@@ -0,0 +1,660 @@
+//! Torrent metainfo rendering.
+//!
+//! In torrent mode, the binary fields of the metainfo layout defined in
+//! [BEP 3](https://www.bittorrent.org/beps/bep_0003.html) and
+//! [BEP 52](https://www.bittorrent.org/beps/bep_0052.html) are written as
+//! hexadecimal hashes instead of opaque byte strings:
+//!
+//! - `info.pieces`: a list of 20-byte SHA-1 hashes.
+//! - `pieces root` fields in `info.file tree`: a 32-byte SHA-256 hash.
+//! - `piece layers`: keys are 32-byte SHA-256 hashes and values are lists of
+//!   32-byte SHA-256 hashes.
+//!
+//! Fields whose length doesn't match the hash size are written as usual.
+//!
+//! The [`InfoHash`] of the raw `info` dictionary is added to the top-level
+//! dictionary as an `info_hash` field. It's not added if the dictionary
+//! already has that field, so the JSON keys are unique.
+//!
+//! [`TorrentInfo`] reads a summary of the metainfo instead: name, files,
+//! pieces and info hash.
+use std::io::Read;
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::{error::Error, json::Layout, options::ParserOptions, path::KeyPath, BencodeParser};
+use crate::value::Value;
+
+/// Length of a SHA-1 hash, used by v1 torrents.
+const SHA1_LEN: usize = 20;
+
+/// Length of a SHA-256 hash, used by v2 torrents.
+const SHA256_LEN: usize = 32;
+
+const INFO_KEY: &[u8] = b"info";
+const PIECES_KEY: &[u8] = b"pieces";
+const PIECES_ROOT_KEY: &[u8] = b"pieces root";
+const PIECE_LAYERS_KEY: &[u8] = b"piece layers";
+const FILE_TREE_KEY: &[u8] = b"file tree";
+
+/// Name of the field added to the top-level dictionary.
+pub const INFO_HASH_FIELD: &str = "info_hash";
+
+/// The hashes of the raw bytes of the `info` dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfoHash {
+    /// SHA-1 hash, the info hash of v1 torrents.
+    pub v1: [u8; SHA1_LEN],
+
+    /// SHA-256 hash, the info hash of v2 torrents.
+    pub v2: [u8; SHA256_LEN],
+}
+
+impl InfoHash {
+    /// It hashes the raw bencoded bytes of an `info` dictionary.
+    #[must_use]
+    pub fn from_info_bytes(info: &[u8]) -> Self {
+        Self {
+            v1: Sha1::digest(info).into(),
+            v2: Sha256::digest(info).into(),
+        }
+    }
+
+    /// It returns the JSON object with both hashes in hexadecimal.
+    pub(crate) fn json(&self, layout: &Layout) -> String {
+        layout.object([
+            ("v1", format!(r#""{}""#, hex::encode(self.v1))),
+            ("v2", format!(r#""{}""#, hex::encode(self.v2))),
+        ])
+    }
+}
+
+/// A summary of a torrent metainfo file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorrentInfo {
+    /// The suggested name of the file or directory. Invalid UTF-8 bytes are
+    /// replaced.
+    pub name: String,
+
+    /// The files, in the order of the metainfo. Single-file torrents have one
+    /// file named after the torrent.
+    pub files: Vec<TorrentFile>,
+
+    /// The total size of the files in bytes.
+    pub total_size: u64,
+
+    /// The number of bytes in each piece.
+    pub piece_length: u64,
+
+    /// The number of pieces. In v2 torrents, pieces don't span files.
+    pub piece_count: u64,
+
+    /// The hashes of the raw `info` dictionary.
+    pub info_hash: InfoHash,
+}
+
+/// A file in a torrent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorrentFile {
+    /// The path of the file, starting with the torrent name and separated by
+    /// `/`.
+    pub path: String,
+
+    /// The size of the file in bytes.
+    pub length: u64,
+}
+
+impl TorrentInfo {
+    /// It reads the metainfo from the input and returns its summary. The
+    /// metainfo can be v1, v2 or hybrid.
+    ///
+    /// The info hash is calculated from the raw bytes of the `info`
+    /// dictionary, so it's valid even if the input is not canonical bencode.
+    ///
+    /// The whole metainfo is kept in memory, so the nesting depth is limited
+    /// to [`DEFAULT_MAX_DEPTH`](crate::value::DEFAULT_MAX_DEPTH) unless the
+    /// options set another limit.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if:
+    ///
+    /// - It can't read from the input.
+    /// - The input is invalid Bencode, or there is more than one value.
+    /// - The input exceeds one of the limits.
+    /// - An integer does not fit into an `i64`.
+    /// - The metainfo lacks one of the fields of the summary.
+    /// - The total size of the files or the piece count overflows a `u64`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn read<R: Read>(reader: R, options: ParserOptions) -> Result<Self, Error> {
+        let mut parser = BencodeParser::with_options(
+            reader,
+            ParserOptions {
+                captured_paths: vec![KeyPath::new(vec![INFO_KEY.to_vec()])],
+                capture_raw_bytes: false,
+                ..options
+            },
+        );
+
+        let torrent = parser
+            .read_value()?
+            .ok_or_else(|| invalid("the input is empty"))?;
+
+        parser.expect_end_of_input()?;
+
+        let info = torrent
+            .get(INFO_KEY)
+            .filter(|info| info.as_dict().is_some())
+            .ok_or_else(|| invalid("missing `info` dictionary"))?;
+
+        let captured = parser
+            .captured_values()
+            .last()
+            .expect("the `info` dictionary is captured");
+
+        let name = String::from_utf8_lossy(bytes_field(info, "name")?).into_owned();
+
+        let piece_length = integer_field(info, "piece length")?;
+
+        let files = files(info, &name)?;
+
+        let total_size = checked_sum(files.iter().map(|file| file.length))
+            .ok_or_else(|| invalid("the total size of the files overflows"))?;
+
+        let piece_count = match info.get(PIECES_KEY).and_then(Value::as_bytes) {
+            Some(pieces) => (pieces.len() / SHA1_LEN) as u64,
+            None if piece_length == 0 => 0,
+            None => checked_sum(files.iter().map(|file| file.length.div_ceil(piece_length)))
+                .ok_or_else(|| invalid("the piece count overflows"))?,
+        };
+
+        Ok(Self {
+            name,
+            files,
+            total_size,
+            piece_length,
+            piece_count,
+            info_hash: InfoHash {
+                v1: captured.sha1,
+                v2: captured.sha256,
+            },
+        })
+    }
+}
+
+/// It returns the sum, or `None` if it overflows.
+fn checked_sum(mut values: impl Iterator<Item = u64>) -> Option<u64> {
+    values.try_fold(0u64, u64::checked_add)
+}
+
+/// It returns the files of the `info` dictionary. The v1 fields are preferred
+/// in hybrid torrents.
+fn files(info: &Value, name: &str) -> Result<Vec<TorrentFile>, Error> {
+    if info.get(b"length").is_some() {
+        return Ok(vec![TorrentFile {
+            path: name.to_string(),
+            length: integer_field(info, "length")?,
+        }]);
+    }
+
+    if let Some(files) = info.get(b"files") {
+        let files = files
+            .as_list()
+            .ok_or_else(|| invalid("`info.files` is not a list"))?;
+
+        return files
+            .iter()
+            .map(|file| {
+                let path = file
+                    .get(b"path")
+                    .and_then(Value::as_list)
+                    .ok_or_else(|| invalid("missing `path` in `info.files`"))?;
+
+                let mut segments = vec![name.to_string()];
+
+                for segment in path {
+                    let segment = segment.as_bytes().ok_or_else(|| {
+                        invalid("`path` in `info.files` is not a list of strings")
+                    })?;
+
+                    segments.push(String::from_utf8_lossy(segment).into_owned());
+                }
+
+                Ok(TorrentFile {
+                    path: segments.join("/"),
+                    length: integer_field(file, "length")?,
+                })
+            })
+            .collect();
+    }
+
+    let file_tree = info
+        .get(FILE_TREE_KEY)
+        .ok_or_else(|| invalid("missing `info.length`, `info.files` or `info.file tree`"))?;
+
+    let mut files = Vec::new();
+
+    file_tree_files(file_tree, &mut vec![name.to_string()], &mut files)?;
+
+    Ok(files)
+}
+
+/// It walks a v2 file tree. Files are dictionaries with an empty key whose
+/// value holds the file `length`.
+fn file_tree_files(
+    node: &Value,
+    path: &mut Vec<String>,
+    files: &mut Vec<TorrentFile>,
+) -> Result<(), Error> {
+    let entries = node
+        .as_dict()
+        .ok_or_else(|| invalid("`info.file tree` contains a value that is not a dictionary"))?;
+
+    for (key, entry) in entries {
+        if key.is_empty() {
+            files.push(TorrentFile {
+                path: path.join("/"),
+                length: integer_field(entry, "length")?,
+            });
+        } else {
+            path.push(String::from_utf8_lossy(key).into_owned());
+            file_tree_files(entry, path, files)?;
+            path.pop();
+        }
+    }
+
+    Ok(())
+}
+
+fn bytes_field<'a>(dict: &'a Value, key: &str) -> Result<&'a [u8], Error> {
+    dict.get(key.as_bytes())
+        .and_then(Value::as_bytes)
+        .ok_or_else(|| invalid(&format!("missing `{key}` string")))
+}
+
+fn integer_field(dict: &Value, key: &str) -> Result<u64, Error> {
+    dict.get(key.as_bytes())
+        .and_then(Value::as_integer)
+        .and_then(|integer| u64::try_from(integer).ok())
+        .ok_or_else(|| invalid(&format!("missing `{key}` non-negative integer")))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::Deserialize(format!("invalid torrent, {message}"))
+}
+
+/// It returns the JSON for a string value if it's a known binary field.
+///
+/// The path contains the keys of the enclosing dictionaries, the last one
+/// being the key of this value. Lists have no key.
+pub(crate) fn value_json(
+    path: &[Option<Vec<u8>>],
+    bytes: &[u8],
+    layout: &Layout,
+) -> Option<String> {
+    if let Some(hash_len) = hash_list_len(path) {
+        return hash_list_json(bytes, hash_len, layout);
+    }
+
+    if is_hash_path(path) {
+        return hash_json(bytes, SHA256_LEN);
+    }
+
+    None
+}
+
+/// It returns the length of the hashes if the path is the one of a field
+/// containing a list of concatenated hashes.
+pub(crate) fn hash_list_len(path: &[Option<Vec<u8>>]) -> Option<usize> {
+    match path {
+        [Some(info), Some(pieces)] if info == INFO_KEY && pieces == PIECES_KEY => Some(SHA1_LEN),
+        [Some(piece_layers), Some(_pieces_root)] if piece_layers == PIECE_LAYERS_KEY => {
+            Some(SHA256_LEN)
+        }
+        _ => None,
+    }
+}
+
+/// It returns `true` if the path is the one of a field containing a single
+/// hash.
+pub(crate) fn is_hash_path(path: &[Option<Vec<u8>>]) -> bool {
+    matches!(path, [Some(info), .., Some(pieces_root)] if info == INFO_KEY && pieces_root == PIECES_ROOT_KEY)
+}
+
+/// It returns the JSON for a dictionary key if it's a known binary field.
+///
+/// The path contains the keys of the enclosing dictionaries, the last one
+/// being the key of the dictionary containing this key.
+pub(crate) fn key_json(path: &[Option<Vec<u8>>], key: &[u8]) -> Option<String> {
+    match path {
+        [Some(piece_layers)] if piece_layers == PIECE_LAYERS_KEY => hash_json(key, SHA256_LEN),
+        _ => None,
+    }
+}
+
+/// It returns `true` if the path is the one of the `info` dictionary.
+pub(crate) fn is_info_path(path: &[Option<Vec<u8>>]) -> bool {
+    matches!(path, [Some(info)] if info == INFO_KEY)
+}
+
+fn hash_json(bytes: &[u8], hash_len: usize) -> Option<String> {
+    if bytes.len() != hash_len {
+        return None;
+    }
+
+    Some(format!(r#""{}""#, hex::encode(bytes)))
+}
+
+fn hash_list_json(bytes: &[u8], hash_len: usize, layout: &Layout) -> Option<String> {
+    if !bytes.len().is_multiple_of(hash_len) {
+        return None;
+    }
+
+    Some(
+        layout.array(
+            bytes
+                .chunks(hash_len)
+                .map(|hash| format!(r#""{}""#, hex::encode(hash))),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{
+        json::Layout,
+        torrent::{is_info_path, key_json, value_json, InfoHash},
+    };
+
+    /// It builds a path where `[]` stands for a list item.
+    fn path(keys: &[&str]) -> Vec<Option<Vec<u8>>> {
+        keys.iter()
+            .map(|key| (*key != "[]").then(|| key.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn it_should_split_the_pieces_into_sha1_hashes() {
+        let pieces = [[0xAA; 20], [0xBB; 20]].concat();
+
+        assert_eq!(
+            value_json(&path(&["info", "pieces"]), &pieces, &Layout::compact()),
+            Some(format!(r#"["{}","{}"]"#, "aa".repeat(20), "bb".repeat(20)))
+        );
+    }
+
+    #[test]
+    fn it_should_not_split_the_pieces_when_the_length_is_not_a_multiple_of_the_hash_size() {
+        assert_eq!(
+            value_json(&path(&["info", "pieces"]), &[0xAA; 21], &Layout::compact()),
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_only_split_the_pieces_of_the_info_dictionary() {
+        assert_eq!(
+            value_json(&path(&["pieces"]), &[0xAA; 20], &Layout::compact()),
+            None
+        );
+        assert_eq!(
+            value_json(
+                &path(&["info", "files", "[]", "pieces"]),
+                &[0xAA; 20],
+                &Layout::compact()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_write_the_pieces_roots_of_the_file_tree_as_sha256_hashes() {
+        assert_eq!(
+            value_json(
+                &path(&["info", "file tree", "dir", "file.txt", "", "pieces root"]),
+                &[0xCC; 32],
+                &Layout::compact()
+            ),
+            Some(format!(r#""{}""#, "cc".repeat(32)))
+        );
+    }
+
+    #[test]
+    fn it_should_write_the_piece_layers_as_sha256_hashes() {
+        let layer = [[0xDD; 32], [0xEE; 32]].concat();
+
+        assert_eq!(
+            key_json(&path(&["piece layers"]), &[0xCC; 32]),
+            Some(format!(r#""{}""#, "cc".repeat(32)))
+        );
+        assert_eq!(
+            value_json(&path(&["piece layers", "root"]), &layer, &Layout::compact()),
+            Some(format!(r#"["{}","{}"]"#, "dd".repeat(32), "ee".repeat(32)))
+        );
+    }
+
+    #[test]
+    fn it_should_not_change_other_fields() {
+        assert_eq!(
+            value_json(&path(&["info", "name"]), b"spam", &Layout::compact()),
+            None
+        );
+        assert_eq!(key_json(&path(&["info"]), &[0xCC; 32]), None);
+    }
+
+    #[test]
+    fn it_should_recognize_the_path_of_the_info_dictionary() {
+        assert!(is_info_path(&path(&["info"])));
+        assert!(!is_info_path(&path(&["[]", "info"])));
+        assert!(!is_info_path(&path(&["announce"])));
+    }
+
+    mod torrent_info {
+        use crate::{
+            encoder::{error::Error, BencodeWriter},
+            parsers::{
+                options::ParserOptions,
+                torrent::{InfoHash, TorrentFile, TorrentInfo},
+            },
+        };
+
+        fn info(build: impl FnOnce(&mut BencodeWriter<Vec<u8>>) -> Result<(), Error>) -> Vec<u8> {
+            let mut writer = BencodeWriter::new(Vec::new());
+
+            writer.begin_dict().unwrap();
+            build(&mut writer).unwrap();
+            writer.end().unwrap();
+
+            writer.finish().unwrap()
+        }
+
+        fn torrent(info: &[u8]) -> Vec<u8> {
+            [&b"d8:announce4:spam4:info"[..], info, b"e"].concat()
+        }
+
+        fn read(input: &[u8]) -> Result<TorrentInfo, crate::parsers::error::Error> {
+            TorrentInfo::read(input, ParserOptions::default())
+        }
+
+        fn file(path: &str, length: u64) -> TorrentFile {
+            TorrentFile {
+                path: path.to_string(),
+                length,
+            }
+        }
+
+        #[test]
+        fn it_should_summarize_a_single_file_torrent() {
+            let info = info(|w| {
+                w.key("length")?.write_int(40_000)?;
+                w.key("name")?.write_bytes("spam.iso")?;
+                w.key("piece length")?.write_int(16_384)?;
+                w.key("pieces")?.write_bytes([0xAA; 60])?;
+                Ok(())
+            });
+
+            let torrent_info = read(&torrent(&info)).unwrap();
+
+            assert_eq!(
+                torrent_info,
+                TorrentInfo {
+                    name: "spam.iso".to_string(),
+                    files: vec![file("spam.iso", 40_000)],
+                    total_size: 40_000,
+                    piece_length: 16_384,
+                    piece_count: 3,
+                    info_hash: InfoHash::from_info_bytes(&info),
+                }
+            );
+            assert_eq!(torrent_info.total_size, 40_000);
+        }
+
+        #[test]
+        fn it_should_summarize_a_multi_file_torrent() {
+            let info = info(|w| {
+                w.key("files")?.begin_list()?;
+                for (length, path) in [(10, ["a", "1.txt"]), (20, ["b", "2.txt"])] {
+                    w.begin_dict()?.key("length")?.write_int(length)?;
+                    w.key("path")?.begin_list()?;
+                    w.write_bytes(path[0])?.write_bytes(path[1])?.end()?.end()?;
+                }
+                w.end()?;
+                w.key("name")?.write_bytes("spam")?;
+                w.key("piece length")?.write_int(16)?;
+                w.key("pieces")?.write_bytes([0xAA; 40])?;
+                Ok(())
+            });
+
+            let torrent_info = read(&torrent(&info)).unwrap();
+
+            assert_eq!(
+                torrent_info.files,
+                vec![file("spam/a/1.txt", 10), file("spam/b/2.txt", 20)]
+            );
+            assert_eq!(torrent_info.total_size, 30);
+            assert_eq!(torrent_info.piece_count, 2);
+        }
+
+        #[test]
+        fn it_should_summarize_a_v2_torrent() {
+            let info = info(|w| {
+                w.key("file tree")?.begin_dict()?;
+                w.key("a.txt")?.begin_dict()?.key("")?.begin_dict()?;
+                w.key("length")?.write_int(20)?.end()?.end()?;
+                w.key("dir")?.begin_dict()?.key("b.txt")?.begin_dict()?;
+                w.key("")?.begin_dict()?.key("length")?.write_int(5)?;
+                w.end()?.end()?.end()?.end()?;
+                w.key("meta version")?.write_int(2)?;
+                w.key("name")?.write_bytes("spam")?;
+                w.key("piece length")?.write_int(16)?;
+                Ok(())
+            });
+
+            let torrent_info = read(&torrent(&info)).unwrap();
+
+            assert_eq!(
+                torrent_info.files,
+                vec![file("spam/a.txt", 20), file("spam/dir/b.txt", 5)]
+            );
+            // Pieces don't span files: 2 pieces for `a.txt` and 1 for `b.txt`
+            assert_eq!(torrent_info.piece_count, 3);
+        }
+
+        #[test]
+        fn it_should_hash_the_raw_info_dictionary_even_if_it_is_not_canonical() {
+            let info = b"d4:name4:spam6:lengthi1e12:piece lengthi1ee";
+
+            assert_eq!(
+                read(&torrent(info)).unwrap().info_hash,
+                InfoHash::from_info_bytes(info)
+            );
+        }
+
+        #[test]
+        fn it_should_fail_when_the_info_dictionary_is_missing() {
+            assert_eq!(
+                read(b"d8:announce4:spame").unwrap_err().to_string(),
+                "Deserialization error: invalid torrent, missing `info` dictionary"
+            );
+        }
+
+        #[test]
+        fn it_should_fail_when_a_summary_field_is_missing() {
+            assert_eq!(
+                read(&torrent(b"d6:lengthi1e12:piece lengthi1ee"))
+                    .unwrap_err()
+                    .to_string(),
+                "Deserialization error: invalid torrent, missing `name` string"
+            );
+            assert_eq!(
+                read(&torrent(b"d6:lengthi-1e4:name4:spam12:piece lengthi1ee"))
+                    .unwrap_err()
+                    .to_string(),
+                "Deserialization error: invalid torrent, missing `length` non-negative integer"
+            );
+        }
+
+        #[test]
+        fn it_should_fail_when_the_total_size_overflows() {
+            let info = info(|w| {
+                w.key("files")?.begin_list()?;
+                for path in ["a", "b", "c"] {
+                    w.begin_dict()?.key("length")?.write_int(i64::MAX)?;
+                    w.key("path")?
+                        .begin_list()?
+                        .write_bytes(path)?
+                        .end()?
+                        .end()?;
+                }
+                w.end()?;
+                w.key("name")?.write_bytes("spam")?;
+                w.key("piece length")?.write_int(16)?;
+                Ok(())
+            });
+
+            assert_eq!(
+                read(&torrent(&info)).unwrap_err().to_string(),
+                "Deserialization error: invalid torrent, the total size of the files overflows"
+            );
+        }
+
+        #[test]
+        fn it_should_limit_the_nesting_depth_by_default() {
+            let deeply_nested_lists = [b"l".repeat(100_000), b"e".repeat(100_000)].concat();
+
+            assert!(matches!(
+                read(&torrent(&deeply_nested_lists)),
+                Err(crate::parsers::error::Error::MaxDepthExceeded(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_fail_when_there_is_more_than_one_value() {
+            let input = [
+                torrent(b"d6:lengthi1e4:name4:spam12:piece lengthi1ee"),
+                b"i1e".to_vec(),
+            ]
+            .concat();
+
+            assert!(read(&input).is_err());
+        }
+    }
+
+    #[test]
+    fn it_should_hash_the_info_dictionary_with_sha1_and_sha256() {
+        let info_hash = InfoHash::from_info_bytes(b"de");
+
+        assert_eq!(
+            hex::encode(info_hash.v1),
+            "600ccd1b71569232d01d110bc63e906beab04d8c"
+        );
+        assert_eq!(
+            hex::encode(info_hash.v2),
+            "959a45d44e6fcf58361ed004681556fe50129f2109e817dec098c00c9e5d2578"
+        );
+    }
+}
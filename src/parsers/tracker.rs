@@ -0,0 +1,209 @@
+//! Tracker response rendering.
+//!
+//! In tracker response mode, the binary fields of announce and scrape
+//! responses are decoded instead of written as opaque byte strings:
+//!
+//! - `peers`: compact IPv4 peer list ([BEP 23](https://www.bittorrent.org/beps/bep_0023.html)),
+//!   6 bytes per peer.
+//! - `peers6`: compact IPv6 peer list ([BEP 7](https://www.bittorrent.org/beps/bep_0007.html)),
+//!   18 bytes per peer.
+//! - `external ip`: the IPv4 or IPv6 address of the client
+//!   ([BEP 24](https://www.bittorrent.org/beps/bep_0024.html)).
+//! - `files`: the keys of the scrape response are 20-byte info hashes
+//!   ([BEP 48](https://www.bittorrent.org/beps/bep_0048.html)).
+//!
+//! Peers are written as `{"ip":"1.2.3.4","port":6881}` objects. Fields whose
+//! length doesn't match the expected format are written as usual.
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::json::Layout;
+
+const PEERS_KEY: &[u8] = b"peers";
+const PEERS6_KEY: &[u8] = b"peers6";
+const EXTERNAL_IP_KEY: &[u8] = b"external ip";
+const FILES_KEY: &[u8] = b"files";
+
+const IPV4_LEN: usize = 4;
+const IPV6_LEN: usize = 16;
+const PORT_LEN: usize = 2;
+
+/// Length of the info hashes used as keys in scrape responses.
+const INFO_HASH_LEN: usize = 20;
+
+/// It returns the JSON for a string value if it's a known binary field.
+///
+/// The path contains the keys of the enclosing dictionaries, the last one
+/// being the key of this value. Lists have no key.
+pub(crate) fn value_json(
+    path: &[Option<Vec<u8>>],
+    bytes: &[u8],
+    layout: &Layout,
+) -> Option<String> {
+    match path {
+        [Some(peers)] if peers == PEERS_KEY => compact_peers_json(bytes, IPV4_LEN, layout),
+        [Some(peers6)] if peers6 == PEERS6_KEY => compact_peers_json(bytes, IPV6_LEN, layout),
+        [Some(external_ip)] if external_ip == EXTERNAL_IP_KEY => {
+            ip_addr(bytes).map(|ip| format!(r#""{ip}""#))
+        }
+        _ => None,
+    }
+}
+
+/// It returns `true` if the path is the one of a known binary field, whose
+/// value is converted as a whole.
+pub(crate) fn is_known_value_path(path: &[Option<Vec<u8>>]) -> bool {
+    matches!(path, [Some(key)] if key == PEERS_KEY || key == PEERS6_KEY || key == EXTERNAL_IP_KEY)
+}
+
+/// It returns the JSON for a dictionary key if it's a known binary field.
+///
+/// The path contains the keys of the enclosing dictionaries, the last one
+/// being the key of the dictionary containing this key.
+pub(crate) fn key_json(path: &[Option<Vec<u8>>], key: &[u8]) -> Option<String> {
+    match path {
+        [Some(files)] if files == FILES_KEY && key.len() == INFO_HASH_LEN => {
+            Some(format!(r#""{}""#, hex::encode(key)))
+        }
+        _ => None,
+    }
+}
+
+/// It writes a compact peer list as a list of peer objects.
+fn compact_peers_json(bytes: &[u8], ip_len: usize, layout: &Layout) -> Option<String> {
+    let peer_len = ip_len + PORT_LEN;
+
+    if !bytes.len().is_multiple_of(peer_len) {
+        return None;
+    }
+
+    let peer_layout = layout.nested();
+
+    Some(layout.array(bytes.chunks(peer_len).map(|peer| {
+        let (ip, port) = peer.split_at(ip_len);
+
+        peer_layout.object([
+            (
+                "ip",
+                format!(
+                    r#""{}""#,
+                    ip_addr(ip).expect("the IP has the length of an IPv4 or IPv6 address")
+                ),
+            ),
+            ("port", u16::from_be_bytes([port[0], port[1]]).to_string()),
+        ])
+    })))
+}
+
+fn ip_addr(bytes: &[u8]) -> Option<IpAddr> {
+    if let Ok(octets) = <[u8; IPV4_LEN]>::try_from(bytes) {
+        return Some(IpAddr::V4(Ipv4Addr::from(octets)));
+    }
+
+    if let Ok(octets) = <[u8; IPV6_LEN]>::try_from(bytes) {
+        return Some(IpAddr::V6(Ipv6Addr::from(octets)));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{
+        json::Layout,
+        tracker::{key_json, value_json},
+    };
+
+    fn path(keys: &[&str]) -> Vec<Option<Vec<u8>>> {
+        keys.iter()
+            .map(|key| Some(key.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn it_should_decode_compact_ipv4_peers() {
+        assert_eq!(
+            value_json(
+                &path(&["peers"]),
+                &[1, 2, 3, 4, 0x1A, 0xE1, 10, 0, 0, 1, 0, 80],
+                &Layout::compact()
+            ),
+            Some(r#"[{"ip":"1.2.3.4","port":6881},{"ip":"10.0.0.1","port":80}]"#.to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_indent_compact_peers_with_the_layout() {
+        assert_eq!(
+            value_json(
+                &path(&["peers"]),
+                &[1, 2, 3, 4, 0x1A, 0xE1],
+                &Layout::new(Some(2), 1)
+            ),
+            Some(
+                "[\n    {\n      \"ip\": \"1.2.3.4\",\n      \"port\": 6881\n    }\n  ]"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_decode_compact_ipv6_peers() {
+        let mut peer = vec![0; 15];
+        peer.extend([1, 0x1A, 0xE1]);
+
+        assert_eq!(
+            value_json(&path(&["peers6"]), &peer, &Layout::compact()),
+            Some(r#"[{"ip":"::1","port":6881}]"#.to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_decode_an_empty_compact_peer_list() {
+        assert_eq!(
+            value_json(&path(&["peers"]), &[], &Layout::compact()),
+            Some("[]".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_not_decode_peers_with_an_invalid_length() {
+        assert_eq!(
+            value_json(&path(&["peers"]), &[1, 2, 3, 4, 5], &Layout::compact()),
+            None
+        );
+        assert_eq!(
+            value_json(&path(&["peers6"]), &[0; 6], &Layout::compact()),
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_only_decode_the_peers_of_the_top_level_dictionary() {
+        assert_eq!(
+            value_json(&path(&["a", "peers"]), &[0; 6], &Layout::compact()),
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_decode_the_external_ip() {
+        assert_eq!(
+            value_json(&path(&["external ip"]), &[1, 2, 3, 4], &Layout::compact()),
+            Some(r#""1.2.3.4""#.to_string())
+        );
+        assert_eq!(
+            value_json(&path(&["external ip"]), &[1, 2, 3], &Layout::compact()),
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_write_the_info_hashes_of_the_scrape_files_as_hex() {
+        assert_eq!(
+            key_json(&path(&["files"]), &[0xAB; 20]),
+            Some(format!(r#""{}""#, "ab".repeat(20)))
+        );
+        assert_eq!(key_json(&path(&["files"]), b"spam"), None);
+        assert_eq!(key_json(&path(&["other"]), &[0xAB; 20]), None);
+    }
+}
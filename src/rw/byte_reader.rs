@@ -8,6 +8,9 @@ use std::io::Read;
 use ringbuffer::AllocRingBuffer;
 use ringbuffer::RingBuffer;
 
+use super::recording::{Recorded, Recording};
+use super::DEFAULT_CAPTURE_SIZE;
+
 /// A reader that reads bytes from an input.
 ///
 /// It's wrapper of a basic reader with extra functionality.
@@ -26,29 +29,76 @@ pub struct ByteReader<R: Read> {
 
     /// A buffer to capture the latest bytes read from the input.
     captured_bytes: AllocRingBuffer<u8>,
+
+    /// Maximum number of bytes that can be read from the input.
+    max_input_bytes: Option<u64>,
+
+    /// The input is longer than the maximum number of bytes allowed.
+    input_limit_exceeded: bool,
+
+    /// The active recordings of the consumed bytes. They are nested: the last
+    /// one is the latest started.
+    recordings: Vec<Recording>,
 }
 
 impl<R: Read> ByteReader<R> {
     pub fn new(reader: R) -> Self {
+        Self::with_capture_size(reader, DEFAULT_CAPTURE_SIZE)
+    }
+
+    /// It creates a reader capturing the given number of latest bytes for the
+    /// error contexts.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn with_capture_size(reader: R, capture_size: usize) -> Self {
         Self {
             reader: BufReader::new(reader),
             input_byte_counter: 0,
             peeked_byte: None,
             last_byte: None,
-            captured_bytes: AllocRingBuffer::new(1024),
+            captured_bytes: AllocRingBuffer::new(capture_size),
+            max_input_bytes: None,
+            input_limit_exceeded: false,
+            recordings: Vec::new(),
         }
     }
 
+    /// It sets the maximum number of bytes that can be read from the input.
+    ///
+    /// Reading more bytes fails with an error and
+    /// [`ByteReader::input_limit_exceeded`] returns `true`.
+    pub fn set_max_input_bytes(&mut self, max_input_bytes: Option<u64>) {
+        self.max_input_bytes = max_input_bytes;
+    }
+
+    /// It returns `true` if it failed reading because the input is longer than
+    /// the maximum number of bytes allowed.
+    pub fn input_limit_exceeded(&self) -> bool {
+        self.input_limit_exceeded
+    }
+
     /// It reads one byte from the input.
     ///
     /// # Errors
     ///
     /// Will return an error if it can't read the byte from the input.
     pub fn read_byte(&mut self) -> Result<u8, Error> {
-        if let Some(byte) = self.peeked_byte.take() {
-            return Ok(byte);
+        let byte = match self.peeked_byte.take() {
+            Some(byte) => byte,
+            None => self.read_new_byte()?,
+        };
+
+        for recording in &mut self.recordings {
+            recording.push(byte);
         }
 
+        Ok(byte)
+    }
+
+    /// It reads one byte from the input, ignoring the peeked one.
+    fn read_new_byte(&mut self) -> Result<u8, Error> {
         let mut byte = [0; 1];
 
         self.reader.read_exact(&mut byte)?;
@@ -60,6 +110,13 @@ impl<R: Read> ByteReader<R> {
         self.last_byte = Some(byte);
         self.captured_bytes.push(byte);
 
+        if let Some(max_input_bytes) = self.max_input_bytes {
+            if self.input_byte_counter > max_input_bytes {
+                self.input_limit_exceeded = true;
+                return Err(Error::other("input limit exceeded"));
+            }
+        }
+
         Ok(byte)
     }
 
@@ -72,7 +129,7 @@ impl<R: Read> ByteReader<R> {
         let byte = if let Some(byte) = self.peeked_byte {
             byte
         } else {
-            let byte = self.read_byte()?;
+            let byte = self.read_new_byte()?;
             self.peeked_byte = Some(byte);
             byte
         };
@@ -85,6 +142,18 @@ impl<R: Read> ByteReader<R> {
         self.input_byte_counter
     }
 
+    /// Returns the number of bytes that have been consumed from the input. It
+    /// doesn't count the peeked byte.
+    pub fn consumed_byte_counter(&self) -> u64 {
+        self.input_byte_counter - u64::from(self.peeked_byte.is_some())
+    }
+
+    /// It returns the input, for example to add more bytes to an in-memory
+    /// buffer. The bytes already buffered are still read first.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.reader.get_mut()
+    }
+
     /// Returns a copy of the bytes that have been read from the input.
     pub fn captured_bytes(&self) -> Vec<u8> {
         self.captured_bytes.to_vec()
@@ -94,6 +163,27 @@ impl<R: Read> ByteReader<R> {
     pub fn last_byte(&self) -> Option<u8> {
         self.last_byte
     }
+
+    /// It starts a new recording of the bytes consumed from now on. A peeked
+    /// byte is recorded when it's consumed.
+    ///
+    /// Recordings can be nested. The bytes are also added to the recordings
+    /// already started. The bytes are only kept when `capture_bytes` is
+    /// `true`, otherwise they are only hashed.
+    pub fn start_recording(&mut self, capture_bytes: bool) {
+        self.recordings.push(Recording::new(capture_bytes));
+    }
+
+    /// It returns `true` if there is any active recording.
+    pub fn is_recording(&self) -> bool {
+        !self.recordings.is_empty()
+    }
+
+    /// It stops the latest started recording and returns its result, or
+    /// `None` if it was not recording.
+    pub fn stop_recording(&mut self) -> Option<Recorded> {
+        self.recordings.pop().map(Recording::finish)
+    }
 }
 
 #[cfg(test)]
@@ -112,6 +202,19 @@ mod tests {
             assert_eq!(byte_reader.read_byte().unwrap(), b'e');
         }
 
+        #[test]
+        fn it_should_count_the_consumed_bytes_without_the_peeked_one() {
+            let input = vec![b'l', b'e'];
+
+            let mut byte_reader = ByteReader::new(input.as_slice());
+
+            let _byte = byte_reader.read_byte().unwrap();
+            let _peeked = byte_reader.peek_byte().unwrap();
+
+            assert_eq!(byte_reader.input_byte_counter(), 2);
+            assert_eq!(byte_reader.consumed_byte_counter(), 1);
+        }
+
         #[test]
         fn it_should_fail_when_there_are_no_more_bytes_to_read() {
             let input = vec![b'l', b'e'];
@@ -206,6 +309,87 @@ mod tests {
         }
     }
 
+    mod for_recording {
+        use crate::rw::byte_reader::ByteReader;
+
+        fn recorded_bytes<R: std::io::Read>(byte_reader: &mut ByteReader<R>) -> Option<Vec<u8>> {
+            byte_reader
+                .stop_recording()
+                .map(|recorded| recorded.bytes.unwrap())
+        }
+
+        #[test]
+        fn it_should_record_the_bytes_consumed_while_recording() {
+            let mut byte_reader = ByteReader::new(&b"abcd"[..]);
+
+            byte_reader.read_byte().unwrap();
+
+            byte_reader.start_recording(true);
+
+            byte_reader.read_byte().unwrap();
+            byte_reader.read_byte().unwrap();
+
+            assert!(byte_reader.is_recording());
+            assert_eq!(recorded_bytes(&mut byte_reader), Some(b"bc".to_vec()));
+
+            byte_reader.read_byte().unwrap();
+
+            assert!(!byte_reader.is_recording());
+            assert_eq!(recorded_bytes(&mut byte_reader), None);
+        }
+
+        #[test]
+        fn it_should_only_record_peeked_bytes_once_they_are_consumed() {
+            let mut byte_reader = ByteReader::new(&b"ab"[..]);
+
+            byte_reader.peek_byte().unwrap();
+
+            byte_reader.start_recording(true);
+
+            byte_reader.peek_byte().unwrap();
+
+            assert_eq!(recorded_bytes(&mut byte_reader), Some(vec![]));
+
+            byte_reader.start_recording(true);
+
+            byte_reader.read_byte().unwrap();
+            byte_reader.peek_byte().unwrap();
+
+            assert_eq!(recorded_bytes(&mut byte_reader), Some(b"a".to_vec()));
+        }
+
+        #[test]
+        fn it_should_allow_nested_recordings() {
+            let mut byte_reader = ByteReader::new(&b"abc"[..]);
+
+            byte_reader.start_recording(true);
+            byte_reader.read_byte().unwrap();
+
+            byte_reader.start_recording(true);
+            byte_reader.read_byte().unwrap();
+
+            assert_eq!(recorded_bytes(&mut byte_reader), Some(b"b".to_vec()));
+
+            byte_reader.read_byte().unwrap();
+
+            assert_eq!(recorded_bytes(&mut byte_reader), Some(b"abc".to_vec()));
+        }
+
+        #[test]
+        fn it_should_only_hash_the_bytes_when_they_are_not_captured() {
+            let mut byte_reader = ByteReader::new(&b"de"[..]);
+
+            byte_reader.start_recording(false);
+            byte_reader.read_byte().unwrap();
+            byte_reader.read_byte().unwrap();
+
+            let recorded = byte_reader.stop_recording().unwrap();
+
+            assert_eq!(recorded.len, 2);
+            assert_eq!(recorded.bytes, None);
+        }
+    }
+
     mod for_capturing {
         use crate::rw::byte_reader::ByteReader;
 
@@ -235,4 +419,31 @@ mod tests {
             assert_eq!(byte_reader.captured_bytes(), part2);
         }
     }
+
+    mod for_limiting_the_input {
+        use crate::rw::byte_reader::ByteReader;
+
+        #[test]
+        fn it_should_read_up_to_the_maximum_number_of_bytes() {
+            let mut byte_reader = ByteReader::new(&b"le"[..]);
+            byte_reader.set_max_input_bytes(Some(2));
+
+            assert_eq!(byte_reader.read_byte().unwrap(), b'l');
+            assert_eq!(byte_reader.read_byte().unwrap(), b'e');
+            assert!(byte_reader.read_byte().is_err());
+            assert!(!byte_reader.input_limit_exceeded());
+        }
+
+        #[test]
+        fn it_should_fail_when_the_input_is_longer_than_the_maximum() {
+            let mut byte_reader = ByteReader::new(&b"lee"[..]);
+            byte_reader.set_max_input_bytes(Some(2));
+
+            byte_reader.read_byte().unwrap();
+            byte_reader.read_byte().unwrap();
+
+            assert!(byte_reader.peek_byte().is_err());
+            assert!(byte_reader.input_limit_exceeded());
+        }
+    }
 }
@@ -8,7 +8,7 @@ use std::io::Write;
 use ringbuffer::AllocRingBuffer;
 use ringbuffer::RingBuffer;
 
-use super::{error::Error, writer::Writer};
+use super::{error::Error, writer::Writer, DEFAULT_CAPTURE_SIZE};
 
 /// A writer that writes to an output implementing `std::io::Write`.
 ///
@@ -29,11 +29,21 @@ pub struct ByteWriter<W: Write> {
 
 impl<W: Write> ByteWriter<W> {
     pub fn new(writer: W) -> Self {
+        Self::with_capture_size(writer, DEFAULT_CAPTURE_SIZE)
+    }
+
+    /// It creates a writer capturing the given number of latest bytes for the
+    /// error contexts.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn with_capture_size(writer: W, capture_size: usize) -> Self {
         Self {
             output_byte_counter: 0,
             writer: BufWriter::new(writer),
             last_byte: None,
-            captured_bytes: AllocRingBuffer::new(1024),
+            captured_bytes: AllocRingBuffer::new(capture_size),
         }
     }
 
@@ -51,6 +61,18 @@ impl<W: Write> ByteWriter<W> {
     pub fn last_byte(&self) -> Option<u8> {
         self.last_byte
     }
+
+    /// It writes the buffered bytes and returns the output, for example to
+    /// take the bytes written so far to a `Vec<u8>`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if it can't write the buffered bytes.
+    pub fn flushed_output(&mut self) -> Result<&mut W, Error> {
+        self.writer.flush()?;
+
+        Ok(self.writer.get_mut())
+    }
 }
 
 impl<W: Write> Writer for ByteWriter<W> {
@@ -104,6 +126,21 @@ mod tests {
             assert_eq!(output, vec![b'l']);
         }
 
+        #[test]
+        fn it_should_return_the_output_with_the_buffered_bytes_written() {
+            let mut byte_writer = ByteWriter::new(Vec::new());
+
+            byte_writer.write_str("le").unwrap();
+
+            let output = byte_writer.flushed_output().unwrap();
+
+            assert_eq!(*output, b"le");
+
+            output.clear();
+
+            assert_eq!(byte_writer.output_byte_counter(), 2);
+        }
+
         #[test]
         fn it_should_increase_the_output_byte_counter_by_one_after_writing_a_new_byte() {
             let mut output = Vec::new();
@@ -2,5 +2,11 @@
 pub mod byte_reader;
 pub mod byte_writer;
 pub mod error;
+pub mod null_writer;
+pub mod recording;
 pub mod string_writer;
 pub mod writer;
+
+/// Default number of latest bytes captured by readers and writers to build
+/// the error contexts.
+pub const DEFAULT_CAPTURE_SIZE: usize = 1024;
@@ -0,0 +1,28 @@
+//! A writer that discards the output.
+use super::{error::Error, writer::Writer};
+
+/// A writer that discards everything written to it.
+///
+/// It's used when the parser does not write any output, for example, when
+/// pulling events or building values. The write contexts in errors are always
+/// empty.
+#[derive(Debug, Default)]
+pub struct NullWriter;
+
+impl Writer for NullWriter {
+    fn write_byte(&mut self, _byte: u8) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn write_str(&mut self, _value: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn output_byte_counter(&self) -> u64 {
+        0
+    }
+
+    fn captured_bytes(&self) -> Vec<u8> {
+        vec![]
+    }
+}
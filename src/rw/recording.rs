@@ -0,0 +1,101 @@
+//! A recording of the raw bytes consumed from an input.
+//!
+//! The bytes are hashed while they are read, so they don't need to be kept in
+//! memory unless they are also captured.
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// It hashes, and optionally captures, the bytes pushed into it.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    len: u64,
+    sha1: Sha1,
+    sha256: Sha256,
+    captured_bytes: Option<Vec<u8>>,
+}
+
+impl Recording {
+    /// It creates a recording that also keeps the bytes when `capture_bytes`
+    /// is `true`.
+    #[must_use]
+    pub fn new(capture_bytes: bool) -> Self {
+        Self {
+            captured_bytes: capture_bytes.then(Vec::new),
+            ..Self::default()
+        }
+    }
+
+    /// It adds one byte to the recording.
+    pub fn push(&mut self, byte: u8) {
+        self.len += 1;
+        self.sha1.update([byte]);
+        self.sha256.update([byte]);
+
+        if let Some(captured_bytes) = &mut self.captured_bytes {
+            captured_bytes.push(byte);
+        }
+    }
+
+    /// It returns the result of the recording.
+    #[must_use]
+    pub fn finish(self) -> Recorded {
+        Recorded {
+            len: self.len,
+            sha1: self.sha1.finalize().into(),
+            sha256: self.sha256.finalize().into(),
+            bytes: self.captured_bytes,
+        }
+    }
+}
+
+/// The result of a [`Recording`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recorded {
+    /// Number of recorded bytes.
+    pub len: u64,
+
+    /// SHA-1 hash of the recorded bytes.
+    pub sha1: [u8; 20],
+
+    /// SHA-256 hash of the recorded bytes.
+    pub sha256: [u8; 32],
+
+    /// The recorded bytes, if they were captured.
+    pub bytes: Option<Vec<u8>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rw::recording::Recording;
+
+    fn record(bytes: &[u8], capture_bytes: bool) -> super::Recorded {
+        let mut recording = Recording::new(capture_bytes);
+
+        for byte in bytes {
+            recording.push(*byte);
+        }
+
+        recording.finish()
+    }
+
+    #[test]
+    fn it_should_hash_the_recorded_bytes() {
+        let recorded = record(b"de", false);
+
+        assert_eq!(recorded.len, 2);
+        assert_eq!(
+            hex::encode(recorded.sha1),
+            "600ccd1b71569232d01d110bc63e906beab04d8c"
+        );
+        assert_eq!(
+            hex::encode(recorded.sha256),
+            "959a45d44e6fcf58361ed004681556fe50129f2109e817dec098c00c9e5d2578"
+        );
+        assert_eq!(recorded.bytes, None);
+    }
+
+    #[test]
+    fn it_should_keep_the_recorded_bytes_when_they_are_captured() {
+        assert_eq!(record(b"de", true).bytes, Some(b"de".to_vec()));
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt::Write;
 
 use ringbuffer::{AllocRingBuffer, RingBuffer};
 
-use super::{error::Error, writer::Writer};
+use super::{error::Error, writer::Writer, DEFAULT_CAPTURE_SIZE};
 
 /// A writer that writes to an output implementing `std::fmt::Write`.
 ///
@@ -25,12 +25,22 @@ pub struct StringWriter<W: Write> {
 
 impl<W: Write> StringWriter<W> {
     pub fn new(writer: W) -> Self {
+        Self::with_capture_size(writer, DEFAULT_CAPTURE_SIZE)
+    }
+
+    /// It creates a writer capturing the given number of latest bytes for the
+    /// error contexts.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the capture size is zero.
+    pub fn with_capture_size(writer: W, capture_size: usize) -> Self {
         Self {
             writer,
             output_byte_counter: 0,
 
             last_char: None,
-            captured_chars: AllocRingBuffer::new(1024),
+            captured_chars: AllocRingBuffer::new(capture_size),
         }
     }
 
@@ -21,6 +21,61 @@ pub(crate) fn bencode_to_json_unchecked(input_buffer: &[u8]) -> String {
     output
 }
 
+/// It converts bencoded bytes into JSON with the sync parser. It returns the
+/// JSON and the error message, if any.
+///
+/// The other parsers are checked against it.
+#[cfg(test)]
+pub(crate) fn sync_json(
+    input: &[u8],
+    options: &crate::parsers::options::ParserOptions,
+) -> (String, Option<String>) {
+    use crate::parsers::BencodeParser;
+
+    let mut output = Vec::new();
+
+    let result = BencodeParser::with_options(input, options.clone()).write_bytes(&mut output);
+
+    (
+        String::from_utf8(output).unwrap(),
+        result.err().map(|err| err.to_string()),
+    )
+}
+
+/// Valid and invalid bencoded inputs to check that the other parsers write
+/// the same JSON and errors as the sync parser.
+#[cfg(test)]
+pub(crate) const PARSER_INPUTS: &[&[u8]] = &[
+    b"",
+    b"i42e",
+    b"i-42e",
+    b"12:spam and egg",
+    b"0:",
+    b"le",
+    b"de",
+    b"li1e3:fooli2eee",
+    b"d3:bar4:spam3:fooi42e4:listl1:a1:bee",
+    b"i1ei2e",
+    b"i1e\n",
+    b"i1e\ni2e\n",
+    b"\ni1e\n\ni2e\n",
+    b"2:\xff\xfe",
+    // Invalid input
+    b"x",
+    b"d4:info d4:name3:egge",
+    b"i12xe",
+    b"i00e",
+    b"li1e",
+    b"d3:foo",
+    b"3:ab",
+    b"1x:a",
+    b"e",
+    b"di1ei2ee",
+    b"d3:fooe",
+    b"i1ex:i2e",
+    b"99999999999999999999999:a",
+];
+
 /// Generates a vector of bytes representing `n` nested empty Bencode
 /// lists.
 ///
@@ -0,0 +1,102 @@
+//! Bencoded value borrowing its strings from the input buffer.
+//!
+//! It's built by the [`BorrowedParser`](crate::parsers::borrowed::BorrowedParser)
+//! without copying strings or dictionary keys.
+use std::collections::BTreeMap;
+
+use super::{builder::Tree, Value};
+
+/// A bencoded value whose strings and dictionary keys are slices of the input.
+///
+/// Dictionary fields are sorted by the raw bytes of their keys, as the Bencode
+/// specification requires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BorrowedValue<'a> {
+    Integer(i64),
+    Bytes(&'a [u8]),
+    List(Vec<BorrowedValue<'a>>),
+    Dict(BTreeMap<&'a [u8], BorrowedValue<'a>>),
+}
+
+impl<'a> Tree for BorrowedValue<'a> {
+    type Key = &'a [u8];
+
+    fn list(items: Vec<Self>) -> Self {
+        BorrowedValue::List(items)
+    }
+
+    fn dict(fields: BTreeMap<Self::Key, Self>) -> Self {
+        BorrowedValue::Dict(fields)
+    }
+}
+
+impl<'a> BorrowedValue<'a> {
+    /// It returns the integer if the value is an integer.
+    #[must_use]
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            BorrowedValue::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    /// It returns the raw bytes if the value is a string.
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            BorrowedValue::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// It returns the string if the value is a string containing only valid
+    /// UTF-8.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&'a str> {
+        self.as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// It returns the items if the value is a list.
+    #[must_use]
+    pub fn as_list(&self) -> Option<&[BorrowedValue<'a>]> {
+        match self {
+            BorrowedValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// It returns the fields if the value is a dictionary.
+    #[must_use]
+    pub fn as_dict(&self) -> Option<&BTreeMap<&'a [u8], BorrowedValue<'a>>> {
+        match self {
+            BorrowedValue::Dict(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// It returns the value of a dictionary field. It returns `None` if the
+    /// value is not a dictionary or the field does not exist.
+    #[must_use]
+    pub fn get(&self, key: &[u8]) -> Option<&BorrowedValue<'a>> {
+        self.as_dict().and_then(|fields| fields.get(key))
+    }
+
+    /// It copies the borrowed value into an owned [`Value`].
+    #[must_use]
+    pub fn to_owned_value(&self) -> Value {
+        match self {
+            BorrowedValue::Integer(integer) => Value::Integer(*integer),
+            BorrowedValue::Bytes(bytes) => Value::Bytes(bytes.to_vec()),
+            BorrowedValue::List(items) => {
+                Value::List(items.iter().map(BorrowedValue::to_owned_value).collect())
+            }
+            BorrowedValue::Dict(fields) => Value::Dict(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.to_vec(), value.to_owned_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Builder for the [`Value`](super::Value) and
+//! [`BorrowedValue`](super::borrowed::BorrowedValue) trees.
+//!
+//! The parsers validate the structure of the bencoded input with the
+//! [`Stack`](crate::parsers::stack::Stack). The builder only has to put the
+//! parsed values in the right container.
+use std::collections::BTreeMap;
+
+/// A value tree the builder can build.
+pub(crate) trait Tree: Sized + std::fmt::Debug {
+    /// The dictionary key type.
+    type Key: Ord + std::fmt::Debug;
+
+    fn list(items: Vec<Self>) -> Self;
+
+    fn dict(fields: BTreeMap<Self::Key, Self>) -> Self;
+}
+
+/// An unfinished list or dictionary.
+#[derive(Debug)]
+enum Container<T: Tree> {
+    List(Vec<T>),
+    Dict {
+        fields: BTreeMap<T::Key, T>,
+        key: Option<T::Key>,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) struct Builder<T: Tree> {
+    containers: Vec<Container<T>>,
+}
+
+impl<T: Tree> Default for Builder<T> {
+    fn default() -> Self {
+        Self { containers: vec![] }
+    }
+}
+
+impl<T: Tree> Builder<T> {
+    pub fn begin_list(&mut self) {
+        self.containers.push(Container::List(vec![]));
+    }
+
+    pub fn begin_dict(&mut self) {
+        self.containers.push(Container::Dict {
+            fields: BTreeMap::new(),
+            key: None,
+        });
+    }
+
+    /// It sets the key for the next field in the current dictionary.
+    pub fn set_dict_field_key(&mut self, new_key: T::Key) {
+        if let Some(Container::Dict { key, .. }) = self.containers.last_mut() {
+            *key = Some(new_key);
+        }
+    }
+
+    /// It returns the finished list or dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is no list or dictionary to end. The parsers do
+    /// not allow ending a list or dictionary without a matching start.
+    pub fn end_list_or_dict(&mut self) -> T {
+        match self.containers.pop() {
+            Some(Container::List(items)) => T::list(items),
+            Some(Container::Dict { fields, .. }) => T::dict(fields),
+            None => panic!("no list or dictionary to end!"),
+        }
+    }
+
+    /// It adds a finished value to the current list or dictionary.
+    ///
+    /// It returns the value back if there is no container, because the value
+    /// is a finished top-level value.
+    pub fn add(&mut self, value: T) -> Option<T> {
+        match self.containers.last_mut() {
+            None => Some(value),
+            Some(Container::List(items)) => {
+                items.push(value);
+                None
+            }
+            Some(Container::Dict { fields, key }) => {
+                if let Some(key) = key.take() {
+                    fields.insert(key, value);
+                }
+                None
+            }
+        }
+    }
+}
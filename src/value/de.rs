@@ -0,0 +1,416 @@
+//! `serde` deserialization for [`Value`].
+//!
+//! - [`Value`] implements `Deserialize`, so it can be built from any `serde`
+//!   format.
+//! - [`Value`] implements `Deserializer`, so any type implementing
+//!   `Deserialize` can be built from a bencoded value.
+//!
+//! Strings are deserialized as Rust strings when they contain only valid UTF-8
+//! and as bytes otherwise. Integers `0` and `1` can also be deserialized as
+//! booleans, since Bencode does not have booleans and flags like the torrent
+//! `private` field are integers.
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    de::{
+        self,
+        value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+        IntoDeserializer, MapAccess, SeqAccess, Unexpected, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize,
+};
+
+use crate::parsers::error::Error;
+
+use super::Value;
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Deserialize(msg.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an integer, string, list or dictionary")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Integer(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        match i64::try_from(value) {
+            Ok(integer) => Ok(Value::Integer(integer)),
+            Err(_) => Err(E::invalid_value(Unexpected::Unsigned(value), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::Bytes(value.as_bytes().to_vec()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Value, E> {
+        Ok(Value::Bytes(value.into_bytes()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(value))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = vec![];
+
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+
+        Ok(Value::List(items))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut fields = BTreeMap::new();
+
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            match key {
+                Value::Bytes(key) => {
+                    fields.insert(key, value);
+                }
+                _ => {
+                    return Err(de::Error::invalid_type(
+                        Unexpected::Other("non-string dictionary key"),
+                        &"a string dictionary key",
+                    ))
+                }
+            }
+        }
+
+        Ok(Value::Dict(fields))
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Integer(integer) => visitor.visit_i64(integer),
+            Value::Bytes(bytes) => match String::from_utf8(bytes) {
+                Ok(string) => visitor.visit_string(string),
+                Err(err) => visitor.visit_byte_buf(err.into_bytes()),
+            },
+            Value::List(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Dict(fields) => {
+                let mut map = MapDeserializer::new(
+                    fields
+                        .into_iter()
+                        .map(|(key, value)| (Value::Bytes(key), value)),
+                );
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Integer(0) => visitor.visit_bool(false),
+            Value::Integer(1) => visitor.visit_bool(true),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Bencode has no null value. Missing fields are deserialized as `None`
+        // by `serde` itself.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            // Unit variants: `4:name`
+            Value::Bytes(bytes) => match String::from_utf8(bytes) {
+                Ok(variant) => visitor.visit_enum(variant.into_deserializer()),
+                Err(err) => Err(de::Error::invalid_type(
+                    Unexpected::Bytes(err.as_bytes()),
+                    &"a UTF-8 enum variant name",
+                )),
+            },
+            // Other variants: `d4:name<value>e`
+            Value::Dict(fields) if fields.len() == 1 => {
+                visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(
+                    fields
+                        .into_iter()
+                        .map(|(key, value)| (Value::Bytes(key), value)),
+                )))
+            }
+            _ => Err(de::Error::invalid_type(
+                Unexpected::Other("bencoded value"),
+                &"a string or a dictionary with one field",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    mod deserializing_into_rust_types {
+        use std::{collections::BTreeMap, fmt};
+
+        use serde::{
+            de::{self, Visitor},
+            Deserialize, Deserializer,
+        };
+
+        use crate::{from_bytes, parsers::error::Error};
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Torrent {
+            announce: String,
+            info: Info,
+            #[serde(rename = "announce-list")]
+            announce_list: Option<Vec<Vec<String>>>,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Info {
+            name: String,
+            length: u64,
+            #[serde(rename = "piece length")]
+            piece_length: u32,
+            pieces: ByteBuf,
+            private: Option<bool>,
+        }
+
+        /// Bytes that do not need to be valid UTF-8.
+        #[derive(Debug, PartialEq)]
+        struct ByteBuf(Vec<u8>);
+
+        impl<'de> Deserialize<'de> for ByteBuf {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct ByteBufVisitor;
+
+                impl Visitor<'_> for ByteBufVisitor {
+                    type Value = ByteBuf;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        formatter.write_str("bytes")
+                    }
+
+                    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<ByteBuf, E> {
+                        Ok(ByteBuf(value))
+                    }
+                }
+
+                deserializer.deserialize_byte_buf(ByteBufVisitor)
+            }
+        }
+
+        #[test]
+        fn structs() {
+            let torrent: Torrent = from_bytes(
+                b"d8:announce9:udp://foo4:infod6:lengthi42e4:name4:spam12:piece lengthi16384e6:pieces2:\xFF\xFE7:privatei1eee",
+            )
+            .unwrap();
+
+            assert_eq!(
+                torrent,
+                Torrent {
+                    announce: "udp://foo".to_string(),
+                    info: Info {
+                        name: "spam".to_string(),
+                        length: 42,
+                        piece_length: 16384,
+                        pieces: ByteBuf(vec![0xFF, 0xFE]),
+                        private: Some(true),
+                    },
+                    announce_list: None,
+                }
+            );
+        }
+
+        #[test]
+        fn maps() {
+            let map: BTreeMap<String, i64> = from_bytes(b"d1:ai1e1:bi2ee").unwrap();
+
+            assert_eq!(map.get("a"), Some(&1));
+            assert_eq!(map.get("b"), Some(&2));
+        }
+
+        #[test]
+        fn enums() {
+            #[derive(Debug, PartialEq, Deserialize)]
+            enum Event {
+                #[serde(rename = "started")]
+                Started,
+                #[serde(rename = "progress")]
+                Progress(u8),
+            }
+
+            assert_eq!(from_bytes::<Event>(b"7:started").unwrap(), Event::Started);
+            assert_eq!(
+                from_bytes::<Event>(b"d8:progressi50ee").unwrap(),
+                Event::Progress(50)
+            );
+        }
+
+        #[test]
+        fn it_should_fail_when_the_types_do_not_match() {
+            let result = from_bytes::<u8>(b"4:spam");
+
+            assert!(matches!(result, Err(Error::Deserialize(_))));
+        }
+
+        #[test]
+        fn it_should_fail_when_the_integer_is_out_of_the_target_range() {
+            let result = from_bytes::<u8>(b"i256e");
+
+            assert!(matches!(result, Err(Error::Deserialize(_))));
+        }
+
+        #[test]
+        fn it_should_fail_when_non_utf8_bytes_are_deserialized_into_a_string() {
+            let result = from_bytes::<String>(b"2:\xFF\xFE");
+
+            assert!(matches!(result, Err(Error::Deserialize(_))));
+        }
+
+        #[test]
+        fn it_should_keep_the_parser_error_context_for_invalid_bencode() {
+            let result = from_bytes::<u8>(b"i00e");
+
+            assert!(matches!(
+                result,
+                Err(Error::LeadingZerosInIntegersNotAllowed(..))
+            ));
+        }
+
+        #[test]
+        fn it_should_fail_with_an_empty_input() {
+            let result = from_bytes::<u8>(b"");
+
+            assert!(matches!(result, Err(Error::Deserialize(_))));
+        }
+
+        #[test]
+        fn it_should_fail_with_trailing_data() {
+            let result = from_bytes::<u8>(b"i1ei2e");
+
+            assert!(matches!(result, Err(Error::TrailingData(..))));
+        }
+    }
+
+    mod deserializing_values_from_other_formats {
+        use std::collections::BTreeMap;
+
+        use crate::value::Value;
+
+        #[test]
+        fn from_json() {
+            let value: Value = serde_json::from_str(r#"{"foo":[1,"bar"]}"#).unwrap();
+
+            let mut fields = BTreeMap::new();
+            fields.insert(
+                b"foo".to_vec(),
+                Value::List(vec![Value::Integer(1), Value::Bytes(b"bar".to_vec())]),
+            );
+
+            assert_eq!(value, Value::Dict(fields));
+        }
+
+        #[test]
+        fn it_should_fail_with_values_that_can_not_be_bencoded() {
+            assert!(serde_json::from_str::<Value>("1.5").is_err());
+            assert!(serde_json::from_str::<Value>("true").is_err());
+            assert!(serde_json::from_str::<Value>("null").is_err());
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Owned in-memory bencoded value.
+//!
+//! The [`BencodeParser`](crate::parsers::BencodeParser) can build a [`Value`]
+//! tree instead of writing JSON. Unlike JSON, the tree keeps bencoded strings as
+//! raw bytes, so there is no need to distinguish between UTF-8 and non UTF-8
+//! strings.
+//!
+//! The value implements `serde` traits:
+//!
+//! - It can be deserialized into any type implementing `Deserialize`. See
+//!   [`from_bytes`](crate::from_bytes).
+//! - It can be serialized into any `serde` format.
+pub mod borrowed;
+pub(crate) mod builder;
+pub mod de;
+pub mod ser;
+
+use std::collections::BTreeMap;
+
+use builder::Tree;
+
+/// Maximum number of nested lists and dictionaries when building a value tree
+/// without a limit in the options. Dropping, cloning or deserializing a tree
+/// is recursive, so deeper trees could overflow the stack.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// A bencoded value.
+///
+/// Dictionary fields are sorted by the raw bytes of their keys, as the Bencode
+/// specification requires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Integer(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl Tree for Value {
+    type Key = Vec<u8>;
+
+    fn list(items: Vec<Self>) -> Self {
+        Value::List(items)
+    }
+
+    fn dict(fields: BTreeMap<Self::Key, Self>) -> Self {
+        Value::Dict(fields)
+    }
+}
+
+impl Value {
+    /// It returns the integer if the value is an integer.
+    #[must_use]
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    /// It returns the raw bytes if the value is a string.
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// It returns the string if the value is a string containing only valid
+    /// UTF-8.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// It returns the items if the value is a list.
+    #[must_use]
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// It returns the fields if the value is a dictionary.
+    #[must_use]
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Value>> {
+        match self {
+            Value::Dict(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// It returns the value of a dictionary field. It returns `None` if the
+    /// value is not a dictionary or the field does not exist.
+    #[must_use]
+    pub fn get(&self, key: &[u8]) -> Option<&Value> {
+        self.as_dict().and_then(|fields| fields.get(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parsers::{error::Error, options::BencodeParserBuilder, BencodeParser},
+        value::{Value, DEFAULT_MAX_DEPTH},
+    };
+
+    fn read_value(input: &[u8]) -> Result<Option<Value>, Error> {
+        BencodeParser::new(input).read_value()
+    }
+
+    mod it_should_build {
+        use std::collections::BTreeMap;
+
+        use crate::value::{tests::read_value, Value};
+
+        #[test]
+        fn integers() {
+            assert_eq!(read_value(b"i-42e").unwrap(), Some(Value::Integer(-42)));
+        }
+
+        #[test]
+        fn strings_with_their_raw_bytes() {
+            assert_eq!(
+                read_value(b"2:\xFF\xFE").unwrap(),
+                Some(Value::Bytes(b"\xFF\xFE".to_vec()))
+            );
+        }
+
+        #[test]
+        fn lists() {
+            assert_eq!(
+                read_value(b"li1e4:spamlee").unwrap(),
+                Some(Value::List(vec![
+                    Value::Integer(1),
+                    Value::Bytes(b"spam".to_vec()),
+                    Value::List(vec![])
+                ]))
+            );
+        }
+
+        #[test]
+        fn dictionaries_with_byte_string_keys() {
+            let mut fields = BTreeMap::new();
+            fields.insert(b"\xFF".to_vec(), Value::Integer(1));
+            fields.insert(b"foo".to_vec(), Value::Dict(BTreeMap::new()));
+
+            assert_eq!(
+                read_value(b"d3:foode1:\xFFi1ee").unwrap(),
+                Some(Value::Dict(fields))
+            );
+        }
+
+        #[test]
+        fn one_top_level_value_at_a_time() {
+            let mut parser = crate::parsers::BencodeParser::new(&b"i1e\n4:spam"[..]);
+
+            assert_eq!(parser.read_value().unwrap(), Some(Value::Integer(1)));
+            assert_eq!(
+                parser.read_value().unwrap(),
+                Some(Value::Bytes(b"spam".to_vec()))
+            );
+            assert_eq!(parser.read_value().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn it_should_return_none_for_an_empty_input() {
+        assert_eq!(read_value(b"").unwrap(), None);
+    }
+
+    #[test]
+    fn it_should_allow_a_nesting_depth_limit_greater_than_the_default() {
+        let depth = DEFAULT_MAX_DEPTH + 1;
+        let deeply_nested_list = format!("{}{}", "l".repeat(depth), "e".repeat(depth));
+
+        let mut parser = BencodeParserBuilder::default()
+            .max_depth(depth)
+            .build(deeply_nested_list.as_bytes());
+
+        assert!(parser.read_value().unwrap().is_some());
+    }
+
+    #[test]
+    fn it_should_allow_getting_dictionary_fields() {
+        let value = read_value(b"d4:spam4:eggse").unwrap().unwrap();
+
+        assert_eq!(value.get(b"spam").and_then(Value::as_str), Some("eggs"));
+        assert_eq!(value.get(b"missing"), None);
+    }
+
+    mod it_should_fail {
+        use crate::{
+            parsers::error::Error,
+            value::{tests::read_value, DEFAULT_MAX_DEPTH},
+        };
+
+        #[test]
+        fn with_integers_that_do_not_fit_into_an_i64() {
+            let big_integer = format!("i{}1e", i64::MAX);
+
+            assert!(matches!(
+                read_value(big_integer.as_bytes()),
+                Err(Error::IntegerOverflow(..))
+            ));
+        }
+
+        #[test]
+        fn with_leading_zeros_in_integers() {
+            assert!(matches!(
+                read_value(b"i00e"),
+                Err(Error::LeadingZerosInIntegersNotAllowed(..))
+            ));
+        }
+
+        #[test]
+        fn with_dictionary_keys_that_are_not_strings() {
+            assert!(matches!(
+                read_value(b"di1ei2ee"),
+                Err(Error::ExpectedStringForDictKeyGot(..))
+            ));
+        }
+
+        #[test]
+        fn with_a_premature_end_of_dictionary() {
+            assert!(matches!(
+                read_value(b"d3:fooe"),
+                Err(Error::PrematureEndOfDict(..))
+            ));
+        }
+
+        #[test]
+        fn with_unfinished_lists() {
+            assert!(matches!(
+                read_value(b"li1e"),
+                Err(Error::UnexpectedEndOfInputExpectingNextListItem(..))
+            ));
+        }
+
+        #[test]
+        fn with_unrecognized_bytes() {
+            assert!(matches!(
+                read_value(b"x"),
+                Err(Error::UnrecognizedFirstBencodeValueByte(..))
+            ));
+        }
+
+        #[test]
+        fn when_the_nesting_depth_exceeds_the_default_limit() {
+            let depth = DEFAULT_MAX_DEPTH + 1;
+            let deeply_nested_list = format!("{}{}", "l".repeat(depth), "e".repeat(depth));
+
+            assert!(matches!(
+                read_value(deeply_nested_list.as_bytes()),
+                Err(Error::MaxDepthExceeded(..))
+            ));
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! `serde` serialization for [`Value`].
+//!
+//! Strings containing only valid UTF-8 are serialized as strings. The rest are
+//! serialized as bytes. The same rule applies to dictionary keys.
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+
+use super::Value;
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Integer(integer) => serializer.serialize_i64(*integer),
+            Value::Bytes(bytes) => ByteString(bytes).serialize(serializer),
+            Value::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Value::Dict(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(&ByteString(key), value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// A bencoded string.
+struct ByteString<'a>(&'a [u8]);
+
+impl Serialize for ByteString<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(self.0) {
+            Ok(string) => serializer.serialize_str(string),
+            Err(_) => serializer.serialize_bytes(self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::value::Value;
+
+    #[test]
+    fn it_should_serialize_utf8_strings_as_strings() {
+        let value = Value::Bytes(b"spam".to_vec());
+
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#""spam""#);
+    }
+
+    #[test]
+    fn it_should_serialize_non_utf8_strings_as_bytes() {
+        let value = Value::Bytes(b"\xFF\xFE".to_vec());
+
+        assert_eq!(serde_json::to_string(&value).unwrap(), "[255,254]");
+    }
+
+    #[test]
+    fn it_should_serialize_nested_values() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            b"foo".to_vec(),
+            Value::List(vec![Value::Integer(-1), Value::Bytes(b"bar".to_vec())]),
+        );
+
+        assert_eq!(
+            serde_json::to_string(&Value::Dict(fields)).unwrap(),
+            r#"{"foo":[-1,"bar"]}"#
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_through_other_formats() {
+        let value = crate::from_bytes::<Value>(b"d3:barli1e3:baze3:fooi42ee").unwrap();
+
+        let json = serde_json::to_string(&value).unwrap();
+
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+    }
+}
@@ -54,6 +54,631 @@ mod it_should {
         assert_eq!(output_content.trim(), r#"["spam"]"#);
     }
 
+    #[test]
+    fn reject_non_canonical_bencode_in_strict_mode() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--strict")
+            .write_stdin("i-0e")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Error: Negative zero"));
+    }
+
+    #[test]
+    fn reject_line_breaks_when_they_are_not_skipped() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--no-skip-newlines")
+            .write_stdin("4:spam\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Error: Unrecognized first"));
+    }
+
+    #[test]
+    fn write_non_utf8_strings_with_the_selected_encoding() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--byte-strings")
+            .arg("lossy")
+            .write_stdin(b"2:a\xFF".to_vec())
+            .assert()
+            .success()
+            .stdout("\"a\u{FFFD}\"");
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--byte-strings")
+            .arg("tagged")
+            .write_stdin(b"d2:a\xFF2:\xFF\xFEe".to_vec())
+            .assert()
+            .success()
+            .stdout(r#"{"a\\xff":{"$bytes":"//4="}}"#);
+    }
+
+    #[test]
+    fn limit_the_bytes_shown_in_error_messages() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--capture-size")
+            .arg("1")
+            .write_stdin("li1ex")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("latest input bytes dump: [120]"));
+    }
+
+    #[test]
+    fn reject_integers_that_do_not_fit_into_an_i64_when_required() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--i64-integers")
+            .write_stdin("i9223372036854775808e")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Error: Integer does not fit into a 64-bit signed integer",
+            ));
+    }
+
+    #[test]
+    fn write_multiple_top_level_values_as_ndjson_or_a_json_array() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--multiple-values")
+            .arg("ndjson")
+            .write_stdin("i1ei2e")
+            .assert()
+            .success()
+            .stdout("1\n2\n");
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--multiple-values")
+            .arg("array")
+            .write_stdin("i1ei2e")
+            .assert()
+            .success()
+            .stdout("[1,2]");
+    }
+
+    #[test]
+    fn reject_trailing_data_after_the_first_top_level_value_when_required() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--multiple-values")
+            .arg("reject")
+            .write_stdin("i1ei2e")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Error: Unexpected trailing data"));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--multiple-values")
+            .arg("reject")
+            .arg("--strict")
+            .write_stdin("i1e\n")
+            .assert()
+            .code(65);
+    }
+
+    #[test]
+    fn render_torrent_files() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--torrent")
+            .write_stdin(b"d4:infod6:pieces20:\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAA\xAAee".to_vec())
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with(format!(
+                r#"{{"info":{{"pieces":["{}"]}},"info_hash":{{"v1":""#,
+                "aa".repeat(20)
+            )));
+    }
+
+    #[test]
+    fn render_tracker_responses() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--tracker-response")
+            .write_stdin(b"d5:peers6:\x01\x02\x03\x04\x1A\xE1e".to_vec())
+            .assert()
+            .success()
+            .stdout(r#"{"peers":[{"ip":"1.2.3.4","port":6881}]}"#);
+    }
+
+    #[test]
+    fn pretty_print_the_json() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--pretty")
+            .write_stdin("d3:foold3:bari42eeee")
+            .assert()
+            .success()
+            .stdout("{\n  \"foo\": [\n    {\n      \"bar\": 42\n    }\n  ]\n}");
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--indent")
+            .arg("1")
+            .arg("--sort-keys")
+            .write_stdin("d1:bi1e1:ai2ee")
+            .assert()
+            .success()
+            .stdout("{\n \"a\": 2,\n \"b\": 1\n}");
+    }
+
+    #[test]
+    fn not_pretty_print_values_written_one_per_line() {
+        for args in [
+            &["--pretty", "--multiple-values", "ndjson"][..],
+            &["--indent", "2", "--on-error", "close"],
+            &["--pretty", "--on-error", "resync"],
+            &["--pretty", "--select", "a"],
+        ] {
+            let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+            cmd.args(args)
+                .write_stdin("d1:ai1eed1:ai2ee")
+                .assert()
+                .failure()
+                .stderr(predicate::str::contains("--pretty"));
+        }
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.args(["query", "a", "--pretty"])
+            .write_stdin("d1:ai1ee")
+            .assert()
+            .failure();
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.args([
+            "--pretty",
+            "--on-error",
+            "close",
+            "--multiple-values",
+            "array",
+        ])
+        .write_stdin("i1ei2e")
+        .assert()
+        .success()
+        .stdout("[\n  1,\n  2\n]");
+    }
+
+    #[test]
+    fn stream_long_strings_in_chunks() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--string-buffer-size")
+            .arg("4")
+            .write_stdin("l12:spam and egg4:spame")
+            .assert()
+            .success()
+            .stdout(r#"["spam and egg","spam"]"#);
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--string-buffer-size")
+            .arg("4")
+            .write_stdin(b"8:spam\xff\xfeeg".to_vec())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid UTF-8"));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--string-buffer-size")
+            .arg("4")
+            .arg("--streamed-invalid-utf8")
+            .arg("switch")
+            .write_stdin(b"8:spam\xff\xfeeg".to_vec())
+            .assert()
+            .success()
+            .stdout(r#""spam<hex>fffe6567</hex>""#);
+    }
+
+    #[test]
+    fn reject_streaming_long_strings_with_sorted_keys() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--string-buffer-size")
+            .arg("4")
+            .arg("--sort-keys")
+            .write_stdin("d1:b4:spam1:a12:spam and egge")
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+
+    #[test]
+    fn close_the_json_when_the_input_is_corrupt() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--on-error")
+            .arg("close")
+            .write_stdin("d3:fooli1ei2x")
+            .assert()
+            .failure()
+            .stdout("{\"foo\":[1,\"<corrupt>\"]}\n")
+            .stderr(predicate::str::contains(
+                "Error: Unexpected byte parsing integer",
+            ));
+    }
+
+    #[test]
+    fn print_errors_as_json() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--error-format")
+            .arg("json")
+            .write_stdin("li1ei2xe")
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(
+                r#"{"code":"unexpected_byte_parsing_integer","input":{"byte":120,"latest_bytes":[108,105,49,101,105,50,120],"path":"[1]","pos":7},"kind":"syntax","#,
+            ));
+    }
+
+    #[test]
+    fn print_errors_with_a_hex_dump_pointing_at_the_offending_byte() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--error-format")
+            .arg("pretty")
+            .arg("--color")
+            .arg("never")
+            .write_stdin("li1ei2xe")
+            .assert()
+            .failure()
+            .stderr(
+                "\
+error[unexpected_byte_parsing_integer]: Unexpected byte parsing integer
+ --> input pos 7, path `[1]`
+00000000: 6c69 3165 6932 78                        li1ei2x
+                         ^^                              ^
+expected one of: `0`-`9`, `e`
+",
+            );
+    }
+
+    #[test]
+    fn exit_with_distinct_codes_for_invalid_input_and_io_errors() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.write_stdin("x").assert().code(65);
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--max-depth")
+            .arg("1")
+            .write_stdin("llee")
+            .assert()
+            .code(65);
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("-i")
+            .arg("./tests/fixtures/missing.bencode")
+            .arg("--error-format")
+            .arg("json")
+            .assert()
+            .code(74)
+            .stderr(predicate::str::contains(r#""code":"io""#));
+    }
+
+    #[test]
+    fn only_write_the_selected_values() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--select")
+            .arg("info.files[*].length")
+            .arg("--select")
+            .arg("announce")
+            .write_stdin("d8:announce4:spam4:infod5:filesld6:lengthi1eed6:lengthi2eeeee")
+            .assert()
+            .success()
+            .stdout("\"spam\"\n1\n2\n");
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--select")
+            .arg("files[x]")
+            .write_stdin("de")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid list index"));
+    }
+
+    #[test]
+    fn reject_selecting_values_with_a_multiple_values_mode() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--select")
+            .arg("x")
+            .arg("--multiple-values")
+            .arg("array")
+            .write_stdin("d1:xi1eed1:xi2ee")
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("query")
+            .arg("x")
+            .arg("--multiple-values")
+            .arg("array")
+            .write_stdin("d1:xi1ee")
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("unexpected argument"));
+    }
+
+    #[test]
+    fn not_resume_parsing_after_an_error_when_only_one_value_is_allowed() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--multiple-values")
+            .arg("reject")
+            .arg("--on-error")
+            .arg("resync")
+            .write_stdin("i1ei2e")
+            .assert()
+            .code(65)
+            .stdout("1")
+            .stderr(predicate::str::contains("Error: Unexpected trailing data"));
+    }
+
+    #[test]
+    fn reject_inputs_exceeding_the_limits() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--max-depth")
+            .arg("2")
+            .write_stdin("llleee")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Error: Maximum nesting depth"));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--max-string-length")
+            .arg("3")
+            .write_stdin("4:spam")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Error: Maximum string length"));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--max-container-items")
+            .arg("1")
+            .write_stdin("li1ei2ee")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Error: Maximum number of list items",
+            ));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("--max-input-bytes")
+            .arg("3")
+            .write_stdin("4:spam")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Error: Maximum input size"));
+    }
+
+    #[test]
+    fn write_bencode_in_canonical_form_and_print_the_changes() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("canonicalize")
+            .write_stdin("d1:bi-0e1:a03:fooe\n")
+            .assert()
+            .success()
+            .stdout("d1:a3:foo1:bi0ee")
+            .stderr(
+                "\
+Changed: negative zero written as `0` at `b`
+Changed: sorted the dictionary keys at the top-level value
+Changed: removed leading zeros from the string length at `a`
+Changed: removed 1 line break
+",
+            );
+    }
+
+    #[test]
+    fn reject_duplicate_keys_when_writing_canonical_bencode_if_required() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("canonicalize")
+            .write_stdin("d1:ai1e1:ai2ee")
+            .assert()
+            .success()
+            .stdout("d1:ai2ee");
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("canonicalize")
+            .arg("--duplicate-keys")
+            .arg("reject")
+            .write_stdin("d1:ai1e1:ai2ee")
+            .assert()
+            .code(65)
+            .stdout("")
+            .stderr(predicate::str::contains(
+                "Error: Duplicate dictionary keys are not allowed",
+            ));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("canonicalize")
+            .arg("--duplicate-keys")
+            .arg("reject")
+            .write_stdin("d1:bi-0e1:ai1e1:ai2ee")
+            .assert()
+            .code(65)
+            .stderr(predicate::str::contains("Changed").not());
+    }
+
+    #[test]
+    fn limit_the_nesting_depth_by_default_when_writing_canonical_bencode() {
+        let deeply_nested_lists = format!("{}{}", "l".repeat(1_001), "e".repeat(1_001));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("canonicalize")
+            .write_stdin(deeply_nested_lists.clone())
+            .assert()
+            .code(65)
+            .stderr(predicate::str::contains("Error: Maximum nesting depth"));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("canonicalize")
+            .arg("--max-depth")
+            .arg("2000")
+            .write_stdin(deeply_nested_lists.clone())
+            .assert()
+            .success()
+            .stdout(deeply_nested_lists);
+    }
+
+    #[test]
+    fn decode_bencode_with_the_decode_subcommand() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("decode")
+            .arg("--sort-keys")
+            .write_stdin("d1:bi2e1:ai1ee")
+            .assert()
+            .success()
+            .stdout(r#"{"a":1,"b":2}"#);
+    }
+
+    #[test]
+    fn encode_json_to_bencode() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("encode")
+            .write_stdin(r#"{"spam":["a",1]}"#)
+            .assert()
+            .success()
+            .stdout("d4:spaml1:ai1eee");
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("encode")
+            .write_stdin(r#"{"spam":1.5}"#)
+            .assert()
+            .code(65)
+            .stderr(predicate::str::contains("Error: JSON error"));
+    }
+
+    #[test]
+    fn leave_the_bencode_written_before_invalid_json_in_the_output() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("encode")
+            .write_stdin("[1,x")
+            .assert()
+            .code(65)
+            .stdout("li1e")
+            .stderr(predicate::str::contains("Error: JSON error"));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("encode")
+            .write_stdin("1 2")
+            .assert()
+            .code(65)
+            .stdout("i1e")
+            .stderr(predicate::str::contains("Error: JSON error"));
+    }
+
+    #[test]
+    fn only_print_the_errors_when_validating_bencode() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("validate")
+            .write_stdin("d4:spami1ee")
+            .assert()
+            .success()
+            .stdout("")
+            .stderr("");
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("validate")
+            .arg("--strict")
+            .write_stdin("i-0e")
+            .assert()
+            .code(65)
+            .stdout("")
+            .stderr(predicate::str::contains("Error: Negative zero"));
+    }
+
+    #[test]
+    fn not_print_the_output_context_when_validating_bencode() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("validate")
+            .write_stdin("li1ex")
+            .assert()
+            .code(65)
+            .stderr(predicate::str::contains("read context"))
+            .stderr(predicate::str::contains("write context").not());
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("validate")
+            .arg("--error-format")
+            .arg("json")
+            .write_stdin("li1ex")
+            .assert()
+            .code(65)
+            .stderr(predicate::str::contains("\"input\""))
+            .stderr(predicate::str::contains("\"output\"").not());
+    }
+
+    #[test]
+    fn print_a_summary_of_a_torrent_file() {
+        let torrent = b"d4:infod6:lengthi40000e4:name8:spam.iso12:piece lengthi16384e6:pieces60:\
+            aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee";
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("info")
+            .write_stdin(torrent.to_vec())
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with(
+                "\
+name: spam.iso
+size: 40000
+piece length: 16384
+pieces: 3
+info hash v1: ",
+            ))
+            .stdout(predicate::str::ends_with(
+                "files: 1\n  spam.iso (40000 bytes)\n",
+            ));
+
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("info")
+            .arg("--json")
+            .write_stdin(torrent.to_vec())
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with(
+                r#"{"files":[{"length":40000,"path":"spam.iso"}],"info_hash":{"v1":""#,
+            ));
+    }
+
+    #[test]
+    fn fail_summarizing_a_file_that_is_not_a_torrent() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("info")
+            .write_stdin("d4:spami1ee")
+            .assert()
+            .code(65)
+            .stderr(predicate::str::contains(
+                "Error: Deserialization error: invalid torrent, missing `info` dictionary",
+            ));
+    }
+
+    #[test]
+    fn limit_the_nesting_depth_by_default_when_summarizing_a_torrent_file() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("info")
+            .write_stdin(format!(
+                "d4:info{}{}e",
+                "l".repeat(100_000),
+                "e".repeat(100_000)
+            ))
+            .assert()
+            .code(65)
+            .stderr(predicate::str::contains("Error: Maximum nesting depth"));
+    }
+
+    #[test]
+    fn extract_the_values_at_the_given_paths() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("query")
+            .arg("info.name")
+            .arg("info.files[*].length")
+            .write_stdin("d4:infod5:filesld6:lengthi1eed6:lengthi2eee4:name4:spamee")
+            .assert()
+            .success()
+            .stdout("1\n2\n\"spam\"\n");
+    }
+
+    #[test]
+    fn extract_the_values_of_quoted_keys() {
+        let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();
+        cmd.arg("query")
+            .arg(r#"["a.b"]"#)
+            .arg(r#"c["d[0]"]"#)
+            .write_stdin("d3:a.bi1e1:cd4:d[0]i2eee")
+            .assert()
+            .success()
+            .stdout("1\n2\n");
+    }
+
     #[test]
     fn fail_when_the_bencoded_input_is_invalid() {
         let mut cmd = Command::cargo_bin("torrust-bencode2json").unwrap();